@@ -0,0 +1,59 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use shai_core::agent::{AuditEvent, AuditFilter, AuditLog, AuditRecord};
+
+/// Parse an RFC3339 timestamp or a duration relative to now, e.g. `30m`, `12h`, `7d`
+pub fn parse_time(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+
+    let invalid = || format!("Invalid time '{}', expected RFC3339 or a duration like 30m, 12h, 7d", value);
+    let (amount, unit) = value.split_at(value.len().saturating_sub(1));
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    let delta = match unit {
+        "s" => TimeDelta::try_seconds(amount),
+        "m" => TimeDelta::try_minutes(amount),
+        "h" => TimeDelta::try_hours(amount),
+        "d" => TimeDelta::try_days(amount),
+        _ => None,
+    }.ok_or_else(invalid)?;
+    Ok(Utc::now() - delta)
+}
+
+/// Print the audit records matching the filter, one per line or as JSON lines
+pub fn handle_audit(filter: AuditFilter, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(log) = AuditLog::open_default() else {
+        return Err("Could not locate the home directory".into());
+    };
+
+    for record in log.query(&filter)? {
+        if json {
+            println!("{}", serde_json::to_string(&record)?);
+        } else {
+            println!("{}", format_record(&record));
+        }
+    }
+    Ok(())
+}
+
+fn format_record(record: &AuditRecord) -> String {
+    let details = match &record.event {
+        AuditEvent::PermissionRequired { parameters, .. } => {
+            format!("requested  {}", parameters)
+        }
+        AuditEvent::PermissionDecision { response, source, .. } => {
+            format!("decision   {:?} by {:?}", response, source)
+        }
+        AuditEvent::ToolExecuted { parameters, success, outcome, duration_ms } => {
+            let status = if *success { "ok" } else { "error" };
+            let outcome = outcome.lines().next().unwrap_or("");
+            format!("executed   {} {} ({}ms) {}", status, parameters, duration_ms, outcome)
+        }
+    };
+
+    format!("{} {:.8} {:<10} {}",
+        record.timestamp.format("%Y-%m-%d %H:%M:%S"),
+        record.session_id,
+        record.tool_name,
+        details)
+}
@@ -2,12 +2,17 @@ use std::sync::Arc;
 
 use crate::headless::tools::ToolConfig;
 
+use super::perm::{HeadlessPermissionHandler, PermissionConfig};
 use super::tools::{ToolName, list_all_tools, parse_tools_list};
-use shai_core::agent::{Agent, AgentBuilder, AgentError, AgentResult, Brain, LoggingConfig, StdoutEventManager};
+use shai_core::agent::{Agent, AgentBuilder, AgentError, AgentResult, Brain, LoggingConfig, Restrictions, StdoutEventManager};
 use shai_core::config::config::ShaiConfig;
+use shai_core::lsp::LspManager;
+use shai_core::redact::Redactor;
+use shai_core::truncate::Truncator;
 use shai_core::runners::coder::coder::CoderBrain;
 use shai_core::runners::searcher::searcher::SearcherBrain;
 use shai_llm::{ChatMessage, ChatMessageContent, LlmClient};
+use uuid::Uuid;
 
 pub enum AgentKind {
     Coder,
@@ -30,7 +35,10 @@ impl AppHeadless {
         list_tools: bool, 
         tools: Option<String>, 
         remove: Option<String>,
-        trace: bool
+        permissions: PermissionConfig,
+        trace: bool,
+        session: Option<String>,
+        restrictions: Restrictions
     ) -> Result<(), Box<dyn std::error::Error>> {   
         // Configure internal debug logging to file
         /*
@@ -46,8 +54,16 @@ impl AppHeadless {
             return Ok(());
         }
     
-        let (llm_client, model) = ShaiConfig::get_llm().await?;
+        let (llm_client, model) = ShaiConfig::get_llm(restrictions).await?;
         eprintln!("\x1b[2m{} on {}\x1b[0m", model, llm_client.provider().name());
+
+        if restrictions.is_restricted() {
+            let modes: Vec<&str> = [(restrictions.read_only, "read-only"), (restrictions.offline, "offline")]
+                .into_iter().filter(|(on, _)| *on).map(|(_, mode)| mode).collect();
+            eprintln!("\x1b[2m{} mode: tools that {} are disabled\x1b[0m", 
+                modes.join(" + "), 
+                if restrictions.read_only && restrictions.offline { "write or use the network" } else if restrictions.read_only { "write" } else { "use the network" });
+        }
     
         // Validate that we have some input
         if initial_trace.is_empty() {
@@ -73,20 +89,33 @@ impl AppHeadless {
         let model = llm_client.default_model().await
             .map_err(|e| format!("Failed to get default model: {}", e))?;
 
-        let toolbox = tools.build_toolbox();
+        // a continued session gets its todo list back
+        let session_id = session.unwrap_or_else(|| Uuid::new_v4().to_string());
+        eprintln!("\x1b[2msession {}\x1b[0m", session_id);
+
+        let lsp = Arc::new(LspManager::load());
+        let toolbox = tools.build_toolbox(lsp.clone(), &session_id, &std::env::current_dir()?);
         let brain: Box<dyn Brain> = match self.kind {
             AgentKind::Coder => Box::new(CoderBrain::new(Arc::new(llm_client), model)),
             AgentKind::Searcher => Box::new(SearcherBrain::new(Arc::new(llm_client), model)),
         };
 
-        let agent = AgentBuilder::new(brain)
+        let mut agent = AgentBuilder::new(brain)
+            .id(&session_id)
             .with_traces(initial_trace)
             .tools(toolbox)
-            .sudo()
+            .permissions(permissions.build_claims())
+            .default_audit()
+            .redactor(Redactor::load())
+            .truncator(Truncator::load())
+            .restrictions(restrictions)
+            .lsp(lsp)
             .build();
 
+        let controller = agent.controller();
         let result = agent
             .with_event_handler(StdoutEventManager::new())
+            .with_event_handler(HeadlessPermissionHandler::new(permissions.mode, controller))
             .run().await;
 
         match result {
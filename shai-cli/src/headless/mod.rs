@@ -1,2 +1,3 @@
 pub mod tools;
-pub mod app;
\ No newline at end of file
+pub mod app;
+pub mod perm;
\ No newline at end of file
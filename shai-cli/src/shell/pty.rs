@@ -4,6 +4,7 @@ use std::thread;
 use std::io::{self, Read, Write};
 use std::sync::atomic::{AtomicI32, Ordering};
 use tempfile::NamedTempFile;
+use shai_core::agent::restrictions::{Restrictions, OFFLINE_ENV, READ_ONLY_ENV};
 
 use crate::fc::server::ShaiSessionServer;
 use crate::shell::terminal::TerminalManager;
@@ -108,7 +109,7 @@ impl ShaiPtyManager {
         Ok((master_fd, slave_fd))
     }
 
-    pub fn start_session(&mut self, shell: Shell, quiet: bool) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn start_session(&mut self, shell: Shell, quiet: bool, restrictions: Restrictions) -> Result<(), Box<dyn std::error::Error>> {
         let terminal = TerminalManager::new()?;
         let window_size = TerminalManager::get_window_size()?;
         TerminalManager::set_window_size(self.master_fd, &window_size)?;
@@ -121,7 +122,7 @@ impl ShaiPtyManager {
         
         if pid == 0 {
             // CHILD: Become the shell 
-            self.setup_child_process(shell, quiet); 
+            self.setup_child_process(shell, quiet, restrictions); 
         } else if pid > 0 {
             // PARENT: Handle I/O and run buffer server
             unsafe { libc::close(self.slave_fd) };
@@ -199,7 +200,7 @@ impl ShaiPtyManager {
         Ok(())
     }
 
-    fn setup_child_process(&self, shell: Shell, quiet: bool) -> ! {
+    fn setup_child_process(&self, shell: Shell, quiet: bool, restrictions: Restrictions) -> ! {
         unsafe {
             libc::close(self.master_fd);
             
@@ -207,6 +208,15 @@ impl ShaiPtyManager {
             let session_env = std::ffi::CString::new("SHAI_SESSION_ID").unwrap();
             let session_value = std::ffi::CString::new(self.session_id.as_str()).unwrap();
             libc::setenv(session_env.as_ptr(), session_value.as_ptr(), 1);
+
+            // the hooks of the session run shai again, they inherit its modes
+            for (name, enabled) in [(READ_ONLY_ENV, restrictions.read_only), (OFFLINE_ENV, restrictions.offline)] {
+                if enabled {
+                    let name = std::ffi::CString::new(name).unwrap();
+                    let value = std::ffi::CString::new("1").unwrap();
+                    libc::setenv(name.as_ptr(), value.as_ptr(), 1);
+                }
+            }
             
             if quiet {
                 let tmux_env = std::ffi::CString::new("TMUX").unwrap();
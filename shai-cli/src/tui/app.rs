@@ -12,7 +12,7 @@ use ratatui::prelude::CrosstermBackend;
 use ratatui::style::Stylize;
 use ratatui::text::{Line, Span, Text};
 use ratatui::Terminal;
-use shai_core::agent::{Agent, AgentRequest, AgentEvent, AgentController, PublicAgentState};
+use shai_core::agent::{Agent, AgentRequest, AgentEvent, AgentController, PublicAgentState, Restrictions};
 use shai_core::agent::events::{PermissionRequest, PermissionResponse};
 use shai_core::agent::output::PrettyFormatter;
 use shai_core::config::config::ShaiConfig;
@@ -28,6 +28,7 @@ use ratatui::{
 };
 use tokio::sync::broadcast;
 use tokio::task::JoinHandle;
+use uuid::Uuid;
 use tokio::time::{interval, Duration};
 use tui_textarea::Input;
 use ansi_to_tui::IntoText;
@@ -37,9 +38,13 @@ use crate::tui::input::InputArea;
 use super::input::UserAction;
 use crate::tui::perm::PermissionWidget;
 use crate::tui::perm_alt_screen::AlternateScreenPermissionModal;
+use crate::tui::todos::TodoPanel;
 use super::perm::PermissionModalAction;
 
 
+/// Bytes of streamed output kept per running tool
+const RUNNING_OUTPUT_TAIL: usize = 4096;
+
 pub enum AppModalState<'a> {
     InputShown,
     PermissionModal {
@@ -62,10 +67,14 @@ pub struct App<'a> {
     pub(crate) state: AppModalState<'a>,
     pub(crate) formatter: PrettyFormatter, // streaming log formatter
     pub(crate) running_tools: HashMap<String, ToolCall>, // (request_id, request)
+    pub(crate) running_output: HashMap<String, String>, // (request_id, tail of the streamed output)
     pub(crate) input: InputArea<'a>,       // input text
+    pub(crate) todos: TodoPanel,           // todo list of the agent
     pub(crate) commands: HashMap<(String, String),Vec<String>>,
     pub(crate) exit: bool,
     pub(crate) permission_queue: VecDeque<(String, PermissionRequest)>, // (request_id, request)
+    pub(crate) session_id: String,         // a continued session gets its todo list back
+    pub(crate) restrictions: Restrictions, // read-only / offline modes of the agent
 }
 
 
@@ -73,11 +82,12 @@ pub struct App<'a> {
 impl App<'_> {
     pub async fn start_agent(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         // Try to load from config file first
-        let (llm, model) = ShaiConfig::get_llm().await?;
+        let (llm, model) = ShaiConfig::get_llm(self.restrictions).await?;
         println!("\x1b[2m{} on {}\x1b[0m", model, llm.provider().name());
         
         // Create and start the agent
-        let mut agent = coder(Arc::new(llm), model);
+        println!("\x1b[2msession {}\x1b[0m", self.session_id);
+        let mut agent = coder(Arc::new(llm), model, &self.session_id, &std::env::current_dir()?, self.restrictions);
         
         // Get Agent I/O
         let controller = agent.controller();
@@ -100,10 +110,14 @@ impl App<'_> {
     }
 
     async fn receive_agent_event(&mut self) -> Option<AgentEvent> {
-        if let Some(ref mut agent) = self.agent {
-            agent.events.recv().await.ok()
-        } else {
-            None
+        let agent = self.agent.as_mut()?;
+        loop {
+            match agent.events.recv().await {
+                Ok(event) => return Some(event),
+                // missing some output is fine, missing the end of the stream is not
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
         }
     }
 
@@ -119,6 +133,22 @@ impl App<'_> {
         }
         if let AgentEvent::ToolCallCompleted { call, .. }= &event {
             self.running_tools.remove(&call.tool_call_id);
+            self.running_output.remove(&call.tool_call_id);
+        }
+        if let AgentEvent::TodoChanged { todos } = &event {
+            self.todos.set_todos(todos.clone());
+        }
+        if let AgentEvent::ToolOutputChunk { tool_call_id, chunk } = &event {
+            let output = self.running_output.entry(tool_call_id.clone()).or_default();
+            output.push_str(chunk);
+            // only the last line is displayed, keep a bounded tail
+            if output.len() > RUNNING_OUTPUT_TAIL {
+                let mut cut = output.len() - RUNNING_OUTPUT_TAIL;
+                while !output.is_char_boundary(cut) {
+                    cut += 1;
+                }
+                output.drain(..cut);
+            }
         }
 
         // Format and display event
@@ -145,7 +175,7 @@ impl App<'_> {
 
 // UI-related Internals
 impl App<'_> {
-    pub fn new() -> Self {
+    pub fn new(restrictions: Restrictions) -> Self {
         Self {
             terminal: None,
             terminal_height: 5,
@@ -153,10 +183,14 @@ impl App<'_> {
             formatter: PrettyFormatter::new(),
             state: AppModalState::InputShown,
             input: InputArea::new(),
+            todos: TodoPanel::new(),
             commands: Self::list_command(),
             exit: false,
             running_tools: HashMap::new(),
+            running_output: HashMap::new(),
             permission_queue: VecDeque::new(),
+            session_id: Uuid::new_v4().to_string(),
+            restrictions,
         }
     }
 
@@ -246,6 +280,10 @@ impl App<'_> {
             self.exit = true;
             return Ok(());
         }
+        if matches!(key_event.code, KeyCode::Char('t')) && key_event.modifiers.contains(crossterm::event::KeyModifiers::CONTROL) {
+            self.todos.toggle();
+            return Ok(());
+        }
 
         match &mut self.state {
             AppModalState::InputShown => {
@@ -356,6 +394,7 @@ impl App<'_> {
         }.max(5);
         let height = modal_height
         + 1 
+        + self.todos.height()
         + self.running_tools.len() as u16;
 
         if let Some(ref mut terminal) = self.terminal {  
@@ -365,17 +404,27 @@ impl App<'_> {
             }
 
             terminal.draw(|frame| {                    
-                let [_, inprogress, modal] = Layout::vertical([
+                let [_, todos, inprogress, modal] = Layout::vertical([
                     Constraint::Length(1), // padding
+                    Constraint::Length(self.todos.height()), // todo list (if any)
                     Constraint::Length(self.running_tools.len() as u16 + 1), // running tool (if any)
                     Constraint::Length(modal_height)])                // input or modal
                     .areas(frame.area()); 
 
+                // draw todo list
+                self.todos.draw(frame, todos);
+
                 // draw running tool
                 if !self.running_tools.is_empty() {
                     let layout: std::rc::Rc<[Rect]> = Layout::vertical(vec![Constraint::Length(1); self.running_tools.len()+1]).split(inprogress);
-                    for ((_,tc), &area) in self.running_tools.iter().zip(layout.into_iter()) {
-                        frame.render_widget(self.formatter.format_tool_running(tc).into_text().unwrap(), area);
+                    for ((id,tc), &area) in self.running_tools.iter().zip(layout.into_iter()) {
+                        let mut line = self.formatter.format_tool_running(tc);
+                        let last = self.running_output.get(id)
+                            .and_then(|output| output.lines().rev().find(|l| !l.trim().is_empty()));
+                        if let Some(last) = last {
+                            line.push_str(&format!("  \x1b[2m{}\x1b[0m", last.trim()));
+                        }
+                        frame.render_widget(line.into_text().unwrap(), area);
                     }
                 }
 
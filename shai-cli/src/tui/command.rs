@@ -9,6 +9,7 @@ impl App<'_> {
             (("/exit","exit from the tui"), vec![]),
             (("/auth","select a provider"), vec![]),
             (("/tc","set the tool call method: [fc | fc2 | so]"), vec!["method"]),
+            (("/todos","show or hide the todo list"), vec![]),
         ])
         .into_iter()
         .map(|((cmd,desc),args)|((cmd.to_string(),desc.to_string()),args.into_iter().map(|s|s.to_string()).collect()))
@@ -24,6 +25,9 @@ impl App<'_> {
             "/exit" => {
                 self.exit = true;
             }
+            "/todos" => {
+                self.todos.toggle();
+            }
             "/tc" => {
                 if let Some(ref agent) = self.agent {
                     match args.into_iter().next() {
@@ -10,7 +10,7 @@ impl HelpArea {
         [
             "  ? to print help      tap esc twice to clear input",
             "  / for commands       tap esc while agent is running to cancel",
-            "                       ctrl^c to exit"
+            "  ctrl^t for todos     ctrl^c to exit"
         ].join("\n").to_string()
     }
 }
@@ -4,6 +4,7 @@ pub mod input;
 pub mod perm;
 pub mod perm_alt_screen;
 pub mod theme;
+pub mod todos;
 pub mod command;
 pub mod helper;
 pub mod cmdnav;
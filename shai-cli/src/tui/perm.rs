@@ -149,7 +149,11 @@ impl PermissionWidget<'_> {
         f.render_widget(&self.preview, inner);
 
         let items = ["Allow", "Yolo", "Deny"];
-        let mut lines = vec![Line::from("Do you want to run this tool?")];
+        let mut lines = vec![if self.request.untrusted {
+            Line::from(Span::styled("⚠ Untrusted content was fetched earlier, do you want to run this tool?", Color::Yellow))
+        } else {
+            Line::from("Do you want to run this tool?")
+        }];
         for (i,s) in items.into_iter().enumerate() {
             if i == self.selected_index {
                 lines.push(Line::from(vec![
@@ -0,0 +1,71 @@
+use ansi_to_tui::IntoText;
+use ratatui::{layout::Rect, Frame};
+use shai_core::tools::{todo_progress, todo_tree, TodoItem, TodoStatus};
+
+/// Tasks listed when the panel is expanded, the others are counted
+const MAX_TODO_LINES: usize = 12;
+
+/// Width of the progress bar, in cells
+const PROGRESS_WIDTH: usize = 10;
+
+/// Todo list of the agent, updated live from TodoChanged events
+pub struct TodoPanel {
+    todos: Vec<TodoItem>,
+    expanded: bool,
+}
+
+impl TodoPanel {
+    pub fn new() -> Self {
+        Self { todos: Vec::new(), expanded: true }
+    }
+
+    pub fn set_todos(&mut self, todos: Vec<TodoItem>) {
+        self.todos = todos;
+    }
+
+    pub fn toggle(&mut self) {
+        self.expanded = !self.expanded;
+    }
+
+    fn header(&self) -> String {
+        let (completed, total) = todo_progress(&self.todos);
+        let filled = completed * PROGRESS_WIDTH / total.max(1);
+        let bar = format!("{}{}", "▰".repeat(filled), "▱".repeat(PROGRESS_WIDTH - filled));
+        let mut header = format!("\x1b[1mtodos\x1b[0m \x1b[32m{}\x1b[0m {}/{}", bar, completed, total);
+        if !self.expanded {
+            if let Some(current) = self.todos.iter().find(|todo| todo.status == TodoStatus::InProgress) {
+                header.push_str(&format!("  \x1b[1;34m{}\x1b[0m", current.content));
+            }
+        }
+        header.push_str(&format!("  \x1b[2m(ctrl^t to {})\x1b[0m", if self.expanded { "collapse" } else { "expand" }));
+        header
+    }
+
+    fn lines(&self) -> Vec<String> {
+        if self.todos.is_empty() {
+            return Vec::new();
+        }
+        let mut lines = vec![self.header()];
+        if self.expanded {
+            let tree = todo_tree(&self.todos);
+            for (depth, todo) in tree.iter().take(MAX_TODO_LINES) {
+                lines.push(format!("  {}{}", "  ".repeat(*depth), todo.format_for_display()));
+            }
+            if tree.len() > MAX_TODO_LINES {
+                lines.push(format!("  \x1b[2m... and {} more\x1b[0m", tree.len() - MAX_TODO_LINES));
+            }
+        }
+        lines
+    }
+
+    /// Nothing is shown until the agent writes a todo list
+    pub fn height(&self) -> u16 {
+        self.lines().len() as u16
+    }
+
+    pub fn draw(&self, f: &mut Frame, area: Rect) {
+        if let Ok(text) = self.lines().join("\n").into_text() {
+            f.render_widget(text, area);
+        }
+    }
+}
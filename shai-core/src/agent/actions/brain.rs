@@ -40,7 +40,8 @@ impl AgentCore {
         self.set_state(InternalAgentState::Processing { 
             task_name: "next_step".to_string(), 
             tools_exec_at: Utc::now(), 
-            cancellation_token
+            cancellation_token,
+            task: None
         }).await;
     }
 
@@ -1,4 +1,6 @@
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
 use chrono::{TimeDelta, Utc};
 use shai_llm::{ChatMessage, ToolCall as LlmToolCall};
@@ -8,10 +10,58 @@ use tokio_util::sync::CancellationToken;
 use tracing::info;
 use serde_json::from_str;
 use uuid::Uuid;
-use crate::agent::{AgentCore, AgentEvent, ClaimManager, InternalAgentEvent, InternalAgentState, PermissionRequest, PermissionResponse};
-use crate::tools::{AnyTool, ToolCall, ToolCapability, ToolResult};
+use crate::agent::{AgentCore, AgentEvent, AuditLog, ClaimManager, Restrictions, InternalAgentEvent, InternalAgentState, PermissionRequest, PermissionResponse, PermissionSource};
+use crate::tools::{attachments_message, AnyTool, ToolCall, ToolCapability, ToolContext, ToolResult, TrustLevel};
+use crate::redact::Redactor;
+use crate::truncate::Truncator;
+use crate::lsp::LspManager;
 use tracing::debug;
 
+/// Time given to a cancelled tool to stop and report what it produced so far
+pub(crate) const CANCEL_GRACE: Duration = Duration::from_millis(500);
+
+async fn cancelled_with_grace(cancel_token: &CancellationToken) {
+    cancel_token.cancelled().await;
+    tokio::time::sleep(CANCEL_GRACE).await;
+}
+
+/// Streamed output is sent on the event bus at most this often, so that a chatty command cannot overflow it
+const OUTPUT_FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Output kept between two flushes, older output is skipped (the tool result still has it)
+const OUTPUT_PENDING_MAX: usize = 16 * 1024;
+
+/// Output of a running tool waiting to be sent as a single ToolOutputChunk
+struct PendingOutput {
+    tool_call_id: String,
+    tx: broadcast::Sender<AgentEvent>,
+    pending: std::sync::Mutex<String>,
+}
+
+impl PendingOutput {
+    fn push(&self, chunk: String) {
+        let mut pending = self.pending.lock().unwrap();
+        pending.push_str(&chunk);
+        if pending.len() > OUTPUT_PENDING_MAX {
+            let mut cut = pending.len() - OUTPUT_PENDING_MAX;
+            while !pending.is_char_boundary(cut) {
+                cut += 1;
+            }
+            pending.drain(..cut);
+        }
+    }
+
+    fn flush(&self) {
+        let chunk = std::mem::take(&mut *self.pending.lock().unwrap());
+        if !chunk.is_empty() {
+            let _ = self.tx.send(AgentEvent::ToolOutputChunk {
+                tool_call_id: self.tool_call_id.clone(),
+                chunk
+            });
+        }
+    }
+}
+
 impl AgentCore {
 
     /// Spawn a cancellable coroutine that runs all tool call in parrallel and waits for them to finish
@@ -25,6 +75,13 @@ impl AgentCore {
         let available_tools = self.available_tools.clone();
         let claims = self.permissions.clone();
         let trace = self.trace.clone();
+        let session_id = self.session_id.clone();
+        let audit = self.audit.clone();
+        let redactor = self.redactor.clone();
+        let truncator = self.truncator.clone();
+        let lsp = self.lsp.clone();
+        let tainted = self.tainted.clone();
+        let restrictions = self.restrictions;
 
         // Spawn a task to wait for all tool executions
         let mut join_handles = Vec::new();
@@ -38,39 +95,52 @@ impl AgentCore {
                 available_tools.clone(),
                 claims.clone(),
                 internal_tx.clone(),
-                trace.clone(),
+                session_id.clone(),
+                audit.clone(),
+                redactor.clone(),
+                truncator.clone(),
+                lsp.clone(),
+                tainted.clone(),
+                restrictions,
             );
             join_handles.push(handle);
         }
             
-        // Wait for all tools to complete or be cancelled
-        tokio::spawn(async move {
-            tokio::select! {
-                _ = cancel_clone.cancelled() => {
-                    // Tools were cancelled, no need to send completion event
-                }
-                _ = async {
-                    // wait for all tools completion
-                    for handle in join_handles {
-                        let _ = handle.await;
-                    }
-                } => {
-                    // All tools completed, move to Running state
-                    let _ = internal_tx.send(InternalAgentEvent::ToolsCompleted);
+        // Wait for all tools to complete, cancelled tools stop within the grace period
+        let task = tokio::spawn(async move {
+            let mut messages = Vec::new();
+            let mut attachments = Vec::new();
+            for handle in join_handles {
+                if let Ok(Some((message, attachment))) = handle.await {
+                    messages.push(message);
+                    attachments.extend(attachment);
                 }
             }
+            // tool messages must directly follow the tool calls, attachments come after all of them
+            let mut trace = trace.write().await;
+            trace.extend(messages);
+            trace.extend(attachments);
+            drop(trace);
+
+            // Tools were cancelled, no need to send completion event
+            if !cancel_clone.is_cancelled() {
+                // All tools completed, move to Running state
+                let _ = internal_tx.send(InternalAgentEvent::ToolsCompleted);
+            }
         });
         
         // Set state to Processing with cancellation token
         self.set_state(InternalAgentState::Processing { 
             task_name: "tools".to_string(), 
             tools_exec_at: Utc::now(), 
-            cancellation_token
+            cancellation_token,
+            task: Some(task)
         }).await;
     }
 
     /// Spawn a cancellable coroutine that runs a single tool call
     /// coordinating the appropriate tool specific event (start/completed)
+    /// returns the tool message and the attachments to add to the trace
     fn spawn_tool_static(
         tc: LlmToolCall,
         cancel_token: CancellationToken,
@@ -78,8 +148,14 @@ impl AgentCore {
         available_tools: Vec<Arc<dyn AnyTool>>,
         claims: Arc<RwLock<ClaimManager>>,
         internal_tx: broadcast::Sender<InternalAgentEvent>,
-        trace: Arc<RwLock<Vec<ChatMessage>>>,
-    ) -> tokio::task::JoinHandle<()> {
+        session_id: String,
+        audit: Option<Arc<AuditLog>>,
+        redactor: Option<Arc<Redactor>>,
+        truncator: Option<Arc<Truncator>>,
+        lsp: Option<Arc<LspManager>>,
+        tainted: Arc<AtomicBool>,
+        restrictions: Restrictions,
+    ) -> tokio::task::JoinHandle<Option<(ChatMessage, Option<ChatMessage>)>> {
         tokio::spawn(async move {
             let tc_for_error = tc.clone();
             match Self::tool_exist(available_tools, tc) {
@@ -96,6 +172,7 @@ impl AgentCore {
                             result: tool_result 
                         });
                     }
+                    None
                 }
 
                 // emit tool call
@@ -103,6 +180,7 @@ impl AgentCore {
                 // emit tool result
                 Ok((tool, call)) => {
                     let start = Utc::now();
+                    let writes = tool.capabilities().contains(&ToolCapability::Write);
 
                     // Emit tool call started event
                     if let Some(tx) = public_event_tx.clone() {
@@ -112,16 +190,26 @@ impl AgentCore {
                         });
                     }
                     
+                    // problems already in the files before the change are not reported as introduced by it
+                    if let Some(lsp) = lsp.as_ref().filter(|_| writes) {
+                        lsp.take_baseline(&call.parameters).await;
+                    }
+
                     // execute tool
                     let tool_handle = Self::spawn_tool_exec(
                         tool, call.clone(), 
                         cancel_token.clone(), 
                         claims, 
                         public_event_tx.clone(), 
-                        internal_tx.subscribe());
+                        internal_tx.subscribe(),
+                        session_id.clone(),
+                        audit,
+                        tainted.clone(),
+                        restrictions);
 
                     // wait for result (or for cancellation)
                     let result: ToolResult = tokio::select! {
+                        biased;
                         join_result = tool_handle => {
                             match join_result {
                                 Ok(tool_result) => tool_result,
@@ -131,18 +219,54 @@ impl AgentCore {
                                 }
                             }
                          },
-                        _ = cancel_token.cancelled() => {
+                        _ = cancelled_with_grace(&cancel_token) => {
                             debug!(target: "agent::tool_completed", "cancelled by user");
                             ToolResult::error("tool call was cancelled by the user".to_string())
                         }
                     };
 
-                    // let's first add tool result to trace
-                    let _ = {
-                        trace.write().await.push(ChatMessage::Tool { 
-                            tool_call_id: call.tool_call_id.clone(),
-                            content: result.to_string()
-                        });
+                    // errors and warnings the change introduced, while the model still has the edit in mind
+                    let result = match &lsp {
+                        Some(lsp) if writes && !cancel_token.is_cancelled() => lsp.annotate_result(result).await,
+                        _ => result
+                    };
+
+                    // mask secrets before the result reaches the trace (and the llm)
+                    let result = match &redactor {
+                        Some(redactor) => {
+                            let (result, kinds) = redactor.redact_result(result);
+                            if !kinds.is_empty() {
+                                info!(target: "agent::redact", call = ?call.tool_name, kinds = ?kinds);
+                                if let Some(tx) = public_event_tx.clone() {
+                                    let _ = tx.send(AgentEvent::SecretsRedacted { 
+                                        call: call.clone(), 
+                                        kinds 
+                                    });
+                                }
+                            }
+                            result
+                        }
+                        None => result
+                    };
+
+                    // oversized results keep their head and tail, the rest is spilled to a scratch file
+                    let result = match &truncator {
+                        Some(truncator) => truncator.truncate_result(&session_id, &call.tool_name, &call.tool_call_id, result),
+                        None => result
+                    };
+
+                    // from now on, side effects need the user's approval
+                    if result.trust() == TrustLevel::Untrusted {
+                        tainted.store(true, Ordering::SeqCst);
+                    }
+
+                    // images and other attachments cannot go in a tool message, they are returned to follow the tool messages
+                    let (result, attachments) = result.take_attachments();
+                    let attachments = attachments_message(&call.tool_name, &attachments);
+
+                    let message = ChatMessage::Tool { 
+                        tool_call_id: call.tool_call_id.clone(),
+                        content: result.to_trace_content(&call.tool_name)
                     };
 
                     // Emit tool call finish event
@@ -154,6 +278,7 @@ impl AgentCore {
                             result 
                         });   
                     }
+                    Some((message, attachments))
                 }
             }
         })
@@ -167,27 +292,95 @@ impl AgentCore {
         cancel_token: CancellationToken,
         claims: Arc<RwLock<ClaimManager>>, 
         public_event_tx: Option<broadcast::Sender<AgentEvent>>, 
-        mut internal_rx: broadcast::Receiver<InternalAgentEvent>) -> JoinHandle<ToolResult> {
+        mut internal_rx: broadcast::Receiver<InternalAgentEvent>,
+        session_id: String,
+        audit: Option<Arc<AuditLog>>,
+        tainted: Arc<AtomicBool>,
+        restrictions: Restrictions) -> JoinHandle<ToolResult> {
         tokio::spawn(async move {
+            let side_effects = tool.capabilities().iter()
+                .any(|c| matches!(c, ToolCapability::Write | ToolCapability::Network));
+
+            // only side-effecting tools are audited
+            let audit = audit.filter(|_| side_effects);
+
+            // once untrusted content entered the context, rules and sudo no longer grant side effects
+            let untrusted = side_effects && tainted.load(Ordering::SeqCst);
+
+            // read-only / offline modes cannot be bypassed, not even by sudo
+            if let Some(capability) = restrictions.violation(tool.capabilities()) {
+                if let Some(audit) = &audit {
+                    audit.record_decision(&session_id, &call, None, PermissionResponse::Deny, PermissionSource::Restriction);
+                }
+                return ToolResult::error(format!("this tool is disabled in {} mode", Restrictions::mode_name(capability)));
+            }
+
+            // explicit denials win over everything, including read-only tools and sudo
+            if claims.read().await.is_denied(tool.name(), &call.parameters) {
+                if let Some(audit) = &audit {
+                    audit.record_decision(&session_id, &call, None, PermissionResponse::Deny, PermissionSource::Rule);
+                }
+                return ToolResult::error("permission to execute this tool was denied by a rule".to_string());
+            }
+
             // check permission, we allow all Read Tool
-            let can_run = tool.capabilities().is_empty()  
-            || tool.capabilities() == &[ToolCapability::Read]
-            || claims.read().await.is_permitted(tool.name(), &call.parameters);
+            let granted_by = if tool.capabilities().is_empty() || tool.capabilities() == &[ToolCapability::Read] {
+                Some(PermissionSource::ReadOnly)
+            } else if untrusted {
+                None
+            } else {
+                claims.read().await.permission_source(tool.name(), &call.parameters)
+            };
+            if let (Some(source), Some(audit)) = (granted_by, &audit) {
+                audit.record_decision(&session_id, &call, None, PermissionResponse::Allow, source);
+            }
 
             // request permission if needed (|| is short-circuiting, so won't call if can_run is true)
-            let can_run = can_run || match Self::request_permission_if_needed(&call, &tool, &public_event_tx, &mut internal_rx, &cancel_token).await {
+            let can_run = granted_by.is_some() || match Self::request_permission_if_needed(&call, &tool, &public_event_tx, &mut internal_rx, &cancel_token, &session_id, &audit, untrusted).await {
                 Ok(permission_granted) => permission_granted,
                 Err(preview_error) => return preview_error, // Return preview error immediately
             };
 
             if can_run {
+                // the tool can observe the cancellation and stream its output while it runs
+                let mut context = ToolContext::new(cancel_token.clone());
+                let mut flusher = None;
+                let output = public_event_tx.clone().map(|tx| Arc::new(PendingOutput {
+                    tool_call_id: call.tool_call_id.clone(),
+                    tx,
+                    pending: Default::default(),
+                }));
+                if let Some(output) = output.clone() {
+                    let sink = output.clone();
+                    context = context.with_output(move |chunk| sink.push(chunk));
+                    flusher = Some(tokio::spawn(async move {
+                        let mut interval = tokio::time::interval(OUTPUT_FLUSH_INTERVAL);
+                        loop {
+                            interval.tick().await;
+                            output.flush();
+                        }
+                    }));
+                }
+
                 // Execute tool with cancellation support
-                tokio::select! {
-                    result = tool.execute_json(call.parameters.clone()) => result,
-                    _ = cancel_token.cancelled() => {
+                let start = Utc::now();
+                let result = tokio::select! {
+                    biased;
+                    result = context.scope(tool.execute_json(call.parameters.clone())) => result,
+                    _ = cancelled_with_grace(&cancel_token) => {
                         ToolResult::error("tool call was cancelled by the user".to_string())
                     }
+                };
+                if let Some(flusher) = flusher {
+                    flusher.abort();
+                }
+                if let Some(output) = &output {
+                    output.flush();
                 }
+                if let Some(audit) = &audit {
+                    audit.record_execution(&session_id, &call, &result, (Utc::now() - start).num_milliseconds());
+                }
+                result
             } else {
                 ToolResult::error("permission to execute this tool was denied by the user".to_string())
             }
@@ -202,9 +395,19 @@ impl AgentCore {
         public_event_tx: &Option<broadcast::Sender<AgentEvent>>,
         internal_rx: &mut broadcast::Receiver<InternalAgentEvent>,
         cancel_token: &CancellationToken,
+        session_id: &str,
+        audit: &Option<Arc<AuditLog>>,
+        untrusted: bool,
     ) -> Result<bool, ToolResult> {
+        let record_decision = |request_id: Option<String>, response: PermissionResponse, source: PermissionSource| {
+            if let Some(audit) = audit {
+                audit.record_decision(session_id, call, request_id, response, source);
+            }
+        };
+
         // Session is not interactive so we cannot ask for permission
         let Some(tx) = public_event_tx.as_ref() else {
+            record_decision(None, PermissionResponse::NoPermissionSystem, PermissionSource::NoUser);
             return Ok(false); 
         };
         
@@ -220,6 +423,9 @@ impl AgentCore {
         
         // Send permission request
         let req_id = Uuid::new_v4().to_string();
+        if let Some(audit) = audit {
+            audit.record_request(session_id, call, &req_id);
+        }
         let _ = tx.send(AgentEvent::PermissionRequired {
             request_id: req_id.clone(),
             request: PermissionRequest {
@@ -227,6 +433,7 @@ impl AgentCore {
                 operation: "do you want to run this tool?".to_string(),
                 call: call.clone(),
                 preview,
+                untrusted,
             }
         });
 
@@ -237,13 +444,19 @@ impl AgentCore {
                     match recv_result {
                         Ok(InternalAgentEvent::PermissionResponseReceived { request_id, response }) 
                             if request_id == req_id => {
-                            return Ok(matches!(response, PermissionResponse::Allow | PermissionResponse::AllowAlways));
+                            let granted = matches!(response, PermissionResponse::Allow | PermissionResponse::AllowAlways);
+                            record_decision(Some(req_id), response, PermissionSource::User);
+                            return Ok(granted);
                         }
                         Ok(_) => continue,
-                        Err(_) => return Ok(false), // Channel closed
+                        Err(_) => {
+                            record_decision(Some(req_id), PermissionResponse::Deny, PermissionSource::NoUser);
+                            return Ok(false); // Channel closed
+                        }
                     }
                 }
                 _ = cancel_token.cancelled() => {
+                    record_decision(Some(req_id), PermissionResponse::Deny, PermissionSource::Cancelled);
                     return Ok(false); // Cancelled during permission wait
                 }
             }
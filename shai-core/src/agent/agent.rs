@@ -1,11 +1,15 @@
 use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 use std::boxed::Box;
 use shai_llm::{ChatMessage, ChatMessageContent, ToolCallMethod};
 use tokio::sync::{mpsc, broadcast, RwLock, oneshot};
 use serde::{Serialize, Deserialize};
 use async_trait::async_trait;
-use crate::tools::AnyTool;
-use crate::agent::ClaimManager;
+use crate::tools::{AnyTool, TodoStorage, UNTRUSTED_CONTENT_TAG};
+use crate::agent::{AuditLog, ClaimManager, Restrictions};
+use crate::redact::Redactor;
+use crate::truncate::Truncator;
+use crate::lsp::LspManager;
 
 // Helper functions to make the main loop more readable
 
@@ -67,6 +71,13 @@ pub struct AgentCore {
     pub trace:           Arc<RwLock<Vec<ChatMessage>>>,
     pub available_tools: Vec<Arc<dyn AnyTool>>,
     pub permissions:     Arc<RwLock<ClaimManager>>,
+    pub audit:           Option<Arc<AuditLog>>,
+    pub redactor:        Option<Arc<Redactor>>,
+    pub truncator:       Option<Arc<Truncator>>,
+    pub tainted:         Arc<AtomicBool>, // untrusted content entered the trace
+    pub restrictions:    Restrictions,
+    pub todos:           Option<Arc<TodoStorage>>, // changes are forwarded as TodoChanged events
+    pub lsp:             Option<Arc<LspManager>>,  // reports the diagnostics of files changed by tools
     pub state:           InternalAgentState,
 
     /// internal event
@@ -88,8 +99,16 @@ impl AgentCore {
         trace: Vec<ChatMessage>,
         available_tools: Vec<Box<dyn AnyTool>>,
         permissions: ClaimManager,
+        audit: Option<Arc<AuditLog>>,
+        redactor: Option<Arc<Redactor>>,
+        truncator: Option<Arc<Truncator>>,
+        restrictions: Restrictions,
+        todos: Option<Arc<TodoStorage>>,
+        lsp: Option<Arc<LspManager>>,
     ) -> Self {
         let (internal_tx, internal_rx) = broadcast::channel(1024);
+        let tainted = trace.iter().any(|m| matches!(m, 
+            ChatMessage::Tool { content, .. } if content.contains(&format!("<{}", UNTRUSTED_CONTENT_TAG))));
         Self {
             session_id: session_id.clone(),
             socket: AgentSocket{
@@ -103,6 +122,13 @@ impl AgentCore {
             trace: Arc::new(RwLock::new(trace)),
             available_tools: available_tools.into_iter().map(|t| Arc::from(t) as Arc<dyn AnyTool>).collect(),
             permissions: Arc::new(RwLock::new(permissions)),
+            audit,
+            redactor,
+            truncator,
+            tainted: Arc::new(AtomicBool::new(tainted)),
+            restrictions,
+            todos,
+            lsp,
             state: InternalAgentState::Starting,
             internal_tx,
             internal_rx,
@@ -199,8 +225,13 @@ impl AgentCore {
         self.assert_socket_created();
         let mut rx =  self.socket.tx_event.as_ref().unwrap().subscribe();
         _ = tokio::spawn(async move {
-            while let Ok(e) = rx.recv().await {
-                handler(e);
+            loop {
+                match rx.recv().await {
+                    Ok(e) => handler(e),
+                    // a slow handler misses events rather than stopping for good
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
             }
         });
         self
@@ -214,8 +245,13 @@ impl AgentCore {
         self.assert_socket_created();
         let mut rx = self.socket.tx_event.as_ref().unwrap().subscribe();
         _ = tokio::spawn(async move {
-            while let Ok(event) = rx.recv().await {
-                handler.handle_event(event).await;
+            loop {
+                match rx.recv().await {
+                    Ok(event) => handler.handle_event(event).await,
+                    // a slow handler misses events rather than stopping for good
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
             }
         });
         self
@@ -252,7 +288,8 @@ impl AgentCore {
                             };
                         }
                     }
-                    Err(_) => {
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => {
                         break AgentResponse::Error { 
                             error: "Event channel closed".to_string() 
                         };
@@ -273,8 +310,30 @@ impl AgentCore {
         }
     }
     
+    /// Emit the todo list as it is (if any), then again after every change
+    async fn forward_todo_changes(&self) {
+        let (Some(todos), Some(tx)) = (self.todos.clone(), self.socket.tx_event.clone()) else {
+            return;
+        };
+        let mut changes = todos.subscribe();
+        let current = todos.get_all().await;
+        if !current.is_empty() {
+            let _ = tx.send(AgentEvent::TodoChanged { todos: current });
+        }
+        tokio::spawn(async move {
+            loop {
+                match changes.recv().await {
+                    Ok(todos) => { let _ = tx.send(AgentEvent::TodoChanged { todos }); }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
     /// Main execution loop with single command receiver
     async fn start(&mut self) -> Result<AgentResult, AgentError> {
+        self.forward_todo_changes().await;
         self.handle_event(InternalAgentEvent::AgentInitialized).await?;
         
         loop {
@@ -284,6 +343,13 @@ impl AgentCore {
             }
 
             // Handle terminal states - exit immediately
+            if matches!(self.state, InternalAgentState::Completed { .. } | InternalAgentState::Failed { .. }) {
+                // release the tools so that what they hold (shell session, background processes) is cleaned up
+                self.available_tools.clear();
+                if let Some(lsp) = self.lsp.take() {
+                    lsp.shutdown().await;
+                }
+            }
             match &self.state {
                 InternalAgentState::Completed { success } => {
                     debug!(target: "agent::terminated", "completed");
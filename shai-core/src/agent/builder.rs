@@ -1,10 +1,16 @@
+use std::sync::Arc;
 use shai_llm::ChatMessage;
 use uuid::Uuid;
 
-use crate::tools::AnyTool;
+use crate::tools::{AnyTool, TodoStorage};
+use crate::redact::Redactor;
+use crate::truncate::Truncator;
+use crate::lsp::LspManager;
 use super::Brain;
 use super::AgentCore;
 use super::claims::ClaimManager;
+use super::audit::AuditLog;
+use super::restrictions::Restrictions;
 
 /// Builder for AgentCore
 pub struct AgentBuilder {
@@ -14,6 +20,12 @@ pub struct AgentBuilder {
     pub trace: Vec<ChatMessage>,
     pub available_tools: Vec<Box<dyn AnyTool>>,
     pub permissions: ClaimManager,
+    pub audit: Option<Arc<AuditLog>>,
+    pub redactor: Option<Arc<Redactor>>,
+    pub truncator: Option<Arc<Truncator>>,
+    pub restrictions: Restrictions,
+    pub todos: Option<Arc<TodoStorage>>,
+    pub lsp: Option<Arc<LspManager>>,
 }
 
 impl AgentBuilder {
@@ -25,6 +37,12 @@ impl AgentBuilder {
             trace: vec![],
             available_tools: vec![],
             permissions: ClaimManager::new(),
+            audit: None,
+            redactor: None,
+            truncator: None,
+            restrictions: Restrictions::default(),
+            todos: None,
+            lsp: None,
         }
     }
 }
@@ -60,6 +78,48 @@ impl AgentBuilder {
         self
     }
 
+    /// Record permission decisions and side-effecting tool calls in an audit log
+    pub fn audit(mut self, audit: AuditLog) -> Self {
+        self.audit = Some(Arc::new(audit));
+        self
+    }
+
+    /// Use the audit log at its default location (~/.shai/audit.jsonl), if there is a home directory
+    pub fn default_audit(mut self) -> Self {
+        self.audit = AuditLog::open_default().map(Arc::new);
+        self
+    }
+
+    /// Mask secrets in tool results before they enter the trace
+    pub fn redactor(mut self, redactor: Redactor) -> Self {
+        self.redactor = Some(Arc::new(redactor));
+        self
+    }
+
+    /// Keep oversized tool results within a budget, the full output is spilled to a scratch file
+    pub fn truncator(mut self, truncator: Truncator) -> Self {
+        self.truncator = Some(Arc::new(truncator));
+        self
+    }
+
+    /// Forbid tool capabilities (read-only / offline), the toolbox is filtered at build time
+    pub fn restrictions(mut self, restrictions: Restrictions) -> Self {
+        self.restrictions = restrictions;
+        self
+    }
+
+    /// Todo list shared with the todo tools, its changes are emitted as TodoChanged events
+    pub fn todos(mut self, todos: Arc<TodoStorage>) -> Self {
+        self.todos = Some(todos);
+        self
+    }
+
+    /// Language servers checking the files changed by tools, their new diagnostics are appended to the results
+    pub fn lsp(mut self, lsp: Arc<LspManager>) -> Self {
+        self.lsp = Some(lsp);
+        self
+    }
+
     /// Enable sudo mode - bypasses all permission checks
     pub fn sudo(mut self) -> Self {
         self.permissions.sudo();
@@ -76,8 +136,14 @@ impl AgentBuilder {
             self.session_id.clone(),
             self.brain,
             self.trace,
-            self.available_tools,
-            self.permissions
+            self.restrictions.filter_tools(self.available_tools),
+            self.permissions,
+            self.audit,
+            self.redactor,
+            self.truncator,
+            self.restrictions,
+            self.todos,
+            self.lsp
         )
     }
 }
@@ -121,10 +121,31 @@ impl Permission {
     }
 }
 
+/// Where a permission decision came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionSource {
+    /// Read-only tools never need a permission
+    ReadOnly,
+    /// A permission or denial rule matched the call
+    Rule,
+    /// Sudo mode was enabled
+    Sudo,
+    /// A read-only or offline mode forbids the tool
+    Restriction,
+    /// The user answered a permission request
+    User,
+    /// Nobody could be asked, the call was refused
+    NoUser,
+    /// The task was cancelled while waiting for the user
+    Cancelled,
+}
+
 /// Permission Manager for storing and retrieving permissions
 #[derive(Debug, Clone)]
 pub struct ClaimManager {
     permissions: Vec<Permission>,
+    denials: Vec<Permission>,
     config_file: Option<PathBuf>,
     sudo_mode: bool,
 }
@@ -135,6 +156,7 @@ impl ClaimManager {
     pub fn new() -> Self {
         Self {
             permissions: Vec::new(),
+            denials: Vec::new(),
             config_file: None,
             sudo_mode: false,
         }
@@ -144,6 +166,7 @@ impl ClaimManager {
     pub fn with_config_file(path: PathBuf) -> Self {
         Self {
             permissions: Vec::new(),
+            denials: Vec::new(),
             config_file: Some(path),
             sudo_mode: false,
         }
@@ -153,6 +176,7 @@ impl ClaimManager {
     pub fn with_sudo() -> Self {
         Self {
             permissions: Vec::new(),
+            denials: Vec::new(),
             config_file: None,
             sudo_mode: true,
         }
@@ -162,6 +186,7 @@ impl ClaimManager {
     pub fn with_config_file_and_sudo(path: PathBuf) -> Self {
         Self {
             permissions: Vec::new(),
+            denials: Vec::new(),
             config_file: Some(path),
             sudo_mode: true,
         }
@@ -187,8 +212,23 @@ impl ClaimManager {
         self.permissions.push(permission);
     }
     
+    /// Add a denial, denials take precedence over permissions and sudo mode
+    pub fn add_denial(&mut self, denial: Permission) {
+        self.denials.push(denial);
+    }
+
+    /// Check if a tool call is explicitly denied
+    pub fn is_denied(&self, tool_name: &str, parameters: &serde_json::Value) -> bool {
+        self.denials.iter()
+            .any(|deny| deny.matches(tool_name, parameters))
+    }
+
     /// Check if a tool call is permitted
     pub fn is_permitted(&self, tool_name: &str, parameters: &serde_json::Value) -> bool {
+        if self.is_denied(tool_name, parameters) {
+            return false;
+        }
+
         // Sudo mode bypasses all permission checks
         if self.sudo_mode {
             return true;
@@ -198,6 +238,21 @@ impl ClaimManager {
             .any(|perm| perm.matches(tool_name, parameters))
     }
     
+    /// Tell why a tool call is permitted without asking the user, None if nothing grants it
+    pub fn permission_source(&self, tool_name: &str, parameters: &serde_json::Value) -> Option<PermissionSource> {
+        if self.is_denied(tool_name, parameters) {
+            return None;
+        }
+
+        if self.permissions.iter().any(|perm| perm.matches(tool_name, parameters)) {
+            Some(PermissionSource::Rule)
+        } else if self.sudo_mode {
+            Some(PermissionSource::Sudo)
+        } else {
+            None
+        }
+    }
+    
     /// Get all permissions for a specific tool
     pub fn get_permissions_for_tool(&self, tool_name: &str) -> Vec<&Permission> {
         self.permissions.iter()
@@ -225,6 +280,11 @@ impl ClaimManager {
         self.permissions.is_empty()
     }
     
+    /// Get all denials (for debugging/inspection)
+    pub fn get_all_denials(&self) -> &[Permission] {
+        &self.denials
+    }
+    
     /// Get all permissions (for debugging/inspection)
     pub fn get_all_permissions(&self) -> &[Permission] {
         &self.permissions
@@ -504,6 +564,54 @@ mod tests {
         })));
     }
 
+    #[test]
+    fn test_denial_overrides_permission_and_sudo() {
+        let mut manager = ClaimManager::with_sudo();
+        manager.add_permission(Permission::new(
+            "bash".to_string(),
+            MatchStrategy::Glob,
+            serde_json::json!({"command": "^cargo .*$"}),
+            true,
+        ));
+        manager.add_denial(Permission::new(
+            "bash".to_string(),
+            MatchStrategy::Glob,
+            serde_json::json!({"command": "^cargo publish.*$"}),
+            true,
+        ));
+
+        assert!(manager.is_permitted("bash", &serde_json::json!({"command": "cargo test"})));
+        assert!(manager.is_denied("bash", &serde_json::json!({"command": "cargo publish"})));
+        assert!(!manager.is_permitted("bash", &serde_json::json!({"command": "cargo publish"})));
+
+        // a denial without parameters matches every call to the tool
+        manager.add_denial(Permission::new(
+            "fetch".to_string(),
+            MatchStrategy::Glob,
+            serde_json::json!({}),
+            true,
+        ));
+        assert!(!manager.is_permitted("fetch", &serde_json::json!({"url": "https://example.com"})));
+    }
+
+    #[test]
+    fn test_permission_source() {
+        let mut manager = ClaimManager::new();
+        manager.add_permission(Permission::new(
+            "bash".to_string(),
+            MatchStrategy::Partial,
+            serde_json::json!({"command": "ls"}),
+            true,
+        ));
+
+        assert_eq!(manager.permission_source("bash", &serde_json::json!({"command": "ls"})), Some(PermissionSource::Rule));
+        assert_eq!(manager.permission_source("bash", &serde_json::json!({"command": "pwd"})), None);
+
+        manager.sudo();
+        assert_eq!(manager.permission_source("bash", &serde_json::json!({"command": "ls"})), Some(PermissionSource::Rule));
+        assert_eq!(manager.permission_source("bash", &serde_json::json!({"command": "pwd"})), Some(PermissionSource::Sudo));
+    }
+
     #[test]
     fn test_builder_patterns() {
         // Test normal creation
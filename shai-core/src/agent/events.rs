@@ -7,7 +7,7 @@ use async_trait::async_trait;
 use super::brain::ThinkerDecision;
 use super::AgentError;
 use crate::agent::PublicAgentState;
-use crate::tools::{ToolResult, ToolCall};
+use crate::tools::{ToolResult, ToolCall, TodoItem};
 use chrono::{DateTime, TimeDelta, Utc};
 
 /// Internal events for agent state machine communication
@@ -76,6 +76,20 @@ pub enum AgentEvent {
         call: ToolCall,
         result: ToolResult
     },
+    /// A running tool produced some output, the final result is reported by ToolCallCompleted
+    ToolOutputChunk {
+        tool_call_id: String,
+        chunk: String
+    },
+    /// Secrets were masked in a tool result before it was added to the trace
+    SecretsRedacted {
+        call: ToolCall,
+        kinds: Vec<String>
+    },
+    /// The todo list of the agent changed, carries the whole list
+    TodoChanged {
+        todos: Vec<TodoItem>
+    },
     /// User provided input to the agent
     UserInput { 
         input: String,
@@ -135,7 +149,10 @@ pub struct PermissionRequest {
     /// Additional details about the request
     pub call: ToolCall,
     /// Preview of what the tool would do (if available)
-    pub preview: Option<ToolResult>
+    pub preview: Option<ToolResult>,
+    /// Untrusted content entered the context before this call, rules and sudo were not applied
+    #[serde(default)]
+    pub untrusted: bool
 }
 
 /// Response to a permission request
@@ -229,6 +246,23 @@ impl std::fmt::Debug for AgentEvent {
                     .field("result", result)
                     .finish()
             }
+            AgentEvent::ToolOutputChunk { tool_call_id, chunk } => {
+                f.debug_struct("ToolOutputChunk")
+                    .field("tool_call_id", tool_call_id)
+                    .field("chunk", chunk)
+                    .finish()
+            }
+            AgentEvent::SecretsRedacted { call, kinds } => {
+                f.debug_struct("SecretsRedacted")
+                    .field("call", call)
+                    .field("kinds", kinds)
+                    .finish()
+            }
+            AgentEvent::TodoChanged { todos } => {
+                f.debug_struct("TodoChanged")
+                    .field("todos", todos)
+                    .finish()
+            }
             AgentEvent::UserInput { input } => {
                 f.debug_struct("UserInput")
                     .field("input", input)
@@ -1,5 +1,7 @@
+pub mod audit;
 pub mod builder;
 pub mod claims;
+pub mod restrictions;
 pub mod error;
 pub mod brain;
 pub mod agent;
@@ -28,7 +30,9 @@ pub use events::{
 pub use output::StdoutEventManager;
     
 pub use builder::AgentBuilder;
-pub use claims::{ClaimManager, PermissionError};
+pub use claims::{ClaimManager, PermissionError, PermissionSource};
+pub use restrictions::Restrictions;
+pub use audit::{AuditLog, AuditRecord, AuditEvent, AuditFilter, AuditError};
 pub use error::{AgentError, AgentExecutionError};
 pub use brain::{Brain, ThinkerContext, ThinkerDecision, ThinkerFlowControl};
 pub use crate::logging::LoggingConfig;
\ No newline at end of file
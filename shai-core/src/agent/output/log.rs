@@ -38,6 +38,15 @@ impl FileEventLogger {
             AgentEvent::ToolCallCompleted { duration, call, result } => {
                 format!("ToolCallCompleted: {} in {:?} - {:?}", call.tool_name, duration, result)
             }
+            AgentEvent::ToolOutputChunk { tool_call_id, chunk } => {
+                format!("ToolOutputChunk: {} - {} bytes", tool_call_id, chunk.len())
+            }
+            AgentEvent::SecretsRedacted { call, kinds } => {
+                format!("SecretsRedacted: {} - {:?}", call.tool_name, kinds)
+            }
+            AgentEvent::TodoChanged { todos } => {
+                format!("TodoChanged: {} todos", todos.len())
+            }
             AgentEvent::UserInput { input } => {
                 format!("UserInput: {}", input)
             }
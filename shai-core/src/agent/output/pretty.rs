@@ -4,6 +4,7 @@ use termimad::crossterm::style::Color;
 use termimad::{rgb, MadSkin};
 use crate::agent::{AgentError, AgentEvent};
 use crate::tools::{ToolCall, ToolResult};
+use crate::redact;
 
 /// Pretty formatter that formats agent events into strings for display
 pub struct PrettyFormatter {
@@ -33,6 +34,17 @@ impl PrettyFormatter {
             AgentEvent::ToolCallCompleted { call, result, .. } => {
                 Some(self.format_tool_result(call, result))
             },
+            AgentEvent::ToolOutputChunk { .. } => {
+                // Streamed as is by the event handlers, not as a formatted line
+                None
+            },
+            AgentEvent::SecretsRedacted { call, kinds } => {
+                Some(format!("\x1b[2;33m🔒 {} in {} output before sending it to the llm\x1b[0m", redact::notice(kinds), call.tool_name))
+            },
+            AgentEvent::TodoChanged { .. } => {
+                // Shown by the todo tools results, and live by the UIs that track the list
+                None
+            },
             AgentEvent::StatusChanged { .. } => {
                 // Don't format state changes - only show brain results and tool calls
                 None
@@ -231,8 +243,12 @@ impl PrettyFormatter {
             let param_names = match tool_name {
                 "read" | "write" | "edit" | "multiedit" => vec!["file_path", "path"],
                 "ls" | "glob" => vec!["path", "pattern"],
+                "move" | "copy" => vec!["source", "destination"],
                 "find" | "grep" => vec!["pattern", "path"],
                 "bash" => vec!["command"],
+                "symbols" => vec!["name", "path"],
+                "hover" | "goto_definition" => vec!["symbol", "path"],
+                "structural_search" | "structural_replace" => vec!["pattern", "path"],
                 _ => vec!["path", "file_path", "pattern", "command", "query", "input"]
             };
             
@@ -19,6 +19,12 @@ impl StdoutEventManager {
 #[async_trait]
 impl AgentEventHandler for StdoutEventManager {
     async fn handle_event(&self, event: AgentEvent) {
+        if let AgentEvent::ToolOutputChunk { chunk, .. } = &event {
+            eprint!("\x1b[2m{}\x1b[0m", chunk);
+            let _ = io::stderr().flush();
+            return;
+        }
+
         if let Some(formatted) = self.formatter.format_event(&event) {
             eprintln!("{}", formatted);
             let _ = io::stdout().flush();
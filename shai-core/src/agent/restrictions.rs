@@ -0,0 +1,146 @@
+use serde::{Serialize, Deserialize};
+
+use crate::config::config::ShaiConfig;
+use crate::tools::{AnyTool, ToolCapability};
+
+/// Environment variable enabling the read-only mode, inherited by hooks of a shell session
+pub const READ_ONLY_ENV: &str = "SHAI_READ_ONLY";
+/// Environment variable enabling the offline mode, inherited by hooks of a shell session
+pub const OFFLINE_ENV: &str = "SHAI_OFFLINE";
+
+/// Global modes restricting what tools may do, enforced even under sudo
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Restrictions {
+    /// Refuse every tool with the Write capability
+    pub read_only: bool,
+    /// Refuse every tool with the Network capability and only talk to local llm endpoints
+    pub offline: bool,
+}
+
+impl Restrictions {
+    /// Restrictions set in the environment (SHAI_READ_ONLY / SHAI_OFFLINE)
+    pub fn from_env() -> Self {
+        let enabled = |name: &str| std::env::var(name)
+            .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+        Self {
+            read_only: enabled(READ_ONLY_ENV),
+            offline: enabled(OFFLINE_ENV),
+        }
+    }
+
+    /// Restrictions of ~/.shai.config combined with the ones set in the environment
+    pub fn load() -> Self {
+        let config = ShaiConfig::load()
+            .map(|config| config.restrictions)
+            .unwrap_or_default();
+        config.union(Self::from_env())
+    }
+
+    /// A mode enabled on either side stays enabled
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            read_only: self.read_only || other.read_only,
+            offline: self.offline || other.offline,
+        }
+    }
+
+    pub fn is_restricted(&self) -> bool {
+        self.read_only || self.offline
+    }
+
+    /// The first capability forbidden by the active modes, None if the tool is allowed
+    pub fn violation(&self, capabilities: &[ToolCapability]) -> Option<ToolCapability> {
+        capabilities.iter().copied().find(|capability| match capability {
+            ToolCapability::Write => self.read_only,
+            ToolCapability::Network => self.offline,
+            ToolCapability::Read => false,
+        })
+    }
+
+    /// Remove from the toolbox every tool the active modes forbid
+    pub fn filter_tools(&self, tools: Vec<Box<dyn AnyTool>>) -> Vec<Box<dyn AnyTool>> {
+        tools.into_iter()
+            .filter(|tool| self.violation(tool.capabilities()).is_none())
+            .collect()
+    }
+
+    /// Name of the mode forbidding a capability, used in error messages
+    pub fn mode_name(capability: ToolCapability) -> &'static str {
+        match capability {
+            ToolCapability::Write => "read-only",
+            ToolCapability::Network => "offline",
+            ToolCapability::Read => "",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::{BashTool, FetchTool, LsTool, ReadTool, WriteTool, FsOperationLog};
+    use crate::config::config::ProviderConfig;
+    use shai_llm::ToolCallMethod;
+    use std::path::Path;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    fn toolbox(dir: &Path) -> Vec<Box<dyn AnyTool>> {
+        let log = Arc::new(FsOperationLog::with_trash_dir(dir.join(".trash")));
+        vec![
+            Box::new(BashTool::new()),
+            Box::new(FetchTool::new()),
+            Box::new(LsTool::new()),
+            Box::new(ReadTool::new(log.clone())),
+            Box::new(WriteTool::new(log)),
+        ]
+    }
+
+    fn names(tools: &[Box<dyn AnyTool>]) -> Vec<String> {
+        tools.iter().map(|t| t.name().to_string()).collect()
+    }
+
+    #[test]
+    fn test_violation() {
+        let read_only = Restrictions { read_only: true, offline: false };
+        assert_eq!(read_only.violation(&[ToolCapability::Read, ToolCapability::Write]), Some(ToolCapability::Write));
+        assert_eq!(read_only.violation(&[ToolCapability::Network]), None);
+
+        let offline = Restrictions { read_only: false, offline: true };
+        assert_eq!(offline.violation(&[ToolCapability::Network]), Some(ToolCapability::Network));
+        assert_eq!(offline.violation(&[ToolCapability::Read]), None);
+
+        assert!(!Restrictions::default().is_restricted());
+    }
+
+    #[test]
+    fn test_local_providers() {
+        let provider = |name: &str, env: &[(&str, &str)]| ProviderConfig {
+            provider: name.to_string(),
+            env_vars: env.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            model: "model".to_string(),
+            tool_method: ToolCallMethod::FunctionCall,
+            vision: Default::default(),
+        };
+
+        assert!(provider("ollama", &[]).is_local());
+        assert!(provider("ollama", &[("OLLAMA_BASE_URL", "http://192.168.1.20:11434/v1")]).is_local());
+        assert!(provider("openai_compatible", &[("OPENAI_COMPATIBLE_BASE_URL", "http://[::1]:8000/v1")]).is_local());
+        assert!(!provider("openai_compatible", &[("OPENAI_COMPATIBLE_BASE_URL", "https://api.example.com/v1")]).is_local());
+        assert!(!provider("openai", &[("OPENAI_API_KEY", "key")]).is_local());
+    }
+
+    #[test]
+    fn test_filter_tools() {
+        let dir = tempdir().unwrap();
+        let read_only = Restrictions { read_only: true, offline: false };
+        assert_eq!(names(&read_only.filter_tools(toolbox(dir.path()))), vec!["fetch", "ls", "read"]);
+
+        let offline = Restrictions { read_only: false, offline: true };
+        assert_eq!(names(&offline.filter_tools(toolbox(dir.path()))), vec!["ls", "read", "write"]);
+
+        let both = read_only.union(offline);
+        assert_eq!(names(&both.filter_tools(toolbox(dir.path()))), vec!["ls", "read"]);
+    }
+}
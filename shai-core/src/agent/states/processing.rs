@@ -1,7 +1,9 @@
 use crate::agent::{
     AgentCore, AgentError, InternalAgentEvent
 };
+use crate::agent::actions::tools::CANCEL_GRACE;
 use super::InternalAgentState;
+use tracing::debug;
 
 impl AgentCore {
     pub async fn state_processing_handle_event(&mut self, event: InternalAgentEvent) -> Result<(), AgentError> {
@@ -24,11 +26,20 @@ impl AgentCore {
 
     /// cancel all pending tasks
     async fn cancel_task(&mut self) -> Result<(), AgentError> {
-        let InternalAgentState::Processing { cancellation_token, .. } = &self.state else {
+        let InternalAgentState::Processing { cancellation_token, task, .. } = &mut self.state else {
             return Err(AgentError::InvalidState(format!("state Processing expected but current state is : {:?}", self.state.to_public())));
         };
 
         cancellation_token.cancel();
+
+        // cancelled tools report what they produced within the grace period, it must reach the trace
+        // before whatever follows the cancel (e.g. the user input), later results are dropped
+        if let Some(mut task) = task.take() {
+            if tokio::time::timeout(CANCEL_GRACE * 2, &mut task).await.is_err() {
+                debug!(target: "agent::cancel", "tools did not stop in time, their results are dropped");
+                task.abort();
+            }
+        }
         Ok(())
     }
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
+use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
 use chrono::{DateTime, Utc};
 
@@ -13,6 +14,8 @@ pub enum InternalAgentState {
         task_name: String,
         tools_exec_at: DateTime<Utc>,
         cancellation_token: CancellationToken,
+        /// waited for on cancel, so that nothing it produces reaches the trace afterwards
+        task: Option<JoinHandle<()>>,
     },
     /// Agent execution is paused
     Paused,
@@ -1,5 +1,5 @@
 use crate::agent::Agent;
-use crate::tools::{AnyTool, ToolResult, ReadTool, LsTool};
+use crate::tools::{AnyTool, ToolResult, ToolEmptyParams, ReadTool, LsTool};
 use crate::tools::tool;
 use super::brain::{ThinkerContext, Brain};
 use super::error::AgentError;
@@ -13,6 +13,7 @@ use schemars::JsonSchema;
 use std::time::Duration;
 use std::sync::{Arc, Once};
 use tokio::sync::Mutex;
+use tempfile::tempdir;
 
 static INIT_LOGGING: Once = Once::new();
 
@@ -329,6 +330,37 @@ async fn test_event_handling() {
     assert!(has_running_status, "Should have seen transition to Running status in events: {:?}", *events);
 }
 
+#[tokio::test]
+async fn test_cancelled_tool_result_precedes_user_input() {
+    init_test_logging();
+
+    let sleeping_tool: Box<dyn AnyTool> = Box::new(SleepingTool::new(5000));
+    let mut agent = AgentBuilder::new(Box::new(SleepingThinker::new()))
+            .id("test-cancel-order-agent")
+            .goal("Test goal to start running")
+            .tools(vec![sleeping_tool])
+            .sudo()
+            .build();
+
+    let mut controller = agent.controller();
+    let handle = tokio::spawn(async move {
+        agent.run().await
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    // the user input is sent right away, while the tool is still in its grace period
+    controller.send(AgentRequest::StopCurrentTask).await.expect("Failed to stop current task");
+    controller.send(AgentRequest::SendUserInput { input: "hello".to_string() }).await.expect("Failed to resume");
+    controller.drop().await.expect("failed to drop the controller");
+
+    let trace = handle.await.unwrap().expect("Agent should complete successfully").trace;
+    let tool = trace.iter().position(|message| matches!(message, ChatMessage::Tool { .. }))
+        .expect("the cancelled tool call should have a result");
+    let user = trace.iter().rposition(|message| matches!(message, ChatMessage::User { .. }))
+        .expect("the user input should be in the trace");
+    assert!(tool < user, "tool result landed after the user input: {:?}", trace);
+}
+
 // Test thinker that uses real tools from the toolkit
 struct RealToolsThinker {
     step: u32,
@@ -406,7 +438,8 @@ async fn test_agent_with_real_tools() {
     init_test_logging();
     
     // Create tools from the actual toolkit
-    let fs_log = Arc::new(crate::tools::FsOperationLog::new());
+    let dir = tempdir().unwrap();
+    let fs_log = Arc::new(crate::tools::FsOperationLog::with_trash_dir(dir.path().join(".trash")));
     let read_tool: Box<dyn AnyTool> = Box::new(ReadTool::new(fs_log));
     let ls_tool: Box<dyn AnyTool> = Box::new(LsTool::new());
     let tools = vec![read_tool, ls_tool];
@@ -461,3 +494,81 @@ async fn test_agent_with_real_tools() {
         }
     }
 }
+
+// Test tool that streams many small chunks of output
+struct ChattyTool;
+
+#[tool(name = "chatty_tool", description = "A tool that streams a lot of output")]
+impl ChattyTool {
+    async fn execute(&self, params: ToolEmptyParams) -> ToolResult {
+        let context = crate::tools::ToolContext::current();
+        for i in 0..5000 {
+            context.emit(format!("line {}\n", i));
+        }
+        ToolResult::success("Finished talking".to_string())
+    }
+}
+
+// Test thinker that calls the chatty tool once then completes
+struct ChattyThinker {
+    called_tool: bool,
+}
+
+#[async_trait]
+impl Brain for ChattyThinker {
+    async fn next_step(&mut self, _: ThinkerContext) -> Result<ThinkerDecision, AgentError> {
+        if self.called_tool {
+            return Ok(ThinkerDecision::agent_pause(ChatMessage::Assistant {
+                content: Some(ChatMessageContent::Text("Done".to_string())),
+                reasoning_content: None,
+                tool_calls: None,
+                name: None,
+                audio: None,
+                refusal: None,
+            }));
+        }
+        self.called_tool = true;
+        Ok(ThinkerDecision::agent_continue(ChatMessage::Assistant {
+            content: None,
+            reasoning_content: None,
+            tool_calls: Some(vec![shai_llm::ToolCall {
+                id: "call_chatty".to_string(),
+                r#type: "function".to_string(),
+                function: shai_llm::Function {
+                    name: "chatty_tool".to_string(),
+                    arguments: "{}".to_string(),
+                },
+            }]),
+            name: None,
+            audio: None,
+            refusal: None,
+        }))
+    }
+}
+
+#[tokio::test]
+async fn test_tool_output_is_coalesced() {
+    init_test_logging();
+
+    let tools: Vec<Box<dyn AnyTool>> = vec![Box::new(ChattyTool)];
+    let mut agent = AgentBuilder::new(Box::new(ChattyThinker { called_tool: false }))
+        .id("test-chatty-agent")
+        .goal("Test goal to stream output")
+        .tools(tools)
+        .sudo()
+        .build();
+    let mut events = agent.watch();
+
+    agent.run().await.expect("agent should complete");
+
+    let mut chunks = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        if let super::AgentEvent::ToolOutputChunk { chunk, .. } = event {
+            chunks.push(chunk);
+        }
+    }
+    assert!(!chunks.is_empty(), "output should be streamed");
+    assert!(chunks.len() < 100, "5000 chunks should be coalesced, got {}", chunks.len());
+    assert!(chunks.iter().all(|chunk| chunk.len() <= 16 * 1024));
+    assert!(chunks.last().unwrap().ends_with("line 4999\n"));
+}
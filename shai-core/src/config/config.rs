@@ -1,21 +1,64 @@
 use std::{collections::HashMap, path::PathBuf};
 use std::fs;
+use std::net::IpAddr;
 use reqwest::Url;
 use serde::{Serialize, Deserialize};
 use shai_llm::{LlmClient, ToolCallMethod};
+use crate::redact::RedactionConfig;
+use crate::truncate::TruncationConfig;
+use crate::lsp::LspConfig;
+use crate::tools::SearchConfig;
+use crate::agent::Restrictions;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProviderConfig {
     pub provider: String,
     pub env_vars: std::collections::HashMap<String, String>,
     pub model: String,
-    pub tool_method: ToolCallMethod
+    pub tool_method: ToolCallMethod,
+    /// Whether each model accepts images, for the ones whose name does not tell
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub vision: HashMap<String, bool>,
+}
+
+impl ProviderConfig {
+    /// Whether the provider is served from this machine or the local network (e.g. ollama)
+    pub fn is_local(&self) -> bool {
+        let base_url = match self.provider.as_str() {
+            "ollama" => self.env_vars.get("OLLAMA_BASE_URL").cloned()
+                .unwrap_or_else(|| "http://localhost:11434/v1".to_string()),
+            "openai_compatible" => match self.env_vars.get("OPENAI_COMPATIBLE_BASE_URL") {
+                Some(url) => url.clone(),
+                None => return false,
+            },
+            _ => return false,
+        };
+
+        let Some(host) = Url::parse(&base_url).ok().and_then(|url| url.host_str().map(str::to_string)) else {
+            return false;
+        };
+        match host.trim_matches(|c| c == '[' || c == ']').parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) => ip.is_loopback() || ip.is_private(),
+            Ok(IpAddr::V6(ip)) => ip.is_loopback(),
+            Err(_) => host == "localhost" || host.ends_with(".localhost"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShaiConfig {
     pub providers: Vec<ProviderConfig>,
     pub selected_provider: usize,
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+    #[serde(default)]
+    pub restrictions: Restrictions,
+    #[serde(default)]
+    pub truncation: TruncationConfig,
+    #[serde(default)]
+    pub search: SearchConfig,
+    #[serde(default)]
+    pub lsp: LspConfig,
 }
 
 impl ShaiConfig {
@@ -31,7 +74,8 @@ impl ShaiConfig {
             provider,
             env_vars,
             model,
-            tool_method: ToolCallMethod::FunctionCall
+            tool_method: ToolCallMethod::FunctionCall,
+            vision: HashMap::new(),
         };
         
         self.providers.push(provider_config);
@@ -168,25 +212,37 @@ impl Default for ShaiConfig {
                     (String::from("OVH_BASE_URL"), String::from("https://qwen-3-32b.endpoints.kepler.ai.cloud.ovh.net/api/openai_compat/v1"))
                 ]),
                 model: "Qwen3-32B".to_string(),
-                tool_method: ToolCallMethod::FunctionCall
+                tool_method: ToolCallMethod::FunctionCall,
+                vision: HashMap::new(),
             }],
             selected_provider: 0,
+            redaction: RedactionConfig::default(),
+            restrictions: Restrictions::default(),
+            truncation: TruncationConfig::default(),
+            search: SearchConfig::default(),
+            lsp: LspConfig::default(),
         }
     }
 }
 
 impl ShaiConfig {
-    pub async fn get_llm() -> Result<(LlmClient, String), Box<dyn std::error::Error>>{
+    /// Client of the selected provider, offline mode only allows a local one
+    pub async fn get_llm(restrictions: Restrictions) -> Result<(LlmClient, String), Box<dyn std::error::Error>>{
         let config = ShaiConfig::load()
             .unwrap_or_else(|_| ShaiConfig::default());
 
         config.set_env_vars();
         
         let llm = if let Some(provider_config) = config.get_selected_provider() {
+            if restrictions.offline && !provider_config.is_local() {
+                return Err(format!("offline mode only allows local providers (ollama or a local openai_compatible endpoint), {} is not one", provider_config.provider).into());
+            }
+
             LlmClient::create_provider(
                 &provider_config.provider, 
                 &provider_config.env_vars)
                 .map_err(|e| format!("Failed to create {} client: {}", provider_config.provider, e))?
+                .with_vision(provider_config.vision.clone())
         } else {
             return Err("No provider configured".into());
         };
@@ -2,4 +2,7 @@ pub mod tools;
 pub mod agent;
 pub mod runners;
 pub mod logging;
-pub mod config;
\ No newline at end of file
+pub mod config;
+pub mod redact;
+pub mod truncate;
+pub mod lsp;
\ No newline at end of file
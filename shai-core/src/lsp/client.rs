@@ -0,0 +1,438 @@
+use crate::tools::fs::text;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::{oneshot, watch};
+use tokio::task::JoinHandle;
+
+/// Longest wait for the answer to a request, indexing a large workspace can take a while
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Servers often publish diagnostics in several rounds (syntax first, then type checking),
+/// the last one is kept unless nothing more comes within this delay
+const SETTLE_DELAY: Duration = Duration::from_millis(300);
+
+type Writer = Arc<tokio::sync::Mutex<Box<dyn AsyncWrite + Send + Unpin>>>;
+type Pending = Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>;
+
+/// 0-based, the character is counted in UTF-16 code units like the protocol does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Position {
+    pub line: u32,
+    pub character: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub range: Range,
+    /// 1 error, 2 warning, 3 information, 4 hint
+    #[serde(default)]
+    pub severity: Option<u8>,
+    #[serde(default)]
+    pub code: Option<Value>,
+    #[serde(default)]
+    pub source: Option<String>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn severity_name(&self) -> &'static str {
+        match self.severity {
+            Some(2) => "warning",
+            Some(3) => "info",
+            Some(4) => "hint",
+            _ => "error",
+        }
+    }
+
+    /// Errors and warnings, what an edit should not introduce
+    pub fn is_problem(&self) -> bool {
+        self.severity.unwrap_or(1) <= 2
+    }
+
+    /// `path:line:column: severity: message [source code]`, 1-based like compilers print them
+    pub fn format(&self, path: &str) -> String {
+        let origin = match (&self.source, &self.code) {
+            (Some(source), Some(code)) => format!(" [{} {}]", source, code.as_str().map_or_else(|| code.to_string(), str::to_string)),
+            (Some(source), None) => format!(" [{}]", source),
+            _ => String::new(),
+        };
+        format!(
+            "{}:{}:{}: {}: {}{}",
+            path, self.range.start.line + 1, self.range.start.character + 1, self.severity_name(), self.message.trim().replace('\n', "\n    "), origin
+        )
+    }
+}
+
+/// Where a symbol is defined, 0-based
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub path: PathBuf,
+    pub line: u32,
+    pub character: u32,
+}
+
+pub fn file_uri(path: &Path) -> Result<String, String> {
+    Url::from_file_path(path)
+        .map(|url| url.to_string())
+        .map_err(|_| format!("{} is not an absolute path", path.display()))
+}
+
+fn uri_path(uri: &str) -> Option<PathBuf> {
+    Url::parse(uri).ok()?.to_file_path().ok()
+}
+
+pub(crate) async fn write_message<W: AsyncWrite + Unpin + ?Sized>(writer: &mut W, message: &Value) -> io::Result<()> {
+    let body = serde_json::to_vec(message)?;
+    writer.write_all(format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes()).await?;
+    writer.write_all(&body).await?;
+    writer.flush().await
+}
+
+/// Next message of the stream, None once it is closed
+pub(crate) async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            if length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let mut body = vec![0; length.unwrap_or_default()];
+    reader.read_exact(&mut body).await?;
+    serde_json::from_slice(&body).map(Some).map_err(io::Error::other)
+}
+
+/// Text of a hover answer, whichever of the shapes allowed by the protocol it takes
+fn hover_text(contents: &Value) -> String {
+    match contents {
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items.iter().map(hover_text).filter(|text| !text.is_empty()).collect::<Vec<_>>().join("\n\n"),
+        Value::Object(object) => match (object.get("language").and_then(Value::as_str), object.get("value").and_then(Value::as_str)) {
+            (Some(language), Some(value)) => format!("```{}\n{}\n```", language, value),
+            (None, Some(value)) => value.to_string(),
+            _ => String::new(),
+        },
+        _ => String::new(),
+    }
+}
+
+/// Locations of a definition answer: a Location, a list of them or a list of LocationLinks
+fn locations(result: &Value) -> Vec<Location> {
+    let items = match result {
+        Value::Array(items) => items.clone(),
+        Value::Null => Vec::new(),
+        other => vec![other.clone()],
+    };
+    items.iter()
+        .filter_map(|item| {
+            let uri = item.get("uri").or_else(|| item.get("targetUri"))?.as_str()?;
+            let range = item.get("range").or_else(|| item.get("targetSelectionRange"))?;
+            let start: Position = serde_json::from_value(range.get("start")?.clone()).ok()?;
+            Some(Location { path: uri_path(uri)?, line: start.line, character: start.character })
+        })
+        .collect()
+}
+
+/// Diagnostics published by the server, each with the generation it was published at
+#[derive(Default)]
+struct Published {
+    generation: u64,
+    documents: HashMap<String, (u64, Vec<Diagnostic>)>,
+}
+
+/// Connection to a language server speaking JSON-RPC over its stdio
+pub struct LspClient {
+    name: String,
+    writer: Writer,
+    pending: Pending,
+    next_id: AtomicI64,
+    published: Arc<Mutex<Published>>,
+    updates: watch::Receiver<u64>,
+    /// Documents opened on the server, with their version and the text it knows
+    documents: tokio::sync::Mutex<HashMap<String, (i32, String)>>,
+    reader: JoinHandle<()>,
+    /// Killed when the client is dropped
+    _child: Option<Child>,
+}
+
+impl Drop for LspClient {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+impl LspClient {
+    /// Start a server in the workspace and initialize it
+    pub async fn spawn(name: &str, command: &str, args: &[String], root: &Path) -> Result<Self, String> {
+        let mut child = Command::new(command)
+            .args(args)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("cannot start {}: {}", command, e))?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(format!("cannot talk to {}", command));
+        };
+
+        let client = Self::connect(name, stdout, stdin, Some(child));
+        client.initialize(root).await?;
+        Ok(client)
+    }
+
+    /// Client over any pair of streams, the server still has to be initialized
+    pub fn connect<R, W>(name: &str, reader: R, writer: W, child: Option<Child>) -> Self
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+        W: AsyncWrite + Send + Unpin + 'static,
+    {
+        let writer: Writer = Arc::new(tokio::sync::Mutex::new(Box::new(writer)));
+        let pending: Pending = Arc::default();
+        let published: Arc<Mutex<Published>> = Arc::default();
+        let (updates_tx, updates) = watch::channel(0);
+        let reader = tokio::spawn(Self::read_loop(BufReader::new(reader), writer.clone(), pending.clone(), published.clone(), updates_tx));
+
+        Self {
+            name: name.to_string(),
+            writer,
+            pending,
+            next_id: AtomicI64::new(1),
+            published,
+            updates,
+            documents: tokio::sync::Mutex::new(HashMap::new()),
+            reader,
+            _child: child,
+        }
+    }
+
+    async fn read_loop<R: AsyncRead + Send + Unpin>(
+        mut reader: BufReader<R>,
+        writer: Writer,
+        pending: Pending,
+        published: Arc<Mutex<Published>>,
+        updates: watch::Sender<u64>,
+    ) {
+        while let Ok(Some(message)) = read_message(&mut reader).await {
+            let method = message.get("method").and_then(Value::as_str);
+            match (method, message.get("id")) {
+                // requests of the server get an empty answer, configuration wants one value per item
+                (Some(method), Some(id)) => {
+                    let result = match method {
+                        "workspace/configuration" => Value::Array(vec![Value::Null; message["params"]["items"].as_array().map_or(0, Vec::len)]),
+                        _ => Value::Null,
+                    };
+                    let response = json!({ "jsonrpc": "2.0", "id": id, "result": result });
+                    let _ = write_message(&mut **writer.lock().await, &response).await;
+                }
+                (Some("textDocument/publishDiagnostics"), None) => {
+                    let Some(uri) = message["params"]["uri"].as_str() else { continue };
+                    let diagnostics: Vec<Diagnostic> = serde_json::from_value(message["params"]["diagnostics"].clone()).unwrap_or_default();
+                    let generation = {
+                        let mut published = published.lock().unwrap();
+                        published.generation += 1;
+                        let generation = published.generation;
+                        published.documents.insert(uri.to_string(), (generation, diagnostics));
+                        generation
+                    };
+                    updates.send_replace(generation);
+                }
+                (None, Some(id)) => {
+                    let Some(sender) = id.as_i64().and_then(|id| pending.lock().unwrap().remove(&id)) else { continue };
+                    let response = match message.get("error") {
+                        Some(error) => Err(error["message"].as_str().unwrap_or("request failed").to_string()),
+                        None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+                    };
+                    let _ = sender.send(response);
+                }
+                _ => {}
+            }
+        }
+        // the server is gone, dropping the senders fails the requests still waiting
+        pending.lock().unwrap().clear();
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn send(&self, message: Value) -> Result<(), String> {
+        write_message(&mut **self.writer.lock().await, &message).await
+            .map_err(|e| format!("cannot talk to {}: {}", self.name, e))
+    }
+
+    pub async fn notify(&self, method: &str, params: Value) -> Result<(), String> {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params })).await
+    }
+
+    pub async fn request(&self, method: &str, params: Value) -> Result<Value, String> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (sender, receiver) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, sender);
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })).await?;
+
+        match tokio::time::timeout(REQUEST_TIMEOUT, receiver).await {
+            Ok(Ok(response)) => response.map_err(|e| format!("{} failed {}: {}", self.name, method, e)),
+            Ok(Err(_)) => Err(format!("{} exited", self.name)),
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&id);
+                Err(format!("{} did not answer {} in time", self.name, method))
+            }
+        }
+    }
+
+    pub async fn initialize(&self, root: &Path) -> Result<(), String> {
+        let root_uri = file_uri(root)?;
+        let root_name = root.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        self.request("initialize", json!({
+            "processId": std::process::id(),
+            "clientInfo": { "name": "shai" },
+            "rootUri": root_uri,
+            "workspaceFolders": [{ "uri": root_uri, "name": root_name }],
+            "capabilities": {
+                "textDocument": {
+                    "synchronization": { "didSave": true },
+                    "publishDiagnostics": { "relatedInformation": false },
+                    "hover": { "contentFormat": ["markdown", "plaintext"] },
+                    "definition": { "linkSupport": true }
+                },
+                "workspace": { "configuration": true, "workspaceFolders": true }
+            }
+        })).await?;
+        self.notify("initialized", json!({})).await
+    }
+
+    /// Politely stop the server, it is killed anyway when the client is dropped
+    pub async fn shutdown(&self) {
+        if self.request("shutdown", Value::Null).await.is_ok() {
+            let _ = self.notify("exit", Value::Null).await;
+        }
+    }
+
+    /// Send the content of a file on disk to the server, tells whether the server saw a new version
+    pub async fn sync(&self, path: &Path, language_id: &str) -> Result<(String, bool), String> {
+        let uri = file_uri(path)?;
+        let (content, _) = text::read_text(&path.to_string_lossy())?;
+
+        let mut documents = self.documents.lock().await;
+        match documents.get_mut(&uri) {
+            None => {
+                self.notify("textDocument/didOpen", json!({
+                    "textDocument": { "uri": uri, "languageId": language_id, "version": 1, "text": content }
+                })).await?;
+                documents.insert(uri.clone(), (1, content));
+            }
+            Some((_, known)) if *known == content => return Ok((uri, false)),
+            Some((version, known)) => {
+                *version += 1;
+                self.notify("textDocument/didChange", json!({
+                    "textDocument": { "uri": uri, "version": *version },
+                    "contentChanges": [{ "text": content }]
+                })).await?;
+                *known = content;
+            }
+        }
+        // the file is already on disk, saving triggers the checks some servers only run on save
+        self.notify("textDocument/didSave", json!({ "textDocument": { "uri": uri } })).await?;
+        Ok((uri, true))
+    }
+
+    fn generation(&self) -> u64 {
+        self.published.lock().unwrap().generation
+    }
+
+    fn published_since(&self, uri: &str, since: u64) -> Option<(u64, Vec<Diagnostic>)> {
+        let published = self.published.lock().unwrap();
+        published.documents.get(uri)
+            .filter(|(generation, _)| *generation > since)
+            .cloned()
+    }
+
+    /// Last diagnostics published for a document
+    pub fn diagnostics(&self, uri: &str) -> Vec<Diagnostic> {
+        self.published_since(uri, 0).map(|(_, diagnostics)| diagnostics).unwrap_or_default()
+    }
+
+    /// Diagnostics published for a document after a generation, None if there were none in time
+    async fn diagnostics_after(&self, uri: &str, mut since: u64, timeout: Duration) -> Option<Vec<Diagnostic>> {
+        let mut updates = self.updates.clone();
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut latest = None;
+        loop {
+            if let Some((generation, diagnostics)) = self.published_since(uri, since) {
+                since = generation;
+                latest = Some(diagnostics);
+            }
+            let wait_until = match latest {
+                Some(_) => (tokio::time::Instant::now() + SETTLE_DELAY).min(deadline),
+                None => deadline,
+            };
+            match tokio::time::timeout_at(wait_until, updates.changed()).await {
+                Ok(Ok(())) => continue,
+                _ => return latest,
+            }
+        }
+    }
+
+    /// Diagnostics of a file once the server has seen its current content
+    pub async fn refresh(&self, path: &Path, language_id: &str, timeout: Duration) -> Result<Vec<Diagnostic>, String> {
+        let since = self.generation();
+        let (uri, changed) = self.sync(path, language_id).await?;
+        let cached = self.published_since(&uri, 0);
+        if !changed && cached.is_some() {
+            return Ok(cached.map(|(_, diagnostics)| diagnostics).unwrap_or_default());
+        }
+        Ok(match self.diagnostics_after(&uri, since, timeout).await {
+            Some(diagnostics) => diagnostics,
+            None => self.diagnostics(&uri),
+        })
+    }
+
+    fn position_params(uri: &str, position: Position) -> Value {
+        json!({ "textDocument": { "uri": uri }, "position": position })
+    }
+
+    /// Documentation and type of the symbol at a position, None if the server has nothing to say
+    pub async fn hover(&self, path: &Path, language_id: &str, position: Position) -> Result<Option<String>, String> {
+        let (uri, _) = self.sync(path, language_id).await?;
+        let result = self.request("textDocument/hover", Self::position_params(&uri, position)).await?;
+        let text = result.get("contents").map(hover_text).unwrap_or_default();
+        Ok(if text.trim().is_empty() { None } else { Some(text) })
+    }
+
+    pub async fn definition(&self, path: &Path, language_id: &str, position: Position) -> Result<Vec<Location>, String> {
+        let (uri, _) = self.sync(path, language_id).await?;
+        let result = self.request("textDocument/definition", Self::position_params(&uri, position)).await?;
+        Ok(locations(&result))
+    }
+}
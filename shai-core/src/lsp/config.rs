@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+/// A language server and the files it handles
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LspServerConfig {
+    /// Name of the language, e.g. `rust`
+    pub language: String,
+    /// Executable of the server, looked up in PATH
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Extensions of the files sent to this server, without the dot
+    pub extensions: Vec<String>,
+}
+
+impl LspServerConfig {
+    fn new(language: &str, command: &str, args: &[&str], extensions: &[&str]) -> Self {
+        Self {
+            language: language.to_string(),
+            command: command.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            extensions: extensions.iter().map(|ext| ext.to_string()).collect(),
+        }
+    }
+
+    pub fn handles(&self, extension: &str) -> bool {
+        self.extensions.iter().any(|ext| ext.eq_ignore_ascii_case(extension))
+    }
+}
+
+/// Language server settings, stored in the `lsp` section of ~/.shai.config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LspConfig {
+    /// Start language servers to report diagnostics after edits, off unless turned on since servers index the whole workspace
+    pub enabled: bool,
+    /// Servers are started the first time a file they handle is edited or looked at
+    pub servers: Vec<LspServerConfig>,
+    /// How long an edit waits for the diagnostics of the file, in milliseconds
+    pub diagnostics_timeout_ms: u64,
+}
+
+impl Default for LspConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            servers: vec![
+                LspServerConfig::new("rust", "rust-analyzer", &[], &["rs"]),
+                LspServerConfig::new("python", "pyright-langserver", &["--stdio"], &["py"]),
+                LspServerConfig::new("typescript", "typescript-language-server", &["--stdio"], &["ts", "tsx", "mts", "cts", "js", "jsx", "mjs", "cjs"]),
+                LspServerConfig::new("go", "gopls", &[], &["go"]),
+            ],
+            diagnostics_timeout_ms: 5000,
+        }
+    }
+}
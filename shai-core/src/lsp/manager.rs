@@ -0,0 +1,265 @@
+use super::client::{Diagnostic, Location, LspClient, Position};
+use super::config::{LspConfig, LspServerConfig};
+use crate::config::config::ShaiConfig;
+use crate::tools::ToolResult;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Diagnostics listed per file after an edit, the others are counted
+const MAX_REPORTED_DIAGNOSTICS: usize = 20;
+
+/// Language servers of the workspace, each one is started the first time it is needed
+pub struct LspManager {
+    config: LspConfig,
+    root: PathBuf,
+    clients: tokio::sync::Mutex<HashMap<String, Arc<LspClient>>>,
+    /// Servers that could not be started and why, they are not tried again
+    failed: Mutex<HashMap<String, String>>,
+    /// Diagnostics of the files a tool is about to change, taken before it runs
+    baselines: Mutex<HashMap<PathBuf, Vec<Diagnostic>>>,
+}
+
+/// Language id of a document, as servers expect it in didOpen
+fn language_id(server: &LspServerConfig, path: &Path) -> String {
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+    match extension {
+        "ts" | "mts" | "cts" => "typescript",
+        "tsx" => "typescriptreact",
+        "js" | "mjs" | "cjs" => "javascript",
+        "jsx" => "javascriptreact",
+        _ => server.language.as_str(),
+    }.to_string()
+}
+
+/// Files a tool result says it changed
+fn changed_paths(metadata: Option<&HashMap<String, Value>>) -> Vec<String> {
+    let Some(metadata) = metadata else { return Vec::new() };
+    let mut paths: Vec<String> = ["path", "destination"].iter()
+        .filter_map(|key| metadata.get(*key).and_then(Value::as_str))
+        .map(str::to_string)
+        .collect();
+    if let Some(Value::Array(list)) = metadata.get("paths") {
+        paths.extend(list.iter().filter_map(Value::as_str).map(str::to_string));
+    }
+    // apply_patch lists every file it touched with its path after the change
+    if let Some(Value::Array(files)) = metadata.get("files") {
+        paths.extend(files.iter().filter_map(|file| file.get("new_path")).filter_map(Value::as_str).map(str::to_string));
+    }
+    paths.dedup();
+    paths
+}
+
+/// Files the parameters of a tool call say it is about to change
+fn target_paths(parameters: &Value) -> Vec<String> {
+    let mut paths: Vec<String> = ["path", "file_path"].iter()
+        .filter_map(|key| parameters.get(*key).and_then(Value::as_str))
+        .map(str::to_string)
+        .collect();
+    if let Some(Value::Array(list)) = parameters.get("paths") {
+        paths.extend(list.iter().filter_map(Value::as_str).map(str::to_string));
+    }
+    // multi_file_edit lists its files with their edits
+    if let Some(Value::Array(files)) = parameters.get("files") {
+        paths.extend(files.iter().filter_map(|file| file.get("path")).filter_map(Value::as_str).map(str::to_string));
+    }
+    paths.dedup();
+    paths
+}
+
+/// Diagnostics of `after` that are not in `before`, and how many were already there;
+/// each old diagnostic accounts for one identical new one, so a second copy of a known problem is new
+fn split_new(before: &[Diagnostic], after: Vec<Diagnostic>) -> (Vec<Diagnostic>, usize) {
+    let mut known: HashMap<(String, Option<u8>), usize> = HashMap::new();
+    for old in before.iter().filter(|diagnostic| diagnostic.is_problem()) {
+        *known.entry((old.message.clone(), old.severity)).or_default() += 1;
+    }
+    let mut old = 0;
+    let new = after.into_iter()
+        .filter(Diagnostic::is_problem)
+        .filter(|diagnostic| match known.get_mut(&(diagnostic.message.clone(), diagnostic.severity)) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                old += 1;
+                false
+            }
+            _ => true,
+        })
+        .collect();
+    (new, old)
+}
+
+impl LspManager {
+    pub fn new(config: LspConfig, root: PathBuf) -> Self {
+        Self {
+            config,
+            root,
+            clients: tokio::sync::Mutex::new(HashMap::new()),
+            failed: Mutex::new(HashMap::new()),
+            baselines: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Manager configured from ~/.shai.config for the current directory
+    pub fn load() -> Self {
+        let config = ShaiConfig::load()
+            .map(|config| config.lsp)
+            .unwrap_or_default();
+        Self::new(config, std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Paths given to tools are relative to the workspace
+    fn absolute(&self, path: &str) -> PathBuf {
+        let path = Path::new(path);
+        if path.is_absolute() { path.to_path_buf() } else { self.root.join(path) }
+    }
+
+    fn server_for(&self, path: &Path) -> Option<&LspServerConfig> {
+        let extension = path.extension()?.to_str()?;
+        self.config.servers.iter().find(|server| server.handles(extension))
+    }
+
+    /// Use an already connected client for a language instead of starting its server
+    pub async fn attach(&self, language: &str, client: LspClient) {
+        self.clients.lock().await.insert(language.to_string(), Arc::new(client));
+    }
+
+    /// Client of the server handling a file, started on first use
+    async fn client_for(&self, path: &Path) -> Result<(Arc<LspClient>, String), String> {
+        let server = self.server_for(path)
+            .ok_or_else(|| format!("No language server is configured for {}", path.display()))?;
+        let language_id = language_id(server, path);
+
+        let mut clients = self.clients.lock().await;
+        if let Some(client) = clients.get(&server.language) {
+            return Ok((client.clone(), language_id));
+        }
+        if let Some(error) = self.failed.lock().unwrap().get(&server.language) {
+            return Err(error.clone());
+        }
+
+        match LspClient::spawn(&server.command, &server.command, &server.args, &self.root).await {
+            Ok(client) => {
+                let client = Arc::new(client);
+                clients.insert(server.language.clone(), client.clone());
+                Ok((client, language_id))
+            }
+            Err(error) => {
+                tracing::warn!(target: "lsp", "{} language server unavailable: {}", server.language, error);
+                self.failed.lock().unwrap().insert(server.language.clone(), error.clone());
+                Err(error)
+            }
+        }
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_millis(self.config.diagnostics_timeout_ms)
+    }
+
+    /// Current diagnostics of a file
+    pub async fn diagnostics(&self, path: &str) -> Result<Vec<Diagnostic>, String> {
+        let absolute = self.absolute(path);
+        let (client, language_id) = self.client_for(&absolute).await?;
+        client.refresh(&absolute, &language_id, self.timeout()).await
+    }
+
+    pub async fn hover(&self, path: &str, position: Position) -> Result<Option<String>, String> {
+        let absolute = self.absolute(path);
+        let (client, language_id) = self.client_for(&absolute).await?;
+        client.hover(&absolute, &language_id, position).await
+    }
+
+    pub async fn definition(&self, path: &str, position: Position) -> Result<Vec<Location>, String> {
+        let absolute = self.absolute(path);
+        let (client, language_id) = self.client_for(&absolute).await?;
+        client.definition(&absolute, &language_id, position).await
+    }
+
+    /// Record the diagnostics of the existing files a tool call is about to change, to tell afterwards which problems it introduced
+    pub async fn take_baseline(&self, parameters: &Value) {
+        if !self.config.enabled {
+            return;
+        }
+        for path in target_paths(parameters) {
+            let absolute = self.absolute(&path);
+            if !absolute.is_file() || self.server_for(&absolute).is_none() {
+                continue;
+            }
+            let Ok((client, language_id)) = self.client_for(&absolute).await else { continue };
+            if let Ok(diagnostics) = client.refresh(&absolute, &language_id, self.timeout()).await {
+                self.baselines.lock().unwrap().insert(absolute, diagnostics);
+            }
+        }
+    }
+
+    /// Errors and warnings of a file after its last change, split into the ones that were not there before
+    /// and the number of older ones, None for the latter when there was no baseline to compare with
+    async fn new_problems(&self, path: &str) -> Option<(Vec<Diagnostic>, Option<usize>)> {
+        let absolute = self.absolute(path);
+        if !absolute.is_file() || self.server_for(&absolute).is_none() {
+            return None;
+        }
+        let before = self.baselines.lock().unwrap().remove(&absolute);
+        let (client, language_id) = self.client_for(&absolute).await.ok()?;
+        let after = client.refresh(&absolute, &language_id, self.timeout()).await.ok()?;
+
+        match before {
+            Some(before) => {
+                let (new, old) = split_new(&before, after);
+                Some((new, Some(old)))
+            }
+            None => Some((after.into_iter().filter(Diagnostic::is_problem).collect(), None)),
+        }
+    }
+
+    /// Append the errors and warnings an edit introduced in the files it changed to its result
+    pub async fn annotate_result(&self, result: ToolResult) -> ToolResult {
+        let (mut output, mut metadata) = match result {
+            ToolResult::Success { output, metadata } if self.config.enabled => (output, metadata),
+            other => return other,
+        };
+
+        let mut introduced = 0;
+        for path in changed_paths(metadata.as_ref()) {
+            let Some((new, old)) = self.new_problems(&path).await else { continue };
+            if new.is_empty() {
+                continue;
+            }
+            match old {
+                Some(_) => {
+                    introduced += new.len();
+                    output.push_str(&format!("\n\nThe language server reports {} new problem(s) in {}:", new.len(), path));
+                }
+                // the file was not checked before the change, its problems may be older than it
+                None => output.push_str(&format!("\n\nThe language server reports {} problem(s) in {} (no baseline):", new.len(), path)),
+            }
+            for diagnostic in new.iter().take(MAX_REPORTED_DIAGNOSTICS) {
+                output.push_str(&format!("\n{}", diagnostic.format(&path)));
+            }
+            if new.len() > MAX_REPORTED_DIAGNOSTICS {
+                output.push_str(&format!("\n... and {} more", new.len() - MAX_REPORTED_DIAGNOSTICS));
+            }
+            if let Some(old) = old.filter(|old| *old > 0) {
+                output.push_str(&format!("\n({} problem(s) already there before this change)", old));
+            }
+        }
+        if introduced > 0 {
+            metadata.get_or_insert_with(HashMap::new).insert("new_diagnostics".to_string(), json!(introduced));
+        }
+        ToolResult::Success { output, metadata }
+    }
+
+    /// Stop every server that was started
+    pub async fn shutdown(&self) {
+        let clients: Vec<Arc<LspClient>> = self.clients.lock().await.drain().map(|(_, client)| client).collect();
+        for client in clients {
+            client.shutdown().await;
+        }
+    }
+}
@@ -0,0 +1,10 @@
+pub mod config;
+pub mod client;
+pub mod manager;
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+pub use config::{LspConfig, LspServerConfig};
+pub use client::{LspClient, Diagnostic, Location, Position, Range};
+pub use manager::LspManager;
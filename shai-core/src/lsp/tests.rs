@@ -0,0 +1,263 @@
+use super::client::{read_message, write_message, file_uri};
+use super::{Diagnostic, LspClient, LspConfig, LspManager, Position};
+use crate::tools::ToolResult;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::io::{AsyncRead, AsyncWrite, BufReader};
+
+/// Diagnostics of the fake server: an error for each line containing `error`, a warning for `warn`
+fn check(text: &str) -> Value {
+    let diagnostics: Vec<Value> = text.lines().enumerate()
+        .filter_map(|(line, code)| {
+            let severity = if code.contains("error") { 1 } else if code.contains("warn") { 2 } else { return None };
+            Some(json!({
+                "range": { "start": { "line": line, "character": 0 }, "end": { "line": line, "character": code.len() } },
+                "severity": severity,
+                "source": "fake",
+                "message": format!("unexpected `{}`", code.trim())
+            }))
+        })
+        .collect();
+    Value::Array(diagnostics)
+}
+
+async fn serve<R, W>(reader: R, mut writer: W)
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut reader = BufReader::new(reader);
+    while let Ok(Some(message)) = read_message(&mut reader).await {
+        let params = &message["params"];
+        let uri = params["textDocument"]["uri"].clone();
+        let text = match message["method"].as_str() {
+            Some("textDocument/didOpen") => params["textDocument"]["text"].as_str(),
+            Some("textDocument/didChange") => params["contentChanges"][0]["text"].as_str(),
+            _ => None,
+        };
+        if let Some(text) = text {
+            let notification = json!({
+                "jsonrpc": "2.0",
+                "method": "textDocument/publishDiagnostics",
+                "params": { "uri": uri, "diagnostics": check(text) }
+            });
+            write_message(&mut writer, &notification).await.unwrap();
+            continue;
+        }
+
+        let Some(id) = message.get("id") else { continue };
+        let result = match message["method"].as_str() {
+            Some("textDocument/hover") => json!({ "contents": { "kind": "markdown", "value": "fn answer() -> u32" } }),
+            Some("textDocument/definition") => json!([{
+                "targetUri": uri,
+                "targetRange": { "start": { "line": 0, "character": 0 }, "end": { "line": 0, "character": 20 } },
+                "targetSelectionRange": { "start": { "line": 0, "character": 3 }, "end": { "line": 0, "character": 9 } }
+            }]),
+            Some("initialize") => json!({ "capabilities": {} }),
+            _ => Value::Null,
+        };
+        write_message(&mut writer, &json!({ "jsonrpc": "2.0", "id": id, "result": result })).await.unwrap();
+    }
+}
+
+/// Client connected to an in-process server
+pub(crate) fn fake_client() -> LspClient {
+    let (client, server) = tokio::io::duplex(64 * 1024);
+    let (client_reader, client_writer) = tokio::io::split(client);
+    let (server_reader, server_writer) = tokio::io::split(server);
+    tokio::spawn(serve(server_reader, server_writer));
+    LspClient::connect("fake", client_reader, client_writer, None)
+}
+
+/// Manager of a temporary workspace whose Rust files are checked by the fake server
+pub(crate) async fn fake_manager(dir: &TempDir) -> LspManager {
+    let config = LspConfig { enabled: true, diagnostics_timeout_ms: 2000, ..LspConfig::default() };
+    let manager = LspManager::new(config, dir.path().to_path_buf());
+    manager.attach("rust", fake_client()).await;
+    manager
+}
+
+fn output(result: ToolResult) -> String {
+    match result {
+        ToolResult::Success { output, .. } => output,
+        ToolResult::Error { error, .. } => panic!("Expected success, got: {}", error),
+    }
+}
+
+fn success_for(path: &str) -> ToolResult {
+    let mut meta = HashMap::new();
+    meta.insert("path".to_string(), json!(path));
+    ToolResult::Success { output: "Edited".to_string(), metadata: Some(meta) }
+}
+
+#[tokio::test]
+async fn test_message_framing_roundtrip() {
+    let mut buffer: Vec<u8> = Vec::new();
+    let message = json!({ "jsonrpc": "2.0", "id": 1, "result": { "text": "héllo\r\n" } });
+    write_message(&mut buffer, &message).await.unwrap();
+    write_message(&mut buffer, &json!({ "jsonrpc": "2.0", "method": "exit" })).await.unwrap();
+    assert!(buffer.starts_with(b"Content-Length: "));
+
+    let mut reader = &buffer[..];
+    assert_eq!(read_message(&mut reader).await.unwrap(), Some(message));
+    assert_eq!(read_message(&mut reader).await.unwrap().unwrap()["method"], "exit");
+    assert_eq!(read_message(&mut reader).await.unwrap(), None);
+}
+
+#[test]
+fn test_diagnostic_format_is_one_based() {
+    let diagnostic: Diagnostic = serde_json::from_value(json!({
+        "range": { "start": { "line": 2, "character": 4 }, "end": { "line": 2, "character": 8 } },
+        "severity": 2,
+        "code": "E0308",
+        "source": "rustc",
+        "message": "mismatched types"
+    })).unwrap();
+    assert_eq!(diagnostic.format("src/main.rs"), "src/main.rs:3:5: warning: mismatched types [rustc E0308]");
+    assert!(diagnostic.is_problem());
+}
+
+#[test]
+fn test_config_defaults_and_partial_section() {
+    assert!(!LspConfig::default().enabled);
+    let config: LspConfig = serde_json::from_value(json!({ "enabled": true })).unwrap();
+    assert!(config.enabled);
+    assert_eq!(config.servers, LspConfig::default().servers);
+    assert!(config.servers.iter().any(|server| server.command == "rust-analyzer" && server.handles("rs")));
+}
+
+#[tokio::test]
+async fn test_client_initializes_and_refreshes_diagnostics() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("main.rs");
+    fs::write(&path, "fn main() {}\nlet error = 1;\n").unwrap();
+
+    let client = fake_client();
+    client.initialize(dir.path()).await.unwrap();
+    let diagnostics = client.refresh(&path, "rust", Duration::from_secs(2)).await.unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].range.start.line, 1);
+    assert_eq!(diagnostics[0].message, "unexpected `let error = 1;`");
+
+    // an unchanged file is answered from what the server already published
+    let again = client.refresh(&path, "rust", Duration::from_secs(2)).await.unwrap();
+    assert_eq!(again, diagnostics);
+
+    fs::write(&path, "fn main() {}\n").unwrap();
+    assert!(client.refresh(&path, "rust", Duration::from_secs(2)).await.unwrap().is_empty());
+    assert!(client.diagnostics(&file_uri(&path).unwrap()).is_empty());
+}
+
+#[tokio::test]
+async fn test_client_hover_and_definition() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("lib.rs");
+    fs::write(&path, "fn answer() -> u32 { 42 }\n").unwrap();
+
+    let client = fake_client();
+    let position = Position { line: 0, character: 4 };
+    let hover = client.hover(&path, "rust", position).await.unwrap();
+    assert_eq!(hover.as_deref(), Some("fn answer() -> u32"));
+
+    let locations = client.definition(&path, "rust", position).await.unwrap();
+    assert_eq!(locations.len(), 1);
+    assert_eq!(locations[0].path.file_name().unwrap(), "lib.rs");
+    assert_eq!((locations[0].line, locations[0].character), (0, 3));
+}
+
+#[tokio::test]
+async fn test_annotate_result_reports_only_new_problems() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("main.rs");
+    fs::write(&path, "fn main() {\n    let error = 1;\n}\n").unwrap();
+    let manager = fake_manager(&dir).await;
+    manager.take_baseline(&json!({ "path": "main.rs", "old_string": "a", "new_string": "b" })).await;
+
+    fs::write(&path, "fn main() {\n    let error = 1;\n    let warn = 2;\n    let other_error = 3;\n}\n").unwrap();
+    let result = manager.annotate_result(success_for("main.rs")).await;
+    let ToolResult::Success { output, metadata } = result else { panic!("Expected success") };
+    assert!(output.starts_with("Edited\n\nThe language server reports 2 new problem(s) in main.rs:"));
+    assert!(output.contains("main.rs:3:1: warning: unexpected `let warn = 2;` [fake]"));
+    assert!(output.contains("main.rs:4:1: error: unexpected `let other_error = 3;` [fake]"));
+    assert!(!output.contains("`let error = 1;`"));
+    assert!(output.contains("(1 problem(s) already there before this change)"));
+    assert_eq!(metadata.unwrap()["new_diagnostics"], json!(2));
+}
+
+#[tokio::test]
+async fn test_annotate_result_without_baseline_does_not_claim_new_problems() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("main.rs");
+    fs::write(&path, "fn main() {\n    let error = 1;\n}\n").unwrap();
+    let manager = fake_manager(&dir).await;
+    // what an earlier look at the file published is not a baseline, the file may have changed since
+    manager.diagnostics("main.rs").await.unwrap();
+
+    let result = manager.annotate_result(success_for("main.rs")).await;
+    let ToolResult::Success { output, metadata } = result else { panic!("Expected success") };
+    assert!(output.starts_with("Edited\n\nThe language server reports 1 problem(s) in main.rs (no baseline):"), "{}", output);
+    assert!(!output.contains("new problem"));
+    assert!(metadata.unwrap().get("new_diagnostics").is_none());
+}
+
+#[tokio::test]
+async fn test_annotate_result_counts_duplicated_problems() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("main.rs");
+    fs::write(&path, "let error = 1;\n").unwrap();
+    let manager = fake_manager(&dir).await;
+    manager.take_baseline(&json!({ "path": "main.rs" })).await;
+
+    // the same line twice gives the same message twice, one of them is new
+    fs::write(&path, "let error = 1;\nlet error = 1;\n").unwrap();
+    let output = output(manager.annotate_result(success_for("main.rs")).await);
+    assert!(output.contains("1 new problem(s) in main.rs:"), "{}", output);
+    assert!(output.contains("main.rs:2:1: error: unexpected `let error = 1;` [fake]"));
+    assert!(output.contains("(1 problem(s) already there before this change)"));
+}
+
+#[tokio::test]
+async fn test_annotate_result_leaves_clean_edits_and_errors_alone() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+    fs::write(dir.path().join("notes.txt"), "error everywhere\n").unwrap();
+    let manager = fake_manager(&dir).await;
+
+    assert_eq!(output(manager.annotate_result(success_for("main.rs")).await), "Edited");
+    // no server handles text files
+    assert_eq!(output(manager.annotate_result(success_for("notes.txt")).await), "Edited");
+
+    let failed = manager.annotate_result(ToolResult::error("Edit failed".to_string())).await;
+    assert!(failed.is_error());
+}
+
+#[tokio::test]
+async fn test_annotate_result_is_skipped_when_disabled() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("main.rs"), "let error = 1;\n").unwrap();
+    let config = LspConfig { enabled: false, ..LspConfig::default() };
+    let manager = LspManager::new(config, dir.path().to_path_buf());
+    manager.attach("rust", fake_client()).await;
+
+    assert_eq!(output(manager.annotate_result(success_for("main.rs")).await), "Edited");
+}
+
+#[tokio::test]
+async fn test_missing_server_is_reported_once() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("main.go"), "package main\n").unwrap();
+    let mut config = LspConfig { enabled: true, ..LspConfig::default() };
+    for server in &mut config.servers {
+        server.command = "shai-no-such-language-server".to_string();
+    }
+    let manager = LspManager::new(config, dir.path().to_path_buf());
+
+    let error = manager.diagnostics("main.go").await.unwrap_err();
+    assert!(error.contains("shai-no-such-language-server"));
+    assert_eq!(manager.diagnostics("main.go").await.unwrap_err(), error);
+    // edits are not held up by a missing server
+    assert_eq!(output(manager.annotate_result(success_for("main.go")).await), "Edited");
+}
@@ -1,3 +1,4 @@
+use std::path::Path;
 use std::sync::Arc;
 
 use openai_dive::v1::resources::chat::ChatCompletionParametersBuilder;
@@ -6,11 +7,14 @@ use async_trait::async_trait;
 use tracing::debug;
 
 use crate::agent::brain::ThinkerDecision;
-use crate::agent::{Agent, AgentBuilder, AgentError, Brain, ThinkerContext};
+use crate::agent::{Agent, AgentBuilder, AgentError, Brain, Restrictions, ThinkerContext};
+use crate::redact::Redactor;
+use crate::truncate::Truncator;
+use crate::lsp::LspManager;
 use crate::runners::coder::prompt::get_todo_read;
 use crate::tools::types::{ContainsAnyTool, IntoToolBox};
 use shai_llm::tool::LlmToolCall;
-use crate::tools::{AnyTool, BashTool, EditTool, FetchTool, FindTool, LsTool, MultiEditTool, ReadTool, TodoReadTool, TodoWriteTool, WriteTool, TodoStorage, FsOperationLog};
+use crate::tools::{AnyTool, ApplyPatchTool, CopyTool, DeleteTool, DiagnosticsTool, GotoDefinitionTool, HoverTool, MoveTool, BashSession, BashTool, BashBackgroundTool, ProcessKillTool, ProcessListTool, ProcessManager, ProcessOutputTool, EditTool, FetchTool, WebSearchTool, FindTool, GlobTool, LsTool, MultiEditTool, MultiFileEditTool, ReadTool, StructuralReplaceTool, StructuralSearchTool, SymbolsTool, TodoReadTool, TodoUpdateTool, TodoWriteTool, WriteTool, TodoStorage, FsOperationLog};
 
 use super::prompt::coder_next_step;
 
@@ -71,26 +75,63 @@ impl Brain for CoderBrain {
 }
 
 
-pub fn coder(llm: Arc<LlmClient>, model: String) -> impl Agent {
-    // Create shared storage for todo tools
-    let todo_storage = Arc::new(TodoStorage::new());
+/// Coder agent of a session, the todo list it saved before is loaded back when the session is continued
+pub fn coder(llm: Arc<LlmClient>, model: String, session_id: &str, workspace: &Path, restrictions: Restrictions) -> impl Agent {
+    // Create shared storage for todo tools, saved with the session
+    let todo_storage = Arc::new(TodoStorage::for_session(session_id));
     
     // Create shared operation log for file system tools
-    let fs_log = Arc::new(FsOperationLog::new());
+    let fs_log = Arc::new(FsOperationLog::for_session(workspace, session_id));
     
-    let bash = Box::new(BashTool::new());
+    // Background processes are killed when the agent drops its tools
+    let processes = Arc::new(ProcessManager::new());
+
+    // Language servers, when enabled, are started the first time a file of their language is checked
+    let lsp = Arc::new(LspManager::load());
+
+    let bash = Box::new(BashTool::with_session(Arc::new(BashSession::new())));
+    let bash_background = Box::new(BashBackgroundTool::new(processes.clone()));
+    let process_output = Box::new(ProcessOutputTool::new(processes.clone()));
+    let process_kill = Box::new(ProcessKillTool::new(processes.clone()));
+    let process_list = Box::new(ProcessListTool::new(processes.clone()));
     let edit = Box::new(EditTool::new(fs_log.clone()));
     let multiedit = Box::new(MultiEditTool::new(fs_log.clone()));
+    let multi_file_edit = Box::new(MultiFileEditTool::new(fs_log.clone()));
+    let apply_patch = Box::new(ApplyPatchTool::new(fs_log.clone()));
+    let move_tool = Box::new(MoveTool::new(fs_log.clone()));
+    let copy = Box::new(CopyTool::new(fs_log.clone()));
+    let delete = Box::new(DeleteTool::new(fs_log.clone()));
     let fetch = Box::new(FetchTool::new());
     let find = Box::new(FindTool::new());
+    let glob = Box::new(GlobTool::new());
     let ls = Box::new(LsTool::new());
     let read = Box::new(ReadTool::new(fs_log.clone()));
+    let structural_search = Box::new(StructuralSearchTool::new());
+    let structural_replace = Box::new(StructuralReplaceTool::new(fs_log.clone()));
+    let symbols = Box::new(SymbolsTool::new());
     let todoread = Box::new(TodoReadTool::new(todo_storage.clone()));
     let todowrite = Box::new(TodoWriteTool::new(todo_storage.clone()));
+    let todoupdate = Box::new(TodoUpdateTool::new(todo_storage.clone()));
     let write = Box::new(WriteTool::new(fs_log.clone()));
-    let toolbox: Vec<Box<dyn AnyTool>> = vec![bash, edit, multiedit, fetch, find, ls, read, todoread, todowrite, write];
+    let mut toolbox: Vec<Box<dyn AnyTool>> = vec![bash, bash_background, process_output, process_kill, process_list, edit, multiedit, multi_file_edit, apply_patch, move_tool, copy, delete, fetch, find, glob, ls, read, structural_search, structural_replace, symbols, todoread, todowrite, todoupdate, write];
+    let web_search = WebSearchTool::load();
+    if web_search.is_configured() {
+        toolbox.push(Box::new(web_search));
+    }
+    if lsp.is_enabled() {
+        toolbox.push(Box::new(DiagnosticsTool::new(lsp.clone())));
+        toolbox.push(Box::new(GotoDefinitionTool::new(lsp.clone())));
+        toolbox.push(Box::new(HoverTool::new(lsp.clone())));
+    }
     
     AgentBuilder::new(Box::new(CoderBrain::new(llm.clone(), model)))
+    .id(session_id)
     .tools(toolbox)
+    .todos(todo_storage)
+    .lsp(lsp)
+    .default_audit()
+    .redactor(Redactor::load())
+    .truncator(Truncator::load())
+    .restrictions(restrictions)
     .build()
 }
\ No newline at end of file
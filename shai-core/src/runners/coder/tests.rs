@@ -5,6 +5,7 @@ use crate::tools::AnyTool;
 use shai_llm::ToolCallMethod;
 use shai_llm::{ChatMessage, ChatMessageContent, client::LlmClient};
 use tokio::sync::RwLock;
+use std::path::Path;
 use std::sync::Arc;
 use tempfile::TempDir;
 use std::sync::Once;
@@ -17,7 +18,7 @@ fn init_test_logging() {
 }
 
 // Helper function to create a coder agent with full toolbox
-async fn create_coder_agent_with_goal(goal: &str) -> impl Agent {
+async fn create_coder_agent_with_goal(dir: &Path, goal: &str) -> impl Agent {
     let llm_client = Arc::new(LlmClient::first_from_env().expect("No LLM provider available"));
     let model = llm_client.default_model().await.expect("default model");
     println!("using model: {:?}", model);
@@ -26,7 +27,7 @@ async fn create_coder_agent_with_goal(goal: &str) -> impl Agent {
     let todo_storage = Arc::new(crate::tools::TodoStorage::new());
     
     // Create shared operation log for file system tools
-    let fs_log = Arc::new(crate::tools::FsOperationLog::new());
+    let fs_log = Arc::new(crate::tools::FsOperationLog::with_trash_dir(dir.join(".trash")));
     
     let bash = Box::new(crate::tools::BashTool::new());
     let edit = Box::new(crate::tools::EditTool::new(fs_log.clone()));
@@ -98,7 +99,7 @@ async fn test_coder_integration_simple_file_creation() {
     std::env::set_current_dir(temp_path).expect("Failed to change directory");
     
     // Create a coder agent with full toolbox and goal
-    let agent = create_coder_agent_with_goal(
+    let agent = create_coder_agent_with_goal(temp_dir.path(), 
         "Create a Python file called 'hello.py' that prints 'Hello, World!' when executed. The file should contain a proper function and a main guard."
     ).await;
     
@@ -145,7 +146,7 @@ async fn test_multi_turn_conversation() {
     
     // Create a coder agent with full toolbox and goal
     let goal = "Create a Python file called 'hello.py' that prints 'Hello, World!' when executed.";
-    let mut agent = create_coder_agent_with_goal(goal).await;
+    let mut agent = create_coder_agent_with_goal(temp_dir.path(), goal).await;
 
     // get a controller
     let controller = agent.controller();
@@ -212,7 +213,7 @@ if __name__ == "__main__":
     println!("🐛 Created buggy file: {:?}", buggy_file_path);
     
     // Create a coder agent with full toolbox and goal
-    let agent = create_coder_agent_with_goal(
+    let agent = create_coder_agent_with_goal(temp_dir.path(), 
         "There's a bug in calculator.py. Please read the file, identify the bug, and fix it so the code calculates the average correctly."
     ).await;
     
@@ -19,7 +19,9 @@ You have access to these READ-ONLY tools:
 - `read`: Read file contents
 - `ls`: List directory contents  
 - `find`: Search for files by name/pattern
+- `glob`: List files matching a glob pattern (e.g. `src/**/*.rs`)
 - `fetch`: Fetch remote content (documentation, APIs)
+- `web_search`: Search the web for pages whose URL you do not know yet
 - `todoread`/`todowrite`: Manage your analysis tasks
 
 You do NOT have access to any write/edit tools like bash, edit, write, or multiedit.
@@ -6,7 +6,7 @@ use async_trait::async_trait;
 
 use crate::agent::brain::ThinkerDecision;
 use crate::agent::{Agent, AgentBuilder, AgentError, Brain, ThinkerContext};
-use crate::tools::{AnyTool, FetchTool, FindTool, LsTool, ReadTool, TodoReadTool, TodoWriteTool, TodoStorage};
+use crate::tools::{AnyTool, FetchTool, WebSearchTool, FindTool, GlobTool, LsTool, ReadTool, TodoReadTool, TodoWriteTool, TodoStorage};
 
 use super::prompt::searcher_next_step;
 
@@ -84,11 +84,16 @@ pub fn searcher(llm: Arc<LlmClient>, model: String) -> impl Agent {
     // Only read-only tools for the searcher
     let fetch = Box::new(FetchTool::new());
     let find = Box::new(FindTool::new());
+    let glob = Box::new(GlobTool::new());
     let ls = Box::new(LsTool::new());
     let read = Box::new(ReadTool::new(Arc::new(crate::tools::FsOperationLog::new())));
     let todoread = Box::new(TodoReadTool::new(todo_storage.clone()));
     let todowrite = Box::new(TodoWriteTool::new(todo_storage.clone()));
-    let toolbox: Vec<Box<dyn AnyTool>> = vec![fetch, find, ls, read, todoread, todowrite];
+    let mut toolbox: Vec<Box<dyn AnyTool>> = vec![fetch, find, glob, ls, read, todoread, todowrite];
+    let web_search = WebSearchTool::load();
+    if web_search.is_configured() {
+        toolbox.push(Box::new(web_search));
+    }
     
     AgentBuilder::new(Box::new(SearcherBrain{llm: llm.clone(), model}))
     .tools(toolbox)
@@ -2,6 +2,7 @@ use super::searcher::SearcherBrain;
 use crate::agent::Agent;
 use crate::logging::LoggingConfig;
 use shai_llm::{ChatMessage, ChatMessageContent, client::LlmClient};
+use std::path::Path;
 use std::sync::Arc;
 use tempfile::TempDir;
 use std::sync::Once;
@@ -14,7 +15,7 @@ fn init_test_logging() {
 }
 
 // Helper function to create a searcher agent with goal
-async fn create_searcher_agent_with_goal(goal: &str) -> impl Agent {
+async fn create_searcher_agent_with_goal(dir: &Path, goal: &str) -> impl Agent {
     let llm_client = Arc::new(LlmClient::first_from_env().expect("No LLM provider available"));
     let model = llm_client.default_model().await.expect("default model");
     println!("using model: {:?}", model);
@@ -25,7 +26,7 @@ async fn create_searcher_agent_with_goal(goal: &str) -> impl Agent {
             Box::new(crate::tools::FetchTool::new()),
             Box::new(crate::tools::FindTool::new()),
             Box::new(crate::tools::LsTool::new()),
-            Box::new(crate::tools::ReadTool::new(Arc::new(crate::tools::FsOperationLog::new()))),
+            Box::new(crate::tools::ReadTool::new(Arc::new(crate::tools::FsOperationLog::with_trash_dir(dir.join(".trash"))))),
             Box::new(crate::tools::TodoReadTool::new(Arc::new(crate::tools::TodoStorage::new()))),
             Box::new(crate::tools::TodoWriteTool::new(Arc::new(crate::tools::TodoStorage::new()))),
         ])
@@ -92,7 +93,7 @@ fn main() {
     println!("🧪 Test: Finding User struct definition in temp directory: {:?}", temp_path);
     
     // Create a searcher agent with goal to find User struct
-    let mut agent = create_searcher_agent_with_goal(
+    let mut agent = create_searcher_agent_with_goal(temp_dir.path(), 
         "Find where the User struct is defined in this rust codebase. Provide the file path and explain its structure, fields, and methods. Be specific about what you found."
     ).await;
     
@@ -247,7 +248,7 @@ impl AuthController {
     println!("🧪 Test: Analyzing authentication feature in temp directory: {:?}", temp_path);
     
     // Create a searcher agent with goal to analyze auth feature
-    let mut agent = create_searcher_agent_with_goal(
+    let mut agent = create_searcher_agent_with_goal(temp_dir.path(), 
         "Analyze the authentication feature in this codebase. Explain how authentication works, what components are involved, and provide a summary of the authentication flow. Be specific about the structs, methods, and error handling you find."
     ).await;
     
@@ -436,7 +437,7 @@ async fn main() {
     println!("🧪 Test: Generating KNOWLEDGE.md for comprehensive API project in: {:?}", temp_path);
     
     // Create a searcher agent with goal to generate KNOWLEDGE.md
-    let mut agent = create_searcher_agent_with_goal(
+    let mut agent = create_searcher_agent_with_goal(temp_dir.path(), 
         "Generate a comprehensive KNOWLEDGE.md summary for this codebase. Include the overall architecture, key components, main functionality, file structure, and API endpoints. Focus on the layers: API, Models, and Database. Provide a clear technical overview that would help a new developer understand this project."
     ).await;
     
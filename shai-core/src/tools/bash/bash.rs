@@ -1,20 +1,153 @@
+use super::session::BashSession;
 use super::structs::BashToolParams;
-use crate::tools::{tool, ToolResult};
+use crate::tools::{tool, ToolContext, ToolResult};
 use serde_json::json;
 use std::collections::HashMap;
-use std::process::{Command, Stdio};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
-use tokio::time::timeout;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::Command;
+use tokio::task::JoinHandle;
 
-pub struct BashTool;
+/// Answer to a reset when the tool is not backed by a shell session
+const NO_SESSION: &str = "There is no shell session to reset, every command runs in a fresh shell from the same path";
+
+/// Time left to the readers to drain the pipes once the process group was killed
+pub(super) const DRAIN_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Why a command did not run to completion
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Interruption {
+    TimedOut(u32),
+    Cancelled,
+}
+
+/// Output of a command, partial if it was interrupted
+pub(super) struct CommandOutput {
+    pub(super) stdout: String,
+    pub(super) stderr: String,
+    pub(super) exit_code: i32,
+    pub(super) interrupted: Option<Interruption>,
+    /// note about the shell session appended to the result
+    pub(super) notice: Option<String>,
+}
+
+/// The process group of a running command, killed when dropped unless released
+struct ProcessGroup {
+    pgid: Option<u32>,
+}
+
+impl ProcessGroup {
+    fn kill(&mut self) {
+        if let Some(pgid) = self.pgid.take() {
+            kill_group(pgid);
+        }
+    }
+
+    /// the command completed, background jobs it started are left alone
+    fn release(&mut self) {
+        self.pgid = None;
+    }
+}
+
+#[cfg(unix)]
+pub(crate) fn kill_group(pgid: u32) {
+    unsafe {
+        libc::killpg(pgid as libc::pid_t, libc::SIGKILL);
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn kill_group(_pgid: u32) {}
+
+/// Whether the process group still has members
+#[cfg(unix)]
+pub(crate) fn group_exists(pgid: u32) -> bool {
+    unsafe { libc::kill(-(pgid as libc::pid_t), 0) == 0 }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn group_exists(_pgid: u32) -> bool {
+    false
+}
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        // the tool call future was dropped (e.g. cancelled), don't leave anything running
+        self.kill();
+    }
+}
+
+/// Read a pipe into a shared buffer so that partial output is available at any time,
+/// every chunk read is also streamed to the tool context
+fn collect<R: AsyncRead + Unpin + Send + 'static>(reader: Option<R>, context: ToolContext) -> (Arc<Mutex<Vec<u8>>>, JoinHandle<()>) {
+    let buffer = Arc::new(Mutex::new(Vec::new()));
+    let sink = buffer.clone();
+    let handle = tokio::spawn(async move {
+        let Some(mut reader) = reader else { return };
+        let mut chunk = [0u8; 8192];
+        // bytes of a utf-8 character split across two reads
+        let mut pending: Vec<u8> = Vec::new();
+        while let Ok(n) = reader.read(&mut chunk).await {
+            if n == 0 {
+                break;
+            }
+            sink.lock().unwrap().extend_from_slice(&chunk[..n]);
+
+            pending.extend_from_slice(&chunk[..n]);
+            let valid = match std::str::from_utf8(&pending) {
+                Ok(_) => pending.len(),
+                Err(e) if e.error_len().is_none() => e.valid_up_to(),
+                Err(_) => pending.len(),
+            };
+            if valid > 0 {
+                let rest = pending.split_off(valid);
+                context.emit(String::from_utf8_lossy(&pending).to_string());
+                pending = rest;
+            }
+        }
+        if !pending.is_empty() {
+            context.emit(String::from_utf8_lossy(&pending).to_string());
+        }
+    });
+    (buffer, handle)
+}
+
+fn snapshot(buffer: &Arc<Mutex<Vec<u8>>>) -> String {
+    String::from_utf8_lossy(&buffer.lock().unwrap()).to_string()
+}
+
+pub struct BashTool {
+    session: Option<Arc<BashSession>>,
+}
 
 impl BashTool {
+    /// Every command runs in a fresh shell
     pub fn new() -> Self {
-        Self
+        Self { session: None }
     }
 
-    async fn execute_command(&self, params: &BashToolParams) -> Result<(String, String, i32), Box<dyn std::error::Error + Send + Sync>> {       
+    /// Commands run in a shell session that keeps its state between calls
+    pub fn with_session(session: Arc<BashSession>) -> Self {
+        Self { session: Some(session) }
+    }
+
+    async fn execute_command(&self, params: &BashToolParams) -> Result<CommandOutput, Box<dyn std::error::Error + Send + Sync>> {       
+        if let Some(session) = &self.session {
+            return session.run(params).await;
+        }
+
         // Validate command is not empty
+        if params.reset && params.command.trim().is_empty() {
+            return Ok(CommandOutput {
+                stdout: String::new(),
+                stderr: String::new(),
+                exit_code: 0,
+                interrupted: None,
+                notice: Some(NO_SESSION.to_string()),
+            });
+        }
         if params.command.trim().is_empty() {
             return Err("Command cannot be empty".into());
         }
@@ -33,40 +166,91 @@ impl BashTool {
             cmd.env(key, value);
         }
 
-        // Configure stdio
-        cmd.stdout(Stdio::piped())
-           .stderr(Stdio::piped());
+        // Configure stdio, the command gets its own process group so that it can be killed with its children
+        cmd.stdin(Stdio::null())
+           .stdout(Stdio::piped())
+           .stderr(Stdio::piped())
+           .kill_on_drop(true);
+        #[cfg(unix)]
+        cmd.process_group(0);
 
-        // Execute with optional timeout
-        match params.timeout {
-            Some(timeout_secs) => {
-                let timeout_duration = Duration::from_secs(timeout_secs as u64);
-                
-                let result = timeout(timeout_duration, async {
-                    let output = cmd.output()?;
-                    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-                    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-                    let exit_code = output.status.code().unwrap_or(-1);
-                    
-                    Ok::<(String, String, i32), Box<dyn std::error::Error + Send + Sync>>((stdout, stderr, exit_code))
-                }).await;
-
-                match result {
-                    Ok(Ok(output)) => Ok(output),
-                    Ok(Err(e)) => Err(format!("Command execution failed: {}", e).into()),
-                    Err(_) => Err(format!("Command timed out after {} seconds", timeout_secs).into()),
+        let mut child = cmd.spawn()
+            .map_err(|e| format!("Command execution failed: {}", e))?;
+        let mut group = ProcessGroup { pgid: child.id() };
+        let context = ToolContext::current();
+        let (stdout, stdout_reader) = collect(child.stdout.take(), context.clone());
+        let (stderr, stderr_reader) = collect(child.stderr.take(), context.clone());
+
+        let deadline = async {
+            match params.timeout {
+                Some(timeout_secs) => tokio::time::sleep(Duration::from_secs(timeout_secs as u64)).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::pin!(deadline);
+        let cancel = context.cancellation_token.clone();
+        let timed_out = Interruption::TimedOut(params.timeout.unwrap_or_default());
+
+        let (exit_code, interrupted) = tokio::select! {
+            status = child.wait() => {
+                let status = status.map_err(|e| format!("Command execution failed: {}", e))?;
+                (status.code().unwrap_or(-1), None)
+            }
+            _ = &mut deadline => (-1, Some(timed_out)),
+            _ = cancel.cancelled() => (-1, Some(Interruption::Cancelled)),
+        };
+
+        let readers = async {
+            let _ = stdout_reader.await;
+            let _ = stderr_reader.await;
+        };
+        tokio::pin!(readers);
+        let interrupted = match interrupted {
+            Some(interruption) => Some(interruption),
+            // the pipes close once the command and everything it spawned in the foreground are done,
+            // a background job holding them open must not outlive the timeout or a cancel
+            None => tokio::select! {
+                _ = &mut readers => {
+                    group.release();
+                    None
                 }
+                _ = &mut deadline => Some(timed_out),
+                _ = cancel.cancelled() => Some(Interruption::Cancelled),
             },
-            None => {
-                // No timeout - run indefinitely
-                let output = cmd.output()?;
-                let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-                let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-                let exit_code = output.status.code().unwrap_or(-1);
-                
-                Ok((stdout, stderr, exit_code))
-            }
+        };
+        if interrupted.is_some() {
+            group.kill();
+            let _ = child.start_kill();
+            let _ = child.wait().await;
+            let _ = tokio::time::timeout(DRAIN_TIMEOUT, readers).await;
         }
+
+        Ok(CommandOutput {
+            stdout: snapshot(&stdout),
+            stderr: snapshot(&stderr),
+            exit_code,
+            interrupted,
+            notice: params.reset.then(|| NO_SESSION.to_string()),
+        })
+    }
+}
+
+/// Combine stdout and stderr for output
+fn combine_output(stdout: String, stderr: String) -> String {
+    if stderr.is_empty() {
+        stdout
+    } else if stdout.is_empty() {
+        stderr
+    } else {
+        format!("{}\n--- STDERR ---\n{}", stdout, stderr)
+    }
+}
+
+fn append_notice(text: String, notice: &Option<String>) -> String {
+    match notice {
+        Some(notice) if text.is_empty() => format!("[{}]", notice),
+        Some(notice) => format!("{}\n[{}]", text, notice),
+        None => text,
     }
 }
 
@@ -83,7 +267,7 @@ Development Workflow:
 - Running Tests: After making changes, always run the project's test suite (e.g., npm test, pytest, cargo test) to verify that your changes haven't introduced any regressions.
 
 Usage Guidelines:
-- this tool always runs from the same path. If you need to execute command in another directory, chain the commands with && for instance "cd subcrate && cargo test"
+- Commands run in a persistent shell session: the current directory, exported variables and shell functions carry over to the next call, so "cd subcrate" once rather than prefixing every command. Set reset to true to restart from a fresh shell in the original directory. If a reset answers that there is no shell session, every command runs from the same path: chain the commands with && instead, for instance "cd subcrate && cargo test"
 - For file system navigation and inspection, prefer the built-in ls, read, and find tools. Use bash for executing other programs or scripts.
 - Always provide a clear, concise description of the command's purpose for the user.
 - Chain commands using && to ensure that subsequent commands only run if the previous ones succeed.
@@ -102,7 +286,7 @@ impl BashTool {
         let start_time = Instant::now();
         
         match self.execute_command(&params).await {
-            Ok((stdout, stderr, exit_code)) => {
+            Ok(CommandOutput { stdout, stderr, exit_code, interrupted, notice }) => {
                 let execution_time = start_time.elapsed();
                 let mut metadata = HashMap::new();
                 
@@ -114,7 +298,8 @@ impl BashTool {
                 } else {
                     metadata.insert("timeout".to_string(), json!("none"));
                 }
-                metadata.insert("success".to_string(), json!(exit_code == 0));
+                metadata.insert("success".to_string(), json!(exit_code == 0 && interrupted.is_none()));
+                metadata.insert("session".to_string(), json!(self.session.is_some()));
                 
                 if let Some(working_dir) = &params.working_dir {
                     metadata.insert("working_dir".to_string(), json!(working_dir));
@@ -130,6 +315,30 @@ impl BashTool {
                     metadata.insert("has_stderr".to_string(), json!(true));
                     metadata.insert("stderr_length".to_string(), json!(stderr.len()));
                 }
+
+                // the process group was killed, report what it printed until then
+                if let Some(interruption) = interrupted {
+                    let reason = match interruption {
+                        Interruption::TimedOut(timeout_secs) => {
+                            metadata.insert("timed_out".to_string(), json!(true));
+                            format!("Command timed out after {} seconds and was killed", timeout_secs)
+                        }
+                        Interruption::Cancelled => {
+                            metadata.insert("cancelled".to_string(), json!(true));
+                            "Command was cancelled by the user and killed".to_string()
+                        }
+                    };
+                    let partial = combine_output(stdout, stderr);
+                    let error = if partial.is_empty() {
+                        reason
+                    } else {
+                        format!("{}, partial output:\n{}", reason, partial)
+                    };
+                    return ToolResult::Error {
+                        error: append_notice(error, &notice),
+                        metadata: Some(metadata),
+                    };
+                }
                 
                 // Prepare error message if needed
                 let error_message = if exit_code != 0 && has_stderr {
@@ -140,23 +349,16 @@ impl BashTool {
                     None
                 };
                 
-                // Combine stdout and stderr for output
-                let output = if stderr.is_empty() {
-                    stdout
-                } else if stdout.is_empty() {
-                    stderr
-                } else {
-                    format!("{}\n--- STDERR ---\n{}", stdout, stderr)
-                };
+                let output = combine_output(stdout, stderr);
                 
                 if exit_code == 0 {
                     ToolResult::Success {
-                        output,
+                        output: append_notice(output, &notice),
                         metadata: Some(metadata),
                     }
                 } else {
                     ToolResult::Error {
-                        error: error_message.unwrap_or_else(|| format!("Command failed with exit code {}", exit_code)),
+                        error: append_notice(error_message.unwrap_or_else(|| format!("Command failed with exit code {}", exit_code)), &notice),
                         metadata: Some(metadata),
                     }
                 }
@@ -1,8 +1,10 @@
 pub mod structs;
 pub mod bash;
+pub mod session;
 
 #[cfg(test)]
 mod tests;
 
 pub use structs::BashToolParams;
-pub use bash::BashTool;
\ No newline at end of file
+pub use bash::BashTool;
+pub use session::BashSession;
\ No newline at end of file
@@ -0,0 +1,304 @@
+use super::bash::{kill_group, CommandOutput, Interruption, DRAIN_TIMEOUT};
+use super::structs::BashToolParams;
+use crate::tools::ToolContext;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::{mpsc, Mutex};
+
+/// A long-lived bash process shared by the calls of an agent,
+/// the working directory, environment and shell functions are kept between calls
+pub struct BashSession {
+    state: Mutex<SessionState>,
+}
+
+#[derive(Default)]
+struct SessionState {
+    /// None until the first command, or after the shell was reset, killed or exited
+    shell: Option<Shell>,
+    /// working directory reported after the last command, a restarted shell starts there
+    cwd: Option<PathBuf>,
+}
+
+/// The shell process, its process group is killed when dropped
+struct Shell {
+    child: Child,
+    pgid: Option<u32>,
+    stdin: ChildStdin,
+    stdout: mpsc::UnboundedReceiver<String>,
+    stderr: mpsc::UnboundedReceiver<String>,
+    /// marks the end of the output of a command on both streams
+    sentinel: String,
+}
+
+impl Drop for Shell {
+    fn drop(&mut self) {
+        if let Some(pgid) = self.pgid.take() {
+            kill_group(pgid);
+        }
+    }
+}
+
+/// How the read of a command output ended
+enum Completion {
+    Done { exit_code: i32, cwd: String },
+    Exited,
+    Interrupted(Interruption),
+}
+
+impl BashSession {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(SessionState::default()),
+        }
+    }
+
+    /// Kill the shell, the next command starts from a fresh shell in the original directory
+    pub async fn reset(&self) {
+        let mut state = self.state.lock().await;
+        *state = SessionState::default();
+    }
+
+    pub(super) async fn run(&self, params: &BashToolParams) -> Result<CommandOutput, Box<dyn std::error::Error + Send + Sync>> {
+        // calls sharing the session run one after the other
+        let mut state = self.state.lock().await;
+
+        if params.reset {
+            *state = SessionState::default();
+        }
+        if params.command.trim().is_empty() {
+            if params.reset {
+                return Ok(CommandOutput {
+                    stdout: "Shell session restarted".to_string(),
+                    stderr: String::new(),
+                    exit_code: 0,
+                    interrupted: None,
+                    notice: None,
+                });
+            }
+            return Err("Command cannot be empty".into());
+        }
+
+        if let Some(key) = params.env.keys().find(|key| !is_identifier(key)) {
+            return Err(format!("Invalid environment variable name: {}", key).into());
+        }
+
+        // the shell is taken out while the command runs, if the call is dropped it is killed
+        let mut shell = match state.shell.take() {
+            Some(shell) => shell,
+            None => Shell::spawn(state.cwd.as_ref())
+                .map_err(|e| format!("Could not start the shell session: {}", e))?,
+        };
+
+        shell.send(params).await
+            .map_err(|e| format!("Could not write to the shell session: {}", e))?;
+
+        let context = ToolContext::current();
+        let mut stdout = String::new();
+        let mut stderr = String::new();
+        let completion = shell.read(params.timeout, &context, &mut stdout, &mut stderr).await;
+
+        // the trailing newline printed before the sentinel is not part of the output
+        if stdout.ends_with('\n') {
+            stdout.pop();
+        }
+        if stderr.ends_with('\n') {
+            stderr.pop();
+        }
+
+        let (exit_code, interrupted, notice) = match completion {
+            Completion::Done { exit_code, cwd } => {
+                if !cwd.is_empty() {
+                    state.cwd = Some(PathBuf::from(cwd));
+                }
+                state.shell = Some(shell);
+                (exit_code, None, None)
+            }
+            Completion::Exited => {
+                let _ = tokio::time::timeout(DRAIN_TIMEOUT, shell.drain_stderr(&mut stderr)).await;
+                let exit_code = match shell.child.wait().await {
+                    Ok(status) => status.code().unwrap_or(-1),
+                    Err(_) => -1,
+                };
+                (exit_code, None, Some("The shell session exited, the next command starts a new shell".to_string()))
+            }
+            Completion::Interrupted(interruption) => {
+                drop(shell);
+                (-1, Some(interruption), Some(format!(
+                    "The shell session was killed and will restart in {}, variables and functions were lost",
+                    state.cwd.as_ref().map(|cwd| cwd.display().to_string()).unwrap_or_else(|| "the original directory".to_string()))))
+            }
+        };
+
+        Ok(CommandOutput {
+            stdout,
+            stderr,
+            exit_code,
+            interrupted,
+            notice,
+        })
+    }
+}
+
+impl Default for BashSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shell {
+    fn spawn(cwd: Option<&PathBuf>) -> std::io::Result<Self> {
+        let mut cmd = Command::new("bash");
+        cmd.args(["--noprofile", "--norc", "-s"]);
+        if let Some(cwd) = cwd.filter(|cwd| cwd.is_dir()) {
+            cmd.current_dir(cwd);
+        }
+
+        // the shell gets its own process group so that it can be killed with the commands it runs
+        cmd.stdin(Stdio::piped())
+           .stdout(Stdio::piped())
+           .stderr(Stdio::piped())
+           .kill_on_drop(true);
+        #[cfg(unix)]
+        cmd.process_group(0);
+
+        let mut child = cmd.spawn()?;
+        let stdin = child.stdin.take().ok_or_else(|| std::io::Error::other("no stdin"))?;
+        let stdout = lines(child.stdout.take());
+        let stderr = lines(child.stderr.take());
+        Ok(Self {
+            pgid: child.id(),
+            child,
+            stdin,
+            stdout,
+            stderr,
+            sentinel: format!("__SHAI_SESSION_{}__", uuid::Uuid::new_v4().simple()),
+        })
+    }
+
+    /// Write the command followed by the sentinels reporting its exit code and the working directory
+    async fn send(&mut self, params: &BashToolParams) -> std::io::Result<()> {
+        // eval keeps syntax errors from killing the shell, stdin must not be read from the session pipe
+        let mut command = format!("eval {}", quote(&params.command));
+        if params.working_dir.is_some() || !params.env.is_empty() {
+            // per call directory and variables don't leak into the session
+            let mut setup = Vec::new();
+            if let Some(working_dir) = &params.working_dir {
+                setup.push(format!("cd {}", quote(working_dir)));
+            }
+            for (key, value) in &params.env {
+                setup.push(format!("export {}={}", key, quote(value)));
+            }
+            command = format!("( {} && {} )", setup.join(" && "), command);
+        }
+
+        let script = format!(
+            "{command} < /dev/null\n__shai_status=$?\nprintf '\\n%s %d %s\\n' '{sentinel}' \"$__shai_status\" \"$PWD\"\nprintf '\\n%s\\n' '{sentinel}' >&2\n",
+            command = command,
+            sentinel = self.sentinel);
+        self.stdin.write_all(script.as_bytes()).await?;
+        self.stdin.flush().await
+    }
+
+    /// Read both streams until their sentinel, the shell exits or the command is interrupted
+    async fn read(&mut self, timeout: Option<u32>, context: &ToolContext, stdout: &mut String, stderr: &mut String) -> Completion {
+        let deadline = async {
+            match timeout {
+                Some(timeout_secs) => tokio::time::sleep(Duration::from_secs(timeout_secs as u64)).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::pin!(deadline);
+
+        let mut status: Option<(i32, String)> = None;
+        let mut stderr_done = false;
+        // a blank line is streamed once the next line shows it is not the one printed before the sentinel
+        let (mut stdout_blank, mut stderr_blank) = (false, false);
+        loop {
+            if stderr_done {
+                if let Some((exit_code, cwd)) = status.take() {
+                    return Completion::Done { exit_code, cwd };
+                }
+            }
+
+            tokio::select! {
+                line = self.stdout.recv(), if status.is_none() => match line {
+                    Some(line) => match line.strip_prefix(self.sentinel.as_str()) {
+                        Some(rest) => {
+                            let rest = rest.trim();
+                            let (code, cwd) = rest.split_once(' ').unwrap_or((rest, ""));
+                            status = Some((code.parse().unwrap_or(-1), cwd.to_string()));
+                        }
+                        None => push_line(context, stdout, &mut stdout_blank, line),
+                    },
+                    None => return Completion::Exited,
+                },
+                line = self.stderr.recv(), if !stderr_done => match line {
+                    Some(line) if line.starts_with(self.sentinel.as_str()) => stderr_done = true,
+                    Some(line) => push_line(context, stderr, &mut stderr_blank, line),
+                    None => return Completion::Exited,
+                },
+                _ = &mut deadline => {
+                    return Completion::Interrupted(Interruption::TimedOut(timeout.unwrap_or_default()));
+                }
+                _ = context.cancellation_token.cancelled() => {
+                    return Completion::Interrupted(Interruption::Cancelled);
+                }
+            }
+        }
+    }
+
+    async fn drain_stderr(&mut self, stderr: &mut String) {
+        while let Some(line) = self.stderr.recv().await {
+            if !line.starts_with(self.sentinel.as_str()) {
+                stderr.push_str(&line);
+            }
+        }
+    }
+}
+
+fn push_line(context: &ToolContext, text: &mut String, blank: &mut bool, line: String) {
+    if *blank {
+        context.emit("\n");
+    }
+    *blank = line == "\n";
+    if !*blank {
+        context.emit(line.clone());
+    }
+    text.push_str(&line);
+}
+
+/// Forward the lines of a pipe, the channel closes with the pipe
+fn lines<R: AsyncRead + Unpin + Send + 'static>(reader: Option<R>) -> mpsc::UnboundedReceiver<String> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let Some(reader) = reader else { return };
+        let mut reader = BufReader::new(reader);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match reader.read_until(b'\n', &mut line).await {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    if tx.send(String::from_utf8_lossy(&line).to_string()).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+    rx
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Single quote a string for bash
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
@@ -13,4 +13,7 @@ pub struct BashToolParams {
     /// Environment variables to set (optional)
     #[serde(default)]
     pub env: HashMap<String, String>,
+    /// Restart the shell session before running the command, dropping its directory, variables and functions (command may then be empty)
+    #[serde(default)]
+    pub reset: bool,
 }
@@ -1,6 +1,9 @@
 use super::structs::BashToolParams;
 use super::bash::BashTool;
-use crate::tools::{Tool, ToolCapability};
+use super::session::BashSession;
+use crate::tools::{Tool, ToolCapability, ToolContext, ToolResult};
+use tokio_util::sync::CancellationToken;
+use std::time::{Duration, Instant};
 use shai_llm::ToolDescription;
 use std::collections::HashMap;
 use serde_json::json;
@@ -30,6 +33,7 @@ async fn test_bash_tool_execution() {
         timeout: None,
         working_dir: None,
         env: HashMap::new(),
+        reset: false,
     };
     
     let result = Tool::execute(&tool, params).await;
@@ -42,4 +46,151 @@ async fn test_bash_tool_execution() {
     } else {
         panic!("Expected success result");
     }
-}
\ No newline at end of file
+}
+
+fn params(command: &str, timeout: Option<u32>) -> BashToolParams {
+    BashToolParams {
+        command: command.to_string(),
+        timeout,
+        working_dir: None,
+        env: HashMap::new(),
+        reset: false,
+    }
+}
+
+#[tokio::test]
+async fn test_bash_tool_timeout_kills_process_group() {
+    let dir = tempfile::tempdir().unwrap();
+    let marker = dir.path().join("marker");
+    let tool = BashTool::new();
+
+    // the background child belongs to the same process group and must die with it
+    let command = format!("echo started; (sleep 2 && touch {}) & sleep 30", marker.display());
+    let start = Instant::now();
+    let result = Tool::execute(&tool, params(&command, Some(1))).await;
+    assert!(start.elapsed() < Duration::from_secs(5), "timeout did not fire: {:?}", start.elapsed());
+
+    let ToolResult::Error { error, metadata } = result else {
+        panic!("Expected error result");
+    };
+    assert!(error.contains("timed out after 1 seconds"));
+    assert!(error.contains("started"), "partial output missing: {}", error);
+    assert_eq!(metadata.unwrap()["timed_out"], json!(true));
+
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert!(!marker.exists(), "background process survived the timeout");
+}
+
+#[tokio::test]
+async fn test_bash_tool_timeout_while_background_job_holds_output() {
+    let tool = BashTool::new();
+
+    // the shell exits at once but the background job keeps stdout open
+    let start = Instant::now();
+    let result = Tool::execute(&tool, params("echo started; sleep 30 &", Some(1))).await;
+    assert!(start.elapsed() < Duration::from_secs(5), "drain was not bounded: {:?}", start.elapsed());
+
+    let ToolResult::Error { error, .. } = result else {
+        panic!("Expected error result");
+    };
+    assert!(error.contains("timed out after 1 seconds"), "unexpected error: {}", error);
+    assert!(error.contains("started"), "partial output missing: {}", error);
+}
+
+#[tokio::test]
+async fn test_bash_tool_cancellation() {
+    let tool = BashTool::new();
+    let token = CancellationToken::new();
+    let cancel = token.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        cancel.cancel();
+    });
+
+    let start = Instant::now();
+    let result = ToolContext::new(token).scope(Tool::execute(&tool, params("echo partial; sleep 30", None))).await;
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    let ToolResult::Error { error, metadata } = result else {
+        panic!("Expected error result");
+    };
+    assert!(error.contains("cancelled"));
+    assert!(error.contains("partial"));
+    assert_eq!(metadata.unwrap()["cancelled"], json!(true));
+}
+
+#[tokio::test]
+async fn test_bash_tool_streams_output() {
+    let tool = BashTool::new();
+    let chunks = std::sync::Arc::new(std::sync::Mutex::new(Vec::<String>::new()));
+    let sink = chunks.clone();
+    let context = ToolContext::new(CancellationToken::new())
+        .with_output(move |chunk| sink.lock().unwrap().push(chunk));
+
+    let result = context.scope(Tool::execute(&tool, params("echo first; sleep 0.2; echo second", None))).await;
+    assert!(result.is_success());
+
+    let streamed = chunks.lock().unwrap().concat();
+    assert!(streamed.contains("first"));
+    assert!(streamed.contains("second"));
+    // the final result still carries the whole output
+    assert!(result.to_string().contains("first\nsecond"));
+}
+
+#[tokio::test]
+async fn test_bash_session_keeps_state() {
+    let dir = tempfile::tempdir().unwrap();
+    let tool = BashTool::with_session(std::sync::Arc::new(BashSession::new()));
+
+    let command = format!("cd \"{}\" && export GREETING=hello && greet() {{ echo \"$GREETING $1\"; }}", dir.path().display());
+    assert!(Tool::execute(&tool, params(&command, None)).await.is_success());
+
+    let result = Tool::execute(&tool, params("pwd; greet world", None)).await;
+    let ToolResult::Success { output, metadata } = result else {
+        panic!("Expected success result");
+    };
+    assert!(output.contains(&dir.path().display().to_string()), "cwd not kept: {}", output);
+    assert!(output.trim_end().ends_with("hello world"), "unexpected output: {:?}", output);
+    assert_eq!(metadata.unwrap()["session"], json!(true));
+}
+
+#[tokio::test]
+async fn test_bash_session_exit_code_and_reset() {
+    let tool = BashTool::with_session(std::sync::Arc::new(BashSession::new()));
+
+    let result = Tool::execute(&tool, params("export KEPT=1; echo oops >&2; false", None)).await;
+    let ToolResult::Error { error, metadata } = result else {
+        panic!("Expected error result");
+    };
+    assert!(error.contains("exit code 1"));
+    assert!(error.contains("oops"));
+    assert_eq!(metadata.unwrap()["exit_code"], json!(1));
+
+    // a syntax error doesn't kill the session
+    assert!(!Tool::execute(&tool, params("if then", None)).await.is_success());
+    assert_eq!(Tool::execute(&tool, params("echo $KEPT", None)).await.to_string().trim(), "1");
+
+    let mut reset = params("echo \"[$KEPT]\"", None);
+    reset.reset = true;
+    assert_eq!(Tool::execute(&tool, reset).await.to_string().trim(), "[]");
+}
+
+#[tokio::test]
+async fn test_bash_session_restarts_after_timeout() {
+    let dir = tempfile::tempdir().unwrap();
+    let tool = BashTool::with_session(std::sync::Arc::new(BashSession::new()));
+
+    let command = format!("cd \"{}\"", dir.path().display());
+    assert!(Tool::execute(&tool, params(&command, None)).await.is_success());
+
+    let result = Tool::execute(&tool, params("sleep 30", Some(1))).await;
+    let ToolResult::Error { error, .. } = result else {
+        panic!("Expected error result");
+    };
+    assert!(error.contains("timed out"));
+    assert!(error.contains("shell session was killed"));
+
+    // the new shell starts where the previous one was
+    let output = Tool::execute(&tool, params("pwd", None)).await.to_string();
+    assert_eq!(output.trim(), dir.path().canonicalize().unwrap().display().to_string());
+}
@@ -1,10 +1,54 @@
-use super::structs::{FetchToolParams, HttpMethod};
-use crate::tools::{ToolResult, tool};
+use super::html::{html_title, html_to_markdown, html_to_text};
+use super::structs::{FetchFormat, FetchToolParams, HttpMethod};
+use crate::tools::{ToolResult, TrustLevel, tool};
+use reqwest::{header, redirect, Url};
 use serde_json::json;
 use std::collections::HashMap;
-use reqwest;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+/// Redirects followed before giving up
+const MAX_REDIRECTS: usize = 10;
+
+/// Kind of content of a response, from its content type or its bytes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ContentKind {
+    Html,
+    Json,
+    Text,
+    Binary,
+}
+
+impl ContentKind {
+    pub(crate) fn detect(content_type: &str, body: &[u8]) -> Self {
+        let mime = content_type.split(';').next().unwrap_or("").trim().to_lowercase();
+        match mime.as_str() {
+            "text/html" | "application/xhtml+xml" => return Self::Html,
+            "application/json" => return Self::Json,
+            m if m.ends_with("+json") => return Self::Json,
+            m if m.starts_with("text/") || m.ends_with("+xml") || m.ends_with("/xml")
+                || m.contains("javascript") || m.contains("yaml") || m.contains("toml") => return Self::Text,
+            m if m.starts_with("image/") || m.starts_with("audio/") || m.starts_with("video/") || m.starts_with("font/") => return Self::Binary,
+            _ => {}
+        }
+
+        // unknown or generic type (e.g. application/octet-stream), look at the bytes
+        let sample = &body[..body.len().min(8192)];
+        let valid_utf8 = match std::str::from_utf8(sample) {
+            Ok(_) => true,
+            // a character cut by the sample is fine
+            Err(e) => e.error_len().is_none(),
+        };
+        if sample.contains(&0) || !valid_utf8 {
+            Self::Binary
+        } else if sample.trim_ascii_start().starts_with(b"<") && String::from_utf8_lossy(sample).to_lowercase().contains("<html") {
+            Self::Html
+        } else {
+            Self::Text
+        }
+    }
+}
+
 pub struct FetchTool;
 
 impl FetchTool {
@@ -13,17 +57,59 @@ impl FetchTool {
     }
 }
 
+/// Download the body up to max_bytes, true if it was cut
+async fn read_body(mut response: reqwest::Response, max_bytes: usize) -> Result<(Vec<u8>, bool), reqwest::Error> {
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if body.len() + chunk.len() > max_bytes {
+            body.extend_from_slice(&chunk[..max_bytes - body.len()]);
+            return Ok((body, true));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok((body, false))
+}
+
+/// Body as returned to the model, Err if it cannot be returned as text
+pub(crate) fn render_body(body: &[u8], kind: ContentKind, format: FetchFormat, base: &Url) -> Result<String, String> {
+    if kind == ContentKind::Binary {
+        return Err("binary content".to_string());
+    }
+
+    let text = String::from_utf8_lossy(body);
+    let rendered = match (format, kind) {
+        (FetchFormat::Raw, _) => text.to_string(),
+        (FetchFormat::Auto, ContentKind::Json) => serde_json::from_str::<serde_json::Value>(&text)
+            .ok()
+            .and_then(|value| serde_json::to_string_pretty(&value).ok())
+            .unwrap_or_else(|| text.to_string()),
+        (FetchFormat::Auto, ContentKind::Html) | (FetchFormat::Markdown, _) => {
+            let markdown = html_to_markdown(&text, Some(base));
+            match html_title(&text) {
+                Some(title) if !markdown.lines().any(|line| line.starts_with("# ")) => format!("# {}\n\n{}", title, markdown),
+                _ => markdown,
+            }
+        }
+        (FetchFormat::Text, _) => html_to_text(&text),
+        (FetchFormat::Auto, _) => text.to_string(),
+    };
+    Ok(rendered)
+}
+
 #[tool(name = "fetch", description = r#"Retrieves content from a URL. This tool is ideal for accessing web pages, APIs, or other online resources.
 
 **Functionality:**
 - Supports `GET`, `POST`, `PUT`, and `DELETE` HTTP methods.
 - Allows for custom headers and request bodies, making it suitable for interacting with REST APIs.
 - Includes a timeout to prevent indefinite hangs on unresponsive servers.
+- Follows redirects and reports the final URL.
 
 **Usage Notes:**
 - Provide a fully-qualified URL.
 - For API interactions, you can set the `Content-Type` header to `application/json` and provide a JSON string as the `body`.
-- The tool will return the raw response body, which you can then parse or analyze.
+- By default (`format='auto'`) HTML pages are converted to Markdown keeping only their main content, JSON is pretty-printed and other text is returned as is. Binary content (images, archives, ...) is not returned.
+- Use `format='text'` for the plain text of a page, or `format='raw'` for the body exactly as received (e.g. to inspect the HTML itself).
+- Bodies larger than `max_bytes` are cut.
 
 **Examples:**
 - **Get a web page:** `fetch(url='https://example.com')`
@@ -32,8 +118,20 @@ impl FetchTool {
 "#, capabilities = [ToolCapability::Network])]
 impl FetchTool {
     async fn execute(&self, params: FetchToolParams) -> ToolResult {
+        // record every hop so the model knows where the content really came from
+        let redirects = Arc::new(Mutex::new(Vec::new()));
+        let hops = redirects.clone();
+        let policy = redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() > MAX_REDIRECTS {
+                return attempt.error("too many redirects");
+            }
+            hops.lock().unwrap().push(attempt.url().to_string());
+            attempt.follow()
+        });
+
         let client = reqwest::Client::builder()
             .timeout(Duration::from_secs(params.timeout))
+            .redirect(policy)
             .build();
 
         let client = match client {
@@ -62,45 +160,85 @@ impl FetchTool {
         }
 
         // Execute the request
-        match request_builder.send().await {
-            Ok(response) => {
-                let status = response.status();
-                let headers: HashMap<String, String> = response
-                    .headers()
-                    .iter()
-                    .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
-                    .collect();
-
-                match response.text().await {
-                    Ok(body) => {
-                        let mut meta = HashMap::new();
-                        meta.insert("url".to_string(), json!(params.url));
-                        meta.insert("method".to_string(), json!(match params.method {
-                            HttpMethod::Get => "GET",
-                            HttpMethod::Post => "POST",
-                            HttpMethod::Put => "PUT",
-                            HttpMethod::Delete => "DELETE",
-                        }));
-                        meta.insert("status_code".to_string(), json!(status.as_u16()));
-                        meta.insert("response_headers".to_string(), json!(headers));
-                        meta.insert("content_length".to_string(), json!(body.len()));
-
-                        if status.is_success() {
-                            ToolResult::Success {
-                                output: body,
-                                metadata: Some(meta),
-                            }
-                        } else {
-                            ToolResult::Error {
-                                error: format!("HTTP request failed with status: {}", status),
-                                metadata: Some(meta),
-                            }
-                        }
-                    },
-                    Err(e) => ToolResult::error(format!("Failed to read response body: {}", e))
-                }
-            },
-            Err(e) => ToolResult::error(format!("HTTP request failed: {}", e))
+        let response = match request_builder.send().await {
+            Ok(response) => response,
+            Err(e) => return ToolResult::error(format!("HTTP request failed: {}", e))
+        };
+
+        let status = response.status();
+        let final_url = response.url().clone();
+        let headers: HashMap<String, String> = response
+            .headers()
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+            .collect();
+        let content_type = response.headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_string();
+        let advertised_length = response.content_length();
+
+        let (body, truncated) = match read_body(response, params.max_bytes).await {
+            Ok(body) => body,
+            Err(e) => return ToolResult::error(format!("Failed to read response body: {}", e))
+        };
+        let redirects = redirects.lock().unwrap().clone();
+        let kind = ContentKind::detect(&content_type, &body);
+
+        let mut meta = HashMap::new();
+        meta.insert("url".to_string(), json!(params.url));
+        meta.insert("method".to_string(), json!(match params.method {
+            HttpMethod::Get => "GET",
+            HttpMethod::Post => "POST",
+            HttpMethod::Put => "PUT",
+            HttpMethod::Delete => "DELETE",
+        }));
+        meta.insert("status_code".to_string(), json!(status.as_u16()));
+        meta.insert("response_headers".to_string(), json!(headers));
+        meta.insert("content_length".to_string(), json!(body.len()));
+        meta.insert("content_type".to_string(), json!(content_type));
+        meta.insert("truncated".to_string(), json!(truncated));
+        if !redirects.is_empty() {
+            meta.insert("final_url".to_string(), json!(final_url.to_string()));
+            meta.insert("redirects".to_string(), json!(redirects));
         }
+
+        if !status.is_success() {
+            return ToolResult::Error {
+                error: format!("HTTP request failed with status: {}", status),
+                metadata: Some(meta),
+            };
+        }
+
+        let rendered = match render_body(&body, kind, params.format, &final_url) {
+            Ok(rendered) => rendered,
+            Err(_) => {
+                let size = match (truncated, advertised_length) {
+                    (true, Some(length)) => format!("{} bytes", length),
+                    (true, None) => format!("more than {} bytes", body.len()),
+                    (false, _) => format!("{} bytes", body.len()),
+                };
+                let content_type = if content_type.is_empty() { "unknown type" } else { content_type.as_str() };
+                return ToolResult::Error {
+                    error: format!("The response is binary content ({}, {}), it cannot be returned as text", content_type, size),
+                    metadata: Some(meta),
+                };
+            }
+        };
+
+        let mut output = String::new();
+        if !redirects.is_empty() {
+            output.push_str(&format!("[Redirected from {} to {}]\n\n", params.url, final_url));
+        }
+        output.push_str(&rendered);
+        if truncated {
+            output.push_str(&format!("\n\n[... body cut at {} bytes, raise max_bytes to get more ...]", params.max_bytes));
+        }
+
+        ToolResult::Success {
+            output,
+            metadata: Some(meta),
+        }.with_trust(TrustLevel::Untrusted)
     }
 }
@@ -0,0 +1,338 @@
+use reqwest::Url;
+use scraper::{ElementRef, Html, Node, Selector};
+
+/// Elements that never carry page content
+const SKIPPED: &[&str] = &[
+    "script", "style", "noscript", "template", "svg", "canvas", "iframe",
+    "nav", "footer", "aside", "form", "button", "select", "input", "head",
+];
+
+/// Where the main content of a page usually lives, by priority
+const MAIN_CONTENT: &[&str] = &["main", "article", "[role=main]", "#content", "#main", "body"];
+
+/// Deepest element converted with its markup, the text of deeper ones is kept as is, so a maliciously nested page cannot overflow the stack
+const MAX_DEPTH: usize = 256;
+
+/// Convert an html page into markdown, keeping only its main content
+pub fn html_to_markdown(html: &str, base: Option<&Url>) -> String {
+    convert(html, base, false)
+}
+
+/// Convert an html page into plain text, keeping only its main content
+pub fn html_to_text(html: &str) -> String {
+    convert(html, None, true)
+}
+
+/// Title of an html page, if any
+pub fn html_title(html: &str) -> Option<String> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("title").unwrap();
+    document.select(&selector).next()
+        .map(|title| collapse_whitespace(&title.text().collect::<String>()).trim().to_string())
+        .filter(|title| !title.is_empty())
+}
+
+fn convert(html: &str, base: Option<&Url>, plain: bool) -> String {
+    let document = Html::parse_document(html);
+    let root = MAIN_CONTENT.iter()
+        .filter_map(|selector| Selector::parse(selector).ok())
+        .find_map(|selector| document.select(&selector).next())
+        .unwrap_or_else(|| document.root_element());
+
+    let mut converter = Converter { base, plain, out: String::new(), lists: Vec::new(), depth: 0 };
+    converter.element(root);
+    cleanup(&converter.out)
+}
+
+enum ListKind {
+    Unordered,
+    Ordered(usize),
+}
+
+struct Converter<'a> {
+    base: Option<&'a Url>,
+    /// no markdown syntax, only the text and the line structure
+    plain: bool,
+    out: String,
+    lists: Vec<ListKind>,
+    /// elements being converted, from the root to the current one
+    depth: usize,
+}
+
+impl Converter<'_> {
+    fn children(&mut self, element: ElementRef) {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => self.text(text),
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        self.element(child);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Render the children of an element on their own, e.g. the text of a link
+    fn render(&mut self, element: ElementRef) -> String {
+        let out = std::mem::take(&mut self.out);
+        self.children(element);
+        std::mem::replace(&mut self.out, out)
+    }
+
+    fn text(&mut self, text: &str) {
+        let text = collapse_whitespace(text);
+        let text = if self.out.is_empty() || self.out.ends_with(char::is_whitespace) {
+            text.trim_start()
+        } else {
+            &text
+        };
+        self.out.push_str(text);
+    }
+
+    fn block_break(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push_str(if self.out.ends_with('\n') { "\n" } else { "\n\n" });
+        }
+    }
+
+    fn element(&mut self, element: ElementRef) {
+        let name = element.value().name();
+        if SKIPPED.contains(&name) || element.value().attr("hidden").is_some() {
+            return;
+        }
+        if self.depth >= MAX_DEPTH {
+            // the text iterator walks the subtree without recursing
+            for text in element.text() {
+                self.text(text);
+            }
+            return;
+        }
+
+        self.depth += 1;
+        self.markup(element, name);
+        self.depth -= 1;
+    }
+
+    fn markup(&mut self, element: ElementRef, name: &str) {
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse::<usize>().unwrap_or(1);
+                let title = self.render(element).trim().to_string();
+                if !title.is_empty() {
+                    self.block_break();
+                    if !self.plain {
+                        self.out.push_str(&format!("{} ", "#".repeat(level)));
+                    }
+                    self.out.push_str(&title);
+                    self.block_break();
+                }
+            }
+            "p" | "div" | "section" | "article" | "main" | "header" | "figure" | "figcaption" | "dl" | "dd" | "dt" | "details" | "summary" => {
+                self.block_break();
+                self.children(element);
+                self.block_break();
+            }
+            "br" => self.out.push('\n'),
+            "hr" => {
+                self.block_break();
+                if !self.plain {
+                    self.out.push_str("---");
+                }
+                self.block_break();
+            }
+            "a" => {
+                let text = self.render(element).trim().to_string();
+                let href = element.value().attr("href").map(str::trim).unwrap_or("");
+                if self.plain || text.is_empty() || href.is_empty() || href.starts_with('#') || href.starts_with("javascript:") {
+                    self.text(&text);
+                } else {
+                    self.inline(&format!("[{}]({})", text, self.resolve(href)));
+                }
+            }
+            "img" => {
+                let alt = element.value().attr("alt").map(str::trim).unwrap_or("");
+                let src = element.value().attr("src").unwrap_or("");
+                if !alt.is_empty() && !self.plain {
+                    self.inline(&format!("![{}]({})", alt, self.resolve(src)));
+                }
+            }
+            "strong" | "b" | "em" | "i" | "code" | "kbd" | "samp" => {
+                let text = self.render(element);
+                let marker = match name {
+                    _ if self.plain => "",
+                    "strong" | "b" => "**",
+                    "em" | "i" => "*",
+                    _ => "`",
+                };
+                if !text.trim().is_empty() {
+                    self.inline(&format!("{}{}{}", marker, text.trim(), marker));
+                }
+            }
+            "pre" => self.code_block(element),
+            "ul" | "ol" => {
+                self.lists.push(match name {
+                    "ol" => ListKind::Ordered(element.value().attr("start").and_then(|s| s.parse().ok()).unwrap_or(1)),
+                    _ => ListKind::Unordered,
+                });
+                if self.lists.len() == 1 {
+                    self.block_break();
+                } else if !self.out.ends_with('\n') {
+                    self.out.push('\n');
+                }
+                self.children(element);
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.block_break();
+                }
+            }
+            "li" => self.list_item(element),
+            "blockquote" => {
+                let quote = cleanup(&self.render(element));
+                self.block_break();
+                let prefix = if self.plain { "" } else { "> " };
+                let quoted: Vec<String> = quote.lines().map(|line| format!("{}{}", prefix, line).trim_end().to_string()).collect();
+                self.out.push_str(&quoted.join("\n"));
+                self.block_break();
+            }
+            "table" => self.table(element),
+            _ => self.children(element),
+        }
+    }
+
+    /// Inline markup, separated from the previous word when needed
+    fn inline(&mut self, markup: &str) {
+        if !self.out.is_empty() && !self.out.ends_with(|c: char| c.is_whitespace() || "([\"'".contains(c)) {
+            self.out.push(' ');
+        }
+        self.out.push_str(markup);
+    }
+
+    fn resolve(&self, href: &str) -> String {
+        match self.base.and_then(|base| base.join(href).ok()) {
+            Some(url) => url.to_string(),
+            None => href.to_string(),
+        }
+    }
+
+    fn code_block(&mut self, element: ElementRef) {
+        let code = element.text().collect::<String>();
+        let code = code.trim_matches('\n');
+        self.block_break();
+        if self.plain {
+            self.out.push_str(code);
+        } else {
+            let fence = if code.contains("```") { "~~~" } else { "```" };
+            self.out.push_str(&format!("{}{}\n{}\n{}", fence, code_language(element).unwrap_or_default(), code, fence));
+        }
+        self.block_break();
+    }
+
+    fn list_item(&mut self, element: ElementRef) {
+        let depth = self.lists.len().saturating_sub(1);
+        let marker = match self.lists.last_mut() {
+            Some(ListKind::Ordered(n)) => {
+                *n += 1;
+                format!("{}.", *n - 1)
+            }
+            _ => "-".to_string(),
+        };
+
+        // nested lists are rendered with the item, one level deeper
+        let content = cleanup(&self.render(element));
+        let indent = "  ".repeat(depth);
+        let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+        self.out.push_str(&format!("{}{} {}\n", indent, marker, lines.next().unwrap_or("").trim_start()));
+        for line in lines {
+            // nested list items already carry their own indentation
+            let nested = line.trim_start().starts_with("- ") || line.trim_start().split_once(". ").is_some_and(|(n, _)| n.parse::<usize>().is_ok());
+            let line = if nested { line.to_string() } else { format!("{}  {}", indent, line.trim_start()) };
+            self.out.push_str(&format!("{}\n", line));
+        }
+    }
+
+    fn table(&mut self, element: ElementRef) {
+        let row_selector = Selector::parse("tr").unwrap();
+        let cell_selector = Selector::parse("th, td").unwrap();
+        let rows: Vec<Vec<String>> = element.select(&row_selector)
+            .map(|row| row.select(&cell_selector)
+                .map(|cell| {
+                    let mut converter = Converter { base: self.base, plain: self.plain, out: String::new(), lists: Vec::new(), depth: self.depth };
+                    converter.children(cell);
+                    cleanup(&converter.out).replace('\n', " ").replace('|', "\\|")
+                })
+                .collect())
+            .filter(|cells: &Vec<String>| !cells.is_empty())
+            .collect();
+        if rows.is_empty() {
+            return;
+        }
+
+        self.block_break();
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        for (i, row) in rows.iter().enumerate() {
+            let mut cells = row.clone();
+            cells.resize(columns, String::new());
+            if self.plain {
+                self.out.push_str(&format!("{}\n", cells.join("\t")));
+                continue;
+            }
+            self.out.push_str(&format!("| {} |\n", cells.join(" | ")));
+            if i == 0 {
+                self.out.push_str(&format!("|{}\n", " --- |".repeat(columns)));
+            }
+        }
+        self.block_break();
+    }
+}
+
+/// Language of a code block from `language-xxx` / `lang-xxx` classes on the pre or its code child
+fn code_language(element: ElementRef) -> Option<String> {
+    let code = Selector::parse("code").unwrap();
+    std::iter::once(element)
+        .chain(element.select(&code))
+        .flat_map(|el| el.value().classes().map(str::to_string).collect::<Vec<_>>())
+        .find_map(|class| class.strip_prefix("language-").or_else(|| class.strip_prefix("lang-")).map(str::to_string))
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut space = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            space = true;
+        } else {
+            if space {
+                out.push(' ');
+                space = false;
+            }
+            out.push(c);
+        }
+    }
+    if space {
+        out.push(' ');
+    }
+    out
+}
+
+/// Trim trailing spaces and collapse runs of blank lines, code blocks included
+fn cleanup(text: &str) -> String {
+    let mut out = Vec::new();
+    let mut blank = false;
+    for line in text.lines().map(str::trim_end) {
+        if line.is_empty() {
+            blank = !out.is_empty();
+            continue;
+        }
+        if blank {
+            out.push("");
+            blank = false;
+        }
+        out.push(line);
+    }
+    out.join("\n")
+}
@@ -1,8 +1,9 @@
 pub mod structs;
 pub mod fetch;
+pub mod html;
 
 #[cfg(test)]
 mod tests;
 
-pub use structs::{FetchToolParams, HttpMethod};
+pub use structs::{FetchFormat, FetchToolParams, HttpMethod};
 pub use fetch::FetchTool;
\ No newline at end of file
@@ -18,6 +18,27 @@ pub struct FetchToolParams {
     /// Request timeout in seconds (optional, defaults to 30)
     #[serde(default = "default_timeout")]
     pub timeout: u64,
+    /// How to return the body (optional, defaults to auto)
+    #[serde(default)]
+    pub format: FetchFormat,
+    /// Maximum size of the body in bytes, the rest is not downloaded (optional, defaults to 5MB)
+    #[serde(default = "default_max_bytes")]
+    pub max_bytes: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+#[schemars(inline)]
+pub enum FetchFormat {
+    /// html as markdown, json pretty-printed, other text as is, binary content refused
+    #[default]
+    Auto,
+    /// html converted to markdown
+    Markdown,
+    /// html converted to plain text
+    Text,
+    /// the body as received
+    Raw,
 }
 
 #[derive(Debug, Clone, Deserialize, JsonSchema)]
@@ -37,3 +58,7 @@ fn default_method() -> HttpMethod {
 fn default_timeout() -> u64 {
     30
 }
+
+fn default_max_bytes() -> usize {
+    5 * 1024 * 1024
+}
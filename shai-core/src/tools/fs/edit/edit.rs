@@ -1,10 +1,10 @@
 use super::structs::EditToolParams;
-use super::super::{FsOperationLog, FsOperationType};
+use super::matcher;
+use super::super::{text, FsOperationLog, FsOperationType};
 use crate::tools::{tool, ToolResult};
 use similar::{ChangeTag, TextDiff};
 use serde_json::json;
 use std::collections::HashMap;
-use std::fs;
 use std::path::Path;
 use std::sync::Arc;
 
@@ -76,27 +76,16 @@ impl EditTool {
     }
 
 
+    /// Replace old_string in content, see [`matcher`](super::matcher) for how it is located
     pub fn perform_edit_on_content(&self, content: &str, old_string: &str, new_string: &str, replace_all: bool) -> Result<(String, usize), String> {
-        // Check if the old_string exists in the content
-        if !content.contains(old_string) {
-            return Err(format!("Pattern '{}' not found in content", old_string));
-        }
-
-        // Perform the replacement
-        let (new_content, replacements) = if replace_all {
-            let new_content = content.replace(old_string, new_string);
-            let replacements = content.matches(old_string).count();
-            (new_content, replacements)
-        } else {
-            let new_content = content.replacen(old_string, new_string, 1);
-            (new_content, 1)
-        };
-
+        let (new_content, replacements, _) = matcher::replace(content, old_string, new_string, replace_all)?;
         Ok((new_content, replacements))
     }
 
+    /// Write the edited content back in the encoding and line ending of the file
     pub fn commit_edit(&self, path: &str, new_content: &str) -> Result<(), String> {
-        fs::write(path, new_content).map_err(|e| e.to_string())
+        text::check_no_new_masks(path, new_content)?;
+        text::write_text(path, new_content)
     }
 
     fn perform_edit(&self, params: &EditToolParams, preview: bool) -> Result<(String, usize), String> {
@@ -108,7 +97,7 @@ impl EditTool {
         }
 
         // Read the file content
-        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let (content, _) = text::read_text(&params.path)?;
 
         // Perform edit on content
         let (new_content, replacements) = self.perform_edit_on_content(&content, &params.old_string, &params.new_string, params.replace_all)?;
@@ -133,10 +122,12 @@ impl EditTool {
 
 **Prerequisites:**
 - Before using this tool, you are required to have inspected the file's content using the `read` tool in the current conversation. An attempt to edit a file without prior reading will result in an error.
+- If the file was modified on disk since you last read or edited it (by the user, a formatter, a command...), the edit is refused and the error shows the changes: read the file again before editing it.
 
 **Usage Guidelines:**
-- The `old_string` parameter demands an exact, literal match of the text to be replaced. This includes all whitespace and indentation. When copying text from the `read` tool's output, you must omit the line number prefix.
-- The operation will fail if the `old_string` is not unique within the file. To resolve this, provide more surrounding context to make the `old_string` unique.
+- The `old_string` parameter should be an exact, literal match of the text to be replaced, including whitespace and indentation. When copying text from the `read` tool's output, you must omit the line number prefix.
+- If there is no exact match, line endings and then indentation and trailing whitespace are ignored, `new_string` is re-indented to fit the file. When nothing matches, the error shows the closest region of the file and how it differs from `old_string`.
+- The operation will fail if the `old_string` is not unique within the file, the error lists the line of each match. To resolve this, provide more surrounding context to make the `old_string` unique.
 - For situations where you intend to replace every occurrence of a string (e.g., renaming a variable), set the `replace_all` parameter to `true`.
 - Prioritize modifying existing files. Avoid creating new files unless the task explicitly requires it.
 "#, capabilities = [ToolCapability::Read, ToolCapability::Write])]
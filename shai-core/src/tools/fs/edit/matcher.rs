@@ -0,0 +1,225 @@
+use similar::TextDiff;
+
+/// Lines of context shown around the closest candidate when nothing matches
+const CANDIDATE_DIFF_CONTEXT: usize = 3;
+
+/// Below this similarity the closest region is too far off to be worth showing
+const MIN_CANDIDATE_RATIO: f32 = 0.5;
+
+/// Line comparisons allowed while looking for the closest candidate, keeps huge files cheap
+const MAX_CANDIDATE_COMPARISONS: usize = 500_000;
+
+/// Line numbers listed when old_string is ambiguous
+const MAX_LISTED_MATCHES: usize = 20;
+
+/// How old_string was located in the content, from the strictest to the most tolerant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchStrategy {
+    Exact,
+    /// Same text once `\r\n` and `\n` are considered equal
+    LineEnding,
+    /// Same lines once indentation and trailing whitespace are ignored
+    Whitespace,
+}
+
+/// A region of the content and the text replacing it
+#[derive(Debug, Clone)]
+pub struct EditMatch {
+    pub start: usize,
+    pub end: usize,
+    pub replacement: String,
+}
+
+/// Byte offsets of a line: `end` excludes the line ending, `next` is the start of the next line
+struct Line {
+    start: usize,
+    end: usize,
+    next: usize,
+}
+
+fn split_lines(content: &str) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for piece in content.split_inclusive('\n') {
+        let text = piece.trim_end_matches(['\n', '\r']);
+        lines.push(Line { start, end: start + text.len(), next: start + piece.len() });
+        start += piece.len();
+    }
+    lines
+}
+
+fn line_number(content: &str, offset: usize) -> usize {
+    content[..offset].matches('\n').count() + 1
+}
+
+fn line_ending(content: &str) -> &'static str {
+    if content.contains("\r\n") { "\r\n" } else { "\n" }
+}
+
+fn with_line_ending(text: &str, eol: &str) -> String {
+    let text = text.replace("\r\n", "\n");
+    if eol == "\n" { text } else { text.replace('\n', eol) }
+}
+
+fn trim_newlines(text: &str) -> &str {
+    text.trim_matches(['\n', '\r'])
+}
+
+fn indentation(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+fn exact_matches(content: &str, old_string: &str, new_string: &str) -> Vec<EditMatch> {
+    content.match_indices(old_string)
+        .map(|(start, found)| EditMatch { start, end: start + found.len(), replacement: new_string.to_string() })
+        .collect()
+}
+
+/// Whole-line matches ignoring indentation and trailing whitespace, new_string is re-indented
+/// by the difference between the indentation of old_string and the one found in the file
+fn whitespace_matches(content: &str, lines: &[Line], old_string: &str, new_string: &str, eol: &str) -> Vec<EditMatch> {
+    let pattern: Vec<&str> = trim_newlines(old_string).lines().collect();
+    if pattern.is_empty() || pattern.len() > lines.len() {
+        return Vec::new();
+    }
+    // a pattern made of whitespace only would match any blank lines
+    let Some(anchor) = pattern.iter().position(|line| !line.trim().is_empty()) else {
+        return Vec::new();
+    };
+    let pattern_indent = indentation(pattern[anchor]);
+    let replacement_lines: Vec<&str> = trim_newlines(new_string).lines().collect();
+
+    let mut matches = Vec::new();
+    let mut i = 0;
+    while i + pattern.len() <= lines.len() {
+        let window = &lines[i..i + pattern.len()];
+        let found = window.iter().zip(&pattern)
+            .all(|(line, expected)| content[line.start..line.end].trim() == expected.trim());
+        if !found {
+            i += 1;
+            continue;
+        }
+
+        let file_indent = indentation(&content[window[anchor].start..window[anchor].end]);
+        let replacement = replacement_lines.iter()
+            .map(|line| match line.strip_prefix(pattern_indent) {
+                _ if line.trim().is_empty() => String::new(),
+                Some(rest) => format!("{}{}", file_indent, rest),
+                None => line.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(eol);
+        let last = &window[window.len() - 1];
+        // removing the lines altogether rather than leaving an empty one
+        let end = if replacement_lines.is_empty() { last.next } else { last.end };
+        matches.push(EditMatch { start: window[0].start, end, replacement });
+        i += pattern.len();
+    }
+    matches
+}
+
+/// Every match of old_string with the first strategy that finds any
+pub fn find_matches(content: &str, old_string: &str, new_string: &str) -> Result<(MatchStrategy, Vec<EditMatch>), String> {
+    if old_string.is_empty() {
+        return Err("old_string cannot be empty".to_string());
+    }
+
+    let matches = exact_matches(content, old_string, new_string);
+    if !matches.is_empty() {
+        return Ok((MatchStrategy::Exact, matches));
+    }
+
+    let eol = line_ending(content);
+    let old_normalized = with_line_ending(old_string, eol);
+    if old_normalized != old_string {
+        let matches = exact_matches(content, &old_normalized, &with_line_ending(new_string, eol));
+        if !matches.is_empty() {
+            return Ok((MatchStrategy::LineEnding, matches));
+        }
+    }
+
+    let lines = split_lines(content);
+    let matches = whitespace_matches(content, &lines, old_string, new_string, eol);
+    if !matches.is_empty() {
+        return Ok((MatchStrategy::Whitespace, matches));
+    }
+
+    Err(not_found_error(content, &lines, old_string))
+}
+
+/// Replace old_string by new_string, it must be unique unless replace_all is set
+pub fn replace(content: &str, old_string: &str, new_string: &str, replace_all: bool) -> Result<(String, usize, MatchStrategy), String> {
+    let (strategy, matches) = find_matches(content, old_string, new_string)?;
+    if matches.len() > 1 && !replace_all {
+        return Err(ambiguous_error(content, &matches));
+    }
+
+    let mut result = String::with_capacity(content.len());
+    let mut cursor = 0;
+    for found in &matches {
+        result.push_str(&content[cursor..found.start]);
+        result.push_str(&found.replacement);
+        cursor = found.end;
+    }
+    result.push_str(&content[cursor..]);
+    Ok((result, matches.len(), strategy))
+}
+
+fn ambiguous_error(content: &str, matches: &[EditMatch]) -> String {
+    let mut lines: Vec<String> = matches.iter()
+        .take(MAX_LISTED_MATCHES)
+        .map(|found| line_number(content, found.start).to_string())
+        .collect();
+    if matches.len() > MAX_LISTED_MATCHES {
+        lines.push("…".to_string());
+    }
+    format!(
+        "old_string matches {} times, at lines {}. Include more surrounding lines to make it unique, or set replace_all to replace every occurrence",
+        matches.len(),
+        lines.join(", ")
+    )
+}
+
+/// The window of lines most similar to old_string, as (first line, last line, similarity)
+fn closest_candidate(content: &str, lines: &[Line], old_string: &str) -> Option<(usize, usize, f32)> {
+    let pattern: Vec<&str> = trim_newlines(old_string).lines().map(str::trim).collect();
+    if pattern.is_empty() || lines.is_empty() || lines.len() * pattern.len() > MAX_CANDIDATE_COMPARISONS {
+        return None;
+    }
+
+    let size = pattern.len().min(lines.len());
+    let mut best: Option<(usize, f32)> = None;
+    for i in 0..=lines.len() - size {
+        let score = lines[i..i + size].iter().zip(&pattern)
+            .map(|(line, expected)| TextDiff::from_chars(content[line.start..line.end].trim(), *expected).ratio())
+            .sum::<f32>() / pattern.len() as f32;
+        match best {
+            Some((_, best_score)) if best_score >= score => {}
+            _ => best = Some((i, score)),
+        }
+    }
+    best.map(|(i, score)| (i, i + size - 1, score))
+}
+
+/// Points the model to the closest region with a diff, so it can fix old_string
+fn not_found_error(content: &str, lines: &[Line], old_string: &str) -> String {
+    let Some((first, last, score)) = closest_candidate(content, lines, old_string).filter(|(_, _, score)| *score >= MIN_CANDIDATE_RATIO) else {
+        return "old_string was not found in the file. Read the file again and copy the text to replace exactly".to_string();
+    };
+
+    let region = content[lines[first].start..lines[last].end].replace("\r\n", "\n");
+    let expected = format!("{}\n", trim_newlines(old_string).replace("\r\n", "\n"));
+    let actual = format!("{}\n", region);
+    let diff = TextDiff::from_lines(&expected, &actual);
+    let diff = diff.unified_diff()
+        .context_radius(CANDIDATE_DIFF_CONTEXT)
+        .header("old_string", "file")
+        .to_string();
+    format!(
+        "old_string was not found in the file. The closest match is at lines {}-{} ({:.0}% similar):\n{}\nCopy the text from the file exactly and retry",
+        first + 1,
+        last + 1,
+        score * 100.0,
+        diff.trim_end()
+    )
+}
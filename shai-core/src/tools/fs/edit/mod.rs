@@ -1,8 +1,10 @@
 pub mod structs;
 pub mod edit;
+pub mod matcher;
 
 #[cfg(test)]
 mod tests;
 
 pub use structs::EditToolParams;
-pub use edit::EditTool;
\ No newline at end of file
+pub use edit::EditTool;
+pub use matcher::MatchStrategy;
\ No newline at end of file
@@ -1,5 +1,6 @@
 use super::structs::EditToolParams;
 use super::edit::EditTool;
+use super::matcher::{self, MatchStrategy};
 use crate::tools::{Tool, ToolCapability, FsOperationLog};
 use shai_llm::ToolDescription;
 use std::fs;
@@ -8,7 +9,8 @@ use tempfile::tempdir;
 
 #[test]
 fn test_edit_tool_permissions() {
-    let log = Arc::new(FsOperationLog::new());
+    let dir = tempdir().unwrap();
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")));
     let tool = EditTool::new(log);
     let perms = tool.capabilities();
     assert!(perms.contains(&ToolCapability::Read));
@@ -18,7 +20,8 @@ fn test_edit_tool_permissions() {
 
 #[tokio::test]
 async fn test_edit_tool_creation() {
-    let log = Arc::new(FsOperationLog::new());
+    let dir = tempdir().unwrap();
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")));
     let tool = EditTool::new(log);
     assert_eq!(tool.name(), "edit");
     assert!(!tool.description().is_empty());
@@ -30,7 +33,7 @@ async fn test_edit_file_replacement() {
     let file_path = dir.path().join("test.txt");
     fs::write(&file_path, "Hello World").unwrap();
     
-    let log = Arc::new(FsOperationLog::new());
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")));
     // First read the file to satisfy the logging requirement
     log.log_operation(crate::tools::FsOperationType::Read, file_path.to_string_lossy().to_string()).await;
     
@@ -55,7 +58,7 @@ async fn test_edit_preview_functionality() {
     let file_path = dir.path().join("test.txt");
     fs::write(&file_path, "Hello World\nSecond line\nThird line").unwrap();
     
-    let log = Arc::new(FsOperationLog::new());
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")));
     // First read the file to satisfy the logging requirement
     log.log_operation(crate::tools::FsOperationType::Read, file_path.to_string_lossy().to_string()).await;
     
@@ -93,7 +96,8 @@ async fn test_edit_preview_functionality() {
 
 #[test]
 fn test_myers_diff_algorithm() {
-    let log = Arc::new(FsOperationLog::new());
+    let dir = tempdir().unwrap();
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")));
     let tool = EditTool::new(log);
     
     let before = "line1\nline2\nline3";
@@ -112,7 +116,8 @@ fn test_myers_diff_algorithm() {
 
 #[test]
 fn test_myers_diff_no_changes() {
-    let log = Arc::new(FsOperationLog::new());
+    let dir = tempdir().unwrap();
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")));
     let tool = EditTool::new(log);
     
     let content = "line1\nline2\nline3";
@@ -125,7 +130,8 @@ fn test_myers_diff_no_changes() {
 
 #[test]
 fn test_myers_diff_multiple_changes() {
-    let log = Arc::new(FsOperationLog::new());
+    let dir = tempdir().unwrap();
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")));
     let tool = EditTool::new(log);
     
     let before = "line1\nline2\nline3\nline4";
@@ -155,7 +161,7 @@ async fn test_execute_vs_preview_behavior() {
     let file_path = dir.path().join("test.txt");
     fs::write(&file_path, "Original content").unwrap();
     
-    let log = Arc::new(FsOperationLog::new());
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")));
     log.log_operation(crate::tools::FsOperationType::Read, file_path.to_string_lossy().to_string()).await;
     
     let tool = EditTool::new(log);
@@ -177,4 +183,110 @@ async fn test_execute_vs_preview_behavior() {
     assert!(execute_result.is_success());
     let content_after_execute = fs::read_to_string(&file_path).unwrap();
     assert_eq!(content_after_execute, "Modified content");
-}
\ No newline at end of file
+}
+#[test]
+fn test_match_engine_rejects_ambiguous_old_string() {
+    let content = "let a = 1;\nlet b = 2;\nlet a = 1;\n";
+    let err = matcher::replace(content, "let a = 1;", "let a = 3;", false).unwrap_err();
+    assert!(err.contains("2 times"));
+    assert!(err.contains("lines 1, 3"));
+
+    let (result, count, strategy) = matcher::replace(content, "let a = 1;", "let a = 3;", true).unwrap();
+    assert_eq!(result, "let a = 3;\nlet b = 2;\nlet a = 3;\n");
+    assert_eq!(count, 2);
+    assert_eq!(strategy, MatchStrategy::Exact);
+}
+
+#[test]
+fn test_match_engine_ignores_line_endings() {
+    let content = "fn main() {\r\n    run();\r\n}\r\n";
+    let (result, count, strategy) = matcher::replace(content, "    run();\n}", "    run();\n    stop();\n}", false).unwrap();
+    assert_eq!(result, "fn main() {\r\n    run();\r\n    stop();\r\n}\r\n");
+    assert_eq!(count, 1);
+    assert_eq!(strategy, MatchStrategy::LineEnding);
+}
+
+#[test]
+fn test_match_engine_reindents_on_whitespace_match() {
+    let content = "impl Foo {\n    fn bar(&self) {\n        self.baz();   \n    }\n}\n";
+    let old_string = "fn bar(&self) {\n    self.baz();\n}";
+    let new_string = "fn bar(&self) {\n    self.baz();\n    self.qux();\n}";
+    let (result, _, strategy) = matcher::replace(content, old_string, new_string, false).unwrap();
+    assert_eq!(strategy, MatchStrategy::Whitespace);
+    assert_eq!(result, "impl Foo {\n    fn bar(&self) {\n        self.baz();\n        self.qux();\n    }\n}\n");
+}
+
+#[test]
+fn test_match_engine_reports_closest_candidate() {
+    let content = "fn first() {}\nfn compute(a: u32, b: u32) -> u32 {\n    a + b\n}\nfn last() {}\n";
+    let err = matcher::replace(content, "fn compute(a: u32, b: u64) -> u32 {\n    a + b\n}", "", false).unwrap_err();
+    assert!(err.contains("lines 2-4"));
+    assert!(err.contains("-fn compute(a: u32, b: u64) -> u32 {"));
+    assert!(err.contains("+fn compute(a: u32, b: u32) -> u32 {"));
+
+    let err = matcher::replace(content, "something else entirely", "", false).unwrap_err();
+    assert!(err.contains("not found"));
+    assert!(!err.contains("closest"));
+}
+
+#[tokio::test]
+async fn test_edit_ambiguous_leaves_file_unchanged() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("test.txt");
+    fs::write(&file_path, "value\nother\nvalue\n").unwrap();
+
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")));
+    log.log_operation(crate::tools::FsOperationType::Read, file_path.to_string_lossy().to_string()).await;
+
+    let tool = EditTool::new(log);
+    let result = tool.execute(EditToolParams {
+        path: file_path.to_string_lossy().to_string(),
+        old_string: "value".to_string(),
+        new_string: "changed".to_string(),
+        replace_all: false,
+    }).await;
+    assert!(!result.is_success());
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "value\nother\nvalue\n");
+}
+
+#[tokio::test]
+async fn test_edit_keeps_encoding_and_line_endings() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("latin1.txt");
+    fs::write(&file_path, b"caf\xE9\r\nbar\r\n").unwrap();
+    let path = file_path.to_string_lossy().to_string();
+
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")));
+    log.log_operation(crate::tools::FsOperationType::Read, path.clone()).await;
+    let tool = EditTool::new(log);
+    let params = EditToolParams {
+        path,
+        old_string: "bar\n".to_string(),
+        new_string: "bar\nbaz\n".to_string(),
+        replace_all: false,
+    };
+
+    assert!(tool.execute(params).await.is_success());
+    assert_eq!(fs::read(&file_path).unwrap(), b"caf\xE9\r\nbar\r\nbaz\r\n");
+}
+
+#[tokio::test]
+async fn test_edit_refuses_to_write_redaction_masks() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("config.toml");
+    fs::write(&file_path, "name = \"app\"\nkey = \"sk-live-0123456789abcdefghij\"\n").unwrap();
+
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")));
+    log.log_operation(crate::tools::FsOperationType::Read, file_path.to_string_lossy().to_string()).await;
+    let tool = EditTool::new(log);
+    let params = EditToolParams {
+        path: file_path.to_string_lossy().to_string(),
+        old_string: "name = \"app\"\nkey = \"sk-live-0123456789abcdefghij\"".to_string(),
+        new_string: "name = \"server\"\nkey = \"[REDACTED:api_key]\"".to_string(),
+        replace_all: false,
+    };
+
+    let result = tool.execute(params).await;
+    assert!(result.to_string().contains("[REDACTED:...] markers"), "{}", result);
+    assert!(fs::read_to_string(&file_path).unwrap().contains("sk-live-0123456789abcdefghij"));
+}
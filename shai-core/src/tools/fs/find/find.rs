@@ -1,13 +1,42 @@
 use super::structs::{FindToolParams, SearchResult, FindType};
 use crate::tools::{tool, ToolResult};
+use ignore::overrides::{Override, OverrideBuilder};
+use ignore::{WalkBuilder, WalkState};
 use serde_json::json;
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
 use regex::Regex;
-use walkdir::WalkDir;
 use std::fs;
 use std::io::{BufRead, BufReader};
 
+/// Bytes looked at to tell binary files apart
+const BINARY_SNIFF_BYTES: usize = 8192;
+
+/// Longest line returned, minified files would otherwise flood the output
+const MAX_LINE_CHARS: usize = 500;
+
+/// A file is considered binary if its first bytes contain a NUL byte, like git and ripgrep do
+pub(crate) fn is_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0)
+}
+
+fn clip_line(line: &str) -> String {
+    match line.char_indices().nth(MAX_LINE_CHARS) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line.to_string(),
+    }
+}
+
+/// Matches of a file, with its modification time to sort the output
+struct FileMatches {
+    modified: SystemTime,
+    path: String,
+    results: Vec<SearchResult>,
+}
+
 pub struct FindTool;
 
 impl FindTool {
@@ -15,86 +44,98 @@ impl FindTool {
         Self
     }
 
-    fn should_include_file(&self, path: &Path, include_extensions: &Option<String>, exclude_patterns: &Option<String>) -> bool {
-        let path_str = path.to_string_lossy();
-        
-        // Check exclude patterns first
-        if let Some(exclude) = exclude_patterns {
-            for pattern in exclude.split(',') {
-                let pattern = pattern.trim();
-                if !pattern.is_empty() && path_str.contains(pattern) {
-                    return false;
-                }
+    /// Include and exclude globs as an override of the walker, gitignore syntax
+    fn build_overrides(&self, root: &Path, params: &FindToolParams) -> Result<Override, ignore::Error> {
+        let mut builder = OverrideBuilder::new(root);
+        if let Some(include) = &params.include_extensions {
+            for ext in include.split(',').map(|ext| ext.trim().trim_start_matches('.')).filter(|ext| !ext.is_empty()) {
+                builder.add(&format!("*.{}", ext))?;
             }
         }
-        
-        // Check include extensions
-        if let Some(include) = include_extensions {
-            if let Some(ext) = path.extension() {
-                let ext_str = ext.to_string_lossy();
-                for allowed_ext in include.split(',') {
-                    let allowed_ext = allowed_ext.trim();
-                    if !allowed_ext.is_empty() && ext_str == allowed_ext {
-                        return true;
-                    }
-                }
-                return false; // Has extension but not in allowed list
-            } else {
-                return false; // No extension but extensions are specified
+        if let Some(include) = &params.include_patterns {
+            for pattern in include.split(',').map(str::trim).filter(|pattern| !pattern.is_empty()) {
+                builder.add(pattern)?;
             }
         }
-        
-        true
+        if let Some(exclude) = &params.exclude_patterns {
+            for pattern in exclude.split(',').map(str::trim).filter(|pattern| !pattern.is_empty()) {
+                builder.add(&format!("!{}", pattern.trim_start_matches('!')))?;
+            }
+        }
+        builder.build()
     }
 
-    fn search_file_content(&self, file_path: &Path, pattern: &Regex, params: &FindToolParams) -> Vec<SearchResult> {
-        let mut results = Vec::new();
-        
+    /// Stream the lines of a file, at most `limit` matches, binary files yield nothing
+    fn search_file_content(&self, file_path: &Path, pattern: &Regex, params: &FindToolParams, limit: usize) -> Vec<SearchResult> {
+        let mut results: Vec<SearchResult> = Vec::new();
+        if limit == 0 {
+            return results;
+        }
+
         let file = match fs::File::open(file_path) {
             Ok(file) => file,
             Err(_) => return results,
         };
-        
-        let reader = BufReader::new(file);
-        let lines: Vec<String> = reader.lines().collect::<Result<Vec<_>, _>>().unwrap_or_default();
-        
-        for (line_num, line) in lines.iter().enumerate() {
-            if pattern.is_match(line) {
-                let line_number = (line_num + 1) as u32;
-                let context_lines = params.context_lines.unwrap_or(0);
-                
-                let mut context_before = Vec::new();
-                let mut context_after = Vec::new();
-                
-                if context_lines > 0 {
-                    let start = line_num.saturating_sub(context_lines as usize);
-                    let end = std::cmp::min(line_num + context_lines as usize + 1, lines.len());
-                    
-                    context_before = lines[start..line_num].iter().cloned().collect();
-                    context_after = lines[line_num + 1..end].iter().cloned().collect();
-                }
-                
+
+        let mut reader = BufReader::with_capacity(BINARY_SNIFF_BYTES * 8, file);
+        match reader.fill_buf() {
+            Ok(head) if !is_binary(head) => {}
+            _ => return results,
+        }
+
+        let context_lines = params.context_lines.unwrap_or(0) as usize;
+        let mut before: VecDeque<String> = VecDeque::with_capacity(context_lines);
+        // matches still collecting their context_after
+        let mut open: Vec<usize> = Vec::new();
+        let mut buffer = Vec::new();
+        let mut line_number = 0u32;
+
+        loop {
+            buffer.clear();
+            match reader.read_until(b'\n', &mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {}
+            }
+            line_number += 1;
+            let line = String::from_utf8_lossy(&buffer);
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            for &i in &open {
+                results[i].context_after.push(clip_line(line));
+            }
+            open.retain(|&i| results[i].context_after.len() < context_lines);
+
+            if results.len() < limit && pattern.is_match(line) {
                 results.push(SearchResult {
                     file_path: file_path.to_string_lossy().to_string(),
                     line_number: if params.show_line_numbers { Some(line_number) } else { None },
-                    line_content: Some(line.clone()),
-                    context_before,
-                    context_after,
+                    line_content: Some(clip_line(line)),
+                    context_before: before.iter().cloned().collect(),
+                    context_after: Vec::new(),
                     match_type: "content".to_string(),
                 });
-                
-                if results.len() >= params.max_results as usize {
-                    break;
+                if context_lines > 0 {
+                    open.push(results.len() - 1);
                 }
             }
+
+            if results.len() >= limit && open.is_empty() {
+                break;
+            }
+            if context_lines > 0 {
+                if before.len() == context_lines {
+                    before.pop_front();
+                }
+                before.push_back(clip_line(line));
+            }
         }
-        
+
         results
     }
 
     fn search_filename(&self, file_path: &Path, pattern: &Regex) -> Option<SearchResult> {
         let filename = file_path.file_name()?.to_string_lossy();
-        
+
         if pattern.is_match(&filename) {
             Some(SearchResult {
                 file_path: file_path.to_string_lossy().to_string(),
@@ -108,6 +149,90 @@ impl FindTool {
             None
         }
     }
+
+    /// Files the walk reaches, with their modification time, most recently modified first
+    fn candidates(&self, root: &Path, overrides: Override, params: &FindToolParams) -> Vec<(SystemTime, PathBuf)> {
+        let files: Mutex<Vec<(SystemTime, PathBuf)>> = Mutex::new(Vec::new());
+
+        let mut builder = WalkBuilder::new(root);
+        builder
+            .hidden(false)
+            .follow_links(false)
+            .require_git(false)
+            .git_ignore(!params.no_ignore)
+            .git_global(!params.no_ignore)
+            .git_exclude(!params.no_ignore)
+            .ignore(!params.no_ignore)
+            .parents(!params.no_ignore)
+            .overrides(overrides)
+            .filter_entry(|entry| entry.file_name() != ".git");
+
+        builder.build_parallel().run(|| {
+            let files = &files;
+            Box::new(move |entry| {
+                let Ok(entry) = entry else {
+                    return WalkState::Continue;
+                };
+                if !entry.file_type().is_some_and(|file_type| file_type.is_file()) {
+                    return WalkState::Continue;
+                }
+                let modified = entry.metadata().ok()
+                    .and_then(|metadata| metadata.modified().ok())
+                    .unwrap_or(SystemTime::UNIX_EPOCH);
+                files.lock().unwrap().push((modified, entry.into_path()));
+                WalkState::Continue
+            })
+        });
+
+        let mut files = files.into_inner().unwrap();
+        files.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        files
+    }
+
+    /// Search the files newest first, in parallel, until more than max_results matches were found;
+    /// files are taken in order so the matches found are the ones of the most recently modified files
+    fn search(&self, root: &Path, overrides: Override, pattern: &Regex, params: &FindToolParams) -> (Vec<FileMatches>, usize) {
+        // one more than requested, to know whether results were left out
+        let wanted = params.max_results as usize + 1;
+        let files = self.candidates(root, overrides, params);
+        let next = AtomicUsize::new(0);
+        let found = AtomicUsize::new(0);
+        let matches: Mutex<Vec<FileMatches>> = Mutex::new(Vec::new());
+        let workers = std::thread::available_parallelism().map_or(1, |n| n.get()).min(files.len().max(1));
+
+        std::thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    if found.load(Ordering::SeqCst) >= wanted {
+                        break;
+                    }
+                    let index = next.fetch_add(1, Ordering::SeqCst);
+                    let Some((modified, path)) = files.get(index) else { break };
+
+                    let mut results = Vec::new();
+                    if matches!(params.find_type, FindType::Filename | FindType::Both) {
+                        results.extend(self.search_filename(path, pattern));
+                    }
+                    if matches!(params.find_type, FindType::Content | FindType::Both) {
+                        results.extend(self.search_file_content(path, pattern, params, wanted - results.len()));
+                    }
+                    if results.is_empty() {
+                        continue;
+                    }
+
+                    found.fetch_add(results.len(), Ordering::SeqCst);
+                    matches.lock().unwrap().push(FileMatches {
+                        modified: *modified,
+                        path: path.to_string_lossy().to_string(),
+                        results,
+                    });
+                });
+            }
+        });
+
+        let files_searched = next.into_inner().min(files.len());
+        (matches.into_inner().unwrap(), files_searched)
+    }
 }
 
 #[tool(name = "find", description = r#"A high-performance search utility for locating files or specific text within files across the project.
@@ -118,11 +243,13 @@ impl FindTool {
 - Use the `find_type` parameter (`'content'`, `'filename'`, or `'both'`) to control the search mode.
 
 **Filtering and Scope:**
-- Narrow your search to specific file types by providing a comma-separated list of extensions to `include_extensions` (e.g., 'rs,js,py').
-- Exclude irrelevant directories and files (like `target` or `.git`) using the `exclude_patterns` parameter to speed up the search.
+- Files ignored by `.gitignore`, `.ignore` or the global git excludes (e.g. `target`, `node_modules`) are skipped, set `no_ignore` to search them too. Binary files are never searched for content.
+- Narrow your search to specific file types by providing a comma-separated list of extensions to `include_extensions` (e.g., 'rs,js,py'), or of globs to `include_patterns` (e.g., 'src/**/*.rs,*.toml'). A file is searched if it matches any of them.
+- Exclude files or directories with comma-separated globs in `exclude_patterns` (e.g., 'tests,*.min.js').
 
 **Output:**
-- Returns a list of matching file paths, sorted with the most recently modified files appearing first. This helps prioritize recently changed files."#, capabilities = [ToolCapability::Read])]
+- Returns a list of matches, files sorted with the most recently modified appearing first. This helps prioritize recently changed files.
+- At most `max_results` matches are returned, the output says when more were found: narrow the pattern or the path in that case."#, capabilities = [ToolCapability::Read])]
 
 impl FindTool {
     async fn execute(&self, params: FindToolParams) -> ToolResult {
@@ -141,7 +268,7 @@ impl FindTool {
         } else {
             "(?i)"
         };
-        
+
         let pattern_str = if params.whole_word {
             format!("{}\\b{}\\b", regex_flags, regex::escape(&params.pattern))
         } else {
@@ -158,60 +285,57 @@ impl FindTool {
             }
         };
 
-        let mut all_results = Vec::new();
-
-        // Walk through directory
-        for entry in WalkDir::new(search_path)
-            .follow_links(false)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            let path = entry.path();
-            
-            // Skip directories for content search
-            if path.is_dir() {
-                continue;
-            }
+        let root = Path::new(search_path).to_path_buf();
+        if !root.exists() {
+            return ToolResult::Error {
+                error: format!("Path does not exist: {}", search_path),
+                metadata: Some(meta),
+            };
+        }
 
-            // Apply file filters
-            if !self.should_include_file(path, &params.include_extensions, &params.exclude_patterns) {
-                continue;
+        let overrides = match self.build_overrides(&root, &params) {
+            Ok(overrides) => overrides,
+            Err(e) => {
+                return ToolResult::Error {
+                    error: format!("Invalid include or exclude pattern: {}", e),
+                    metadata: Some(meta),
+                };
             }
+        };
 
-            // Search based on find_type
-            match params.find_type {
-                FindType::Content => {
-                    let mut content_results = self.search_file_content(path, &pattern, &params);
-                    all_results.append(&mut content_results);
-                },
-                FindType::Filename => {
-                    if let Some(filename_result) = self.search_filename(path, &pattern) {
-                        all_results.push(filename_result);
-                    }
-                },
-                FindType::Both => {
-                    // Search filename first
-                    if let Some(filename_result) = self.search_filename(path, &pattern) {
-                        all_results.push(filename_result);
-                    }
-                    // Then search content
-                    let mut content_results = self.search_file_content(path, &pattern, &params);
-                    all_results.append(&mut content_results);
-                }
+        // The walk is blocking, keep it off the runtime threads
+        let tool = FindTool::new();
+        let search_params = params.clone();
+        let search = tokio::task::spawn_blocking(move || tool.search(&root, overrides, &pattern, &search_params)).await;
+        let (mut files, files_searched) = match search {
+            Ok(search) => search,
+            Err(e) => {
+                return ToolResult::Error {
+                    error: format!("Search failed: {}", e),
+                    metadata: Some(meta),
+                };
             }
+        };
 
-            if all_results.len() >= params.max_results as usize {
-                break;
-            }
-        }
+        // Most recently modified files first, then by path for a stable output
+        files.sort_by(|a, b| b.modified.cmp(&a.modified).then_with(|| a.path.cmp(&b.path)));
+        let mut all_results: Vec<SearchResult> = files.into_iter().flat_map(|file| file.results).collect();
+        let truncated = all_results.len() > params.max_results as usize;
 
         // Truncate results to max_results
         all_results.truncate(params.max_results as usize);
 
         meta.insert("results_count".to_string(), json!(all_results.len()));
+        meta.insert("files_searched".to_string(), json!(files_searched));
+        meta.insert("truncated".to_string(), json!(truncated));
+
+        let mut output = serde_json::to_string_pretty(&all_results).unwrap_or_default();
+        if truncated {
+            output.push_str(&format!("\n[... more than {} matches, only the first {} are shown: narrow the pattern or the path to see the others ...]", params.max_results, params.max_results));
+        }
 
         ToolResult::Success {
-            output: serde_json::to_string_pretty(&all_results).unwrap_or_default(),
+            output,
             metadata: Some(meta),
         }
     }
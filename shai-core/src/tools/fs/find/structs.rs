@@ -11,9 +11,15 @@ pub struct FindToolParams {
     /// File extensions to include (e.g., "rs,js,py")
     #[serde(default)]
     pub include_extensions: Option<String>,
-    /// File patterns to exclude (e.g., "target,node_modules,.git")
+    /// Globs of the files to include (e.g., "src/**/*.rs,*.toml")
+    #[serde(default)]
+    pub include_patterns: Option<String>,
+    /// Globs of the files or directories to exclude (e.g., "tests,*.min.js")
     #[serde(default)]
     pub exclude_patterns: Option<String>,
+    /// Also search files ignored by .gitignore and .ignore files
+    #[serde(default)]
+    pub no_ignore: bool,
     /// Maximum number of results to return
     #[serde(default = "default_max_results")]
     pub max_results: u32,
@@ -4,6 +4,7 @@ use crate::tools::Tool;
 use shai_llm::ToolDescription;
 use tempfile::TempDir;
 use std::fs;
+use std::time::{Duration, SystemTime};
 
 
 #[tokio::test]
@@ -54,7 +55,9 @@ fn main() {
         pattern: "struct".to_string(),
         path: Some(temp_path.to_string_lossy().to_string()),
         include_extensions: Some("rs".to_string()),
+        include_patterns: None,
         exclude_patterns: None,
+        no_ignore: false,
         max_results: 10,
         case_sensitive: false,
         find_type: FindType::Content,
@@ -80,7 +83,9 @@ fn main() {
         pattern: "email".to_string(),
         path: Some(temp_path.to_string_lossy().to_string()),
         include_extensions: Some("rs".to_string()),
+        include_patterns: None,
         exclude_patterns: None,
+        no_ignore: false,
         max_results: 10,
         case_sensitive: true,
         find_type: FindType::Content,
@@ -119,7 +124,9 @@ async fn test_find_tool_filename_search() {
         pattern: "user".to_string(),
         path: Some(temp_path.to_string_lossy().to_string()),
         include_extensions: None,
+        include_patterns: None,
         exclude_patterns: None,
+        no_ignore: false,
         max_results: 10,
         case_sensitive: false,
         find_type: FindType::Filename,
@@ -172,7 +179,9 @@ async fn test_find_tool_with_filters() {
         pattern: "struct".to_string(),
         path: Some(temp_path.to_string_lossy().to_string()),
         include_extensions: Some("rs".to_string()),
+        include_patterns: None,
         exclude_patterns: Some("target".to_string()),
+        no_ignore: false,
         max_results: 10,
         case_sensitive: false,
         find_type: FindType::Content,
@@ -221,7 +230,9 @@ fn calculate_guest_score() -> u32 {
         pattern: r"fn calculate_\w+".to_string(),
         path: Some(temp_path.to_string_lossy().to_string()),
         include_extensions: Some("rs".to_string()),
+        include_patterns: None,
         exclude_patterns: None,
+        no_ignore: false,
         max_results: 10,
         case_sensitive: false,
         find_type: FindType::Content,
@@ -261,7 +272,9 @@ async fn test_find_tool_invalid_regex() {
         pattern: "[invalid regex(".to_string(),
         path: Some(temp_path.to_string_lossy().to_string()),
         include_extensions: None,
+        include_patterns: None,
         exclude_patterns: None,
+        no_ignore: false,
         max_results: 10,
         case_sensitive: false,
         find_type: FindType::Content,
@@ -279,4 +292,143 @@ async fn test_find_tool_invalid_regex() {
             assert!(error.contains("Invalid regex pattern"), "Should indicate regex error");
         }
     }
-}
\ No newline at end of file
+}
+fn content_search(path: &std::path::Path, pattern: &str) -> FindToolParams {
+    FindToolParams {
+        pattern: pattern.to_string(),
+        path: Some(path.to_string_lossy().to_string()),
+        include_extensions: None,
+        include_patterns: None,
+        exclude_patterns: None,
+        no_ignore: false,
+        max_results: 100,
+        case_sensitive: false,
+        find_type: FindType::Content,
+        show_line_numbers: true,
+        context_lines: None,
+        whole_word: false,
+    }
+}
+
+fn found_files(output: &str) -> Vec<String> {
+    let json = output.split("\n[...").next().unwrap();
+    let results: Vec<crate::tools::fs::find::SearchResult> = serde_json::from_str(json).expect("Should parse JSON results");
+    let mut files: Vec<String> = results.into_iter().map(|r| r.file_path).collect();
+    files.sort();
+    files.dedup();
+    files
+}
+
+#[tokio::test]
+async fn test_find_tool_respects_gitignore() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let temp_path = temp_dir.path();
+    fs::create_dir_all(temp_path.join("src")).unwrap();
+    fs::create_dir_all(temp_path.join("target/debug")).unwrap();
+    fs::create_dir_all(temp_path.join("node_modules/dep")).unwrap();
+    fs::write(temp_path.join(".gitignore"), "target/\n").unwrap();
+    fs::write(temp_path.join(".ignore"), "node_modules\n").unwrap();
+    fs::write(temp_path.join("src/lib.rs"), "needle").unwrap();
+    fs::write(temp_path.join("target/debug/out.rs"), "needle").unwrap();
+    fs::write(temp_path.join("node_modules/dep/index.js"), "needle").unwrap();
+
+    let find_tool = FindTool::new();
+    let output = find_tool.execute(content_search(temp_path, "needle")).await.to_string();
+    assert_eq!(found_files(&output), vec![temp_path.join("src/lib.rs").to_string_lossy().to_string()]);
+
+    let mut params = content_search(temp_path, "needle");
+    params.no_ignore = true;
+    let output = find_tool.execute(params).await.to_string();
+    assert_eq!(found_files(&output).len(), 3);
+}
+
+#[tokio::test]
+async fn test_find_tool_skips_binary_files() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("text.txt"), "needle in text").unwrap();
+    fs::write(temp_path.join("blob.bin"), b"needle\x00\x01\x02 binary").unwrap();
+
+    let output = FindTool::new().execute(content_search(temp_path, "needle")).await.to_string();
+    assert_eq!(found_files(&output), vec![temp_path.join("text.txt").to_string_lossy().to_string()]);
+}
+
+#[tokio::test]
+async fn test_find_tool_globs() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let temp_path = temp_dir.path();
+    fs::create_dir_all(temp_path.join("src/generated")).unwrap();
+    fs::write(temp_path.join("src/lib.rs"), "needle").unwrap();
+    fs::write(temp_path.join("src/generated/api.rs"), "needle").unwrap();
+    fs::write(temp_path.join("Cargo.toml"), "needle").unwrap();
+    fs::write(temp_path.join("app.min.js"), "needle").unwrap();
+
+    let mut params = content_search(temp_path, "needle");
+    params.include_patterns = Some("src/**/*.rs, *.toml".to_string());
+    params.exclude_patterns = Some("generated".to_string());
+    let output = FindTool::new().execute(params).await.to_string();
+    assert_eq!(found_files(&output), vec![
+        temp_path.join("Cargo.toml").to_string_lossy().to_string(),
+        temp_path.join("src/lib.rs").to_string_lossy().to_string(),
+    ]);
+}
+
+#[tokio::test]
+async fn test_find_tool_reports_truncation() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let temp_path = temp_dir.path();
+    for i in 0..20 {
+        fs::write(temp_path.join(format!("file{}.txt", i)), "needle\nneedle\n").unwrap();
+    }
+
+    let mut params = content_search(temp_path, "needle");
+    params.max_results = 5;
+    let result = FindTool::new().execute(params).await;
+    let crate::tools::ToolResult::Success { output, metadata } = result else {
+        panic!("Find tool should succeed");
+    };
+    let metadata = metadata.unwrap();
+    assert_eq!(metadata["results_count"], serde_json::json!(5));
+    assert_eq!(metadata["truncated"], serde_json::json!(true));
+    assert!(output.contains("more than 5 matches"), "{}", output);
+
+    let output = FindTool::new().execute(content_search(temp_path, "needle")).await.to_string();
+    assert!(!output.contains("more than"));
+}
+
+#[tokio::test]
+async fn test_find_tool_truncation_keeps_newest_files() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let temp_path = temp_dir.path();
+    for i in 0..50u64 {
+        let path = temp_path.join(format!("file{}.txt", i));
+        fs::write(&path, "needle\n").unwrap();
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(1000 * (i + 1))).unwrap();
+    }
+
+    let mut params = content_search(temp_path, "needle");
+    params.max_results = 3;
+    let output = FindTool::new().execute(params).await.to_string();
+    let first = output.find("file0.txt").expect("newest file should be listed");
+    let second = output.find("file1.txt").expect("second newest file should be listed");
+    assert!(first < second);
+    assert!(output.contains("file2.txt"));
+    assert!(!output.contains("file3.txt"));
+}
+
+#[tokio::test]
+async fn test_find_tool_streaming_context() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let temp_path = temp_dir.path();
+    fs::write(temp_path.join("lines.txt"), "one\ntwo\nneedle\nthree\nfour\nfive\n").unwrap();
+
+    let mut params = content_search(temp_path, "needle");
+    params.context_lines = Some(2);
+    let output = FindTool::new().execute(params).await.to_string();
+    let results: Vec<crate::tools::fs::find::SearchResult> = serde_json::from_str(&output).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].line_number, Some(3));
+    assert_eq!(results[0].context_before, vec!["one", "two"]);
+    assert_eq!(results[0].context_after, vec!["three", "four"]);
+}
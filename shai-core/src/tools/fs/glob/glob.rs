@@ -0,0 +1,111 @@
+use super::structs::GlobToolParams;
+use crate::tools::{tool, ToolResult};
+use globset::{GlobBuilder, GlobMatcher};
+use ignore::WalkBuilder;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+pub struct GlobTool;
+
+impl GlobTool {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Files under root whose relative path matches, with their modification time
+    fn walk(root: &Path, matcher: &GlobMatcher, no_ignore: bool) -> Vec<(SystemTime, PathBuf)> {
+        let mut builder = WalkBuilder::new(root);
+        builder
+            .hidden(false)
+            .follow_links(false)
+            .require_git(false)
+            .git_ignore(!no_ignore)
+            .git_global(!no_ignore)
+            .git_exclude(!no_ignore)
+            .ignore(!no_ignore)
+            .parents(!no_ignore)
+            .filter_entry(|entry| entry.file_name() != ".git");
+
+        builder.build()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_some_and(|file_type| file_type.is_file()))
+            .filter(|entry| entry.path().strip_prefix(root).is_ok_and(|relative| matcher.is_match(relative)))
+            .map(|entry| {
+                let modified = entry.metadata().ok()
+                    .and_then(|metadata| metadata.modified().ok())
+                    .unwrap_or(SystemTime::UNIX_EPOCH);
+                (modified, entry.into_path())
+            })
+            .collect()
+    }
+}
+
+#[tool(name = "glob", description = r#"Finds files by name with a glob pattern and returns their paths, most recently modified first.
+
+**Usage Notes:**
+- The pattern is matched against paths relative to `path` (the current directory by default): `*` does not cross directories, `**` matches any number of directories and `{a,b}` alternatives. For example `**/*.rs`, `src/**/mod.rs` or `**/*.{ts,tsx}`.
+- Files ignored by `.gitignore`, `.ignore` or the global git excludes (e.g. `target`, `node_modules`) are skipped, set `no_ignore` to include them.
+- Use this tool to explore a repository or locate files, and the `find` tool to search inside files."#, capabilities = [ToolCapability::Read])]
+impl GlobTool {
+    async fn execute(&self, params: GlobToolParams) -> ToolResult {
+        let mut meta = HashMap::new();
+        meta.insert("pattern".to_string(), json!(params.pattern));
+        let search_path = params.path.clone().unwrap_or_else(|| ".".to_string());
+        meta.insert("path".to_string(), json!(search_path));
+
+        let matcher = match GlobBuilder::new(params.pattern.trim_start_matches("./")).literal_separator(true).build() {
+            Ok(glob) => glob.compile_matcher(),
+            Err(e) => {
+                return ToolResult::Error {
+                    error: format!("Invalid glob pattern: {}", e),
+                    metadata: Some(meta),
+                };
+            }
+        };
+
+        let root = PathBuf::from(&search_path);
+        if !root.is_dir() {
+            return ToolResult::Error {
+                error: format!("Directory does not exist: {}", search_path),
+                metadata: Some(meta),
+            };
+        }
+
+        // The walk is blocking, keep it off the runtime threads
+        let no_ignore = params.no_ignore;
+        let walk = tokio::task::spawn_blocking(move || Self::walk(&root, &matcher, no_ignore)).await;
+        let mut files = match walk {
+            Ok(files) => files,
+            Err(e) => return ToolResult::error(format!("Glob failed: {}", e)),
+        };
+
+        // Most recently modified first, then by path for a stable output
+        files.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        let total = files.len();
+        let max_results = params.max_results as usize;
+        let paths: Vec<String> = files.into_iter()
+            .take(max_results)
+            .map(|(_, path)| path.strip_prefix(".").map(Path::to_path_buf).unwrap_or(path).to_string_lossy().to_string())
+            .collect();
+
+        meta.insert("results_count".to_string(), json!(paths.len()));
+        meta.insert("total".to_string(), json!(total));
+        meta.insert("truncated".to_string(), json!(total > max_results));
+
+        let mut output = if paths.is_empty() {
+            format!("No files match {}", params.pattern)
+        } else {
+            paths.join("\n")
+        };
+        if total > max_results {
+            output.push_str(&format!("\n[... {} more files, narrow the pattern or the path to see them ...]", total - max_results));
+        }
+
+        ToolResult::Success {
+            output,
+            metadata: Some(meta),
+        }
+    }
+}
@@ -0,0 +1,8 @@
+pub mod structs;
+pub mod glob;
+
+#[cfg(test)]
+mod tests;
+
+pub use structs::GlobToolParams;
+pub use glob::GlobTool;
@@ -0,0 +1,19 @@
+use serde::Deserialize;
+use schemars::JsonSchema;
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct GlobToolParams {
+    /// Glob pattern matched against paths relative to the search directory (e.g. "src/**/*.rs", "**/*.{ts,tsx}")
+    pub pattern: String,
+    /// Directory to search in (defaults to current directory)
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Also list files ignored by .gitignore and .ignore files
+    #[serde(default)]
+    pub no_ignore: bool,
+    /// Maximum number of paths to return
+    #[serde(default = "default_max_results")]
+    pub max_results: u32,
+}
+
+fn default_max_results() -> u32 { 200 }
@@ -0,0 +1,98 @@
+use super::glob::GlobTool;
+use super::structs::GlobToolParams;
+use crate::tools::{Tool, ToolCapability, ToolResult};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+fn glob(path: &Path, pattern: &str) -> GlobToolParams {
+    GlobToolParams {
+        pattern: pattern.to_string(),
+        path: Some(path.to_string_lossy().to_string()),
+        no_ignore: false,
+        max_results: 200,
+    }
+}
+
+fn relative(output: &str, root: &Path) -> Vec<String> {
+    output.lines()
+        .map(|line| Path::new(line).strip_prefix(root).unwrap().to_string_lossy().to_string())
+        .collect()
+}
+
+fn touch(path: &Path, age_secs: u64) {
+    fs::write(path, "").unwrap();
+    let file = fs::File::options().write(true).open(path).unwrap();
+    file.set_modified(SystemTime::now() - Duration::from_secs(age_secs)).unwrap();
+}
+
+#[test]
+fn test_glob_tool_capabilities() {
+    assert_eq!(GlobTool::new().capabilities(), &[ToolCapability::Read]);
+}
+
+#[tokio::test]
+async fn test_glob_matches_and_sorts_by_mtime() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("src/tools")).unwrap();
+    touch(&root.join("src/lib.rs"), 300);
+    touch(&root.join("src/tools/mod.rs"), 100);
+    touch(&root.join("src/tools/bash.rs"), 200);
+    touch(&root.join("build.rs"), 10);
+    touch(&root.join("README.md"), 0);
+
+    let output = Tool::execute(&GlobTool::new(), glob(root, "src/**/*.rs")).await.to_string();
+    assert_eq!(relative(&output, root), vec!["src/tools/mod.rs", "src/tools/bash.rs", "src/lib.rs"]);
+
+    // `*` does not cross directories
+    let output = Tool::execute(&GlobTool::new(), glob(root, "*.rs")).await.to_string();
+    assert_eq!(relative(&output, root), vec!["build.rs"]);
+
+    let output = Tool::execute(&GlobTool::new(), glob(root, "**/*.{md,rs}")).await.to_string();
+    assert_eq!(relative(&output, root).len(), 5);
+}
+
+#[tokio::test]
+async fn test_glob_respects_ignore_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("target/debug")).unwrap();
+    fs::write(root.join(".gitignore"), "target/\n").unwrap();
+    fs::write(root.join("main.rs"), "").unwrap();
+    fs::write(root.join("target/debug/build.rs"), "").unwrap();
+
+    let output = Tool::execute(&GlobTool::new(), glob(root, "**/*.rs")).await.to_string();
+    assert_eq!(relative(&output, root), vec!["main.rs"]);
+
+    let mut params = glob(root, "**/*.rs");
+    params.no_ignore = true;
+    let output = Tool::execute(&GlobTool::new(), params).await.to_string();
+    assert_eq!(relative(&output, root).len(), 2);
+}
+
+#[tokio::test]
+async fn test_glob_truncation_and_errors() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    for i in 0..10 {
+        fs::write(root.join(format!("file{}.txt", i)), "").unwrap();
+    }
+
+    let mut params = glob(root, "*.txt");
+    params.max_results = 3;
+    let result = Tool::execute(&GlobTool::new(), params).await;
+    let ToolResult::Success { output, metadata } = result else {
+        panic!("Expected success result");
+    };
+    assert_eq!(output.lines().count(), 4);
+    assert!(output.ends_with("[... 7 more files, narrow the pattern or the path to see them ...]"));
+    assert_eq!(metadata.unwrap()["truncated"], serde_json::json!(true));
+
+    let output = Tool::execute(&GlobTool::new(), glob(root, "*.rs")).await.to_string();
+    assert_eq!(output, "No files match *.rs");
+
+    let result = Tool::execute(&GlobTool::new(), glob(root, "src/{a,")).await;
+    assert!(matches!(result, ToolResult::Error { .. }));
+}
@@ -0,0 +1,254 @@
+use super::ops::{check_not_inside, check_not_protected, copy_recursive, list_contents, move_path, move_to_trash, remove_path};
+use super::structs::{CopyToolParams, DeleteToolParams, MoveToolParams};
+use super::super::{FsOperationLog, FsOperationType};
+use crate::tools::{tool, ToolResult};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Checks shared by move and copy, tells whether an existing destination file gets replaced
+fn check_transfer(source: &str, destination: &str, overwrite: bool) -> Result<bool, String> {
+    let source_metadata = fs::symlink_metadata(source)
+        .map_err(|_| format!("Source does not exist: {}", source))?;
+    if source_metadata.is_dir() {
+        check_not_inside(Path::new(source), Path::new(destination))?;
+    }
+
+    let Ok(destination_metadata) = fs::symlink_metadata(destination) else {
+        return Ok(false);
+    };
+    if destination_metadata.is_dir() {
+        return Err(format!("Destination is an existing directory: {}, give the full path of the new entry instead", destination));
+    }
+    if source_metadata.is_dir() {
+        return Err(format!("Destination is an existing file: {}, a directory cannot replace it", destination));
+    }
+    if fs::canonicalize(source).ok() == fs::canonicalize(destination).ok() {
+        return Err(format!("Source and destination are the same file: {}", source));
+    }
+    if !overwrite {
+        return Err(format!("Destination already exists: {}, set overwrite to replace it", destination));
+    }
+    Ok(true)
+}
+
+fn create_parent(path: &str) -> Result<(), String> {
+    match Path::new(path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => fs::create_dir_all(parent).map_err(|e| e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// What is affected by a move or a copy, for previews
+fn describe_transfer(verb: &str, source: &str, destination: &str, replaces: bool) -> String {
+    let mut lines = vec![format!("{} {} -> {}", verb, source, destination)];
+    if replaces {
+        lines.push(format!("replaces the existing {}, which is kept in the trash", destination));
+    }
+    if Path::new(source).is_dir() {
+        lines.push(list_contents(Path::new(source)).describe());
+    }
+    lines.join("\n")
+}
+
+#[derive(Clone)]
+pub struct MoveTool {
+    operation_log: Arc<FsOperationLog>,
+}
+
+impl MoveTool {
+    pub fn new(operation_log: Arc<FsOperationLog>) -> Self {
+        Self { operation_log }
+    }
+
+    fn check(&self, params: &MoveToolParams) -> Result<bool, String> {
+        let replaces = check_transfer(&params.source, &params.destination, params.overwrite)?;
+        check_not_protected(Path::new(&params.source))?;
+        Ok(replaces)
+    }
+}
+
+#[tool(name = "move", description = r#"Moves or renames a file or a directory. Use it instead of `mv` in `bash`, so that the move is tracked and what it affects is shown before it happens.
+
+**Usage:**
+- `destination` is the full new path, not the directory to move into: to move `src/a.rs` into `lib/`, give `lib/a.rs`. Missing parent directories are created.
+- An existing destination file is only replaced when `overwrite` is set, the replaced file is kept in the trash. A directory is never replaced.
+- Files you read before the move are still considered read at their new path."#, capabilities = [ToolCapability::Write])]
+impl MoveTool {
+    async fn execute_preview(&self, params: MoveToolParams) -> Option<ToolResult> {
+        Some(match self.check(&params) {
+            Ok(replaces) => ToolResult::success(describe_transfer("Move", &params.source, &params.destination, replaces)),
+            Err(e) => ToolResult::error(format!("Move failed: {}", e)),
+        })
+    }
+
+    async fn execute(&self, params: MoveToolParams) -> ToolResult {
+        let replaces = match self.check(&params) {
+            Ok(replaces) => replaces,
+            Err(e) => return ToolResult::error(format!("Move failed: {}", e)),
+        };
+
+        let mut output = format!("Moved {} to {}", params.source, params.destination);
+        if replaces {
+            match move_to_trash(self.operation_log.trash_dir(), Path::new(&params.destination)) {
+                Ok(trashed) => output.push_str(&format!("\nThe previous {} was moved to {}", params.destination, trashed.display())),
+                Err(e) => return ToolResult::error(format!("Move failed: cannot replace {}: {}", params.destination, e)),
+            }
+        }
+        if let Err(e) = create_parent(&params.destination) {
+            return ToolResult::error(format!("Move failed: {}", e));
+        }
+        if let Err(e) = move_path(Path::new(&params.source), Path::new(&params.destination)) {
+            return ToolResult::error(format!("Move failed: {}", e));
+        }
+
+        self.operation_log.rename_path(&params.source, &params.destination).await;
+        self.operation_log.log_operation(FsOperationType::Move, params.source.clone()).await;
+        self.operation_log.log_operation(FsOperationType::Move, params.destination.clone()).await;
+
+        let mut meta = HashMap::new();
+        meta.insert("source".to_string(), json!(params.source));
+        meta.insert("destination".to_string(), json!(params.destination));
+        meta.insert("replaced".to_string(), json!(replaces));
+        meta.insert("operation".to_string(), json!("move"));
+
+        ToolResult::Success {
+            output,
+            metadata: Some(meta),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct CopyTool {
+    operation_log: Arc<FsOperationLog>,
+}
+
+impl CopyTool {
+    pub fn new(operation_log: Arc<FsOperationLog>) -> Self {
+        Self { operation_log }
+    }
+}
+
+#[tool(name = "copy", description = r#"Copies a file or a directory with everything it contains. Use it instead of `cp` in `bash`, so that the copy is tracked and what it affects is shown before it happens.
+
+**Usage:**
+- `destination` is the full path of the copy, not the directory to copy into. Missing parent directories are created.
+- An existing destination file is only replaced when `overwrite` is set, the replaced file is kept in the trash. A directory is never replaced."#, capabilities = [ToolCapability::Write])]
+impl CopyTool {
+    async fn execute_preview(&self, params: CopyToolParams) -> Option<ToolResult> {
+        Some(match check_transfer(&params.source, &params.destination, params.overwrite) {
+            Ok(replaces) => ToolResult::success(describe_transfer("Copy", &params.source, &params.destination, replaces)),
+            Err(e) => ToolResult::error(format!("Copy failed: {}", e)),
+        })
+    }
+
+    async fn execute(&self, params: CopyToolParams) -> ToolResult {
+        let replaces = match check_transfer(&params.source, &params.destination, params.overwrite) {
+            Ok(replaces) => replaces,
+            Err(e) => return ToolResult::error(format!("Copy failed: {}", e)),
+        };
+
+        let mut output = format!("Copied {} to {}", params.source, params.destination);
+        if replaces {
+            match move_to_trash(self.operation_log.trash_dir(), Path::new(&params.destination)) {
+                Ok(trashed) => output.push_str(&format!("\nThe previous {} was moved to {}", params.destination, trashed.display())),
+                Err(e) => return ToolResult::error(format!("Copy failed: cannot replace {}: {}", params.destination, e)),
+            }
+        }
+        if let Err(e) = create_parent(&params.destination) {
+            return ToolResult::error(format!("Copy failed: {}", e));
+        }
+        if let Err(e) = copy_recursive(Path::new(&params.source), Path::new(&params.destination)) {
+            // no half copied directory left behind
+            let _ = remove_path(Path::new(&params.destination));
+            return ToolResult::error(format!("Copy failed: {}", e));
+        }
+
+        self.operation_log.log_operation(FsOperationType::Copy, params.destination.clone()).await;
+
+        let contents = list_contents(Path::new(&params.destination));
+        let mut meta = HashMap::new();
+        meta.insert("source".to_string(), json!(params.source));
+        meta.insert("destination".to_string(), json!(params.destination));
+        meta.insert("replaced".to_string(), json!(replaces));
+        meta.insert("files".to_string(), json!(contents.files));
+        meta.insert("operation".to_string(), json!("copy"));
+
+        ToolResult::Success {
+            output,
+            metadata: Some(meta),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct DeleteTool {
+    operation_log: Arc<FsOperationLog>,
+}
+
+impl DeleteTool {
+    pub fn new(operation_log: Arc<FsOperationLog>) -> Self {
+        Self { operation_log }
+    }
+
+    fn check(&self, params: &DeleteToolParams) -> Result<(), String> {
+        let metadata = fs::symlink_metadata(&params.path)
+            .map_err(|_| format!("Path does not exist: {}", params.path))?;
+        if metadata.is_dir() && !params.recursive {
+            return Err(format!("{} is a directory, set recursive to delete it with everything it contains", params.path));
+        }
+        check_not_protected(Path::new(&params.path))
+    }
+}
+
+#[tool(name = "delete", description = r#"Deletes a file or a directory. Use it instead of `rm` in `bash`: what gets deleted is shown before it happens, and deleted entries are moved to a trash folder for the session rather than destroyed, so they can be restored with `move`.
+
+**Usage:**
+- Deleting a directory requires `recursive`, everything it contains is deleted with it.
+- The working directory and its parents cannot be deleted."#, capabilities = [ToolCapability::Write])]
+impl DeleteTool {
+    async fn execute_preview(&self, params: DeleteToolParams) -> Option<ToolResult> {
+        if let Err(e) = self.check(&params) {
+            return Some(ToolResult::error(format!("Delete failed: {}", e)));
+        }
+        let path = Path::new(&params.path);
+        let contents = list_contents(path);
+        let preview = if path.is_dir() {
+            format!("Delete {} and everything it contains:\n{}", params.path, contents.describe())
+        } else {
+            format!("Delete {} ({} bytes)", params.path, contents.bytes)
+        };
+        Some(ToolResult::success(preview))
+    }
+
+    async fn execute(&self, params: DeleteToolParams) -> ToolResult {
+        if let Err(e) = self.check(&params) {
+            return ToolResult::error(format!("Delete failed: {}", e));
+        }
+
+        let contents = list_contents(Path::new(&params.path));
+        let trashed = match move_to_trash(self.operation_log.trash_dir(), Path::new(&params.path)) {
+            Ok(trashed) => trashed,
+            Err(e) => return ToolResult::error(format!("Delete failed: {}", e)),
+        };
+
+        self.operation_log.log_operation(FsOperationType::Delete, params.path.clone()).await;
+
+        let mut meta = HashMap::new();
+        meta.insert("path".to_string(), json!(params.path));
+        meta.insert("trash_path".to_string(), json!(trashed.to_string_lossy()));
+        meta.insert("files".to_string(), json!(contents.files));
+        meta.insert("operation".to_string(), json!("delete"));
+
+        ToolResult::Success {
+            output: format!(
+                "Deleted {} ({} file(s)), it was moved to {} and can be restored from there",
+                params.path, contents.files, trashed.display()
+            ),
+            metadata: Some(meta),
+        }
+    }
+}
@@ -0,0 +1,9 @@
+pub mod structs;
+pub mod ops;
+pub mod manage;
+
+#[cfg(test)]
+mod tests;
+
+pub use structs::{MoveToolParams, CopyToolParams, DeleteToolParams};
+pub use manage::{MoveTool, CopyTool, DeleteTool};
@@ -0,0 +1,182 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Entries listed in a preview, the others are only counted
+const MAX_LISTED_ENTRIES: usize = 50;
+
+/// Bytes a delete may copy to a trash on another file system, bigger trees are refused
+const MAX_CROSS_DEVICE_TRASH_BYTES: u64 = 100 * 1024 * 1024;
+
+/// What a path holds, for previews and outputs
+pub struct Contents {
+    /// Paths relative to the listed directory, directories end with `/`
+    pub listed: Vec<String>,
+    /// Entries beyond the listed ones
+    pub omitted: usize,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// Files and directories below a path, a file lists nothing but counts for itself
+pub fn list_contents(path: &Path) -> Contents {
+    let mut contents = Contents { listed: Vec::new(), omitted: 0, files: 0, bytes: 0 };
+    let mut pending = vec![path.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let Ok(metadata) = fs::symlink_metadata(&current) else {
+            continue;
+        };
+        if current != path {
+            if contents.listed.len() < MAX_LISTED_ENTRIES {
+                let relative = current.strip_prefix(path).unwrap_or(&current).to_string_lossy().to_string();
+                contents.listed.push(if metadata.is_dir() { format!("{}/", relative) } else { relative });
+            } else {
+                contents.omitted += 1;
+            }
+        }
+        if !metadata.is_dir() {
+            contents.files += 1;
+            contents.bytes += metadata.len();
+            continue;
+        }
+        let Ok(entries) = fs::read_dir(&current) else {
+            continue;
+        };
+        let mut children: Vec<PathBuf> = entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect();
+        // popped from the end, reversed to list them in order
+        children.sort_by(|a, b| b.cmp(a));
+        pending.extend(children);
+    }
+    contents
+}
+
+impl Contents {
+    /// The listing as indented lines, with a note for the entries left out
+    pub fn describe(&self) -> String {
+        let mut lines: Vec<String> = self.listed.iter().map(|entry| format!("  {}", entry)).collect();
+        if self.omitted > 0 {
+            lines.push(format!("  ... and {} more", self.omitted));
+        }
+        lines.push(format!("{} file(s), {} bytes", self.files, self.bytes));
+        lines.join("\n")
+    }
+}
+
+/// Copy a file, a symlink or a directory and everything it contains
+pub fn copy_recursive(source: &Path, destination: &Path) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(source)?;
+    if metadata.is_dir() {
+        fs::create_dir_all(destination)?;
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &destination.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else if metadata.file_type().is_symlink() {
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(fs::read_link(source)?, destination)?;
+        #[cfg(not(unix))]
+        fs::copy(source, destination)?;
+        Ok(())
+    } else {
+        // permissions come along
+        fs::copy(source, destination).map(|_| ())
+    }
+}
+
+pub fn remove_path(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Rename, or copy then remove when the destination is on another file system
+pub fn move_path(source: &Path, destination: &Path) -> io::Result<()> {
+    let Err(error) = fs::rename(source, destination) else {
+        return Ok(());
+    };
+    if destination.exists() {
+        return Err(error);
+    }
+    if copy_recursive(source, destination).is_err() {
+        let _ = remove_path(destination);
+        return Err(error);
+    }
+    remove_path(source)
+}
+
+#[cfg(unix)]
+fn same_device(a: &Path, b: &Path) -> io::Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+    Ok(fs::symlink_metadata(a)?.dev() == fs::metadata(b)?.dev())
+}
+
+#[cfg(not(unix))]
+fn same_device(_a: &Path, _b: &Path) -> io::Result<bool> {
+    Ok(true)
+}
+
+/// Move a path to the trash, under a name not taken yet, returns where it is now.
+/// The trash keeps git away, and a big tree on another file system is refused rather than copied there
+pub fn move_to_trash(trash_dir: &Path, path: &Path) -> io::Result<PathBuf> {
+    fs::create_dir_all(trash_dir)?;
+    let ignore = trash_dir.join(".gitignore");
+    if !ignore.exists() {
+        fs::write(&ignore, "*\n")?;
+    }
+    if !same_device(path, trash_dir)? {
+        let bytes = list_contents(path).bytes;
+        if bytes > MAX_CROSS_DEVICE_TRASH_BYTES {
+            return Err(io::Error::other(format!(
+                "{} holds {} bytes on another file system than the trash ({}), moving them there would copy them all",
+                path.display(), bytes, trash_dir.display()
+            )));
+        }
+    }
+    let name = path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "unnamed".to_string());
+    let mut index = 1;
+    let mut target = trash_dir.join(&name);
+    while fs::symlink_metadata(&target).is_ok() {
+        index += 1;
+        target = trash_dir.join(format!("{}.{}", name, index));
+    }
+    move_path(path, &target)?;
+    Ok(target)
+}
+
+/// Moving or deleting these would take the working directory along
+pub fn check_not_protected(path: &Path) -> Result<(), String> {
+    let canonical = path.canonicalize().map_err(|e| format!("{}: {}", path.display(), e))?;
+    let cwd = std::env::current_dir().map_err(|e| e.to_string())?;
+    if cwd.starts_with(&canonical) {
+        return Err(format!("{} is the working directory or one of its parents", path.display()));
+    }
+    Ok(())
+}
+
+/// A destination within its own source would copy or move a directory into itself
+pub fn check_not_inside(source: &Path, destination: &Path) -> Result<(), String> {
+    let source = source.canonicalize().map_err(|e| format!("{}: {}", source.display(), e))?;
+    // the destination does not exist yet, resolve its closest existing ancestor
+    let mut existing = destination;
+    let mut rest = Vec::new();
+    while !existing.exists() {
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                existing = if parent.as_os_str().is_empty() { Path::new(".") } else { parent };
+            }
+            _ => break,
+        }
+    }
+    let mut resolved = existing.canonicalize().unwrap_or_else(|_| existing.to_path_buf());
+    resolved.extend(rest.iter().rev());
+    if resolved.starts_with(&source) {
+        return Err(format!("{} is inside {}", destination.display(), source.display()));
+    }
+    Ok(())
+}
@@ -0,0 +1,33 @@
+use serde::Deserialize;
+use schemars::JsonSchema;
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct MoveToolParams {
+    /// Path of the file or directory to move
+    pub source: String,
+    /// New path of the file or directory, missing parent directories are created
+    pub destination: String,
+    /// Replace the destination file if it exists, it is kept in the trash (default: false)
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct CopyToolParams {
+    /// Path of the file or directory to copy
+    pub source: String,
+    /// Path of the copy, missing parent directories are created
+    pub destination: String,
+    /// Replace the destination file if it exists, it is kept in the trash (default: false)
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DeleteToolParams {
+    /// Path of the file or directory to delete
+    pub path: String,
+    /// Required to delete a directory and everything it contains (default: false)
+    #[serde(default)]
+    pub recursive: bool,
+}
@@ -0,0 +1,156 @@
+use super::manage::{CopyTool, DeleteTool, MoveTool};
+use super::structs::{CopyToolParams, DeleteToolParams, MoveToolParams};
+use crate::tools::{Tool, ToolCapability, ToolResult, FsOperationLog, FsOperationType};
+use shai_llm::ToolDescription;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::tempdir;
+
+fn path_str(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+fn output(result: ToolResult) -> String {
+    match result {
+        ToolResult::Success { output, .. } => output,
+        ToolResult::Error { error, .. } => panic!("Expected success, got: {}", error),
+    }
+}
+
+#[test]
+fn test_manage_tools_require_write() {
+    let dir = tempdir().unwrap();
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")));
+    assert_eq!(MoveTool::new(log.clone()).name(), "move");
+    assert_eq!(CopyTool::new(log.clone()).name(), "copy");
+    assert_eq!(DeleteTool::new(log.clone()).name(), "delete");
+    assert_eq!(MoveTool::new(log.clone()).capabilities(), &[ToolCapability::Write]);
+    assert_eq!(CopyTool::new(log.clone()).capabilities(), &[ToolCapability::Write]);
+    assert_eq!(DeleteTool::new(log).capabilities(), &[ToolCapability::Write]);
+}
+
+#[tokio::test]
+async fn test_move_keeps_read_state() {
+    let dir = tempdir().unwrap();
+    let source = dir.path().join("a.txt");
+    let destination = dir.path().join("nested/b.txt");
+    fs::write(&source, "content").unwrap();
+
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")));
+    log.log_operation(FsOperationType::Read, path_str(&source)).await;
+    let tool = MoveTool::new(log.clone());
+    let params = MoveToolParams { source: path_str(&source), destination: path_str(&destination), overwrite: false };
+
+    assert!(output(tool.execute_preview(params.clone()).await.unwrap()).contains("->"));
+    assert!(source.exists());
+
+    output(tool.execute(params).await);
+    assert!(!source.exists());
+    assert_eq!(fs::read_to_string(&destination).unwrap(), "content");
+    assert!(log.has_been_read(&path_str(&destination)).await);
+    assert!(!log.has_been_read(&path_str(&source)).await);
+}
+
+#[tokio::test]
+async fn test_move_refuses_to_overwrite_unless_asked() {
+    let dir = tempdir().unwrap();
+    let source = dir.path().join("new.txt");
+    let destination = dir.path().join("old.txt");
+    fs::write(&source, "new").unwrap();
+    fs::write(&destination, "old").unwrap();
+
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join("trash")));
+    let tool = MoveTool::new(log.clone());
+    let result = tool.execute(MoveToolParams { source: path_str(&source), destination: path_str(&destination), overwrite: false }).await;
+    assert!(!result.is_success());
+    assert_eq!(fs::read_to_string(&destination).unwrap(), "old");
+
+    output(tool.execute(MoveToolParams { source: path_str(&source), destination: path_str(&destination), overwrite: true }).await);
+    assert_eq!(fs::read_to_string(&destination).unwrap(), "new");
+    // the replaced file went to the trash
+    assert_eq!(fs::read_to_string(log.trash_dir().join("old.txt")).unwrap(), "old");
+}
+
+#[tokio::test]
+async fn test_move_directory_into_itself_fails() {
+    let dir = tempdir().unwrap();
+    let source = dir.path().join("src");
+    fs::create_dir(&source).unwrap();
+
+    let tool = MoveTool::new(Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash"))));
+    let result = tool.execute(MoveToolParams { source: path_str(&source), destination: path_str(&source.join("inner")), overwrite: false }).await;
+    assert!(!result.is_success());
+    assert!(source.is_dir());
+}
+
+#[tokio::test]
+async fn test_copy_directory() {
+    let dir = tempdir().unwrap();
+    let source = dir.path().join("src");
+    fs::create_dir_all(source.join("sub")).unwrap();
+    fs::write(source.join("a.txt"), "a").unwrap();
+    fs::write(source.join("sub/b.txt"), "b").unwrap();
+    let destination = dir.path().join("copy");
+
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")));
+    let tool = CopyTool::new(log.clone());
+    let params = CopyToolParams { source: path_str(&source), destination: path_str(&destination), overwrite: false };
+    let preview = output(tool.execute_preview(params.clone()).await.unwrap());
+    assert!(preview.contains("sub/b.txt"));
+    assert!(preview.contains("2 file(s)"));
+
+    output(tool.execute(params).await);
+    assert_eq!(fs::read_to_string(destination.join("sub/b.txt")).unwrap(), "b");
+    assert_eq!(fs::read_to_string(source.join("a.txt")).unwrap(), "a");
+    assert_eq!(log.get_summary().await.copy_count, 1);
+}
+
+#[tokio::test]
+async fn test_delete_moves_to_trash() {
+    let dir = tempdir().unwrap();
+    let target = dir.path().join("build");
+    fs::create_dir_all(&target).unwrap();
+    fs::write(target.join("out.o"), "binary").unwrap();
+
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join("trash")));
+    let tool = DeleteTool::new(log.clone());
+
+    // directories need recursive
+    let result = tool.execute(DeleteToolParams { path: path_str(&target), recursive: false }).await;
+    assert!(!result.is_success());
+    assert!(target.exists());
+
+    let params = DeleteToolParams { path: path_str(&target), recursive: true };
+    let preview = output(tool.execute_preview(params.clone()).await.unwrap());
+    assert!(preview.contains("out.o"));
+    assert!(target.exists());
+
+    output(tool.execute(params).await);
+    assert!(!target.exists());
+    assert_eq!(fs::read_to_string(log.trash_dir().join("build/out.o")).unwrap(), "binary");
+    // what is in the trash is not picked up by git
+    assert_eq!(fs::read_to_string(log.trash_dir().join(".gitignore")).unwrap(), "*\n");
+    assert_eq!(log.get_summary().await.delete_count, 1);
+}
+
+#[test]
+fn test_session_trash_is_in_the_workspace() {
+    let dir = tempdir().unwrap();
+    let log = FsOperationLog::for_session(dir.path(), "session-1");
+    assert_eq!(log.trash_dir(), dir.path().join(".shai").join("trash").join("session-1"));
+    assert!(!dir.path().join(".shai").exists());
+
+    // a log without a workspace never puts its trash in the working directory
+    let cwd = std::env::current_dir().unwrap();
+    assert!(!FsOperationLog::new().trash_dir().starts_with(cwd.join(".shai")));
+}
+
+#[tokio::test]
+async fn test_delete_refuses_working_directory() {
+    let dir = tempdir().unwrap();
+    let tool = DeleteTool::new(Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash"))));
+    let result = tool.execute(DeleteToolParams { path: ".".to_string(), recursive: true }).await;
+    assert!(!result.is_success());
+    assert!(Path::new(".").exists());
+}
@@ -1,9 +1,15 @@
 pub mod edit;
 pub mod find;
+pub mod glob;
 pub mod ls;
+pub mod manage;
 pub mod multiedit;
+pub mod multi_file_edit;
 pub mod operation_log;
+pub mod patch;
 pub mod read;
+pub mod text;
+pub mod transaction;
 pub mod write;
 
 #[cfg(test)]
@@ -11,8 +17,13 @@ mod tests;
 
 pub use edit::EditTool;
 pub use find::FindTool;
+pub use glob::GlobTool;
 pub use ls::LsTool;
+pub use manage::{MoveTool, CopyTool, DeleteTool};
 pub use multiedit::MultiEditTool;
+pub use multi_file_edit::MultiFileEditTool;
+pub use patch::ApplyPatchTool;
 pub use operation_log::{FsOperationLog, FsOperationType, FsOperation, FsOperationSummary};
 pub use read::ReadTool;
+pub use transaction::FsTransaction;
 pub use write::WriteTool;
\ No newline at end of file
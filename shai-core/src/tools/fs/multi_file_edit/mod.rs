@@ -0,0 +1,8 @@
+pub mod structs;
+pub mod multi_file_edit;
+
+#[cfg(test)]
+mod tests;
+
+pub use structs::{MultiFileEditToolParams, FileEdits};
+pub use multi_file_edit::MultiFileEditTool;
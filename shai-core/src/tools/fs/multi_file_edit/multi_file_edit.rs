@@ -0,0 +1,141 @@
+use super::structs::MultiFileEditToolParams;
+use super::super::{EditTool, FsOperationLog, FsOperationType, FsTransaction};
+use crate::tools::{tool, ToolResult};
+use serde_json::json;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct MultiFileEditTool {
+    operation_log: Arc<FsOperationLog>,
+    edit_tool: EditTool,
+}
+
+impl MultiFileEditTool {
+    pub fn new(operation_log: Arc<FsOperationLog>) -> Self {
+        let edit_tool = EditTool::new(operation_log.clone());
+        Self { operation_log, edit_tool }
+    }
+
+    /// Apply every edit in a transaction, the problems of all files are reported at once
+    async fn stage(&self, params: &MultiFileEditToolParams) -> Result<(FsTransaction, usize), Vec<String>> {
+        let mut transaction = FsTransaction::begin();
+        let mut errors = Vec::new();
+        let mut replacements = 0;
+
+        for file in &params.files {
+            if file.edits.is_empty() {
+                errors.push(format!("{}: at least one edit operation is required", file.path));
+                continue;
+            }
+            if let Err(error) = self.operation_log.validate_edit_permission(&file.path).await {
+                errors.push(error);
+                continue;
+            }
+
+            // a file listed twice sees the edits staged for it before
+            let mut content = match transaction.read(&file.path) {
+                Ok(content) => content,
+                Err(error) => {
+                    errors.push(error);
+                    continue;
+                }
+            };
+            let mut failed = false;
+            for (index, edit) in file.edits.iter().enumerate() {
+                match self.edit_tool.perform_edit_on_content(&content, &edit.old_string, &edit.new_string, edit.replace_all) {
+                    Ok((new_content, count)) => {
+                        content = new_content;
+                        replacements += count;
+                    }
+                    Err(error) => {
+                        errors.push(format!("{}: edit #{}: {}", file.path, index + 1, error));
+                        failed = true;
+                        break;
+                    }
+                }
+            }
+            if !failed {
+                if let Err(error) = transaction.write(&file.path, content) {
+                    errors.push(error);
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok((transaction, replacements))
+        } else {
+            transaction.rollback();
+            Err(errors)
+        }
+    }
+
+    fn diff(&self, transaction: &FsTransaction) -> String {
+        transaction.changes()
+            .map(|(path, before, after)| {
+                let diff = self.edit_tool.myers_diff(before.unwrap_or_default(), after.unwrap_or_default());
+                format!("\x1b[1m{}\x1b[0m\n{}", path, diff)
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+#[tool(name = "multi_file_edit", description = r#"Applies find-and-replace edits to several files as a single transaction. Use it for refactors spanning multiple files (renaming a function and its call sites, changing a signature and its callers...), so that the project is never left half-modified.
+
+**Execution Logic:**
+- For each file, its edits are applied in order, like with `multiedit`: each one operates on the result of the previous one, and each `old_string` is located like with `edit`.
+- All changes are prepared in memory first. If any edit of any file fails, nothing is written and the error lists every failed edit; otherwise all the files are written together.
+
+**Critical Considerations:**
+- You must first use the `read` tool on every file you edit, and they must not have changed since.
+- Only existing files can be edited, use `write` or `apply_patch` to create files."#, capabilities = [ToolCapability::Read, ToolCapability::Write])]
+impl MultiFileEditTool {
+    async fn execute_preview(&self, params: MultiFileEditToolParams) -> Option<ToolResult> {
+        Some(self.execute_internal(params, true).await)
+    }
+
+    async fn execute(&self, params: MultiFileEditToolParams) -> ToolResult {
+        self.execute_internal(params, false).await
+    }
+
+    async fn execute_internal(&self, params: MultiFileEditToolParams, preview: bool) -> ToolResult {
+        if params.files.is_empty() {
+            return ToolResult::error("At least one file to edit is required".to_string());
+        }
+
+        let (transaction, replacements) = match self.stage(&params).await {
+            Ok(staged) => staged,
+            Err(errors) => {
+                return ToolResult::error(format!(
+                    "Edit failed, no file was changed:\n{}",
+                    errors.iter().map(|error| format!("- {}", error)).collect::<Vec<_>>().join("\n")
+                ));
+            }
+        };
+
+        let diff = self.diff(&transaction);
+        let paths: BTreeSet<String> = params.files.iter().map(|file| file.path.clone()).collect();
+
+        if !preview {
+            if let Err(error) = transaction.commit() {
+                return ToolResult::error(format!("Edit failed, no file was changed: {}", error));
+            }
+            for path in &paths {
+                self.operation_log.log_operation(FsOperationType::MultiEdit, path.clone()).await;
+            }
+        }
+
+        let mut meta = HashMap::new();
+        meta.insert("paths".to_string(), json!(paths));
+        meta.insert("file_count".to_string(), json!(paths.len()));
+        meta.insert("edit_count".to_string(), json!(params.files.iter().map(|file| file.edits.len()).sum::<usize>()));
+        meta.insert("total_replacements".to_string(), json!(replacements));
+        meta.insert("preview_mode".to_string(), json!(preview));
+
+        ToolResult::Success {
+            output: diff,
+            metadata: Some(meta),
+        }
+    }
+}
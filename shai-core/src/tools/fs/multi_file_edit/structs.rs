@@ -0,0 +1,18 @@
+use super::super::multiedit::EditOperation;
+use serde::Deserialize;
+use schemars::JsonSchema;
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[schemars(inline)]
+pub struct FileEdits {
+    /// Path to the file to edit
+    pub path: String,
+    /// Array of edit operations to perform sequentially on this file
+    pub edits: Vec<EditOperation>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct MultiFileEditToolParams {
+    /// Files to edit, either every edit succeeds and all the files are written, or none is
+    pub files: Vec<FileEdits>,
+}
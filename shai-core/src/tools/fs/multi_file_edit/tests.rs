@@ -0,0 +1,116 @@
+use super::structs::{FileEdits, MultiFileEditToolParams};
+use super::multi_file_edit::MultiFileEditTool;
+use crate::tools::fs::multiedit::EditOperation;
+use crate::tools::{Tool, ToolCapability, ToolResult, FsOperationLog, FsOperationType};
+use shai_llm::ToolDescription;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::tempdir;
+
+fn edit(old_string: &str, new_string: &str) -> EditOperation {
+    EditOperation {
+        old_string: old_string.to_string(),
+        new_string: new_string.to_string(),
+        replace_all: false,
+    }
+}
+
+fn file_edits(path: &Path, edits: Vec<EditOperation>) -> FileEdits {
+    FileEdits { path: path.to_string_lossy().to_string(), edits }
+}
+
+async fn read_log(dir: &Path, paths: &[&Path]) -> Arc<FsOperationLog> {
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.join(".trash")));
+    for path in paths {
+        log.log_operation(FsOperationType::Read, path.to_string_lossy().to_string()).await;
+    }
+    log
+}
+
+#[test]
+fn test_multi_file_edit_tool_creation() {
+    let dir = tempdir().unwrap();
+    let tool = MultiFileEditTool::new(Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash"))));
+    assert_eq!(tool.name(), "multi_file_edit");
+    assert!(tool.capabilities().contains(&ToolCapability::Write));
+}
+
+#[tokio::test]
+async fn test_multi_file_edit_renames_across_files() {
+    let dir = tempdir().unwrap();
+    let lib = dir.path().join("lib.rs");
+    let main = dir.path().join("main.rs");
+    fs::write(&lib, "pub fn compute() -> u32 {\n    42\n}\n").unwrap();
+    fs::write(&main, "fn main() {\n    println!(\"{}\", compute());\n}\n").unwrap();
+
+    let tool = MultiFileEditTool::new(read_log(dir.path(), &[&lib, &main]).await);
+    let result = tool.execute(MultiFileEditToolParams {
+        files: vec![
+            file_edits(&lib, vec![edit("pub fn compute()", "pub fn answer()")]),
+            file_edits(&main, vec![edit("compute()", "answer()")]),
+        ],
+    }).await;
+    assert!(result.is_success(), "{:?}", result);
+
+    assert_eq!(fs::read_to_string(&lib).unwrap(), "pub fn answer() -> u32 {\n    42\n}\n");
+    assert_eq!(fs::read_to_string(&main).unwrap(), "fn main() {\n    println!(\"{}\", answer());\n}\n");
+}
+
+#[tokio::test]
+async fn test_multi_file_edit_failure_writes_nothing() {
+    let dir = tempdir().unwrap();
+    let first = dir.path().join("first.txt");
+    let second = dir.path().join("second.txt");
+    fs::write(&first, "alpha\n").unwrap();
+    fs::write(&second, "beta\n").unwrap();
+
+    let tool = MultiFileEditTool::new(read_log(dir.path(), &[&first, &second]).await);
+    let result = tool.execute(MultiFileEditToolParams {
+        files: vec![
+            file_edits(&first, vec![edit("alpha", "ALPHA")]),
+            file_edits(&second, vec![edit("beta", "BETA"), edit("gamma", "GAMMA")]),
+        ],
+    }).await;
+    match result {
+        ToolResult::Error { error, .. } => {
+            assert!(error.contains("no file was changed"));
+            assert!(error.contains("edit #2"));
+        }
+        _ => panic!("Expected the edit to fail"),
+    }
+    assert_eq!(fs::read_to_string(&first).unwrap(), "alpha\n");
+    assert_eq!(fs::read_to_string(&second).unwrap(), "beta\n");
+}
+
+#[tokio::test]
+async fn test_multi_file_edit_preview_and_read_requirement() {
+    let dir = tempdir().unwrap();
+    let read = dir.path().join("read.txt");
+    let unread = dir.path().join("unread.txt");
+    fs::write(&read, "Hello\n").unwrap();
+    fs::write(&unread, "World\n").unwrap();
+
+    let tool = MultiFileEditTool::new(read_log(dir.path(), &[&read]).await);
+    let params = MultiFileEditToolParams {
+        files: vec![file_edits(&read, vec![edit("Hello", "Hi")])],
+    };
+    match tool.execute_preview(params).await.unwrap() {
+        ToolResult::Success { output, .. } => {
+            assert!(output.contains("read.txt"));
+            assert!(output.contains("Hello"));
+            assert!(output.contains("Hi"));
+        }
+        _ => panic!("Expected a preview"),
+    }
+    assert_eq!(fs::read_to_string(&read).unwrap(), "Hello\n");
+
+    let result = tool.execute(MultiFileEditToolParams {
+        files: vec![
+            file_edits(&read, vec![edit("Hello", "Hi")]),
+            file_edits(&unread, vec![edit("World", "Earth")]),
+        ],
+    }).await;
+    assert!(!result.is_success());
+    assert_eq!(fs::read_to_string(&read).unwrap(), "Hello\n");
+}
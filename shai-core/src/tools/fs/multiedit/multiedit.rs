@@ -1,10 +1,9 @@
 use super::structs::MultiEditToolParams;
-use super::super::{FsOperationLog, FsOperationType, EditTool};
+use super::super::{text, FsOperationLog, FsOperationType, EditTool};
 use crate::tools::{tool, ToolResult};
 use serde_json::json;
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::fs;
 use std::path::Path;
 
 #[derive(Clone)]
@@ -28,7 +27,7 @@ impl MultiEditTool {
         }
 
         // Read initial content
-        let mut current_content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let (mut current_content, _) = text::read_text(&params.file_path)?;
         let original_content = current_content.clone();
         let mut replacements_per_edit = Vec::new();
 
@@ -61,10 +60,11 @@ impl MultiEditTool {
 
 **Execution Logic:**
 - Edits are applied in the exact order they are provided. The second edit operates on the result of the first, the third on the result of the second, and so on.
-- The entire sequence is atomic. If any single edit fails (e.g., its `old_string` is not found), the whole operation is rolled back, and the file remains unmodified.
+- The entire sequence is atomic. If any single edit fails (e.g., its `old_string` is not found or matches several places without `replace_all`), the whole operation is rolled back, and the file remains unmodified.
 
 **Critical Considerations:**
 - You must first use the `read` tool to understand the file's contents.
+- Each `old_string` is located like with the `edit` tool: exact match first, then ignoring line endings, indentation and trailing whitespace.
 - Plan your sequence of edits carefully. An earlier edit might alter the text that a later edit is intended to match, which could cause the later edit to fail."#, capabilities = [ToolCapability::Read, ToolCapability::Write])]
 impl MultiEditTool {
     async fn execute_preview(&self, params: MultiEditToolParams) -> Option<ToolResult> {
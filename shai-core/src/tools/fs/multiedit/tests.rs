@@ -8,7 +8,8 @@ use tempfile::tempdir;
 
 #[test]
 fn test_multiedit_tool_permissions() {
-    let log = Arc::new(FsOperationLog::new());
+    let dir = tempdir().unwrap();
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")));
     let tool = MultiEditTool::new(log);
     let perms = tool.capabilities();
     assert!(perms.contains(&ToolCapability::Read));
@@ -18,7 +19,8 @@ fn test_multiedit_tool_permissions() {
 
 #[tokio::test]
 async fn test_multiedit_tool_creation() {
-    let log = Arc::new(FsOperationLog::new());
+    let dir = tempdir().unwrap();
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")));
     let tool = MultiEditTool::new(log);
     assert_eq!(tool.name(), "multiedit");
     assert!(!tool.description().is_empty());
@@ -30,7 +32,7 @@ async fn test_multiedit_multiple_replacements() {
     let file_path = dir.path().join("test.txt");
     fs::write(&file_path, "Hello World, Hello Universe").unwrap();
     
-    let log = Arc::new(FsOperationLog::new());
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")));
     // First read the file to satisfy the logging requirement
     log.log_operation(crate::tools::FsOperationType::Read, file_path.to_string_lossy().to_string()).await;
     
@@ -39,8 +41,8 @@ async fn test_multiedit_multiple_replacements() {
         file_path: file_path.to_string_lossy().to_string(),
         edits: vec![
             EditOperation {
-                old_string: "Hello".to_string(),
-                new_string: "Hi".to_string(),
+                old_string: "Hello World".to_string(),
+                new_string: "Hi World".to_string(),
                 replace_all: false,
             },
             EditOperation {
@@ -64,7 +66,7 @@ async fn test_multiedit_preview_diff_output() {
     let file_path = dir.path().join("test.txt");
     fs::write(&file_path, "line1\nHello World\nline3\nGoodbye World").unwrap();
     
-    let log = Arc::new(FsOperationLog::new());
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")));
     log.log_operation(crate::tools::FsOperationType::Read, file_path.to_string_lossy().to_string()).await;
     
     let tool = MultiEditTool::new(log);
@@ -1,7 +1,18 @@
-use std::collections::HashSet;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use tokio::sync::RwLock;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use similar::TextDiff;
+
+/// Files larger than this are only hashed, their external changes cannot be shown
+const MAX_SNAPSHOT_BYTES: usize = 1024 * 1024;
+
+/// Lines of the diff of an external change shown in the error
+const MAX_EXTERNAL_DIFF_LINES: usize = 200;
 
 /// Represents a file system operation
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +29,58 @@ pub enum FsOperationType {
     Write,
     Edit,
     MultiEdit,
+    Patch,
+    Move,
+    Copy,
+    Delete,
+}
+
+/// A file as the agent last saw it, through a read or its own write
+#[derive(Debug, Clone)]
+struct FileSnapshot {
+    hash: u64,
+    len: u64,
+    modified: Option<SystemTime>,
+    /// Kept for text files small enough, to show what changed behind the agent's back
+    content: Option<String>,
+}
+
+impl FileSnapshot {
+    async fn capture(path: &str) -> Option<Self> {
+        // metadata first: a change in between leaves an older mtime, caught by the hash later
+        let metadata = tokio::fs::metadata(path).await.ok()?;
+        if !metadata.is_file() {
+            return None;
+        }
+        let bytes = tokio::fs::read(path).await.ok()?;
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        let content = if bytes.len() <= MAX_SNAPSHOT_BYTES { String::from_utf8(bytes.clone()).ok() } else { None };
+        Some(Self {
+            hash: hasher.finish(),
+            len: bytes.len() as u64,
+            modified: metadata.modified().ok(),
+            content,
+        })
+    }
+
+    /// Unified diff from this snapshot to a newer one
+    fn diff_to(&self, current: &FileSnapshot) -> String {
+        let (Some(before), Some(after)) = (&self.content, &current.content) else {
+            return "(binary or large file, changes not shown)".to_string();
+        };
+        let diff = TextDiff::from_lines(before, after)
+            .unified_diff()
+            .context_radius(3)
+            .header("last seen", "on disk")
+            .to_string();
+        let lines: Vec<&str> = diff.lines().collect();
+        if lines.len() > MAX_EXTERNAL_DIFF_LINES {
+            format!("{}\n[... {} more lines ...]", lines[..MAX_EXTERNAL_DIFF_LINES].join("\n"), lines.len() - MAX_EXTERNAL_DIFF_LINES)
+        } else {
+            diff.trim_end().to_string()
+        }
+    }
 }
 
 /// Shared log for tracking file system operations
@@ -25,14 +88,61 @@ pub enum FsOperationType {
 pub struct FsOperationLog {
     operations: RwLock<Vec<FsOperation>>,
     read_files: RwLock<HashSet<String>>, // Tracks which files have been read
+    snapshots: RwLock<HashMap<String, FileSnapshot>>, // Content last seen by the agent, per file
+    trash_dir: PathBuf, // Deleted files of the session, kept to be restored
 }
 
 impl FsOperationLog {
-    /// Create a new operation log
+    /// Create a new operation log, not tied to a workspace: its trash is `~/.shai/trash/<uuid>`
     pub fn new() -> Self {
+        let home = dirs::home_dir().unwrap_or_else(std::env::temp_dir);
+        Self::with_trash_dir(home.join(".shai").join("trash").join(uuid::Uuid::new_v4().to_string()))
+    }
+
+    /// Operation log of a session, its trash is `.shai/trash/<session_id>` in the workspace:
+    /// on the file system of the files deleted, so that deleting them is a rename rather than a copy
+    pub fn for_session(workspace: &Path, session_id: &str) -> Self {
+        Self::with_trash_dir(workspace.join(".shai").join("trash").join(session_id))
+    }
+
+    pub fn with_trash_dir(trash_dir: PathBuf) -> Self {
         Self {
             operations: RwLock::new(Vec::new()),
             read_files: RwLock::new(HashSet::new()),
+            snapshots: RwLock::new(HashMap::new()),
+            trash_dir,
+        }
+    }
+
+    /// Where the files deleted during the session are moved, so that they can be restored
+    pub fn trash_dir(&self) -> &Path {
+        &self.trash_dir
+    }
+
+    /// Carry what the agent knows about a path, and the files below it, over to its new location
+    pub async fn rename_path(&self, from: &str, to: &str) {
+        let moved = |path: &str| -> Option<String> {
+            let rest = path.strip_prefix(from)?;
+            (rest.is_empty() || rest.starts_with('/')).then(|| format!("{}{}", to, rest))
+        };
+
+        let mut read_files = self.read_files.write().await;
+        let renamed: Vec<(String, String)> = read_files.iter()
+            .filter_map(|path| moved(path).map(|new_path| (path.clone(), new_path)))
+            .collect();
+        for (old_path, new_path) in renamed {
+            read_files.remove(&old_path);
+            read_files.insert(new_path);
+        }
+
+        let mut snapshots = self.snapshots.write().await;
+        let renamed: Vec<(String, String)> = snapshots.keys()
+            .filter_map(|path| moved(path).map(|new_path| (path.clone(), new_path)))
+            .collect();
+        for (old_path, new_path) in renamed {
+            if let Some(snapshot) = snapshots.remove(&old_path) {
+                snapshots.insert(new_path, snapshot);
+            }
         }
     }
 
@@ -50,6 +160,16 @@ impl FsOperationLog {
             ops.push(operation);
         }
 
+        // Whatever the operation, the agent now knows the current content of the file
+        match FileSnapshot::capture(&file_path).await {
+            Some(snapshot) => {
+                self.snapshots.write().await.insert(file_path.clone(), snapshot);
+            }
+            None => {
+                self.snapshots.write().await.remove(&file_path);
+            }
+        }
+
         // If it's a read operation, track it in read_files
         if operation_type == FsOperationType::Read {
             let mut read_files = self.read_files.write().await;
@@ -63,7 +183,7 @@ impl FsOperationLog {
         read_files.contains(file_path)
     }
 
-    /// Validate that a file can be edited (must have been read first)
+    /// Validate that a file can be edited (must have been read first, and not changed since)
     pub async fn validate_edit_permission(&self, file_path: &str) -> Result<(), String> {
         if !self.has_been_read(file_path).await {
             return Err(format!(
@@ -71,7 +191,52 @@ impl FsOperationLog {
                 file_path
             ));
         }
-        Ok(())
+        self.validate_unchanged(file_path).await
+    }
+
+    /// Validate that a file can be overwritten, the agent must know its content from a read or its own write
+    pub async fn validate_overwrite_permission(&self, file_path: &str) -> Result<(), String> {
+        let written = self.operations.read().await.iter()
+            .any(|op| op.file_path == file_path && op.operation_type != FsOperationType::Read);
+        if !written && !self.has_been_read(file_path).await {
+            return Err(format!(
+                "Cannot overwrite file '{}': The file must be read first using the Read tool before it can be overwritten.",
+                file_path
+            ));
+        }
+        self.validate_unchanged(file_path).await
+    }
+
+    /// Check that a file was not modified on disk since the agent last read or wrote it
+    pub async fn validate_unchanged(&self, file_path: &str) -> Result<(), String> {
+        let Some(seen) = self.snapshots.read().await.get(file_path).cloned() else {
+            return Ok(());
+        };
+
+        // same size and mtime, no need to read the file again
+        if let Ok(metadata) = tokio::fs::metadata(file_path).await {
+            if metadata.is_file() && metadata.len() == seen.len && seen.modified.is_some() && metadata.modified().ok() == seen.modified {
+                return Ok(());
+            }
+        }
+
+        let Some(current) = FileSnapshot::capture(file_path).await else {
+            return Err(format!(
+                "Cannot edit file '{}': The file was deleted or became unreadable since it was last read.",
+                file_path
+            ));
+        };
+        if current.hash == seen.hash {
+            // only touched, remember the new mtime
+            self.snapshots.write().await.insert(file_path.to_string(), current);
+            return Ok(());
+        }
+
+        Err(format!(
+            "Cannot edit file '{}': The file was modified since it was last read, by the user or another program. Read it again before editing it. Changes made outside:\n{}",
+            file_path,
+            seen.diff_to(&current)
+        ))
     }
 
     /// Get all operations for a specific file
@@ -106,6 +271,7 @@ impl FsOperationLog {
             let mut read_files = self.read_files.write().await;
             read_files.clear();
         }
+        self.snapshots.write().await.clear();
     }
 
     /// Get summary statistics
@@ -117,6 +283,10 @@ impl FsOperationLog {
         let mut write_count = 0;
         let mut edit_count = 0;
         let mut multiedit_count = 0;
+        let mut patch_count = 0;
+        let mut move_count = 0;
+        let mut copy_count = 0;
+        let mut delete_count = 0;
 
         for op in operations.iter() {
             match op.operation_type {
@@ -124,6 +294,10 @@ impl FsOperationLog {
                 FsOperationType::Write => write_count += 1,
                 FsOperationType::Edit => edit_count += 1,
                 FsOperationType::MultiEdit => multiedit_count += 1,
+                FsOperationType::Patch => patch_count += 1,
+                FsOperationType::Move => move_count += 1,
+                FsOperationType::Copy => copy_count += 1,
+                FsOperationType::Delete => delete_count += 1,
             }
         }
 
@@ -133,6 +307,10 @@ impl FsOperationLog {
             write_count,
             edit_count,
             multiedit_count,
+            patch_count,
+            move_count,
+            copy_count,
+            delete_count,
             unique_files_read: read_files.len(),
         }
     }
@@ -146,6 +324,10 @@ pub struct FsOperationSummary {
     pub write_count: usize,
     pub edit_count: usize,
     pub multiedit_count: usize,
+    pub patch_count: usize,
+    pub move_count: usize,
+    pub copy_count: usize,
+    pub delete_count: usize,
     pub unique_files_read: usize,
 }
 
@@ -161,7 +343,8 @@ mod tests {
 
     #[tokio::test]
     async fn test_new_log_is_empty() {
-        let log = FsOperationLog::new();
+        let dir = tempfile::tempdir().unwrap();
+        let log = FsOperationLog::with_trash_dir(dir.path().join(".trash"));
         let operations = log.get_all_operations().await;
         let read_files = log.get_read_files().await;
         
@@ -171,7 +354,8 @@ mod tests {
 
     #[tokio::test]
     async fn test_log_read_operation() {
-        let log = FsOperationLog::new();
+        let dir = tempfile::tempdir().unwrap();
+        let log = FsOperationLog::with_trash_dir(dir.path().join(".trash"));
         log.log_operation(FsOperationType::Read, "test.txt".to_string()).await;
         
         assert!(log.has_been_read("test.txt").await);
@@ -185,7 +369,8 @@ mod tests {
 
     #[tokio::test]
     async fn test_validate_edit_permission() {
-        let log = FsOperationLog::new();
+        let dir = tempfile::tempdir().unwrap();
+        let log = FsOperationLog::with_trash_dir(dir.path().join(".trash"));
         
         // Should fail before reading
         let result = log.validate_edit_permission("test.txt").await;
@@ -200,7 +385,8 @@ mod tests {
 
     #[tokio::test]
     async fn test_multiple_operations() {
-        let log = FsOperationLog::new();
+        let dir = tempfile::tempdir().unwrap();
+        let log = FsOperationLog::with_trash_dir(dir.path().join(".trash"));
         
         // Log multiple operations
         log.log_operation(FsOperationType::Read, "file1.txt".to_string()).await;
@@ -225,7 +411,8 @@ mod tests {
 
     #[tokio::test]
     async fn test_clear_log() {
-        let log = FsOperationLog::new();
+        let dir = tempfile::tempdir().unwrap();
+        let log = FsOperationLog::with_trash_dir(dir.path().join(".trash"));
         
         log.log_operation(FsOperationType::Read, "test.txt".to_string()).await;
         assert!(!log.get_all_operations().await.is_empty());
@@ -235,4 +422,52 @@ mod tests {
         assert!(log.get_all_operations().await.is_empty());
         assert!(!log.has_been_read("test.txt").await);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_validate_edit_permission_detects_external_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+        let path_str = path.to_string_lossy().to_string();
+        std::fs::write(&path, "one\ntwo\n").unwrap();
+
+        let log = FsOperationLog::with_trash_dir(dir.path().join(".trash"));
+        log.log_operation(FsOperationType::Read, path_str.clone()).await;
+        assert!(log.validate_edit_permission(&path_str).await.is_ok());
+
+        // rewriting the same content is not a change
+        std::fs::write(&path, "one\ntwo\n").unwrap();
+        assert!(log.validate_edit_permission(&path_str).await.is_ok());
+
+        std::fs::write(&path, "one\n2\nthree\n").unwrap();
+        let error = log.validate_edit_permission(&path_str).await.unwrap_err();
+        assert!(error.contains("modified since it was last read"));
+        assert!(error.contains("-two"));
+        assert!(error.contains("+three"));
+
+        // reading it again, or writing it ourselves, brings the log up to date
+        log.log_operation(FsOperationType::Read, path_str.clone()).await;
+        assert!(log.validate_edit_permission(&path_str).await.is_ok());
+        std::fs::write(&path, "mine\n").unwrap();
+        log.log_operation(FsOperationType::Edit, path_str.clone()).await;
+        assert!(log.validate_edit_permission(&path_str).await.is_ok());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(log.validate_edit_permission(&path_str).await.unwrap_err().contains("deleted"));
+    }
+
+    #[tokio::test]
+    async fn test_validate_overwrite_permission() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+        let path_str = path.to_string_lossy().to_string();
+        std::fs::write(&path, "content").unwrap();
+
+        let log = FsOperationLog::with_trash_dir(dir.path().join(".trash"));
+        assert!(log.validate_overwrite_permission(&path_str).await.unwrap_err().contains("must be read first"));
+
+        log.log_operation(FsOperationType::Write, path_str.clone()).await;
+        assert!(log.validate_overwrite_permission(&path_str).await.is_ok());
+        // writing a file does not count as reading it for edits
+        assert!(log.validate_edit_permission(&path_str).await.is_err());
+    }
+}
@@ -0,0 +1,148 @@
+use super::parser::{Hunk, HunkLine};
+
+/// Context lines that may be ignored at each end of a hunk that does not apply as is
+const MAX_FUZZ: usize = 2;
+
+fn same(a: &str, b: &str) -> bool {
+    a == b
+}
+
+fn same_ignoring_trailing_whitespace(a: &str, b: &str) -> bool {
+    a.trim_end() == b.trim_end()
+}
+
+fn same_ignoring_whitespace(a: &str, b: &str) -> bool {
+    a.trim() == b.trim()
+}
+
+/// Ways lines are compared, from the strictest
+const COMPARISONS: [fn(&str, &str) -> bool; 3] = [same, same_ignoring_trailing_whitespace, same_ignoring_whitespace];
+
+/// Lines of a file, with what is needed to write it back the same way
+struct TextFile {
+    lines: Vec<String>,
+    eol: &'static str,
+    trailing_newline: bool,
+}
+
+impl TextFile {
+    fn parse(content: &str) -> Self {
+        Self {
+            lines: content.lines().map(str::to_string).collect(),
+            eol: if content.contains("\r\n") { "\r\n" } else { "\n" },
+            trailing_newline: content.is_empty() || content.ends_with('\n'),
+        }
+    }
+
+    fn render(&self) -> String {
+        if self.lines.is_empty() {
+            return String::new();
+        }
+        let mut content = self.lines.join(self.eol);
+        if self.trailing_newline {
+            content.push_str(self.eol);
+        }
+        content
+    }
+}
+
+/// Where a hunk applies, without its `lead` first and `trail` last context lines when fuzzed
+struct Placement {
+    start: usize,
+    lead: usize,
+    trail: usize,
+}
+
+/// The position of `old` closest to `expected`, not before `min_pos`
+fn find_block(lines: &[String], old: &[&str], expected: usize, min_pos: usize, same: fn(&str, &str) -> bool) -> Option<usize> {
+    if old.is_empty() {
+        return Some(expected.clamp(min_pos, lines.len()));
+    }
+    if lines.len() < old.len() {
+        return None;
+    }
+    (min_pos..=lines.len() - old.len())
+        .filter(|&start| old.iter().enumerate().all(|(i, line)| same(&lines[start + i], line)))
+        .min_by_key(|&start| start.abs_diff(expected))
+}
+
+/// Like `patch`, tries the hunk as is first, then with looser whitespace, then without the
+/// outermost context lines
+fn locate(lines: &[String], hunk: &Hunk, offset: isize, min_pos: usize) -> Option<Placement> {
+    let context_lead = hunk.lines.iter().take_while(|line| matches!(line, HunkLine::Context(_))).count();
+    let context_trail = hunk.lines.iter().rev().take_while(|line| matches!(line, HunkLine::Context(_))).count();
+
+    for fuzz in 0..=MAX_FUZZ {
+        let (lead, trail) = (fuzz.min(context_lead), fuzz.min(context_trail));
+        if fuzz > 0 && (fuzz > lead.max(trail) || lead + trail >= hunk.lines.len()) {
+            break;
+        }
+        let old: Vec<&str> = hunk.lines[lead..hunk.lines.len() - trail].iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(text) | HunkLine::Remove(text) => Some(text.as_str()),
+                HunkLine::Add(_) => None,
+            })
+            .collect();
+        let expected = (hunk.old_start as isize - 1 + lead as isize + offset).max(0) as usize;
+        for same in COMPARISONS {
+            if let Some(start) = find_block(lines, &old, expected, min_pos, same) {
+                return Some(Placement { start, lead, trail });
+            }
+        }
+    }
+    None
+}
+
+/// Apply the hunks of a file to its content, reports every hunk that does not apply
+pub fn apply_hunks(content: &str, hunks: &[Hunk]) -> Result<String, Vec<String>> {
+    let mut file = TextFile::parse(content);
+    let mut errors = Vec::new();
+    // shift between the line numbers of the patch and the file, as hunks are applied
+    let mut offset: isize = 0;
+    // hunks apply in order and never overlap
+    let mut min_pos = 0;
+
+    for (index, hunk) in hunks.iter().enumerate() {
+        let Some(Placement { start, lead, trail }) = locate(&file.lines, hunk, offset, min_pos) else {
+            errors.push(format!(
+                "hunk #{} `{}` does not match the file, its context and removed lines were not found near line {}",
+                index + 1,
+                hunk.header,
+                hunk.old_start
+            ));
+            continue;
+        };
+
+        let mut replacement = Vec::new();
+        let mut end = start;
+        for line in &hunk.lines[lead..hunk.lines.len() - trail] {
+            match line {
+                // keep the line of the file, it may differ in whitespace
+                HunkLine::Context(_) => {
+                    replacement.push(file.lines[end].clone());
+                    end += 1;
+                }
+                HunkLine::Remove(_) => end += 1,
+                HunkLine::Add(text) => replacement.push(text.clone()),
+            }
+        }
+
+        let removed = end - start;
+        let added = replacement.len();
+        file.lines.splice(start..end, replacement);
+        offset = start as isize - (hunk.old_start as isize - 1 + lead as isize) + added as isize - removed as isize;
+        min_pos = start + added;
+
+        if hunk.new_missing_newline {
+            file.trailing_newline = false;
+        } else if hunk.old_missing_newline {
+            file.trailing_newline = true;
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(file.render())
+    } else {
+        Err(errors)
+    }
+}
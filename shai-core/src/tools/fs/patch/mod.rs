@@ -0,0 +1,11 @@
+pub mod structs;
+pub mod parser;
+pub mod apply;
+pub mod patch;
+
+#[cfg(test)]
+mod tests;
+
+pub use structs::ApplyPatchToolParams;
+pub use parser::{parse_patch, FilePatch, FileChangeKind, Hunk, HunkLine};
+pub use patch::ApplyPatchTool;
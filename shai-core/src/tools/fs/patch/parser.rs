@@ -0,0 +1,200 @@
+/// A line of a hunk, without its prefix
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HunkLine {
+    Context(String),
+    Remove(String),
+    Add(String),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Hunk {
+    /// The `@@ ... @@` line, to name the hunk in errors
+    pub header: String,
+    /// First line of the hunk in the original file, 1-based, 0 for an empty file
+    pub old_start: usize,
+    pub lines: Vec<HunkLine>,
+    /// `\ No newline at end of file` follows the last line of the original side
+    pub old_missing_newline: bool,
+    /// `\ No newline at end of file` follows the last line of the new side
+    pub new_missing_newline: bool,
+}
+
+/// Changes to one file, a missing path stands for `/dev/null`
+#[derive(Debug, Clone, Default)]
+pub struct FilePatch {
+    pub old_path: Option<String>,
+    pub new_path: Option<String>,
+    pub hunks: Vec<Hunk>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileChangeKind {
+    Create,
+    Delete,
+    Rename,
+    Modify,
+}
+
+impl FilePatch {
+    pub fn kind(&self) -> FileChangeKind {
+        match (&self.old_path, &self.new_path) {
+            (None, _) => FileChangeKind::Create,
+            (_, None) => FileChangeKind::Delete,
+            (Some(old), Some(new)) if old != new => FileChangeKind::Rename,
+            _ => FileChangeKind::Modify,
+        }
+    }
+
+    /// The path the change is reported under
+    pub fn path(&self) -> &str {
+        self.new_path.as_deref().or(self.old_path.as_deref()).unwrap_or_default()
+    }
+}
+
+/// Path of a `---`/`+++` header, without timestamp nor `a/`/`b/` prefix
+fn header_path(raw: &str) -> Option<String> {
+    let path = raw.split('\t').next().unwrap_or_default().trim();
+    let path = path.trim_matches('"');
+    if path == "/dev/null" || path.is_empty() {
+        return None;
+    }
+    Some(strip_prefix(path).to_string())
+}
+
+fn strip_prefix(path: &str) -> &str {
+    path.strip_prefix("a/").or_else(|| path.strip_prefix("b/")).unwrap_or(path)
+}
+
+/// `@@ -12,5 +12,6 @@ fn context` gives 12
+fn hunk_old_start(header: &str) -> Result<usize, String> {
+    let old = header.trim_start_matches('@').trim_start()
+        .split_whitespace()
+        .next()
+        .and_then(|range| range.strip_prefix('-'))
+        .ok_or_else(|| format!("malformed hunk header: {}", header))?;
+    let start = old.split(',').next().unwrap_or_default();
+    start.parse().map_err(|_| format!("malformed hunk header: {}", header))
+}
+
+/// `-12,5 +12,6` gives (5, 6), a missing count is 1
+fn hunk_counts(header: &str) -> Option<(usize, usize)> {
+    let mut ranges = header.trim_start_matches('@').split_whitespace();
+    let count = |range: &str| -> Option<usize> {
+        match range[1..].split_once(',') {
+            Some((_, count)) => count.parse().ok(),
+            None => Some(1),
+        }
+    };
+    let old = ranges.next().filter(|range| range.starts_with('-'))?;
+    let new = ranges.next().filter(|range| range.starts_with('+'))?;
+    Some((count(old)?, count(new)?))
+}
+
+fn is_file_header(lines: &[&str], i: usize) -> bool {
+    lines[i].starts_with("diff --git ")
+        || (lines[i].starts_with("--- ") && lines.get(i + 1).is_some_and(|next| next.starts_with("+++ ")))
+}
+
+/// Parse the lines of a hunk starting at `lines[i]`, the line after its header. The counts of the
+/// header are trusted to skip blank separators, but lines that still look like hunk lines past
+/// them are kept too, models often get the counts wrong
+fn parse_hunk(lines: &[&str], mut i: usize, hunk: &mut Hunk) -> usize {
+    let (mut old_left, mut new_left) = hunk_counts(&hunk.header).unwrap_or((0, 0));
+    while i < lines.len() {
+        let line = lines[i];
+        let counted = old_left > 0 || new_left > 0;
+        if line.starts_with("@@") || is_file_header(lines, i) {
+            break;
+        }
+        if line.starts_with('\\') {
+            // `\ No newline at end of file` applies to the line before
+            match hunk.lines.last() {
+                Some(HunkLine::Remove(_)) => hunk.old_missing_newline = true,
+                Some(HunkLine::Add(_)) => hunk.new_missing_newline = true,
+                Some(HunkLine::Context(_)) => {
+                    hunk.old_missing_newline = true;
+                    hunk.new_missing_newline = true;
+                }
+                None => {}
+            }
+        } else if let Some(text) = line.strip_prefix('+') {
+            hunk.lines.push(HunkLine::Add(text.to_string()));
+            new_left = new_left.saturating_sub(1);
+        } else if let Some(text) = line.strip_prefix('-') {
+            hunk.lines.push(HunkLine::Remove(text.to_string()));
+            old_left = old_left.saturating_sub(1);
+        } else if let Some(text) = line.strip_prefix(' ') {
+            hunk.lines.push(HunkLine::Context(text.to_string()));
+            old_left = old_left.saturating_sub(1);
+            new_left = new_left.saturating_sub(1);
+        } else if line.is_empty() && counted {
+            // editors strip the trailing space of empty context lines
+            hunk.lines.push(HunkLine::Context(String::new()));
+            old_left = old_left.saturating_sub(1);
+            new_left = new_left.saturating_sub(1);
+        } else {
+            break;
+        }
+        i += 1;
+    }
+    i
+}
+
+/// Parse a unified diff, as produced by `diff -u` or `git diff`, into per-file changes
+pub fn parse_patch(patch: &str) -> Result<Vec<FilePatch>, String> {
+    let lines: Vec<&str> = patch.lines().collect();
+    let mut files: Vec<FilePatch> = Vec::new();
+    // a `diff --git` header whose `---`/`+++` lines were not seen yet
+    let mut git_header_open = false;
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        if let Some(rest) = line.strip_prefix("diff --git ") {
+            let (old, new) = rest.rsplit_once(" b/")
+                .map(|(old, new)| (strip_prefix(old).to_string(), new.to_string()))
+                .ok_or_else(|| format!("malformed diff header: {}", line))?;
+            files.push(FilePatch { old_path: Some(old), new_path: Some(new), hunks: Vec::new() });
+            git_header_open = true;
+            i += 1;
+        } else if git_header_open && line.starts_with("new file mode") {
+            files.last_mut().unwrap().old_path = None;
+            i += 1;
+        } else if git_header_open && line.starts_with("deleted file mode") {
+            files.last_mut().unwrap().new_path = None;
+            i += 1;
+        } else if let Some(path) = line.strip_prefix("rename from ").filter(|_| git_header_open) {
+            files.last_mut().unwrap().old_path = Some(path.trim().to_string());
+            i += 1;
+        } else if let Some(path) = line.strip_prefix("rename to ").filter(|_| git_header_open) {
+            files.last_mut().unwrap().new_path = Some(path.trim().to_string());
+            i += 1;
+        } else if is_file_header(&lines, i) {
+            let old_path = header_path(&line[4..]);
+            let new_path = header_path(&lines[i + 1][4..]);
+            if git_header_open {
+                let file = files.last_mut().unwrap();
+                file.old_path = old_path;
+                file.new_path = new_path;
+            } else {
+                files.push(FilePatch { old_path, new_path, hunks: Vec::new() });
+            }
+            git_header_open = false;
+            i += 2;
+        } else if line.starts_with("@@") {
+            let file = files.last_mut().ok_or_else(|| format!("hunk without a file header: {}", line))?;
+            let mut hunk = Hunk { header: line.to_string(), old_start: hunk_old_start(line)?, ..Default::default() };
+            i = parse_hunk(&lines, i + 1, &mut hunk);
+            file.hunks.push(hunk);
+            git_header_open = false;
+        } else {
+            // `index`, `similarity index`, mode lines and any text around the diff
+            i += 1;
+        }
+    }
+
+    if files.is_empty() {
+        return Err("no file changes found, the patch must be a unified diff with `---`/`+++` headers".to_string());
+    }
+    Ok(files)
+}
@@ -0,0 +1,251 @@
+use super::apply::apply_hunks;
+use super::parser::{parse_patch, FileChangeKind, FilePatch, HunkLine};
+use super::structs::ApplyPatchToolParams;
+use super::super::{text, EditTool, FsOperationLog, FsOperationType, FsTransaction};
+use crate::tools::{tool, ToolResult};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+
+/// A validated change, ready to be written
+struct PlannedChange {
+    kind: FileChangeKind,
+    old_path: Option<String>,
+    new_path: Option<String>,
+    before: String,
+    /// None once the file is deleted
+    after: Option<String>,
+}
+
+impl PlannedChange {
+    fn summary(&self) -> String {
+        let old_path = self.old_path.as_deref().unwrap_or_default();
+        let new_path = self.new_path.as_deref().unwrap_or_default();
+        match self.kind {
+            FileChangeKind::Create => format!("A {}", new_path),
+            FileChangeKind::Delete => format!("D {}", old_path),
+            FileChangeKind::Rename => format!("R {} -> {}", old_path, new_path),
+            FileChangeKind::Modify => format!("M {}", new_path),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ApplyPatchTool {
+    operation_log: Arc<FsOperationLog>,
+    edit_tool: EditTool,
+}
+
+impl ApplyPatchTool {
+    pub fn new(operation_log: Arc<FsOperationLog>) -> Self {
+        let edit_tool = EditTool::new(operation_log.clone());
+        Self { operation_log, edit_tool }
+    }
+
+    fn read_existing(path: &str) -> Result<String, String> {
+        if !Path::new(path).is_file() {
+            return Err(format!("{}: file does not exist", path));
+        }
+        text::read_text(path).map(|(content, _)| content)
+    }
+
+    /// A file is only deleted if the patch lists exactly its current lines
+    fn check_deleted_lines(file: &FilePatch, before: &str) -> Result<(), String> {
+        if file.hunks.is_empty() {
+            return match before.is_empty() {
+                true => Ok(()),
+                false => Err(format!("{}: a deletion must list the lines of the file as removed", file.path())),
+            };
+        }
+        let listed: Vec<&str> = file.hunks.iter()
+            .flat_map(|hunk| hunk.lines.iter())
+            .filter_map(|line| match line {
+                HunkLine::Remove(text) | HunkLine::Context(text) => Some(text.as_str()),
+                HunkLine::Add(_) => None,
+            })
+            .collect();
+        let current: Vec<&str> = before.lines().collect();
+        if listed != current {
+            return Err(format!("{}: the lines the patch deletes are not the current content of the file, read it again", file.path()));
+        }
+        Ok(())
+    }
+
+    /// Check and apply every file of the patch in memory, all problems are reported at once
+    async fn plan(&self, files: &[FilePatch]) -> Result<Vec<PlannedChange>, Vec<String>> {
+        let mut changes = Vec::new();
+        let mut errors = Vec::new();
+        let mut touched = HashSet::new();
+
+        for file in files {
+            let kind = file.kind();
+            let paths: HashSet<&String> = [&file.old_path, &file.new_path].into_iter().flatten().collect();
+            for path in paths {
+                if !touched.insert(path.clone()) {
+                    errors.push(format!("{}: changed twice by the patch, merge its hunks", path));
+                }
+            }
+
+            let before = match (&kind, &file.old_path) {
+                (FileChangeKind::Create, _) | (_, None) => String::new(),
+                (_, Some(old_path)) => match Self::read_existing(old_path) {
+                    Ok(content) => content,
+                    Err(error) => {
+                        errors.push(error);
+                        continue;
+                    }
+                },
+            };
+
+            if let (FileChangeKind::Create | FileChangeKind::Rename, Some(new_path)) = (&kind, &file.new_path) {
+                if Path::new(new_path).exists() {
+                    errors.push(format!("{}: already exists", new_path));
+                    continue;
+                }
+            }
+
+            if kind == FileChangeKind::Modify && file.hunks.is_empty() {
+                errors.push(format!("{}: no hunks to apply", file.path()));
+                continue;
+            }
+
+            // same rule as the edit tools, the model must know what it changes or deletes
+            if (!file.hunks.is_empty() && kind != FileChangeKind::Create) || kind == FileChangeKind::Delete {
+                let old_path = file.old_path.as_deref().unwrap_or_default();
+                if let Err(error) = self.operation_log.validate_edit_permission(old_path).await {
+                    errors.push(error);
+                    continue;
+                }
+            }
+            if kind == FileChangeKind::Delete {
+                if let Err(error) = Self::check_deleted_lines(file, &before) {
+                    errors.push(error);
+                    continue;
+                }
+            }
+
+            let after = if kind == FileChangeKind::Delete {
+                None
+            } else {
+                match apply_hunks(&before, &file.hunks) {
+                    Ok(after) => Some(after),
+                    Err(hunk_errors) => {
+                        errors.extend(hunk_errors.into_iter().map(|error| format!("{}: {}", file.path(), error)));
+                        continue;
+                    }
+                }
+            };
+
+            changes.push(PlannedChange {
+                kind,
+                old_path: file.old_path.clone(),
+                new_path: file.new_path.clone(),
+                before,
+                after,
+            });
+        }
+
+        if errors.is_empty() {
+            Ok(changes)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Write every change at once, nothing is written if one of them fails; deleted files go to the trash of the session
+    fn commit(&self, changes: &[PlannedChange]) -> Result<(), String> {
+        let mut transaction = FsTransaction::begin();
+        for change in changes {
+            if let (Some(new_path), Some(after)) = (&change.new_path, &change.after) {
+                transaction.write(new_path, after.clone())?;
+            }
+            if let (Some(old_path), FileChangeKind::Delete | FileChangeKind::Rename) = (&change.old_path, change.kind) {
+                transaction.delete(old_path)?;
+            }
+        }
+        transaction.commit_to_trash(self.operation_log.trash_dir())
+    }
+
+    fn diff(&self, changes: &[PlannedChange]) -> String {
+        changes.iter()
+            .map(|change| {
+                let after = change.after.as_deref().unwrap_or_default();
+                format!("\x1b[1m{}\x1b[0m\n{}", change.summary(), self.edit_tool.myers_diff(&change.before, after))
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+#[tool(name = "apply_patch", description = r#"Applies a unified diff, as produced by `diff -u` or `git diff`, to one or several files at once. Prefer it to `edit` and `multiedit` when changes span multiple files or when a diff is easier to write than exact replacements.
+
+**Format:**
+- Each file starts with `--- a/path` and `+++ b/path` headers, followed by `@@ -start,count +start,count @@` hunks whose lines begin with a space (context), `-` (removed) or `+` (added). Paths are relative to the working directory.
+- Create a file with `--- /dev/null`, delete one with `+++ /dev/null`. `git diff` headers (`diff --git`, `new file mode`, `deleted file mode`, `rename from`, `rename to`) are understood, a rename may come with hunks.
+- Give about 3 lines of context around each change, copied exactly from the file.
+
+**Behavior:**
+- Files modified or renamed with hunks, and files deleted, must have been read with the `read` tool first. A deletion lists every line of the file as removed, the deleted file is kept in the trash of the session.
+- Hunks are located near their line numbers; when the context does not match exactly, whitespace differences and up to 2 context lines at each end of a hunk are tolerated.
+- Every hunk of every file is checked before anything is written: if one does not apply, nothing is changed and the error lists all the hunks that failed."#, capabilities = [ToolCapability::Read, ToolCapability::Write])]
+impl ApplyPatchTool {
+    async fn execute_preview(&self, params: ApplyPatchToolParams) -> Option<ToolResult> {
+        Some(self.execute_internal(params, true).await)
+    }
+
+    async fn execute(&self, params: ApplyPatchToolParams) -> ToolResult {
+        self.execute_internal(params, false).await
+    }
+
+    async fn execute_internal(&self, params: ApplyPatchToolParams, preview: bool) -> ToolResult {
+        let files = match parse_patch(&params.patch) {
+            Ok(files) => files,
+            Err(error) => return ToolResult::error(format!("Invalid patch: {}", error)),
+        };
+
+        let changes = match self.plan(&files).await {
+            Ok(changes) => changes,
+            Err(errors) => {
+                return ToolResult::error(format!(
+                    "Patch failed, no file was changed:\n{}",
+                    errors.iter().map(|error| format!("- {}", error)).collect::<Vec<_>>().join("\n")
+                ));
+            }
+        };
+
+        if !preview {
+            if let Err(error) = self.commit(&changes) {
+                return ToolResult::error(format!("Patch failed, no file was changed: {}", error));
+            }
+            for change in &changes {
+                for path in [&change.old_path, &change.new_path].into_iter().flatten().collect::<HashSet<_>>() {
+                    self.operation_log.log_operation(FsOperationType::Patch, path.clone()).await;
+                }
+            }
+        }
+
+        let summary: Vec<String> = changes.iter().map(PlannedChange::summary).collect();
+        let files_meta: Vec<serde_json::Value> = changes.iter().map(|change| json!({
+            "change": format!("{:?}", change.kind).to_lowercase(),
+            "old_path": change.old_path,
+            "new_path": change.new_path,
+        })).collect();
+
+        let mut meta = HashMap::new();
+        meta.insert("files".to_string(), json!(files_meta));
+        meta.insert("files_changed".to_string(), json!(changes.len()));
+        meta.insert("preview_mode".to_string(), json!(preview));
+
+        let output = if preview {
+            self.diff(&changes)
+        } else {
+            format!("Patched {} file(s):\n{}\n\n{}", changes.len(), summary.join("\n"), self.diff(&changes))
+        };
+
+        ToolResult::Success {
+            output,
+            metadata: Some(meta),
+        }
+    }
+}
@@ -0,0 +1,8 @@
+use serde::Deserialize;
+use schemars::JsonSchema;
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ApplyPatchToolParams {
+    /// Unified diff to apply, with `---`/`+++` file headers and `@@` hunks, paths relative to the working directory
+    pub patch: String,
+}
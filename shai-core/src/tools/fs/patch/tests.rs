@@ -0,0 +1,286 @@
+use super::apply::apply_hunks;
+use super::parser::{parse_patch, FileChangeKind, HunkLine};
+use super::structs::ApplyPatchToolParams;
+use super::patch::ApplyPatchTool;
+use crate::tools::{Tool, ToolCapability, ToolResult, FsOperationLog, FsOperationType};
+use shai_llm::ToolDescription;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::tempdir;
+
+fn path_str(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+/// Log of a session that read `paths`, its trash is in `dir`
+async fn read_log(dir: &Path, paths: &[&Path]) -> Arc<FsOperationLog> {
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.join(".trash")));
+    for path in paths {
+        log.log_operation(FsOperationType::Read, path_str(path)).await;
+    }
+    log
+}
+
+#[test]
+fn test_apply_patch_tool_permissions() {
+    let dir = tempdir().unwrap();
+    let tool = ApplyPatchTool::new(Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash"))));
+    assert_eq!(tool.name(), "apply_patch");
+    let perms = tool.capabilities();
+    assert!(perms.contains(&ToolCapability::Read));
+    assert!(perms.contains(&ToolCapability::Write));
+}
+
+#[test]
+fn test_parse_git_patch() {
+    let patch = "diff --git a/src/lib.rs b/src/lib.rs
+index 83db48f..bf269f4 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@
+ fn main() {
+-    old();
++    new();
+ }
+diff --git a/notes.txt b/notes.txt
+new file mode 100644
+--- /dev/null
++++ b/notes.txt
+@@ -0,0 +1 @@
++hello
+diff --git a/old.rs b/new.rs
+similarity index 100%
+rename from old.rs
+rename to new.rs
+";
+    let files = parse_patch(patch).unwrap();
+    assert_eq!(files.len(), 3);
+
+    assert_eq!(files[0].kind(), FileChangeKind::Modify);
+    assert_eq!(files[0].path(), "src/lib.rs");
+    assert_eq!(files[0].hunks[0].old_start, 1);
+    assert_eq!(files[0].hunks[0].lines, vec![
+        HunkLine::Context("fn main() {".to_string()),
+        HunkLine::Remove("    old();".to_string()),
+        HunkLine::Add("    new();".to_string()),
+        HunkLine::Context("}".to_string()),
+    ]);
+
+    assert_eq!(files[1].kind(), FileChangeKind::Create);
+    assert_eq!(files[1].path(), "notes.txt");
+
+    assert_eq!(files[2].kind(), FileChangeKind::Rename);
+    assert_eq!(files[2].old_path.as_deref(), Some("old.rs"));
+    assert_eq!(files[2].new_path.as_deref(), Some("new.rs"));
+    assert!(files[2].hunks.is_empty());
+}
+
+#[test]
+fn test_parse_rejects_text_without_diff() {
+    assert!(parse_patch("just some text\n").is_err());
+}
+
+#[test]
+fn test_apply_hunks_with_offset_and_fuzz() {
+    let content = "header\nadded later\nfn a() {\n    one();\n    two();\n}\n";
+    // two lines were added above the hunk and its first context line was reformatted since
+    let patch = "--- a/f.rs
++++ b/f.rs
+@@ -1,4 +1,4 @@
+ fn  a()  {
+     one();
+-    two();
++    three();
+ }
+";
+    let files = parse_patch(patch).unwrap();
+    let result = apply_hunks(content, &files[0].hunks).unwrap();
+    assert_eq!(result, "header\nadded later\nfn a() {\n    one();\n    three();\n}\n");
+}
+
+#[test]
+fn test_apply_hunks_reports_every_failing_hunk() {
+    let content = "a\nb\nc\n";
+    let patch = "--- a/f
++++ b/f
+@@ -1,1 +1,1 @@
+-x
++y
+@@ -3,1 +3,1 @@
+-z
++w
+";
+    let files = parse_patch(patch).unwrap();
+    let errors = apply_hunks(content, &files[0].hunks).unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].contains("hunk #1"));
+    assert!(errors[1].contains("hunk #2"));
+}
+
+#[test]
+fn test_apply_hunks_keeps_crlf_and_missing_newline() {
+    let content = "one\r\ntwo";
+    let patch = "--- a/f
++++ b/f
+@@ -1,2 +1,2 @@
+ one
+-two
+\\ No newline at end of file
++deux
+\\ No newline at end of file
+";
+    let files = parse_patch(patch).unwrap();
+    assert_eq!(apply_hunks(content, &files[0].hunks).unwrap(), "one\r\ndeux");
+}
+
+#[tokio::test]
+async fn test_apply_patch_across_files() {
+    let dir = tempdir().unwrap();
+    let modified = dir.path().join("lib.rs");
+    let deleted = dir.path().join("old.txt");
+    let created = dir.path().join("sub").join("new.txt");
+    fs::write(&modified, "fn main() {\n    old();\n}\n").unwrap();
+    fs::write(&deleted, "bye\n").unwrap();
+
+    let patch = format!("--- {m}
++++ {m}
+@@ -1,3 +1,3 @@
+ fn main() {{
+-    old();
++    new();
+ }}
+--- {d}
++++ /dev/null
+@@ -1 +0,0 @@
+-bye
+--- /dev/null
++++ {c}
+@@ -0,0 +1,2 @@
++hello
++world
+", m = path_str(&modified), d = path_str(&deleted), c = path_str(&created));
+
+    let log = read_log(dir.path(), &[&modified, &deleted]).await;
+    let tool = ApplyPatchTool::new(log.clone());
+    let result = tool.execute(ApplyPatchToolParams { patch }).await;
+    assert!(result.is_success(), "{:?}", result);
+
+    assert_eq!(fs::read_to_string(&modified).unwrap(), "fn main() {\n    new();\n}\n");
+    assert!(!deleted.exists());
+    assert_eq!(fs::read_to_string(log.trash_dir().join("old.txt")).unwrap(), "bye\n");
+    assert_eq!(fs::read_to_string(&created).unwrap(), "hello\nworld\n");
+
+    let patched: Vec<String> = log.get_all_operations().await.into_iter()
+        .filter(|op| op.operation_type == FsOperationType::Patch)
+        .map(|op| op.file_path)
+        .collect();
+    assert_eq!(patched.len(), 3);
+    assert!(patched.contains(&path_str(&created)));
+}
+
+#[tokio::test]
+async fn test_apply_patch_rename_with_hunk() {
+    let dir = tempdir().unwrap();
+    let old = dir.path().join("a.txt");
+    let new = dir.path().join("b.txt");
+    fs::write(&old, "first\nsecond\n").unwrap();
+
+    let patch = format!("diff --git a/{o} b/{n}
+similarity index 50%
+rename from {o}
+rename to {n}
+--- a/{o}
++++ b/{n}
+@@ -1,2 +1,2 @@
+ first
+-second
++2nd
+", o = path_str(&old), n = path_str(&new));
+
+    let tool = ApplyPatchTool::new(read_log(dir.path(), &[&old]).await);
+    let result = tool.execute(ApplyPatchToolParams { patch }).await;
+    assert!(result.is_success(), "{:?}", result);
+    assert!(!old.exists());
+    assert_eq!(fs::read_to_string(&new).unwrap(), "first\n2nd\n");
+}
+
+#[tokio::test]
+async fn test_apply_patch_writes_nothing_when_a_hunk_fails() {
+    let dir = tempdir().unwrap();
+    let good = dir.path().join("good.txt");
+    let bad = dir.path().join("bad.txt");
+    fs::write(&good, "keep\nchange\n").unwrap();
+    fs::write(&bad, "something else\n").unwrap();
+
+    let patch = format!("--- {g}
++++ {g}
+@@ -1,2 +1,2 @@
+ keep
+-change
++changed
+--- {b}
++++ {b}
+@@ -1 +1 @@
+-not in the file
++whatever
+", g = path_str(&good), b = path_str(&bad));
+
+    let tool = ApplyPatchTool::new(read_log(dir.path(), &[&good, &bad]).await);
+    let result = tool.execute(ApplyPatchToolParams { patch }).await;
+    match result {
+        ToolResult::Error { error, .. } => {
+            assert!(error.contains("no file was changed"));
+            assert!(error.contains("bad.txt"));
+        }
+        _ => panic!("Expected the patch to fail"),
+    }
+    assert_eq!(fs::read_to_string(&good).unwrap(), "keep\nchange\n");
+}
+
+#[tokio::test]
+async fn test_apply_patch_requires_read_and_previews() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("file.txt");
+    fs::write(&file, "Hello\n").unwrap();
+    let patch = format!("--- {f}\n+++ {f}\n@@ -1 +1 @@\n-Hello\n+Hi\n", f = path_str(&file));
+
+    let tool = ApplyPatchTool::new(Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash"))));
+    let result = tool.execute(ApplyPatchToolParams { patch: patch.clone() }).await;
+    assert!(!result.is_success());
+
+    let tool = ApplyPatchTool::new(read_log(dir.path(), &[&file]).await);
+    let preview = tool.execute_preview(ApplyPatchToolParams { patch }).await.unwrap();
+    match preview {
+        ToolResult::Success { output, .. } => {
+            assert!(output.contains("Hello"));
+            assert!(output.contains("Hi"));
+        }
+        _ => panic!("Expected a preview"),
+    }
+    assert_eq!(fs::read_to_string(&file).unwrap(), "Hello\n");
+}
+
+#[tokio::test]
+async fn test_apply_patch_deletes_only_read_and_unchanged_files() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("old.txt");
+    fs::write(&file, "one\ntwo\n").unwrap();
+    let delete = |lines: &str| format!("--- {f}\n+++ /dev/null\n@@ -1,2 +0,0 @@\n{lines}", f = path_str(&file), lines = lines);
+
+    let tool = ApplyPatchTool::new(read_log(dir.path(), &[]).await);
+    let result = tool.execute(ApplyPatchToolParams { patch: delete("-one\n-two\n") }).await;
+    assert!(result.to_string().contains("must be read first"), "{}", result);
+    assert!(file.exists());
+
+    let tool = ApplyPatchTool::new(read_log(dir.path(), &[&file]).await);
+    let result = tool.execute(ApplyPatchToolParams { patch: delete("-one\n-deux\n") }).await;
+    assert!(result.to_string().contains("not the current content"), "{}", result);
+    let result = tool.execute(ApplyPatchToolParams { patch: delete("-one\n") }).await;
+    assert!(!result.is_success());
+    assert!(file.exists());
+
+    let result = tool.execute(ApplyPatchToolParams { patch: delete("-one\n-two\n") }).await;
+    assert!(result.is_success(), "{}", result);
+    assert!(!file.exists());
+}
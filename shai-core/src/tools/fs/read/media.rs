@@ -0,0 +1,125 @@
+use std::path::Path;
+use tokio::process::Command;
+
+/// Largest image sent to the model, providers reject bigger ones
+pub const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Extensions read as images rather than text
+pub const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp"];
+
+/// Media type of an image from its magic bytes
+pub fn image_media_type(bytes: &[u8]) -> Option<&'static str> {
+    match bytes {
+        [0x89, b'P', b'N', b'G', ..] => Some("image/png"),
+        [0xFF, 0xD8, 0xFF, ..] => Some("image/jpeg"),
+        [b'G', b'I', b'F', b'8', ..] => Some("image/gif"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        _ => None,
+    }
+}
+
+fn be16(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_be_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as u32)
+}
+
+fn le16(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as u32)
+}
+
+fn le24(bytes: &[u8], at: usize) -> Option<u32> {
+    let b = bytes.get(at..at + 3)?;
+    Some(b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16)
+}
+
+/// Width and height of an image, read from its header
+pub fn image_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    match image_media_type(bytes)? {
+        "image/png" => Some((
+            u32::from_be_bytes(bytes.get(16..20)?.try_into().ok()?),
+            u32::from_be_bytes(bytes.get(20..24)?.try_into().ok()?),
+        )),
+        "image/gif" => Some((le16(bytes, 6)?, le16(bytes, 8)?)),
+        "image/jpeg" => {
+            // walk the segments up to the start of frame
+            let mut i = 2;
+            while i + 9 < bytes.len() {
+                if bytes[i] != 0xFF {
+                    return None;
+                }
+                let marker = bytes[i + 1];
+                if (0xC0..=0xCF).contains(&marker) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+                    return Some((be16(bytes, i + 7)?, be16(bytes, i + 5)?));
+                }
+                i += 2 + be16(bytes, i + 2)? as usize;
+            }
+            None
+        }
+        "image/webp" => match bytes.get(12..16)? {
+            b"VP8X" => Some((le24(bytes, 24)? + 1, le24(bytes, 27)? + 1)),
+            b"VP8 " => Some((le16(bytes, 26)? & 0x3FFF, le16(bytes, 28)? & 0x3FFF)),
+            b"VP8L" => {
+                let bits = u32::from_le_bytes(bytes.get(21..25)?.try_into().ok()?);
+                Some(((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Pages selected by a spec such as "1-3,7", 1-based and within the document
+pub fn parse_pages(spec: &str, page_count: u32) -> Result<Vec<u32>, String> {
+    let mut pages = Vec::new();
+    for range in spec.split(',').map(str::trim).filter(|range| !range.is_empty()) {
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (start.trim(), end.trim()),
+            None => (range, range),
+        };
+        let start: u32 = start.parse().map_err(|_| format!("Invalid page range: {}", range))?;
+        let end: u32 = if end.is_empty() { page_count } else { end.parse().map_err(|_| format!("Invalid page range: {}", range))? };
+        if start == 0 || start > end || end > page_count {
+            return Err(format!("Page range {} is outside of the document, which has {} pages", range, page_count));
+        }
+        for page in start..=end {
+            if !pages.contains(&page) {
+                pages.push(page);
+            }
+        }
+    }
+    if pages.is_empty() {
+        return Err(format!("No page selected by '{}'", spec));
+    }
+    Ok(pages)
+}
+
+/// Text of the given pages of a pdf document
+pub fn pdf_pages_text(bytes: &[u8], pages: Option<&str>) -> Result<(u32, Vec<(u32, String)>), String> {
+    let document = lopdf::Document::load_mem(bytes).map_err(|e| format!("Failed to parse PDF: {}", e))?;
+    let page_count = document.get_pages().len() as u32;
+    let pages = match pages {
+        Some(spec) => parse_pages(spec, page_count)?,
+        None => (1..=page_count).collect(),
+    };
+    let texts = pages.into_iter()
+        .map(|page| (page, document.extract_text(&[page]).unwrap_or_default().trim().to_string()))
+        .collect();
+    Ok((page_count, texts))
+}
+
+/// Render a page of a pdf as png with poppler's pdftoppm, in a directory of its own that nobody else can write to
+pub async fn render_pdf_page(path: &Path, page: u32) -> Result<Vec<u8>, String> {
+    let dir = tempfile::tempdir().map_err(|e| format!("Failed to create a directory for the page: {}", e))?;
+    let root = dir.path().join("page");
+    let output = Command::new("pdftoppm")
+        .args(["-png", "-r", "100", "-singlefile", "-f", &page.to_string(), "-l", &page.to_string()])
+        .arg(path)
+        .arg(&root)
+        .output()
+        .await
+        .map_err(|e| format!("pdftoppm is required to render pages ({})", e))?;
+    if !output.status.success() {
+        return Err(format!("pdftoppm failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    tokio::fs::read(root.with_extension("png")).await.map_err(|e| format!("pdftoppm produced no image: {}", e))
+}
@@ -1,5 +1,6 @@
 pub mod structs;
 pub mod read;
+pub mod media;
 
 #[cfg(test)]
 mod tests;
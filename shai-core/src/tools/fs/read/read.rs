@@ -1,13 +1,17 @@
-use crate::tools::{ToolResult, tool};
+use crate::tools::{Attachment, ToolResult, tool};
+use super::media::{image_dimensions, image_media_type, pdf_pages_text, render_pdf_page, IMAGE_EXTENSIONS, MAX_IMAGE_BYTES};
 use super::structs::ReadToolParams;
+use super::super::text::{self, TextFormat};
 use super::super::{FsOperationLog, FsOperationType};
 use serde_json::json;
 use std::collections::HashMap;
 use std::fs;
-use std::io::{self, BufRead, BufReader};
 use std::path::Path;
 use std::sync::Arc;
 
+/// Pages of a pdf attached as images in a single read
+const MAX_PAGE_IMAGES: usize = 10;
+
 #[derive(Clone)]
 pub struct ReadTool {
     operation_log: Arc<FsOperationLog>,
@@ -18,92 +22,101 @@ impl ReadTool {
         Self { operation_log }
     }
 
-    fn read_file_content(&self, params: &ReadToolParams) -> io::Result<String> {
-        let file = fs::File::open(&params.path)?;
-        let reader = BufReader::new(file);
-        
-        match (params.line_start, params.line_end) {
-            // Read specific line range
-            (Some(start), Some(end)) => {
-                let lines: Result<Vec<(u32, String)>, io::Error> = reader
-                    .lines()
-                    .enumerate()
-                    .filter_map(|(i, line)| {
-                        let line_num = i as u32 + 1; // 1-based line numbers
-                        if line_num >= start && line_num <= end {
-                            Some(line.map(|l| (line_num, l)))
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-                
-                match lines {
-                    Ok(filtered_lines) => Ok(self.format_lines(filtered_lines, params.show_line_numbers)),
-                    Err(e) => Err(e)
-                }
-            },
-            // Read from start line to end of file
-            (Some(start), None) => {
-                let lines: Result<Vec<(u32, String)>, io::Error> = reader
-                    .lines()
-                    .enumerate()
-                    .filter_map(|(i, line)| {
-                        let line_num = i as u32 + 1; // 1-based line numbers
-                        if line_num >= start {
-                            Some(line.map(|l| (line_num, l)))
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-                
-                match lines {
-                    Ok(filtered_lines) => Ok(self.format_lines(filtered_lines, params.show_line_numbers)),
-                    Err(e) => Err(e)
-                }
-            },
-            // Read from beginning to end line
-            (None, Some(end)) => {
-                let lines: Result<Vec<(u32, String)>, io::Error> = reader
-                    .lines()
-                    .enumerate()
-                    .filter_map(|(i, line)| {
-                        let line_num = i as u32 + 1; // 1-based line numbers
-                        if line_num <= end {
-                            Some(line.map(|l| (line_num, l)))
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-                
-                match lines {
-                    Ok(filtered_lines) => Ok(self.format_lines(filtered_lines, params.show_line_numbers)),
-                    Err(e) => Err(e)
-                }
-            },
-            // Read entire file
-            (None, None) => {
-                if params.show_line_numbers {
-                    let lines: Result<Vec<(u32, String)>, io::Error> = reader
-                        .lines()
-                        .enumerate()
-                        .map(|(i, line)| {
-                            let line_num = i as u32 + 1;
-                            line.map(|l| (line_num, l))
-                        })
-                        .collect();
-                    
-                    match lines {
-                        Ok(numbered_lines) => Ok(self.format_lines(numbered_lines, true)),
-                        Err(e) => Err(e)
+    /// Decode the file whatever its encoding and keep the requested lines, binary files are refused
+    fn read_file_content(&self, params: &ReadToolParams) -> Result<(String, TextFormat), String> {
+        let bytes = fs::read(&params.path).map_err(|e| format!("Failed to read file: {}", e))?;
+        let (content, format) = text::decode(&bytes).map_err(|_| format!(
+            "{} is a binary file ({} bytes) and cannot be read as text", params.path, bytes.len()))?;
+
+        if params.line_start.is_none() && params.line_end.is_none() && !params.show_line_numbers {
+            return Ok((content, format));
+        }
+        let start = params.line_start.unwrap_or(1);
+        let end = params.line_end.unwrap_or(u32::MAX);
+        let lines = content
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i as u32 + 1, line.to_string())) // 1-based line numbers
+            .filter(|(line_num, _)| *line_num >= start && *line_num <= end)
+            .collect();
+        Ok((self.format_lines(lines, params.show_line_numbers), format))
+    }
+
+    async fn read_image(&self, params: &ReadToolParams) -> ToolResult {
+        let bytes = match fs::read(&params.path) {
+            Ok(bytes) => bytes,
+            Err(e) => return ToolResult::error(format!("Failed to read file: {}", e)),
+        };
+        let Some(media_type) = image_media_type(&bytes) else {
+            return ToolResult::error(format!("{} is not a valid PNG, JPEG, GIF or WebP image", params.path));
+        };
+        if bytes.len() > MAX_IMAGE_BYTES {
+            return ToolResult::error(format!("Image is too large to be attached ({} bytes, at most {})", bytes.len(), MAX_IMAGE_BYTES));
+        }
+        self.operation_log.log_operation(FsOperationType::Read, params.path.clone()).await;
+
+        let dimensions = image_dimensions(&bytes);
+        let description = format!("Image {} ({}, {} bytes{})",
+            params.path,
+            media_type,
+            bytes.len(),
+            dimensions.map(|(width, height)| format!(", {}x{}", width, height)).unwrap_or_default());
+
+        let mut meta = HashMap::new();
+        meta.insert("path".to_string(), json!(params.path));
+        meta.insert("media_type".to_string(), json!(media_type));
+        meta.insert("size".to_string(), json!(bytes.len()));
+        if let Some((width, height)) = dimensions {
+            meta.insert("width".to_string(), json!(width));
+            meta.insert("height".to_string(), json!(height));
+        }
+
+        ToolResult::Success {
+            output: format!("{}, attached below", description),
+            metadata: Some(meta),
+        }.with_attachment(Attachment::image(media_type, &bytes, description))
+    }
+
+    async fn read_pdf(&self, params: &ReadToolParams) -> ToolResult {
+        let bytes = match fs::read(&params.path) {
+            Ok(bytes) => bytes,
+            Err(e) => return ToolResult::error(format!("Failed to read file: {}", e)),
+        };
+        let (page_count, pages) = match pdf_pages_text(&bytes, params.pages.as_deref()) {
+            Ok(pages) => pages,
+            Err(e) => return ToolResult::error(e),
+        };
+        self.operation_log.log_operation(FsOperationType::Read, params.path.clone()).await;
+
+        let mut output = format!("PDF {} ({} pages)", params.path, page_count);
+        for (page, text) in &pages {
+            output.push_str(&format!("\n\n--- page {} ---\n{}", page, if text.is_empty() { "[no text on this page]" } else { text }));
+        }
+
+        let mut meta = HashMap::new();
+        meta.insert("path".to_string(), json!(params.path));
+        meta.insert("page_count".to_string(), json!(page_count));
+        meta.insert("pages".to_string(), json!(pages.iter().map(|(page, _)| page).collect::<Vec<_>>()));
+
+        let mut attachments = Vec::new();
+        if params.page_images {
+            for (page, _) in pages.iter().take(MAX_PAGE_IMAGES) {
+                match render_pdf_page(Path::new(&params.path), *page).await {
+                    Ok(png) => attachments.push(Attachment::image("image/png", &png, format!("Page {} of {}", page, params.path))),
+                    Err(e) => {
+                        output.push_str(&format!("\n\n[page images unavailable: {}]", e));
+                        break;
                     }
-                } else {
-                    fs::read_to_string(&params.path)
                 }
             }
+            if pages.len() > MAX_PAGE_IMAGES {
+                output.push_str(&format!("\n\n[only the first {} pages are attached as images, select fewer pages to see the others]", MAX_PAGE_IMAGES));
+            }
         }
+
+        attachments.into_iter().fold(
+            ToolResult::Success { output, metadata: Some(meta) },
+            |result, attachment| result.with_attachment(attachment))
     }
 
     fn format_lines(&self, lines: Vec<(u32, String)>, show_line_numbers: bool) -> String {
@@ -129,6 +142,9 @@ impl ReadTool {
 - An absolute `path` to the file is required.
 - For large files, you can read a specific portion by specifying `line_start` and `line_end`. If omitted, the entire file is read (within system limits).
 - The output is formatted with line numbers for easy reference, which is crucial context for subsequent `edit` operations.
+- Images (PNG, JPEG, GIF, WebP) are returned as images you can look at.
+- Text files are decoded whatever their encoding (UTF-8, UTF-16, Latin-1), a note after the content tells when it is not plain UTF-8 or uses CRLF line endings. Other binary files cannot be read.
+- PDFs are returned as text, page by page. Select pages with `pages` (e.g. '1-3,7') and set `page_images` to also see them as images, for figures or layouts.
 
 **Best Practices:**
 - When investigating a task, it is often effective to read multiple potentially relevant files in a single turn to build a complete understanding of the context."#, capabilities = [Read])]
@@ -146,15 +162,26 @@ impl ReadTool {
             return ToolResult::error(format!("Path is not a file: {}", params.path));
         }
 
+        let extension = path.extension().map(|ext| ext.to_string_lossy().to_lowercase()).unwrap_or_default();
+        if extension == "pdf" {
+            return self.read_pdf(&params).await;
+        }
+        if IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+            return self.read_image(&params).await;
+        }
+
         // Read the file
         match self.read_file_content(&params) {
-            Ok(content) => {
+            Ok((mut content, format)) => {
                 // Log the read operation
                 self.operation_log.log_operation(FsOperationType::Read, params.path.clone()).await;
 
                 let mut meta = HashMap::new();
                 meta.insert("path".to_string(), json!(params.path));
                 meta.insert("total_lines".to_string(), json!(content.lines().count()));
+                meta.insert("encoding".to_string(), json!(format.encoding.name()));
+                meta.insert("bom".to_string(), json!(format.bom));
+                meta.insert("line_ending".to_string(), json!(format.line_ending.name()));
                 
                 if let Some(start) = params.line_start {
                     meta.insert("line_start".to_string(), json!(start));
@@ -163,12 +190,17 @@ impl ReadTool {
                     meta.insert("line_end".to_string(), json!(end));
                 }
 
+                // edits and writes keep the encoding, the model only needs to know about it
+                if let Some(description) = format.describe() {
+                    content.push_str(&format!("\n\n[file encoding: {}]", description));
+                }
+
                 ToolResult::Success {
                     output: content,
                     metadata: Some(meta),
                 }
             },
-            Err(e) => ToolResult::error(format!("Failed to read file: {}", e))
+            Err(e) => ToolResult::error(e)
         }
     }
 }
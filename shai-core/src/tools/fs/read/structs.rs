@@ -14,4 +14,10 @@ pub struct ReadToolParams {
     /// Whether to include line numbers in the output
     #[serde(default)]
     pub show_line_numbers: bool,
+    /// Pages of a PDF to read, e.g. "1-3,7" (optional, defaults to every page)
+    #[serde(default)]
+    pub pages: Option<String>,
+    /// Also attach the selected PDF pages as images, for figures and layouts (optional)
+    #[serde(default)]
+    pub page_images: bool,
 }
\ No newline at end of file
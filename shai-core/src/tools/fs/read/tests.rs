@@ -8,7 +8,8 @@ use std::sync::Arc;
 
 #[test]
 fn test_read_tool_creation() {
-    let log = Arc::new(FsOperationLog::new());
+    let dir = TempDir::new().unwrap();
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")));
     let tool = ReadTool::new(log);
     
     // Verify tool properties
@@ -37,7 +38,7 @@ End of file"#;
     let test_file_path = temp_path.join("test.txt");
     fs::write(&test_file_path, test_content).expect("Failed to write test file");
 
-    let log = Arc::new(FsOperationLog::new());
+    let log = Arc::new(FsOperationLog::with_trash_dir(temp_dir.path().join(".trash")));
     let read_tool = ReadTool::new(log);
 
     // Test 1: Read entire file
@@ -46,6 +47,8 @@ End of file"#;
         line_start: None,
         line_end: None,
         show_line_numbers: false,
+        pages: None,
+        page_images: false,
     };
 
     let result = read_tool.execute(params).await;
@@ -66,6 +69,8 @@ End of file"#;
         line_start: None,
         line_end: None,
         show_line_numbers: true,
+        pages: None,
+        page_images: false,
     };
 
     let result_with_lines = read_tool.execute(params_with_lines).await;
@@ -97,7 +102,7 @@ async fn test_read_tool_line_range_reading() {
     let test_file_path = temp_path.join("numbered_test.txt");
     fs::write(&test_file_path, test_content).expect("Failed to write test file");
 
-    let log = Arc::new(FsOperationLog::new());
+    let log = Arc::new(FsOperationLog::with_trash_dir(temp_dir.path().join(".trash")));
     let read_tool = ReadTool::new(log);
 
     // Test 1: Read specific line range (lines 5-10)
@@ -106,6 +111,8 @@ async fn test_read_tool_line_range_reading() {
         line_start: Some(5),
         line_end: Some(10),
         show_line_numbers: true,
+        pages: None,
+        page_images: false,
     };
 
     let result_range = read_tool.execute(params_range).await;
@@ -131,6 +138,8 @@ async fn test_read_tool_line_range_reading() {
         line_start: Some(15),
         line_end: None,
         show_line_numbers: true,
+        pages: None,
+        page_images: false,
     };
 
     let result_from_line = read_tool.execute(params_from_line).await;
@@ -155,6 +164,8 @@ async fn test_read_tool_line_range_reading() {
         line_start: None,
         line_end: None,
         show_line_numbers: false,
+        pages: None,
+        page_images: false,
     };
 
     let result_nonexistent = read_tool.execute(params_nonexistent).await;
@@ -167,4 +178,189 @@ async fn test_read_tool_line_range_reading() {
                    "Should indicate file not found error, got: {}", error);
         }
     }
-}
\ No newline at end of file
+}
+fn read_params(path: &std::path::Path) -> ReadToolParams {
+    ReadToolParams {
+        path: path.to_string_lossy().to_string(),
+        line_start: None,
+        line_end: None,
+        show_line_numbers: false,
+        pages: None,
+        page_images: false,
+    }
+}
+
+/// 1x1 transparent png
+const PNG: &[u8] = &[
+    0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00, 0x00, 0x1F, 0x15, 0xC4,
+    0x89, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9C, 0x63, 0x00, 0x01, 0x00, 0x00,
+    0x05, 0x00, 0x01, 0x0D, 0x0A, 0x2D, 0xB4, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4E, 0x44, 0xAE,
+    0x42, 0x60, 0x82,
+];
+
+#[tokio::test]
+async fn test_read_tool_image_attachment() {
+    let temp_dir = TempDir::new().unwrap();
+    let image_path = temp_dir.path().join("pixel.png");
+    fs::write(&image_path, PNG).unwrap();
+
+    let read_tool = ReadTool::new(Arc::new(FsOperationLog::with_trash_dir(temp_dir.path().join(".trash"))));
+    let result = read_tool.execute(read_params(&image_path)).await;
+    assert!(result.to_string().contains("image/png, 67 bytes, 1x1"), "{}", result);
+
+    let (result, attachments) = result.take_attachments();
+    assert_eq!(attachments.len(), 1);
+    assert_eq!(attachments[0].media_type, "image/png");
+    assert!(attachments[0].data_url().starts_with("data:image/png;base64,iVBORw0KGgo"));
+    let crate::tools::ToolResult::Success { metadata, .. } = result else {
+        panic!("Expected success result");
+    };
+    // the image data leaves the metadata, its description stays
+    let metadata = metadata.unwrap();
+    assert_eq!(metadata["attachments"], serde_json::json!([attachments[0].description]));
+    assert_eq!(metadata["width"], serde_json::json!(1));
+
+    let message = crate::tools::attachments_message("read", &attachments).unwrap();
+    let shai_llm::ChatMessage::User { content: shai_llm::ChatMessageContent::ContentPart(parts), .. } = message else {
+        panic!("Expected a multipart user message");
+    };
+    assert_eq!(parts.len(), 3);
+    assert!(matches!(&parts[2], shai_llm::ChatMessageContentPart::Image(_)));
+    assert!(crate::tools::attachments_message("read", &[]).is_none());
+}
+
+#[tokio::test]
+async fn test_read_tool_invalid_image() {
+    let temp_dir = TempDir::new().unwrap();
+    let image_path = temp_dir.path().join("fake.jpg");
+    fs::write(&image_path, "not an image").unwrap();
+
+    let read_tool = ReadTool::new(Arc::new(FsOperationLog::with_trash_dir(temp_dir.path().join(".trash"))));
+    let result = read_tool.execute(read_params(&image_path)).await;
+    assert!(result.is_error());
+    assert!(result.take_attachments().1.is_empty());
+}
+
+fn write_pdf(path: &std::path::Path, pages: &[&str]) {
+    use lopdf::content::{Content, Operation};
+    use lopdf::{dictionary, Document, Object, Stream};
+
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Courier",
+    });
+    let resources_id = doc.add_object(dictionary! {
+        "Font" => dictionary! { "F1" => font_id },
+    });
+
+    let mut kids: Vec<Object> = Vec::new();
+    for text in pages {
+        let content = Content {
+            operations: vec![
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec!["F1".into(), 24.into()]),
+                Operation::new("Td", vec![100.into(), 600.into()]),
+                Operation::new("Tj", vec![Object::string_literal(*text)]),
+                Operation::new("ET", vec![]),
+            ],
+        };
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+        });
+        kids.push(page_id.into());
+    }
+    doc.objects.insert(pages_id, Object::Dictionary(dictionary! {
+        "Type" => "Pages",
+        "Count" => pages.len() as i64,
+        "Kids" => kids,
+        "Resources" => resources_id,
+        "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+    }));
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.save(path).unwrap();
+}
+
+#[tokio::test]
+async fn test_read_tool_pdf_pages() {
+    let temp_dir = TempDir::new().unwrap();
+    let pdf_path = temp_dir.path().join("doc.pdf");
+    write_pdf(&pdf_path, &["First page", "Second page", "Third page"]);
+
+    let read_tool = ReadTool::new(Arc::new(FsOperationLog::with_trash_dir(temp_dir.path().join(".trash"))));
+    let output = read_tool.execute(read_params(&pdf_path)).await.to_string();
+    assert!(output.starts_with(&format!("PDF {} (3 pages)", pdf_path.display())), "{}", output);
+    assert!(output.contains("--- page 1 ---\nFirst page"), "{}", output);
+    assert!(output.contains("--- page 3 ---\nThird page"), "{}", output);
+
+    let mut params = read_params(&pdf_path);
+    params.pages = Some("2-".to_string());
+    let output = read_tool.execute(params).await.to_string();
+    assert!(!output.contains("First page"));
+    assert!(output.contains("Second page") && output.contains("Third page"));
+
+    let mut params = read_params(&pdf_path);
+    params.pages = Some("4".to_string());
+    assert!(read_tool.execute(params).await.is_error());
+}
+
+#[test]
+fn test_page_ranges_and_image_headers() {
+    use super::media::{image_dimensions, parse_pages};
+
+    assert_eq!(parse_pages("1-3, 2, 7", 10).unwrap(), vec![1, 2, 3, 7]);
+    assert_eq!(parse_pages("9-", 10).unwrap(), vec![9, 10]);
+    assert!(parse_pages("0", 10).is_err());
+    assert!(parse_pages("3-1", 10).is_err());
+    assert!(parse_pages("a", 10).is_err());
+
+    assert_eq!(image_dimensions(PNG), Some((1, 1)));
+    assert_eq!(image_dimensions(b"GIF89a\x20\x00\x10\x00"), Some((32, 16)));
+    let jpeg = [0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00, 0xFF, 0xC0, 0x00, 0x11, 0x08, 0x00, 0x30, 0x00, 0x40, 0x03];
+    assert_eq!(image_dimensions(&jpeg), Some((64, 48)));
+}
+
+#[tokio::test]
+async fn test_read_tool_reports_encoding() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("utf16.txt");
+    let mut bytes = vec![0xFF, 0xFE];
+    bytes.extend("héllo\r\nworld\r\n".encode_utf16().flat_map(u16::to_le_bytes));
+    fs::write(&file_path, bytes).unwrap();
+
+    let read_tool = ReadTool::new(Arc::new(FsOperationLog::with_trash_dir(temp_dir.path().join(".trash"))));
+    match read_tool.execute(read_params(&file_path)).await {
+        crate::tools::ToolResult::Success { output, metadata } => {
+            assert!(output.starts_with("héllo\r\nworld"), "{}", output);
+            assert!(output.contains("[file encoding: UTF-16LE with BOM, CRLF line endings]"), "{}", output);
+            let metadata = metadata.unwrap();
+            assert_eq!(metadata["encoding"], serde_json::json!("UTF-16LE"));
+            assert_eq!(metadata["line_ending"], serde_json::json!("CRLF"));
+        },
+        crate::tools::ToolResult::Error { error, .. } => panic!("Expected success, got: {}", error),
+    }
+}
+
+#[tokio::test]
+async fn test_read_tool_refuses_binary_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("program.bin");
+    fs::write(&file_path, b"\x7FELF\x02\x01\x01\0\0\0\0\0").unwrap();
+
+    let log = Arc::new(FsOperationLog::with_trash_dir(temp_dir.path().join(".trash")));
+    let read_tool = ReadTool::new(log.clone());
+    let result = read_tool.execute(read_params(&file_path)).await;
+    assert!(result.is_error());
+    assert!(result.to_string().contains("binary file"), "{}", result);
+    assert!(!log.has_been_read(&file_path.to_string_lossy()).await);
+}
@@ -23,7 +23,7 @@ mod integration_tests {
         let temp_path = temp_dir.path();
         
         // Create shared operation log
-        let fs_log = Arc::new(FsOperationLog::new());
+        let fs_log = Arc::new(FsOperationLog::with_trash_dir(temp_dir.path().join(".trash")));
         
         // Initialize tools
         let ls_tool = LsTool::new();
@@ -71,6 +71,8 @@ mod integration_tests {
             line_start: None,
             line_end: None,
             show_line_numbers: false,
+            pages: None,
+            page_images: false,
         }).await;
         assert!(read_result.is_success());
         if let crate::tools::types::ToolResult::Success { output, .. } = read_result {
@@ -111,6 +113,8 @@ mod integration_tests {
             line_start: None,
             line_end: None,
             show_line_numbers: false,
+            pages: None,
+            page_images: false,
         }).await;
         assert!(final_read.is_success());
         if let crate::tools::types::ToolResult::Success { output, .. } = final_read {
@@ -127,7 +131,7 @@ mod integration_tests {
         let temp_path = temp_dir.path();
         
         // Create shared operation log
-        let fs_log = Arc::new(FsOperationLog::new());
+        let fs_log = Arc::new(FsOperationLog::with_trash_dir(temp_dir.path().join(".trash")));
         
         // Initialize tools
         let write_tool = WriteTool::new(fs_log.clone());
@@ -184,6 +188,8 @@ mod integration_tests {
             line_start: None,
             line_end: None,
             show_line_numbers: false,
+            pages: None,
+            page_images: false,
         }).await;
         
         let edit_result = edit_tool.execute(EditToolParams {
@@ -200,6 +206,8 @@ mod integration_tests {
             line_start: None,
             line_end: None,
             show_line_numbers: false,
+            pages: None,
+            page_images: false,
         }).await;
         
         let multiedit_result = multiedit_tool.execute(MultiEditToolParams {
@@ -223,7 +231,7 @@ mod integration_tests {
         let temp_path = temp_dir.path();
         
         // Create shared operation log
-        let fs_log = Arc::new(FsOperationLog::new());
+        let fs_log = Arc::new(FsOperationLog::with_trash_dir(temp_dir.path().join(".trash")));
         
         // Initialize tools
         let find_tool = FindTool::new();
@@ -254,7 +262,9 @@ mod integration_tests {
             pattern: "name".to_string(), // Search for "name" in file contents
             path: Some(temp_path.to_string_lossy().to_string()),
             include_extensions: Some("json".to_string()),
+            include_patterns: None,
             exclude_patterns: None,
+            no_ignore: false,
             max_results: 100,
             case_sensitive: false,
             find_type: crate::tools::fs::find::structs::FindType::Content,
@@ -271,6 +281,8 @@ mod integration_tests {
             line_start: None,
             line_end: None,
             show_line_numbers: false,
+            pages: None,
+            page_images: false,
         }).await;
         assert!(read_result.is_success());
         
@@ -290,6 +302,8 @@ mod integration_tests {
             line_start: None,
             line_end: None,
             show_line_numbers: false,
+            pages: None,
+            page_images: false,
         }).await;
         assert!(read_result.is_success());
         
@@ -307,6 +321,8 @@ mod integration_tests {
             line_start: None,
             line_end: None,
             show_line_numbers: false,
+            pages: None,
+            page_images: false,
         }).await;
         assert!(final_config_read.is_success());
         if let crate::tools::types::ToolResult::Success { output, .. } = final_config_read {
@@ -318,6 +334,8 @@ mod integration_tests {
             line_start: None,
             line_end: None,
             show_line_numbers: false,
+            pages: None,
+            page_images: false,
         }).await;
         assert!(final_script_read.is_success());
         if let crate::tools::types::ToolResult::Success { output, .. } = final_script_read {
@@ -0,0 +1,412 @@
+use super::find::find::is_binary;
+use crate::redact::MASK_PREFIX;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Bytes looked at to guess an encoding without BOM
+const SNIFF_BYTES: usize = 8192;
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+/// Numbers the temporary files of this process, so that concurrent writes of a file never share one
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// Any byte sequence that is not UTF-8, each byte being one character
+    Latin1,
+}
+
+impl Encoding {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Latin1 => "Latin-1",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "LF",
+            LineEnding::CrLf => "CRLF",
+        }
+    }
+}
+
+/// How a text file is stored, so that it is written back the same way
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextFormat {
+    pub encoding: Encoding,
+    pub bom: bool,
+    /// The line ending used by most lines
+    pub line_ending: LineEnding,
+    pub trailing_newline: bool,
+}
+
+impl TextFormat {
+    /// Encoding, BOM and line ending of the format, unless plain UTF-8 with LF
+    pub fn describe(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.encoding != Encoding::Utf8 || self.bom {
+            parts.push(format!("{}{}", self.encoding.name(), if self.bom { " with BOM" } else { "" }));
+        }
+        if self.line_ending == LineEnding::CrLf {
+            parts.push("CRLF line endings".to_string());
+        }
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+
+    /// Give text the trailing newline of the file it replaces
+    pub fn keep_trailing_newline(&self, text: &str) -> String {
+        match (self.trailing_newline, text.ends_with('\n')) {
+            (true, false) if !text.is_empty() => format!("{}\n", text),
+            (false, true) => text.trim_end_matches(['\r', '\n']).to_string(),
+            _ => text.to_string(),
+        }
+    }
+
+    /// Bytes of text in this format, every line ending made the dominant one
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>, String> {
+        let lf = text.replace("\r\n", "\n");
+        let text = match self.line_ending {
+            LineEnding::Lf => lf,
+            LineEnding::CrLf => lf.replace('\n', "\r\n"),
+        };
+
+        let mut bytes = Vec::with_capacity(text.len() + 3);
+        match self.encoding {
+            Encoding::Utf8 => {
+                if self.bom {
+                    bytes.extend_from_slice(UTF8_BOM);
+                }
+                bytes.extend_from_slice(text.as_bytes());
+            }
+            Encoding::Utf16Le => {
+                if self.bom {
+                    bytes.extend_from_slice(UTF16LE_BOM);
+                }
+                bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+            }
+            Encoding::Utf16Be => {
+                if self.bom {
+                    bytes.extend_from_slice(UTF16BE_BOM);
+                }
+                bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+            }
+            Encoding::Latin1 => {
+                for c in text.chars() {
+                    let byte = u8::try_from(u32::from(c))
+                        .map_err(|_| format!("'{}' cannot be written in a Latin-1 file", c))?;
+                    bytes.push(byte);
+                }
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+fn line_ending_of(text: &str) -> LineEnding {
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count() - crlf;
+    if crlf > lf { LineEnding::CrLf } else { LineEnding::Lf }
+}
+
+fn decode_utf16(bytes: &[u8], encoding: Encoding) -> Option<String> {
+    let pairs = bytes.chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return None;
+    }
+    let units = pairs.map(|pair| match encoding {
+        Encoding::Utf16Be => u16::from_be_bytes([pair[0], pair[1]]),
+        _ => u16::from_le_bytes([pair[0], pair[1]]),
+    });
+    char::decode_utf16(units).collect::<Result<String, _>>().ok()
+}
+
+/// UTF-16 without BOM, recognized by the zero bytes of its ASCII characters
+fn sniff_utf16(bytes: &[u8]) -> Option<Encoding> {
+    let sample = &bytes[..bytes.len().min(SNIFF_BYTES) & !1];
+    if sample.is_empty() {
+        return None;
+    }
+    let pairs = sample.len() / 2;
+    let zero_at = |offset: usize| sample.iter().skip(offset).step_by(2).filter(|byte| **byte == 0).count();
+    let (even, odd) = (zero_at(0), zero_at(1));
+    if even == 0 && odd * 10 >= pairs * 4 {
+        Some(Encoding::Utf16Le)
+    } else if odd == 0 && even * 10 >= pairs * 4 {
+        Some(Encoding::Utf16Be)
+    } else {
+        None
+    }
+}
+
+/// Control characters are rare in text, common in binary data
+fn looks_binary(bytes: &[u8]) -> bool {
+    if is_binary(bytes) {
+        return true;
+    }
+    let sample = &bytes[..bytes.len().min(SNIFF_BYTES)];
+    let controls = sample.iter()
+        .filter(|byte| matches!(byte, 0x01..=0x08 | 0x0E..=0x1A | 0x1C..=0x1F | 0x7F))
+        .count();
+    controls * 50 > sample.len()
+}
+
+/// Text of a file and its format, binary data is refused
+pub fn decode(bytes: &[u8]) -> Result<(String, TextFormat), String> {
+    let (text, encoding, bom) = if let Some(rest) = bytes.strip_prefix(UTF8_BOM) {
+        let text = String::from_utf8(rest.to_vec()).map_err(|_| "invalid UTF-8 after a UTF-8 BOM".to_string())?;
+        (text, Encoding::Utf8, true)
+    } else if let Some(rest) = bytes.strip_prefix(UTF16LE_BOM) {
+        (decode_utf16(rest, Encoding::Utf16Le).ok_or("invalid UTF-16LE")?, Encoding::Utf16Le, true)
+    } else if let Some(rest) = bytes.strip_prefix(UTF16BE_BOM) {
+        (decode_utf16(rest, Encoding::Utf16Be).ok_or("invalid UTF-16BE")?, Encoding::Utf16Be, true)
+    } else if let Some((text, encoding)) = sniff_utf16(bytes)
+        .and_then(|encoding| decode_utf16(bytes, encoding).map(|text| (text, encoding)))
+    {
+        (text, encoding, false)
+    } else if looks_binary(bytes) {
+        return Err("binary file".to_string());
+    } else {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => (text.to_string(), Encoding::Utf8, false),
+            Err(_) => (bytes.iter().map(|byte| char::from(*byte)).collect(), Encoding::Latin1, false),
+        }
+    };
+
+    let format = TextFormat {
+        encoding,
+        bom,
+        line_ending: line_ending_of(&text),
+        trailing_newline: text.ends_with('\n'),
+    };
+    Ok((text, format))
+}
+
+/// Read a text file whatever its encoding
+pub fn read_text(path: &str) -> Result<(String, TextFormat), String> {
+    let bytes = fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+    decode(&bytes).map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Bytes of text to write at path: in the format of the file there, or as UTF-8 for a new file
+pub fn encode_like(path: &str, text: &str) -> Result<Vec<u8>, String> {
+    match fs::read(path).ok().and_then(|bytes| decode(&bytes).ok()) {
+        Some((_, format)) => format.encode(text).map_err(|e| format!("{}: {}", path, e)),
+        None => Ok(text.as_bytes().to_vec()),
+    }
+}
+
+/// Refuse new content that holds more redaction masks than the file has now: the model copied a secret it was
+/// only shown masked, writing it would replace the real value with the mask
+pub fn check_no_new_masks(path: &str, text: &str) -> Result<(), String> {
+    let added = text.matches(MASK_PREFIX).count();
+    if added == 0 {
+        return Ok(());
+    }
+    let existing = read_text(path).map_or(0, |(current, _)| current.matches(MASK_PREFIX).count());
+    if added > existing {
+        return Err(format!(
+            "{}: the new content contains {}...] markers. They stand for secrets that were masked in what you read, not for their values: \
+            leave the text around them untouched instead of copying the marker, or ask the user to make that change",
+            path, MASK_PREFIX
+        ));
+    }
+    Ok(())
+}
+
+/// File a write to `path` replaces: the file a symlink points to, so that the link is kept
+fn resolve_target(path: &str) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path))
+}
+
+#[cfg(unix)]
+fn is_hard_linked(path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).map_or(false, |metadata| metadata.nlink() > 1)
+}
+
+#[cfg(not(unix))]
+fn is_hard_linked(_path: &Path) -> bool {
+    false
+}
+
+/// Write bytes to a temporary file next to the file `path` designates, so that renaming it over that file is atomic
+pub fn write_temp(path: &str, bytes: &[u8]) -> Result<PathBuf, String> {
+    let target = resolve_target(path);
+    let dir = target.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let name = target.file_name().ok_or_else(|| format!("{}: not a file path", path))?;
+    fs::create_dir_all(dir).map_err(|e| format!("{}: {}", path, e))?;
+
+    let (temp, mut file) = loop {
+        let temp = dir.join(format!(".{}.{}.{}.tmp", name.to_string_lossy(), std::process::id(), TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)));
+        match fs::OpenOptions::new().write(true).create_new(true).open(&temp) {
+            Ok(file) => break (temp, file),
+            // left behind by an earlier process with the same id
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("{}: {}", path, e)),
+        }
+    };
+    if let Err(e) = file.write_all(bytes) {
+        let _ = fs::remove_file(&temp);
+        return Err(format!("{}: {}", path, e));
+    }
+    // keep the permissions of the file replaced, a script stays executable
+    if let Ok(metadata) = fs::metadata(&target) {
+        let _ = fs::set_permissions(&temp, metadata.permissions());
+    }
+    Ok(temp)
+}
+
+/// Put a temporary file made by `write_temp` in place of the file `path` designates.
+/// A file with several hard links is overwritten in place instead, a rename would detach it from the other links
+pub fn replace_with_temp(temp: &Path, path: &str) -> Result<(), String> {
+    let target = resolve_target(path);
+    let replaced = if is_hard_linked(&target) {
+        fs::copy(temp, &target).and_then(|_| fs::remove_file(temp))
+    } else {
+        fs::rename(temp, &target)
+    };
+    replaced.map_err(|e| {
+        let _ = fs::remove_file(temp);
+        format!("{}: {}", path, e)
+    })
+}
+
+/// Replace a file at once, a crash never leaves it half written (unless it has other hard links)
+pub fn write_atomic(path: &str, bytes: &[u8]) -> Result<(), String> {
+    let temp = write_temp(path, bytes)?;
+    replace_with_temp(&temp, path)
+}
+
+/// Write text over a file, keeping its encoding, BOM, line ending and permissions
+pub fn write_text(path: &str, text: &str) -> Result<(), String> {
+    write_atomic(path, &encode_like(path, text)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn roundtrip(bytes: &[u8]) -> TextFormat {
+        let (text, format) = decode(bytes).unwrap();
+        assert_eq!(format.encode(&text).unwrap(), bytes);
+        format
+    }
+
+    #[test]
+    fn test_check_no_new_masks() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("notes.md").to_string_lossy().to_string();
+        assert!(check_no_new_masks(&path, "token = [REDACTED:github_token]").is_err());
+
+        fs::write(&path, "masks look like [REDACTED:api_key]\n").unwrap();
+        assert!(check_no_new_masks(&path, "masks look like [REDACTED:api_key], see redact.rs\n").is_ok());
+        assert!(check_no_new_masks(&path, "[REDACTED:api_key] and [REDACTED:jwt]\n").is_err());
+        assert!(check_no_new_masks(&path, "no secret here\n").is_ok());
+    }
+
+    #[test]
+    fn test_decode_detects_encodings() {
+        let format = roundtrip(b"plain\n");
+        assert_eq!((format.encoding, format.bom, format.line_ending), (Encoding::Utf8, false, LineEnding::Lf));
+        assert!(format.describe().is_none());
+
+        let format = roundtrip(b"\xEF\xBB\xBFbom\r\nline\r\n");
+        assert_eq!((format.encoding, format.bom, format.line_ending), (Encoding::Utf8, true, LineEnding::CrLf));
+        assert_eq!(format.describe().unwrap(), "UTF-8 with BOM, CRLF line endings");
+
+        let format = roundtrip(b"\xFF\xFEh\0i\0\n\0");
+        assert_eq!((format.encoding, format.bom), (Encoding::Utf16Le, true));
+        let format = roundtrip(b"\0h\0i\0\n");
+        assert_eq!((format.encoding, format.bom), (Encoding::Utf16Be, false));
+
+        let (text, format) = decode(b"caf\xE9\n").unwrap();
+        assert_eq!(text, "café\n");
+        assert_eq!(format.encoding, Encoding::Latin1);
+        assert!(format.encode("caf→").is_err());
+    }
+
+    #[test]
+    fn test_decode_refuses_binary() {
+        assert!(decode(b"\x7FELF\x02\x01\x01\0\0\0\0").is_err());
+        assert!(decode(&[0x1F, 0x8B, 0x08, 0x01, 0x02, 0x03, 0x04, 0x05]).is_err());
+    }
+
+    #[test]
+    fn test_encode_uses_dominant_line_ending() {
+        let (_, format) = decode(b"a\r\nb\r\nc\n").unwrap();
+        assert_eq!(format.line_ending, LineEnding::CrLf);
+        assert_eq!(format.encode("x\ny\r\n").unwrap(), b"x\r\ny\r\n");
+        assert_eq!(format.keep_trailing_newline("x"), "x\n");
+    }
+
+    #[test]
+    fn test_write_text_keeps_format_and_permissions() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("script.sh").to_string_lossy().to_string();
+        fs::write(&path, b"\xEF\xBB\xBF#!/bin/sh\r\necho hi\r\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        write_text(&path, "#!/bin/sh\necho bye\n").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"\xEF\xBB\xBF#!/bin/sh\r\necho bye\r\n");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o755);
+        }
+        // no temporary file left behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_write_temp_names_are_unique() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file.txt").to_string_lossy().to_string();
+        let first = write_temp(&path, b"one").unwrap();
+        let second = write_temp(&path, b"two").unwrap();
+        assert_ne!(first, second);
+        assert_eq!(fs::read(&first).unwrap(), b"one");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_keeps_symlinks_and_hard_links() {
+        let dir = tempdir().unwrap();
+        let real = dir.path().join("real.txt");
+        let link = dir.path().join("link.txt");
+        let hard = dir.path().join("hard.txt");
+        fs::write(&real, "old\n").unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+        fs::hard_link(&real, &hard).unwrap();
+
+        write_atomic(&link.to_string_lossy(), b"new\n").unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read(&real).unwrap(), b"new\n");
+        assert_eq!(fs::read(&hard).unwrap(), b"new\n");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 3);
+    }
+}
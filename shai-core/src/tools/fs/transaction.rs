@@ -0,0 +1,220 @@
+use super::manage::ops::move_to_trash;
+use super::text;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A file staged in a transaction
+#[derive(Debug, Clone)]
+struct StagedFile {
+    /// Content on disk when the file was first staged, None if it did not exist
+    original: Option<String>,
+    /// Content to write, None to delete the file
+    content: Option<String>,
+}
+
+/// Changes to several files staged in memory, then written all together or not at all.
+/// Nothing touches the disk before `commit`, dropping the transaction rolls it back
+#[derive(Debug, Default)]
+pub struct FsTransaction {
+    staged: BTreeMap<String, StagedFile>,
+}
+
+/// New content in the encoding and line ending of the file it replaces
+fn encoded_temp(path: &str, content: &str) -> Result<PathBuf, String> {
+    text::check_no_new_masks(path, content)?;
+    text::write_temp(path, &text::encode_like(path, content)?)
+}
+
+impl FsTransaction {
+    pub fn begin() -> Self {
+        Self::default()
+    }
+
+    fn read_disk(path: &str) -> Result<Option<String>, String> {
+        if !Path::new(path).exists() {
+            return Ok(None);
+        }
+        text::read_text(path).map(|(content, _)| Some(content))
+    }
+
+    fn entry(&mut self, path: &str) -> Result<&mut StagedFile, String> {
+        if !self.staged.contains_key(path) {
+            let original = Self::read_disk(path)?;
+            self.staged.insert(path.to_string(), StagedFile { content: original.clone(), original });
+        }
+        Ok(self.staged.get_mut(path).unwrap())
+    }
+
+    /// Content of a file as the transaction sees it, staged changes included
+    pub fn read(&self, path: &str) -> Result<String, String> {
+        let content = match self.staged.get(path) {
+            Some(file) => file.content.clone(),
+            None => Self::read_disk(path)?,
+        };
+        content.ok_or_else(|| format!("{}: file does not exist", path))
+    }
+
+    /// Stage the new content of a file, created if needed
+    pub fn write(&mut self, path: &str, content: String) -> Result<(), String> {
+        self.entry(path)?.content = Some(content);
+        Ok(())
+    }
+
+    /// Stage the deletion of a file
+    pub fn delete(&mut self, path: &str) -> Result<(), String> {
+        self.entry(path)?.content = None;
+        Ok(())
+    }
+
+    /// Staged files as (path, content before, content after), None when the file does not exist
+    pub fn changes(&self) -> impl Iterator<Item = (&str, Option<&str>, Option<&str>)> {
+        self.staged.iter().map(|(path, file)| (path.as_str(), file.original.as_deref(), file.content.as_deref()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.staged.is_empty()
+    }
+
+    /// Forget every staged change, the disk was never touched
+    pub fn rollback(self) {}
+
+    /// Put back the files already changed by a failed commit
+    fn restore(&self, paths: &[&str]) {
+        for path in paths.iter().rev() {
+            let _ = match &self.staged[*path].original {
+                Some(original) => text::write_text(path, original),
+                None => fs::remove_file(path).map_err(|e| e.to_string()),
+            };
+        }
+    }
+
+    /// Write every staged change. New contents are first written to temporary files, then renamed
+    /// over their targets; if anything fails, the files already changed are restored
+    pub fn commit(self) -> Result<(), String> {
+        self.commit_with(|path| fs::remove_file(path).map_err(|e| e.to_string()))
+    }
+
+    /// Like `commit`, but deleted files are moved to a trash instead of being destroyed
+    pub fn commit_to_trash(self, trash_dir: &Path) -> Result<(), String> {
+        self.commit_with(|path| move_to_trash(trash_dir, Path::new(path)).map(|_| ()).map_err(|e| e.to_string()))
+    }
+
+    fn commit_with(self, remove: impl Fn(&str) -> Result<(), String>) -> Result<(), String> {
+        let mut temps: Vec<(PathBuf, &str)> = Vec::new();
+        for (path, file) in &self.staged {
+            if file.content == file.original {
+                continue;
+            }
+            let Some(content) = &file.content else {
+                continue;
+            };
+            match encoded_temp(path, content) {
+                Ok(temp) => temps.push((temp, path)),
+                Err(error) => {
+                    temps.iter().for_each(|(temp, _)| { let _ = fs::remove_file(temp); });
+                    return Err(error);
+                }
+            }
+        }
+
+        let mut done: Vec<&str> = Vec::new();
+        for (index, (temp, path)) in temps.iter().enumerate() {
+            if let Err(error) = text::replace_with_temp(temp, path) {
+                temps[index..].iter().for_each(|(temp, _)| { let _ = fs::remove_file(temp); });
+                self.restore(&done);
+                return Err(error);
+            }
+            done.push(path);
+        }
+
+        for (path, file) in &self.staged {
+            if file.content.is_none() && file.original.is_some() {
+                if let Err(error) = remove(path) {
+                    self.restore(&done);
+                    return Err(format!("{}: {}", path, error));
+                }
+                done.push(path);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_transaction_stages_until_commit() {
+        let dir = tempdir().unwrap();
+        let kept = dir.path().join("kept.txt").to_string_lossy().to_string();
+        let created = dir.path().join("sub/created.txt").to_string_lossy().to_string();
+        let deleted = dir.path().join("deleted.txt").to_string_lossy().to_string();
+        fs::write(&kept, "before").unwrap();
+        fs::write(&deleted, "bye").unwrap();
+
+        let mut transaction = FsTransaction::begin();
+        transaction.write(&kept, "after".to_string()).unwrap();
+        transaction.write(&created, "new".to_string()).unwrap();
+        transaction.delete(&deleted).unwrap();
+
+        assert_eq!(transaction.read(&kept).unwrap(), "after");
+        assert!(transaction.read(&deleted).is_err());
+        assert_eq!(fs::read_to_string(&kept).unwrap(), "before");
+        assert!(!Path::new(&created).exists());
+
+        transaction.commit().unwrap();
+        assert_eq!(fs::read_to_string(&kept).unwrap(), "after");
+        assert_eq!(fs::read_to_string(&created).unwrap(), "new");
+        assert!(!Path::new(&deleted).exists());
+        // no temporary file left behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_transaction_commit_failure_restores_files() {
+        let dir = tempdir().unwrap();
+        let first = dir.path().join("a.txt").to_string_lossy().to_string();
+        // a directory cannot be replaced by a file, the rename fails
+        let blocked = dir.path().join("b_dir").to_string_lossy().to_string();
+        fs::write(&first, "original").unwrap();
+        fs::create_dir(&blocked).unwrap();
+        fs::write(dir.path().join("b_dir/inner"), "x").unwrap();
+
+        let mut transaction = FsTransaction::begin();
+        transaction.write(&first, "changed".to_string()).unwrap();
+        transaction.staged.insert(blocked.clone(), StagedFile { original: None, content: Some("file".to_string()) });
+        assert!(transaction.commit().is_err());
+
+        assert_eq!(fs::read_to_string(&first).unwrap(), "original");
+        assert!(Path::new(&blocked).is_dir());
+    }
+
+    #[test]
+    fn test_transaction_commit_to_trash() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("gone.txt").to_string_lossy().to_string();
+        let trash = dir.path().join("trash");
+        fs::write(&path, "kept").unwrap();
+
+        let mut transaction = FsTransaction::begin();
+        transaction.delete(&path).unwrap();
+        transaction.commit_to_trash(&trash).unwrap();
+        assert!(!Path::new(&path).exists());
+        assert_eq!(fs::read_to_string(trash.join("gone.txt")).unwrap(), "kept");
+    }
+
+    #[test]
+    fn test_transaction_rollback_touches_nothing() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file.txt").to_string_lossy().to_string();
+        fs::write(&path, "content").unwrap();
+
+        let mut transaction = FsTransaction::begin();
+        transaction.write(&path, "other".to_string()).unwrap();
+        transaction.rollback();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "content");
+    }
+}
@@ -9,7 +9,8 @@ use tempfile::tempdir;
 
 #[test]
 fn test_write_tool_permissions() {
-    let log = Arc::new(FsOperationLog::new());
+    let dir = tempdir().unwrap();
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")));
     let tool = WriteTool::new(log);
     let perms = tool.capabilities();
     assert!(perms.contains(&ToolCapability::Write));
@@ -18,7 +19,8 @@ fn test_write_tool_permissions() {
 
 #[tokio::test]
 async fn test_write_tool_creation() {
-    let log = Arc::new(FsOperationLog::new());
+    let dir = tempdir().unwrap();
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")));
     let tool = WriteTool::new(log);
     assert_eq!(tool.name(), "write");
     assert!(!tool.description().is_empty());
@@ -29,7 +31,7 @@ async fn test_write_new_file() {
     let dir = tempdir().unwrap();
     let file_path = dir.path().join("new_file.txt");
     
-    let log = Arc::new(FsOperationLog::new());
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")));
     let tool = WriteTool::new(log);
     let params = WriteToolParams {
         path: file_path.to_string_lossy().to_string(),
@@ -46,4 +48,49 @@ async fn test_write_new_file() {
     
     let content = fs::read_to_string(&file_path).unwrap();
     assert_eq!(content, "Hello, World!");
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_write_overwrite_requires_fresh_read() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("existing.txt");
+    fs::write(&file_path, "original").unwrap();
+    let path = file_path.to_string_lossy().to_string();
+
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")));
+    let tool = WriteTool::new(log.clone());
+    let params = WriteToolParams { path: path.clone(), content: "replaced".to_string() };
+
+    // never read
+    assert!(!tool.execute(params.clone()).await.is_success());
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "original");
+
+    // read, then changed behind the agent's back
+    log.log_operation(crate::tools::FsOperationType::Read, path.clone()).await;
+    fs::write(&file_path, "changed outside").unwrap();
+    assert!(!tool.execute(params.clone()).await.is_success());
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "changed outside");
+
+    log.log_operation(crate::tools::FsOperationType::Read, path.clone()).await;
+    assert!(tool.execute(params.clone()).await.is_success());
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), "replaced");
+
+    // its own write is known, it can be overwritten again
+    assert!(tool.execute(WriteToolParams { path, content: "again".to_string() }).await.is_success());
+}
+
+#[tokio::test]
+async fn test_write_overwrite_keeps_file_format() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("windows.txt");
+    fs::write(&file_path, b"\xEF\xBB\xBFold\r\ncontent\r\n").unwrap();
+    let path = file_path.to_string_lossy().to_string();
+
+    let log = Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")));
+    log.log_operation(crate::tools::FsOperationType::Read, path.clone()).await;
+    let tool = WriteTool::new(log);
+
+    let result = tool.execute(WriteToolParams { path, content: "new\ncontent".to_string() }).await;
+    assert!(result.is_success());
+    assert_eq!(fs::read(&file_path).unwrap(), b"\xEF\xBB\xBFnew\r\ncontent\r\n");
+}
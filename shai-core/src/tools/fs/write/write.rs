@@ -1,10 +1,9 @@
 use super::structs::WriteToolParams;
-use super::super::{FsOperationLog, FsOperationType};
+use super::super::{text, FsOperationLog, FsOperationType};
 use crate::tools::{ToolResult, tool};
 //use crate::tools::highlight::highlight_content;
 use serde_json::json;
 use std::collections::HashMap;
-use std::fs;
 use std::path::Path;
 use std::sync::Arc;
 
@@ -23,16 +22,16 @@ impl WriteTool {
 
         // Check if file exists before writing
         let file_existed = path.exists();
+        text::check_no_new_masks(&params.path, &params.content)?;
 
-        // Create parent directories if they don't exist
-        if let Some(parent) = path.parent() {
-            if !parent.exists() {
-                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
-            }
-        }
+        // An existing file keeps its encoding, BOM, line ending, trailing newline and permissions
+        let bytes = match text::read_text(&params.path) {
+            Ok((_, format)) if file_existed => format.encode(&format.keep_trailing_newline(&params.content))?,
+            _ => params.content.as_bytes().to_vec(),
+        };
 
-        // Write content to file (overwrites if exists)
-        fs::write(path, &params.content).map_err(|e| e.to_string())?;
+        // Written to a temporary file renamed over the target, parent directories are created
+        text::write_atomic(&params.path, &bytes)?;
 
         let action = if file_existed { "updated" } else { "created" };
         
@@ -44,7 +43,8 @@ impl WriteTool {
 #[tool(name = "write", description = r#"Creates a new file with specified content or completely overwrites an existing file. This tool should be used with caution.
 
 **Guidelines**
-- To overwrite an existing file, you must first have read it with the `read` tool. This is a safety measure to ensure you are aware of the content being replaced.
+- To overwrite an existing file, you must first have read it with the `read` tool (or written it yourself), and it must not have changed on disk since. This is a safety measure to ensure you are aware of the content being replaced.
+- An overwritten file keeps its encoding, BOM, line endings, trailing newline and permissions, write the content with plain `\n` line endings.
 - This tool is primarily for creating new files when explicitly instructed. For modifying existing files, the `edit` or `multiedit` tools are the correct choice.
 - Do not create files proactively, especially documentation. Only create files when the user's request cannot be fulfilled by modifying existing ones."#, capabilities = [ToolCapability::Write])]
 impl WriteTool {
@@ -65,6 +65,12 @@ impl WriteTool {
     }
 
     async fn execute(&self, params: WriteToolParams) -> ToolResult {
+        if Path::new(&params.path).exists() {
+            if let Err(err) = self.operation_log.validate_overwrite_permission(&params.path).await {
+                return ToolResult::error(err);
+            }
+        }
+
         match self.perform_write(&params) {
             Ok(message) => {
                 // Log the write operation
@@ -36,8 +36,8 @@ impl SyntaxTheme {
     }
 }
 
-pub fn highlight_content(content: &str, file_path: &str) -> String {
-    // Determine language from file extension
+/// Language of a file from its extension, None for unknown extensions
+pub fn language_for_path(file_path: &str) -> Option<&'static str> {
     let extension = Path::new(file_path)
         .extension()
         .and_then(|ext| ext.to_str())
@@ -45,8 +45,8 @@ pub fn highlight_content(content: &str, file_path: &str) -> String {
     
     let language_name = match extension {
         "rs" => "rust",
-        "js" | "jsx" => "javascript",
-        "ts" | "tsx" => "typescript",
+        "js" | "jsx" | "mjs" | "cjs" => "javascript",
+        "ts" | "tsx" | "mts" | "cts" => "typescript",
         "py" => "python",
         "go" => "go",
         "java" => "java",
@@ -61,7 +61,15 @@ pub fn highlight_content(content: &str, file_path: &str) -> String {
         "toml" => "toml",
         "md" => "markdown",
         "sh" | "bash" => "bash",
-        _ => return content.to_string(), // No highlighting for unknown extensions
+        _ => return None,
+    };
+    Some(language_name)
+}
+
+pub fn highlight_content(content: &str, file_path: &str) -> String {
+    // Determine language from file extension
+    let Some(language_name) = language_for_path(file_path) else {
+        return content.to_string(); // No highlighting for unknown extensions
     };
 
     let theme = SyntaxTheme::dark();
@@ -0,0 +1,189 @@
+use super::structs::{DiagnosticsToolParams, LspPositionParams};
+use crate::lsp::{Location, LspManager, Position};
+use crate::tools::fs::text;
+use crate::tools::{tool, ToolResult};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+const DISABLED: &str = "Language servers are disabled in the lsp section of ~/.shai.config";
+
+/// Protocol position of a symbol on a 1-based line: the symbol if given, else the 1-based column, else the first non-blank character
+pub(crate) fn position_for(source: &str, line: u32, symbol: Option<&str>, column: Option<u32>) -> Result<Position, String> {
+    let text = source.lines()
+        .nth(line.saturating_sub(1) as usize)
+        .filter(|_| line > 0)
+        .ok_or_else(|| format!("Line {} is past the end of the file ({} lines)", line, source.lines().count()))?;
+
+    let byte = match (symbol.filter(|symbol| !symbol.is_empty()), column) {
+        (Some(symbol), _) => text.find(symbol)
+            .ok_or_else(|| format!("'{}' does not appear on line {}: {}", symbol, line, text.trim()))?,
+        (None, Some(column)) => text.char_indices()
+            .nth(column.saturating_sub(1) as usize)
+            .map_or(text.len(), |(byte, _)| byte),
+        (None, None) => text.len() - text.trim_start().len(),
+    };
+    let character = text[..byte].chars().map(char::len_utf16).sum::<usize>() as u32;
+    Ok(Position { line: line - 1, character })
+}
+
+/// Path of a location as the user would write it, relative to the workspace when inside it
+fn display_path(path: &Path) -> String {
+    std::env::current_dir().ok()
+        .and_then(|root| path.strip_prefix(root).ok().map(|relative| relative.display().to_string()))
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+fn describe(location: &Location) -> String {
+    let path = display_path(&location.path);
+    let code = text::read_text(&location.path.to_string_lossy()).ok()
+        .and_then(|(source, _)| source.lines().nth(location.line as usize).map(|line| line.trim().to_string()))
+        .unwrap_or_default();
+    format!("{}:{}:{}: {}", path, location.line + 1, location.character + 1, code)
+}
+
+async fn position_of(params: &LspPositionParams) -> Result<Position, String> {
+    let path = params.path.clone();
+    let (source, _) = tokio::task::spawn_blocking(move || text::read_text(&path)).await
+        .map_err(|e| e.to_string())??;
+    position_for(&source, params.line, params.symbol.as_deref(), params.column)
+}
+
+fn position_meta(params: &LspPositionParams) -> HashMap<String, serde_json::Value> {
+    let mut meta = HashMap::new();
+    meta.insert("path".to_string(), json!(params.path));
+    meta.insert("line".to_string(), json!(params.line));
+    if let Some(symbol) = &params.symbol {
+        meta.insert("symbol".to_string(), json!(symbol));
+    }
+    meta
+}
+
+pub struct DiagnosticsTool {
+    lsp: Arc<LspManager>,
+}
+
+impl DiagnosticsTool {
+    pub fn new(lsp: Arc<LspManager>) -> Self {
+        Self { lsp }
+    }
+}
+
+#[tool(name = "diagnostics", description = r#"Lists the errors, warnings and hints the language server of a file reports for it, the way an editor would underline them: type errors, unresolved names, unused variables...
+
+**Usage Notes:**
+- Files are checked by rust-analyzer, pyright, typescript-language-server or gopls when they are installed, other servers can be set in the `lsp` section of ~/.shai.config.
+- Errors and warnings introduced by an edit are already reported after the edit, use this tool to see what a file looked like before you started or to check it again.
+- The first check of a workspace can take a while, the server indexes it first."#, capabilities = [ToolCapability::Read])]
+impl DiagnosticsTool {
+    async fn execute(&self, params: DiagnosticsToolParams) -> ToolResult {
+        let mut meta = HashMap::new();
+        meta.insert("path".to_string(), json!(params.path));
+
+        if !self.lsp.is_enabled() {
+            return ToolResult::Error { error: DISABLED.to_string(), metadata: Some(meta) };
+        }
+        if !Path::new(&params.path).is_file() {
+            return ToolResult::Error {
+                error: format!("File does not exist: {}", params.path),
+                metadata: Some(meta),
+            };
+        }
+
+        match self.lsp.diagnostics(&params.path).await {
+            Ok(diagnostics) => {
+                meta.insert("count".to_string(), json!(diagnostics.len()));
+                meta.insert("errors".to_string(), json!(diagnostics.iter().filter(|d| d.severity_name() == "error").count()));
+                let output = if diagnostics.is_empty() {
+                    format!("No problem reported in {}", params.path)
+                } else {
+                    let mut lines: Vec<String> = diagnostics.iter().map(|d| d.format(&params.path)).collect();
+                    lines.push(format!("\n{} diagnostic(s) in {}", diagnostics.len(), params.path));
+                    lines.join("\n")
+                };
+                ToolResult::Success { output, metadata: Some(meta) }
+            }
+            Err(e) => ToolResult::Error { error: e, metadata: Some(meta) },
+        }
+    }
+}
+
+pub struct HoverTool {
+    lsp: Arc<LspManager>,
+}
+
+impl HoverTool {
+    pub fn new(lsp: Arc<LspManager>) -> Self {
+        Self { lsp }
+    }
+}
+
+#[tool(name = "hover", description = r#"Asks the language server what a symbol is: its type, signature and documentation, as an editor shows them when hovering it. Useful to learn the inferred type of a variable or the signature of a function defined in a dependency without reading its source.
+
+**Usage:**
+- Give the file, the 1-based line of the symbol and the symbol itself, its first occurrence on the line is used. A 1-based `column` can be given instead of the symbol."#, capabilities = [ToolCapability::Read])]
+impl HoverTool {
+    async fn execute(&self, params: LspPositionParams) -> ToolResult {
+        let meta = position_meta(&params);
+        if !self.lsp.is_enabled() {
+            return ToolResult::Error { error: DISABLED.to_string(), metadata: Some(meta) };
+        }
+        let position = match position_of(&params).await {
+            Ok(position) => position,
+            Err(e) => return ToolResult::Error { error: e, metadata: Some(meta) },
+        };
+
+        match self.lsp.hover(&params.path, position).await {
+            Ok(Some(text)) => ToolResult::Success { output: text, metadata: Some(meta) },
+            Ok(None) => ToolResult::Success {
+                output: format!("The language server has no information about {}:{}", params.path, params.line),
+                metadata: Some(meta),
+            },
+            Err(e) => ToolResult::Error { error: e, metadata: Some(meta) },
+        }
+    }
+}
+
+pub struct GotoDefinitionTool {
+    lsp: Arc<LspManager>,
+}
+
+impl GotoDefinitionTool {
+    pub fn new(lsp: Arc<LspManager>) -> Self {
+        Self { lsp }
+    }
+}
+
+#[tool(name = "goto_definition", description = r#"Asks the language server where a symbol used in a file is defined, following imports, re-exports and method resolution like an editor does, including into dependencies.
+
+**Usage:**
+- Give the file, the 1-based line where the symbol is used and the symbol itself, its first occurrence on the line is used. A 1-based `column` can be given instead of the symbol.
+- Each definition is listed as `path:line:column` with its line of code, read it with the `read` tool."#, capabilities = [ToolCapability::Read])]
+impl GotoDefinitionTool {
+    async fn execute(&self, params: LspPositionParams) -> ToolResult {
+        let mut meta = position_meta(&params);
+        if !self.lsp.is_enabled() {
+            return ToolResult::Error { error: DISABLED.to_string(), metadata: Some(meta) };
+        }
+        let position = match position_of(&params).await {
+            Ok(position) => position,
+            Err(e) => return ToolResult::Error { error: e, metadata: Some(meta) },
+        };
+
+        match self.lsp.definition(&params.path, position).await {
+            Ok(locations) if locations.is_empty() => ToolResult::Success {
+                output: format!("The language server found no definition for {}:{}", params.path, params.line),
+                metadata: Some(meta),
+            },
+            Ok(locations) => {
+                meta.insert("count".to_string(), json!(locations.len()));
+                ToolResult::Success {
+                    output: locations.iter().map(describe).collect::<Vec<_>>().join("\n"),
+                    metadata: Some(meta),
+                }
+            }
+            Err(e) => ToolResult::Error { error: e, metadata: Some(meta) },
+        }
+    }
+}
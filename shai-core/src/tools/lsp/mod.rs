@@ -0,0 +1,8 @@
+pub mod structs;
+pub mod lsp;
+
+#[cfg(test)]
+mod tests;
+
+pub use structs::{DiagnosticsToolParams, LspPositionParams};
+pub use lsp::{DiagnosticsTool, HoverTool, GotoDefinitionTool};
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DiagnosticsToolParams {
+    /// File to check
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LspPositionParams {
+    /// File containing the symbol
+    pub path: String,
+    /// Line of the symbol (1-based)
+    pub line: u32,
+    /// Symbol on that line, its first occurrence is used
+    #[serde(default)]
+    pub symbol: Option<String>,
+    /// Column of the symbol (1-based), used when no symbol is given
+    #[serde(default)]
+    pub column: Option<u32>,
+}
@@ -0,0 +1,94 @@
+use super::lsp::{position_for, DiagnosticsTool, GotoDefinitionTool, HoverTool};
+use super::structs::{DiagnosticsToolParams, LspPositionParams};
+use crate::lsp::tests::fake_manager;
+use crate::lsp::{LspConfig, LspManager, Position};
+use crate::tools::{Tool, ToolCapability, ToolResult};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+fn output(result: ToolResult) -> String {
+    match result {
+        ToolResult::Success { output, .. } => output,
+        ToolResult::Error { error, .. } => panic!("Expected success, got: {}", error),
+    }
+}
+
+fn at(path: &Path, line: u32, symbol: Option<&str>) -> LspPositionParams {
+    LspPositionParams {
+        path: path.to_string_lossy().to_string(),
+        line,
+        symbol: symbol.map(str::to_string),
+        column: None,
+    }
+}
+
+#[test]
+fn test_position_for_symbol_column_and_indent() {
+    let source = "fn main() {\n    let total = add(1, 2);\n}\n";
+    assert_eq!(position_for(source, 2, Some("add"), None).unwrap(), Position { line: 1, character: 16 });
+    assert_eq!(position_for(source, 2, None, Some(9)).unwrap(), Position { line: 1, character: 8 });
+    assert_eq!(position_for(source, 2, None, None).unwrap(), Position { line: 1, character: 4 });
+    assert!(position_for(source, 2, Some("sub"), None).unwrap_err().contains("does not appear on line 2"));
+    assert!(position_for(source, 0, None, None).is_err());
+    assert!(position_for(source, 9, None, None).unwrap_err().contains("past the end"));
+}
+
+#[test]
+fn test_position_for_counts_utf16_units() {
+    // 🦀 is one char but two UTF-16 code units, é is one of each
+    let source = "let s = \"🦀é\"; call(s);";
+    assert_eq!(position_for(source, 1, Some("call"), None).unwrap(), Position { line: 0, character: 15 });
+    assert_eq!(position_for(source, 1, None, Some(12)).unwrap(), Position { line: 0, character: 12 });
+}
+
+#[tokio::test]
+async fn test_diagnostics_tool() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("main.rs");
+    fs::write(&path, "fn main() {\n    let error = 1;\n}\n").unwrap();
+    let tool = DiagnosticsTool::new(Arc::new(fake_manager(&dir).await));
+    assert_eq!(tool.capabilities(), &[ToolCapability::Read]);
+
+    let path_name = path.to_string_lossy().to_string();
+    let out = output(tool.execute(DiagnosticsToolParams { path: path_name.clone() }).await);
+    assert!(out.contains(&format!("{}:2:1: error: unexpected `let error = 1;` [fake]", path_name)));
+    assert!(out.contains("1 diagnostic(s)"));
+
+    fs::write(&path, "fn main() {}\n").unwrap();
+    let out = output(tool.execute(DiagnosticsToolParams { path: path_name.clone() }).await);
+    assert!(out.starts_with("No problem reported"));
+
+    let missing = tool.execute(DiagnosticsToolParams { path: dir.path().join("gone.rs").to_string_lossy().to_string() }).await;
+    assert!(missing.is_error());
+}
+
+#[tokio::test]
+async fn test_hover_and_goto_definition_tools() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("lib.rs");
+    fs::write(&path, "fn answer() -> u32 { 42 }\nfn main() { answer(); }\n").unwrap();
+    let manager = Arc::new(fake_manager(&dir).await);
+
+    let hover = HoverTool::new(manager.clone());
+    assert_eq!(output(hover.execute(at(&path, 2, Some("answer"))).await), "fn answer() -> u32");
+    assert!(hover.execute(at(&path, 2, Some("question"))).await.is_error());
+
+    let goto = GotoDefinitionTool::new(manager);
+    let out = output(goto.execute(at(&path, 2, Some("answer"))).await);
+    assert_eq!(out, format!("{}:1:4: fn answer() -> u32 {{ 42 }}", path.display()));
+}
+
+#[tokio::test]
+async fn test_tools_refuse_when_disabled() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("lib.rs");
+    fs::write(&path, "fn answer() -> u32 { 42 }\n").unwrap();
+    let config = LspConfig { enabled: false, ..LspConfig::default() };
+    let manager = Arc::new(LspManager::new(config, dir.path().to_path_buf()));
+
+    let result = HoverTool::new(manager).execute(at(&path, 1, Some("answer"))).await;
+    let ToolResult::Error { error, .. } = result else { panic!("Expected an error") };
+    assert!(error.contains("disabled"));
+}
@@ -4,15 +4,23 @@ pub mod todo;
 pub mod fs;
 pub mod fetch;
 pub mod bash;
+pub mod process;
+pub mod search;
+pub mod symbols;
+pub mod lsp;
 
 #[cfg(test)]
 mod tests_llm;
 
 pub use shai_macros::tool;
-pub use types::{Tool, ToolCall, ToolResult, ToolError, ToolCapability, AnyTool, AnyToolBox, ToolEmptyParams};
+pub use types::{Tool, ToolCall, ToolResult, ToolError, ToolCapability, AnyTool, AnyToolBox, ToolEmptyParams, TrustLevel, UNTRUSTED_CONTENT_TAG, Attachment, attachments_message, ToolContext, ToolOutputSink};
 
 // Re-export all tools
-pub use bash::BashTool;
+pub use bash::{BashTool, BashSession};
 pub use fetch::FetchTool;
-pub use fs::{EditTool, FindTool, LsTool, MultiEditTool, ReadTool, WriteTool, FsOperationLog, FsOperationType, FsOperation, FsOperationSummary};
-pub use todo::{TodoReadTool, TodoWriteTool, TodoStorage, TodoItem, TodoStatus, TodoWriteParams, TodoItemInput};
+pub use search::{WebSearchTool, SearchBackend, SearchConfig};
+pub use process::{BashBackgroundTool, ProcessKillTool, ProcessListTool, ProcessOutputTool, ProcessManager};
+pub use fs::{ApplyPatchTool, CopyTool, DeleteTool, EditTool, MoveTool, FindTool, GlobTool, LsTool, MultiEditTool, MultiFileEditTool, ReadTool, WriteTool, FsOperationLog, FsOperationType, FsOperation, FsOperationSummary, FsTransaction};
+pub use symbols::{SymbolsTool, StructuralSearchTool, StructuralReplaceTool};
+pub use lsp::{DiagnosticsTool, HoverTool, GotoDefinitionTool};
+pub use todo::{TodoReadTool, TodoWriteTool, TodoUpdateTool, TodoStorage, TodoItem, TodoStatus, TodoPriority, TodoWriteParams, TodoUpdateParams, TodoItemInput, TodoSubtaskInput, TodoAddInput, TodoChangeInput, todo_tree, todo_progress};
@@ -0,0 +1,8 @@
+pub mod structs;
+pub mod process;
+
+#[cfg(test)]
+mod tests;
+
+pub use structs::{BackgroundProcess, BashBackgroundParams, OutputChunk, ProcessKillParams, ProcessManager, ProcessOutputParams, ProcessStatus};
+pub use process::{BashBackgroundTool, ProcessKillTool, ProcessListTool, ProcessOutputTool};
\ No newline at end of file
@@ -0,0 +1,184 @@
+use super::structs::{BackgroundProcess, BashBackgroundParams, OutputChunk, ProcessKillParams, ProcessManager, ProcessOutputParams, ProcessStatus};
+use crate::tools::{tool, ToolEmptyParams, ToolResult};
+use chrono::Utc;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Longest wait accepted by process_output
+const MAX_WAIT_SECS: u32 = 60;
+
+fn process_metadata(process: &BackgroundProcess) -> HashMap<String, serde_json::Value> {
+    let mut metadata = HashMap::new();
+    metadata.insert("id".to_string(), json!(process.id));
+    metadata.insert("command".to_string(), json!(process.command));
+    metadata.insert("pid".to_string(), json!(process.pid));
+    metadata.insert("status".to_string(), json!(process.status().to_string()));
+    metadata
+}
+
+fn format_output(chunk: &OutputChunk) -> String {
+    let mut output = String::new();
+    if chunk.dropped > 0 {
+        output.push_str(&format!("[... {} bytes of older output dropped ...]\n", chunk.dropped));
+    }
+    output.push_str(&chunk.text);
+    output
+}
+
+fn unknown_process(id: &str) -> ToolResult {
+    ToolResult::error(format!("No background process with id '{}', use process_list to see the running processes", id))
+}
+
+#[derive(Clone)]
+pub struct BashBackgroundTool {
+    processes: Arc<ProcessManager>,
+}
+
+#[tool(name = "bash_background", description = r#"
+Starts a shell command in the background and returns immediately with a process id, for long running commands such as dev servers, file watchers or long builds that you want to keep running while you work.
+
+Usage Guidelines:
+- Use process_output with the returned id to read what the command printed since the last poll, optionally waiting for new output.
+- Use process_kill to stop it once you no longer need it and process_list to see every background process.
+- Output is kept in a bounded buffer, poll regularly if the command is verbose.
+- The command does not read from stdin. Every background process is killed when the session ends.
+- Use the bash tool instead for commands that complete on their own.
+"#, capabilities = [ToolCapability::Read, ToolCapability::Write, ToolCapability::Network])]
+impl BashBackgroundTool {
+    pub fn new(processes: Arc<ProcessManager>) -> Self {
+        Self { processes }
+    }
+
+    async fn execute(&self, params: BashBackgroundParams) -> ToolResult {
+        if params.command.trim().is_empty() {
+            return ToolResult::error("Command cannot be empty".to_string());
+        }
+
+        match self.processes.spawn(&params) {
+            Ok(process) => ToolResult::Success {
+                output: format!("Started background process {} (pid {}), use process_output with id \"{}\" to read its output",
+                    process.id,
+                    process.pid.map(|pid| pid.to_string()).unwrap_or_else(|| "unknown".to_string()),
+                    process.id),
+                metadata: Some(process_metadata(&process)),
+            },
+            Err(e) => ToolResult::error(format!("Failed to start the command: {}", e)),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ProcessOutputTool {
+    processes: Arc<ProcessManager>,
+}
+
+#[tool(name = "process_output", description = "Reads the output (stdout and stderr interleaved) printed by a background process since the last call, and its status. Set wait to a number of seconds to wait for new output or for the process to exit.", capabilities = [ToolCapability::Read])]
+impl ProcessOutputTool {
+    pub fn new(processes: Arc<ProcessManager>) -> Self {
+        Self { processes }
+    }
+
+    async fn execute(&self, params: ProcessOutputParams) -> ToolResult {
+        let Some(process) = self.processes.get(&params.id) else {
+            return unknown_process(&params.id);
+        };
+
+        if let Some(wait) = params.wait.filter(|wait| *wait > 0) {
+            process.wait_for_output(Duration::from_secs(wait.min(MAX_WAIT_SECS) as u64)).await;
+        }
+
+        let chunk = process.take_output();
+        let status = process.status();
+        let output = format_output(&chunk);
+        let mut metadata = process_metadata(&process);
+        metadata.insert("bytes".to_string(), json!(chunk.text.len()));
+        metadata.insert("dropped".to_string(), json!(chunk.dropped));
+
+        ToolResult::Success {
+            output: if output.is_empty() {
+                format!("[{} is {}, no new output]", process.id, status)
+            } else {
+                format!("{}\n[{} is {}]", output.trim_end_matches('\n'), process.id, status)
+            },
+            metadata: Some(metadata),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ProcessKillTool {
+    processes: Arc<ProcessManager>,
+}
+
+// Only processes started by the agent itself can be killed, hence no Write capability
+#[tool(name = "process_kill", description = "Stops a background process started with bash_background, along with every process it spawned, and returns its remaining output.", capabilities = [ToolCapability::Read])]
+impl ProcessKillTool {
+    pub fn new(processes: Arc<ProcessManager>) -> Self {
+        Self { processes }
+    }
+
+    async fn execute(&self, params: ProcessKillParams) -> ToolResult {
+        let Some(process) = self.processes.get(&params.id) else {
+            return unknown_process(&params.id);
+        };
+
+        let status = process.status();
+        if status != ProcessStatus::Running {
+            // the command is done but what it left running in the background is not
+            process.kill();
+            return ToolResult::Success {
+                output: format!("{} already {}\n{}", process.id, status, format_output(&process.take_output())).trim_end().to_string(),
+                metadata: Some(process_metadata(&process)),
+            };
+        }
+
+        process.kill();
+        // leave the readers some time to collect what was printed before the kill
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let output = format_output(&process.take_output());
+        ToolResult::Success {
+            output: format!("Killed {}\n{}", process.id, output).trim_end().to_string(),
+            metadata: Some(process_metadata(&process)),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ProcessListTool {
+    processes: Arc<ProcessManager>,
+}
+
+#[tool(name = "process_list", description = "Lists the background processes started with bash_background, with their id, status, age and command.", capabilities = [ToolCapability::Read])]
+impl ProcessListTool {
+    pub fn new(processes: Arc<ProcessManager>) -> Self {
+        Self { processes }
+    }
+
+    async fn execute(&self, params: ToolEmptyParams) -> ToolResult {
+        let processes = self.processes.list();
+        if processes.is_empty() {
+            return ToolResult::success("No background process".to_string());
+        }
+
+        let now = Utc::now();
+        let output = processes.iter()
+            .map(|process| format!("{}  {}  started {}s ago  {}",
+                process.id,
+                process.status(),
+                (now - process.started_at).num_seconds(),
+                process.command))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let running = processes.iter().filter(|p| p.status() == ProcessStatus::Running).count();
+        let mut metadata = HashMap::new();
+        metadata.insert("count".to_string(), json!(processes.len()));
+        metadata.insert("running".to_string(), json!(running));
+        ToolResult::Success {
+            output,
+            metadata: Some(metadata),
+        }
+    }
+}
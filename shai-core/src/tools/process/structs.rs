@@ -0,0 +1,288 @@
+use chrono::{DateTime, Utc};
+use ringbuffer::{AllocRingBuffer, RingBuffer};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::Command;
+use tokio::sync::Notify;
+
+use crate::tools::bash::bash::{group_exists, kill_group};
+
+/// Bytes of output kept per background process, older output is dropped
+pub const OUTPUT_CAPACITY: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct BashBackgroundParams {
+    /// The bash command to start in the background
+    pub command: String,
+    /// Working directory of the command (optional)
+    pub working_dir: Option<String>,
+    /// Environment variables to set (optional)
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ProcessOutputParams {
+    /// Id of the background process, as returned by bash_background
+    pub id: String,
+    /// Seconds to wait for new output or for the process to exit (optional, default 0)
+    pub wait: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ProcessKillParams {
+    /// Id of the background process, as returned by bash_background
+    pub id: String,
+}
+
+/// State of a background process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessStatus {
+    Running,
+    Exited(i32),
+    Killed,
+}
+
+impl std::fmt::Display for ProcessStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProcessStatus::Running => write!(f, "running"),
+            ProcessStatus::Exited(code) => write!(f, "exited with code {}", code),
+            ProcessStatus::Killed => write!(f, "killed"),
+        }
+    }
+}
+
+/// Output of a background process, stdout and stderr interleaved
+struct OutputBuffer {
+    buffer: AllocRingBuffer<u8>,
+    /// bytes written since the start
+    written: usize,
+    /// bytes returned to the model so far
+    read: usize,
+}
+
+/// Output returned by a poll
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputChunk {
+    pub text: String,
+    /// bytes that were dropped from the ring buffer before they were read
+    pub dropped: usize,
+}
+
+/// A command started by bash_background
+pub struct BackgroundProcess {
+    pub id: String,
+    pub command: String,
+    pub pid: Option<u32>,
+    pub started_at: DateTime<Utc>,
+    output: Mutex<OutputBuffer>,
+    status: Mutex<ProcessStatus>,
+    /// process group of the command, cleared once the group has no members left so that a
+    /// reused pgid is never signalled
+    group: Mutex<Option<u32>>,
+    /// notified on new output and on exit
+    changed: Notify,
+}
+
+impl BackgroundProcess {
+    pub fn status(&self) -> ProcessStatus {
+        *self.status.lock().unwrap()
+    }
+
+    fn append(&self, bytes: &[u8]) {
+        let mut output = self.output.lock().unwrap();
+        for &byte in bytes {
+            output.buffer.enqueue(byte);
+        }
+        output.written += bytes.len();
+        self.changed.notify_waiters();
+    }
+
+    /// Output written since the last call
+    pub fn take_output(&self) -> OutputChunk {
+        let mut output = self.output.lock().unwrap();
+        let unread = output.written - output.read;
+        let available = unread.min(output.buffer.len());
+        let bytes: Vec<u8> = output.buffer.iter().skip(output.buffer.len() - available).copied().collect();
+        output.read = output.written;
+        OutputChunk {
+            text: String::from_utf8_lossy(&bytes).to_string(),
+            dropped: unread - available,
+        }
+    }
+
+    fn has_unread_output(&self) -> bool {
+        let output = self.output.lock().unwrap();
+        output.written > output.read
+    }
+
+    /// Wait until there is unread output, the process is no longer running or the timeout elapsed
+    pub async fn wait_for_output(&self, timeout: Duration) {
+        let _ = tokio::time::timeout(timeout, async {
+            loop {
+                // registered before the check so that a change in between is not missed
+                let changed = self.changed.notified();
+                tokio::pin!(changed);
+                changed.as_mut().enable();
+                if self.has_unread_output() || self.status() != ProcessStatus::Running {
+                    return;
+                }
+                changed.await;
+            }
+        }).await;
+    }
+
+    /// Kill the process group of the command, also once the command exited since what it started
+    /// in the background is still in the group (a group already gone is not an error)
+    pub fn kill(&self) {
+        let mut status = self.status.lock().unwrap();
+        if let Some(pgid) = self.group.lock().unwrap().take() {
+            // the pgid cannot be reused while the leader is unreaped or the group has members
+            if *status == ProcessStatus::Running || group_exists(pgid) {
+                kill_group(pgid);
+            }
+        }
+        if *status == ProcessStatus::Running {
+            *status = ProcessStatus::Killed;
+            self.changed.notify_waiters();
+        }
+    }
+
+    fn exited(&self, code: i32) {
+        let mut status = self.status.lock().unwrap();
+        if *status == ProcessStatus::Running {
+            *status = ProcessStatus::Exited(code);
+        }
+        self.changed.notify_waiters();
+    }
+
+    /// Forget the process group once its last member is gone, true if it is gone
+    fn check_group(&self) -> bool {
+        let mut group = self.group.lock().unwrap();
+        if group.is_some_and(|pgid| !group_exists(pgid)) {
+            *group = None;
+        }
+        group.is_none()
+    }
+}
+
+/// Background processes of an agent, every process still running is killed when the manager is dropped
+pub struct ProcessManager {
+    processes: Mutex<Vec<Arc<BackgroundProcess>>>,
+    next_id: AtomicUsize,
+}
+
+impl ProcessManager {
+    pub fn new() -> Self {
+        Self {
+            processes: Mutex::new(Vec::new()),
+            next_id: AtomicUsize::new(1),
+        }
+    }
+
+    /// Start a command detached in its own process group
+    pub fn spawn(&self, params: &BashBackgroundParams) -> Result<Arc<BackgroundProcess>, std::io::Error> {
+        let mut cmd = Command::new("bash");
+        cmd.args(["-c", &params.command]);
+        if let Some(working_dir) = &params.working_dir {
+            cmd.current_dir(working_dir);
+        }
+        for (key, value) in &params.env {
+            cmd.env(key, value);
+        }
+        cmd.stdin(Stdio::null())
+           .stdout(Stdio::piped())
+           .stderr(Stdio::piped())
+           .kill_on_drop(true);
+        #[cfg(unix)]
+        cmd.process_group(0);
+
+        let mut child = cmd.spawn()?;
+        let process = Arc::new(BackgroundProcess {
+            id: format!("p{}", self.next_id.fetch_add(1, Ordering::SeqCst)),
+            command: params.command.clone(),
+            pid: child.id(),
+            started_at: Utc::now(),
+            output: Mutex::new(OutputBuffer {
+                buffer: AllocRingBuffer::new(OUTPUT_CAPACITY),
+                written: 0,
+                read: 0,
+            }),
+            status: Mutex::new(ProcessStatus::Running),
+            group: Mutex::new(child.id()),
+            changed: Notify::new(),
+        });
+
+        let stdout = collect(child.stdout.take(), process.clone());
+        let stderr = collect(child.stderr.take(), process.clone());
+        let waiter = process.clone();
+        tokio::spawn(async move {
+            let code = match child.wait().await {
+                Ok(status) => status.code().unwrap_or(-1),
+                Err(_) => -1,
+            };
+            // the exit is reported once the output was read
+            let _ = tokio::time::timeout(Duration::from_millis(500), async {
+                let _ = stdout.await;
+                let _ = stderr.await;
+            }).await;
+            waiter.exited(code);
+
+            // the leader is reaped, watch what it left behind in the group until it is gone
+            while !waiter.check_group() {
+                tokio::time::sleep(Duration::from_millis(500)).await;
+            }
+        });
+
+        self.processes.lock().unwrap().push(process.clone());
+        Ok(process)
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<BackgroundProcess>> {
+        self.processes.lock().unwrap().iter().find(|p| p.id == id).cloned()
+    }
+
+    /// Every process started so far, in start order
+    pub fn list(&self) -> Vec<Arc<BackgroundProcess>> {
+        self.processes.lock().unwrap().clone()
+    }
+
+    /// Kill every process still running
+    pub fn kill_all(&self) {
+        for process in self.processes.lock().unwrap().iter() {
+            process.kill();
+        }
+    }
+}
+
+impl Default for ProcessManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for ProcessManager {
+    fn drop(&mut self) {
+        self.kill_all();
+    }
+}
+
+fn collect<R: AsyncRead + Unpin + Send + 'static>(reader: Option<R>, process: Arc<BackgroundProcess>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let Some(mut reader) = reader else { return };
+        let mut chunk = [0u8; 8192];
+        while let Ok(n) = reader.read(&mut chunk).await {
+            if n == 0 {
+                break;
+            }
+            process.append(&chunk[..n]);
+        }
+    })
+}
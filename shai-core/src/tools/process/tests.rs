@@ -0,0 +1,128 @@
+use super::structs::{BashBackgroundParams, ProcessKillParams, ProcessManager, ProcessOutputParams, ProcessStatus, OUTPUT_CAPACITY};
+use super::process::{BashBackgroundTool, ProcessKillTool, ProcessListTool, ProcessOutputTool};
+use crate::tools::{Tool, ToolCapability, ToolEmptyParams, ToolResult};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn background(command: &str) -> BashBackgroundParams {
+    BashBackgroundParams {
+        command: command.to_string(),
+        working_dir: None,
+        env: HashMap::new(),
+    }
+}
+
+fn poll(id: &str, wait: Option<u32>) -> ProcessOutputParams {
+    ProcessOutputParams {
+        id: id.to_string(),
+        wait,
+    }
+}
+
+#[test]
+fn test_process_tools_capabilities() {
+    let processes = Arc::new(ProcessManager::new());
+    assert_eq!(BashBackgroundTool::new(processes.clone()).capabilities(), &[ToolCapability::Read, ToolCapability::Write, ToolCapability::Network]);
+    assert_eq!(ProcessOutputTool::new(processes.clone()).capabilities(), &[ToolCapability::Read]);
+    assert_eq!(ProcessKillTool::new(processes.clone()).capabilities(), &[ToolCapability::Read]);
+    assert_eq!(ProcessListTool::new(processes).capabilities(), &[ToolCapability::Read]);
+}
+
+#[tokio::test]
+async fn test_background_output_is_incremental() {
+    let processes = Arc::new(ProcessManager::new());
+    let start = BashBackgroundTool::new(processes.clone());
+    let output = ProcessOutputTool::new(processes.clone());
+
+    let result = Tool::execute(&start, background("echo first; sleep 0.5; echo second; sleep 30")).await;
+    assert!(result.is_success());
+    let id = processes.list()[0].id.clone();
+
+    let first = Tool::execute(&output, poll(&id, Some(5))).await.to_string();
+    assert!(first.contains("first"), "{}", first);
+    assert!(!first.contains("second"), "{}", first);
+    assert!(first.contains("running"));
+
+    let second = Tool::execute(&output, poll(&id, Some(5))).await.to_string();
+    assert!(second.contains("second"), "{}", second);
+    assert!(!second.contains("first"), "{}", second);
+
+    let kill = ProcessKillTool::new(processes.clone());
+    assert!(Tool::execute(&kill, ProcessKillParams { id: id.clone() }).await.is_success());
+    assert_eq!(processes.get(&id).unwrap().status(), ProcessStatus::Killed);
+}
+
+#[tokio::test]
+async fn test_background_exit_status() {
+    let processes = Arc::new(ProcessManager::new());
+    let process = processes.spawn(&background("echo done; exit 3")).unwrap();
+    let result = Tool::execute(&ProcessOutputTool::new(processes.clone()), poll(&process.id, Some(5))).await;
+    assert!(result.to_string().contains("done"));
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while process.status() == ProcessStatus::Running {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }).await.unwrap();
+    assert_eq!(process.status(), ProcessStatus::Exited(3));
+
+    let list = Tool::execute(&ProcessListTool::new(processes), ToolEmptyParams { _unused: None }).await.to_string();
+    assert!(list.contains(&process.id));
+    assert!(list.contains("exited with code 3"));
+}
+
+#[tokio::test]
+async fn test_background_output_ring_buffer() {
+    let processes = Arc::new(ProcessManager::new());
+    let process = processes.spawn(&background(&format!("head -c {} /dev/zero | tr '\\0' 'x'", OUTPUT_CAPACITY * 2))).unwrap();
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while process.status() == ProcessStatus::Running {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }).await.unwrap();
+
+    let chunk = process.take_output();
+    assert_eq!(chunk.text.len(), OUTPUT_CAPACITY);
+    assert_eq!(chunk.dropped, OUTPUT_CAPACITY);
+    assert!(process.take_output().text.is_empty());
+}
+
+#[tokio::test]
+async fn test_processes_killed_on_drop() {
+    let dir = tempfile::tempdir().unwrap();
+    let marker = dir.path().join("marker");
+    let processes = ProcessManager::new();
+    processes.spawn(&background(&format!("sleep 1 && touch \"{}\"", marker.display()))).unwrap();
+    drop(processes);
+
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert!(!marker.exists(), "background process survived the manager");
+}
+
+#[tokio::test]
+async fn test_leftover_children_killed_after_exit() {
+    let dir = tempfile::tempdir().unwrap();
+    let marker = dir.path().join("marker");
+    let processes = ProcessManager::new();
+    let process = processes.spawn(&background(&format!("(sleep 2 && touch \"{}\") > /dev/null 2>&1 &", marker.display()))).unwrap();
+
+    // the command itself exits at once, its child stays in the process group
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while process.status() == ProcessStatus::Running {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }).await.unwrap();
+    drop(processes);
+
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    assert!(!marker.exists(), "background child survived its exited parent");
+}
+
+#[tokio::test]
+async fn test_unknown_process() {
+    let processes = Arc::new(ProcessManager::new());
+    let result = Tool::execute(&ProcessOutputTool::new(processes), poll("p42", None)).await;
+    assert!(matches!(result, ToolResult::Error { .. }));
+}
@@ -0,0 +1,170 @@
+use super::structs::{SearchBackendConfig, SearchResult};
+use async_trait::async_trait;
+use reqwest::Url;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Debug, thiserror::Error)]
+pub enum SearchError {
+    #[error("invalid search url: {0}")]
+    InvalidUrl(String),
+
+    #[error("search request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("search engine answered with status {0}")]
+    Status(reqwest::StatusCode),
+
+    #[error("unexpected search response: {0}")]
+    InvalidResponse(String),
+}
+
+/// A search engine queried by the web_search tool
+#[async_trait]
+pub trait SearchBackend: Send + Sync {
+    /// Name shown to the model and in the metadata
+    fn name(&self) -> &str;
+
+    /// Results of the query, best first
+    async fn search(&self, query: &str, max_results: usize) -> Result<Vec<SearchResult>, SearchError>;
+}
+
+impl SearchBackendConfig {
+    pub fn build(&self) -> Box<dyn SearchBackend> {
+        match self.clone() {
+            SearchBackendConfig::Searxng { url } => Box::new(SearxngBackend::new(url)),
+            SearchBackendConfig::Json { url, headers, results, title, link, snippet } => Box::new(JsonHttpBackend {
+                url,
+                headers,
+                results,
+                title,
+                link,
+                snippet,
+            }),
+        }
+    }
+}
+
+async fn get_json(url: Url, headers: &HashMap<String, String>) -> Result<Value, SearchError> {
+    let client = reqwest::Client::builder()
+        .timeout(SEARCH_TIMEOUT)
+        .build()?;
+    let mut request = client.get(url).header(reqwest::header::ACCEPT, "application/json");
+    for (key, value) in headers {
+        request = request.header(key, expand_vars(value, |name| std::env::var(name).ok()));
+    }
+
+    let response = request.send().await?;
+    if !response.status().is_success() {
+        return Err(SearchError::Status(response.status()));
+    }
+    let body = response.text().await?;
+    serde_json::from_str(&body).map_err(|e| SearchError::InvalidResponse(e.to_string()))
+}
+
+/// Replace `${VAR}` by the value `lookup` gives for VAR, the environment for headers so that api keys stay out of the config
+pub(super) fn expand_vars(value: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(end) = rest[start..].find('}') else { break };
+        out.push_str(&rest[..start]);
+        out.push_str(&lookup(&rest[start + 2..start + end]).unwrap_or_default());
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Percent-encode everything but the unreserved characters of RFC 3986
+fn percent_encode(value: &str) -> String {
+    value.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn text_at(item: &Value, pointer: &str) -> String {
+    match item.pointer(pointer) {
+        Some(Value::String(s)) => s.trim().to_string(),
+        Some(Value::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// Results of a json array, items without an url are skipped
+fn collect_results(items: &[Value], title: &str, link: &str, snippet: &str, max_results: usize) -> Vec<SearchResult> {
+    items.iter()
+        .map(|item| SearchResult {
+            title: text_at(item, title),
+            url: text_at(item, link),
+            snippet: text_at(item, snippet),
+        })
+        .filter(|result| !result.url.is_empty())
+        .take(max_results)
+        .collect()
+}
+
+/// SearXNG metasearch instance, its json output must be enabled in settings.yml
+pub struct SearxngBackend {
+    url: String,
+}
+
+impl SearxngBackend {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into() }
+    }
+}
+
+#[async_trait]
+impl SearchBackend for SearxngBackend {
+    fn name(&self) -> &str {
+        "searxng"
+    }
+
+    async fn search(&self, query: &str, max_results: usize) -> Result<Vec<SearchResult>, SearchError> {
+        let endpoint = format!("{}/search", self.url.trim_end_matches('/'));
+        let url = Url::parse_with_params(&endpoint, &[("q", query), ("format", "json")])
+            .map_err(|e| SearchError::InvalidUrl(format!("{}: {}", endpoint, e)))?;
+
+        let response = get_json(url, &HashMap::new()).await?;
+        let items = response.get("results")
+            .and_then(Value::as_array)
+            .ok_or_else(|| SearchError::InvalidResponse("no results array".to_string()))?;
+        // searxng already sorts its results by score
+        Ok(collect_results(items, "/title", "/url", "/content", max_results))
+    }
+}
+
+/// Generic search API answering a GET request with json, mapped with JSON pointers
+pub struct JsonHttpBackend {
+    pub url: String,
+    pub headers: HashMap<String, String>,
+    pub results: String,
+    pub title: String,
+    pub link: String,
+    pub snippet: String,
+}
+
+#[async_trait]
+impl SearchBackend for JsonHttpBackend {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    async fn search(&self, query: &str, max_results: usize) -> Result<Vec<SearchResult>, SearchError> {
+        let raw = self.url.replace("{query}", &percent_encode(query));
+        let url = Url::parse(&raw).map_err(|e| SearchError::InvalidUrl(format!("{}: {}", raw, e)))?;
+
+        let response = get_json(url, &self.headers).await?;
+        let items = response.pointer(&self.results)
+            .and_then(Value::as_array)
+            .ok_or_else(|| SearchError::InvalidResponse(format!("no results array at {}", self.results)))?;
+        Ok(collect_results(items, &self.title, &self.link, &self.snippet, max_results))
+    }
+}
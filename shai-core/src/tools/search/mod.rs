@@ -0,0 +1,10 @@
+pub mod structs;
+pub mod backend;
+pub mod search;
+
+#[cfg(test)]
+mod tests;
+
+pub use structs::{SearchConfig, SearchBackendConfig, SearchResult, WebSearchParams};
+pub use backend::{SearchBackend, SearchError, SearxngBackend, JsonHttpBackend};
+pub use search::WebSearchTool;
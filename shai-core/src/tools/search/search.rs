@@ -0,0 +1,96 @@
+use super::backend::SearchBackend;
+use super::structs::{SearchConfig, SearchResult, WebSearchParams};
+use crate::config::config::ShaiConfig;
+use crate::tools::{tool, ToolResult, TrustLevel};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Most results a single search may return
+const MAX_RESULTS: usize = 20;
+
+fn format_results(results: &[SearchResult]) -> String {
+    results.iter()
+        .enumerate()
+        .map(|(i, result)| {
+            let mut entry = format!("{}. {}\n   {}", i + 1, if result.title.is_empty() { "(no title)" } else { &result.title }, result.url);
+            if !result.snippet.is_empty() {
+                entry.push_str(&format!("\n   {}", result.snippet.split_whitespace().collect::<Vec<_>>().join(" ")));
+            }
+            entry
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[derive(Clone)]
+pub struct WebSearchTool {
+    backend: Option<Arc<dyn SearchBackend>>,
+    max_results: usize,
+}
+
+#[tool(name = "web_search", description = r#"
+Searches the web and returns a ranked list of results with their title, URL and a short snippet.
+
+Usage Guidelines:
+- Use it to find documentation, error messages, release notes or any page whose URL you do not know yet.
+- Then use the fetch tool on the most relevant URLs to read them, snippets are often incomplete or outdated.
+- Keep queries short and specific, e.g. the exact error message or the crate name and version.
+"#, capabilities = [ToolCapability::Network])]
+impl WebSearchTool {
+    pub fn new(backend: Arc<dyn SearchBackend>) -> Self {
+        Self { backend: Some(backend), max_results: SearchConfig::default().max_results }
+    }
+
+    /// Tool using the backend of the config, it reports an error on every call if there is none
+    pub fn from_config(config: &SearchConfig) -> Self {
+        Self {
+            backend: config.backend.as_ref().map(|backend| Arc::from(backend.build())),
+            max_results: config.max_results,
+        }
+    }
+
+    /// Tool configured from ~/.shai.config
+    pub fn load() -> Self {
+        let config = ShaiConfig::load()
+            .map(|config| config.search)
+            .unwrap_or_default();
+        Self::from_config(&config)
+    }
+
+    /// Whether a search backend is set, the tool is only worth offering then
+    pub fn is_configured(&self) -> bool {
+        self.backend.is_some()
+    }
+
+    async fn execute(&self, params: WebSearchParams) -> ToolResult {
+        let Some(backend) = &self.backend else {
+            return ToolResult::error("Web search is not configured, a search backend must be set in the `search` section of ~/.shai.config".to_string());
+        };
+        if params.query.trim().is_empty() {
+            return ToolResult::error("Query cannot be empty".to_string());
+        }
+
+        let max_results = params.max_results.unwrap_or(self.max_results).clamp(1, MAX_RESULTS);
+        let results = match backend.search(params.query.trim(), max_results).await {
+            Ok(results) => results,
+            Err(e) => return ToolResult::error(format!("Web search failed: {}", e)),
+        };
+
+        let mut metadata = HashMap::new();
+        metadata.insert("query".to_string(), json!(params.query));
+        metadata.insert("backend".to_string(), json!(backend.name()));
+        metadata.insert("count".to_string(), json!(results.len()));
+        metadata.insert("results".to_string(), json!(results));
+
+        let output = if results.is_empty() {
+            format!("No results for \"{}\"", params.query.trim())
+        } else {
+            format_results(&results)
+        };
+        ToolResult::Success {
+            output,
+            metadata: Some(metadata),
+        }.with_trust(TrustLevel::Untrusted)
+    }
+}
@@ -0,0 +1,84 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct WebSearchParams {
+    /// The search query
+    pub query: String,
+    /// Maximum number of results to return (optional, defaults to the configured value)
+    pub max_results: Option<usize>,
+}
+
+/// A search hit, results are returned in rank order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+}
+
+/// Web search settings, stored in the `search` section of ~/.shai.config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    /// Search engine queried by web_search, the tool is unavailable without one
+    pub backend: Option<SearchBackendConfig>,
+    /// Results returned when the model does not ask for a number
+    pub max_results: usize,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            backend: None,
+            max_results: 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SearchBackendConfig {
+    /// A SearXNG instance with the json format enabled
+    Searxng {
+        /// Base url of the instance, e.g. `http://localhost:8888`
+        url: String,
+    },
+    /// Any search API answering a GET request with json
+    Json {
+        /// Request url, `{query}` is replaced by the url-encoded query
+        url: String,
+        /// Headers of the request, `${VAR}` is replaced by the environment variable VAR
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        /// JSON pointer to the array of results
+        #[serde(default = "default_results_pointer")]
+        results: String,
+        /// JSON pointer to the title within a result
+        #[serde(default = "default_title_pointer")]
+        title: String,
+        /// JSON pointer to the url within a result
+        #[serde(default = "default_url_pointer")]
+        link: String,
+        /// JSON pointer to the snippet within a result
+        #[serde(default = "default_snippet_pointer")]
+        snippet: String,
+    },
+}
+
+fn default_results_pointer() -> String {
+    "/results".to_string()
+}
+
+fn default_title_pointer() -> String {
+    "/title".to_string()
+}
+
+fn default_url_pointer() -> String {
+    "/url".to_string()
+}
+
+fn default_snippet_pointer() -> String {
+    "/snippet".to_string()
+}
@@ -0,0 +1,150 @@
+use super::backend::{expand_vars, JsonHttpBackend, SearxngBackend};
+use super::search::WebSearchTool;
+use super::structs::{SearchBackendConfig, SearchConfig, WebSearchParams};
+use crate::tools::{Tool, ToolCapability, ToolResult, TrustLevel};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+/// Answer a single request with a json body, the handle returns the head of the request
+async fn stub_server(status: &'static str, body: &'static str) -> (String, JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let handle = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buffer = [0u8; 1024];
+        while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+            let n = socket.read(&mut buffer).await.unwrap();
+            if n == 0 {
+                break;
+            }
+            request.extend_from_slice(&buffer[..n]);
+        }
+        let response = format!("HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body);
+        socket.write_all(response.as_bytes()).await.unwrap();
+        String::from_utf8_lossy(&request).to_string()
+    });
+    (url, handle)
+}
+
+fn search(query: &str, max_results: Option<usize>) -> WebSearchParams {
+    WebSearchParams {
+        query: query.to_string(),
+        max_results,
+    }
+}
+
+const SEARXNG: &str = r#"{"query": "tokio", "results": [
+    {"title": "Tokio", "url": "https://tokio.rs/", "content": "An asynchronous   runtime\nfor Rust", "score": 9.0},
+    {"title": "tokio - crates.io", "url": "https://crates.io/crates/tokio", "content": "", "score": 4.0},
+    {"title": "no url", "content": "skipped"},
+    {"title": "docs.rs", "url": "https://docs.rs/tokio", "content": "API docs", "score": 1.0}
+]}"#;
+
+#[test]
+fn test_web_search_capabilities() {
+    let tool = WebSearchTool::from_config(&SearchConfig::default());
+    assert_eq!(tool.capabilities(), &[ToolCapability::Network]);
+}
+
+#[tokio::test]
+async fn test_searxng_backend() {
+    let (url, request) = stub_server("200 OK", SEARXNG).await;
+    let tool = WebSearchTool::new(Arc::new(SearxngBackend::new(format!("{}/", url))));
+
+    let result = Tool::execute(&tool, search("tokio runtime", Some(2))).await;
+    assert_eq!(result.trust(), TrustLevel::Untrusted);
+    let ToolResult::Success { output, metadata } = result else {
+        panic!("Expected success result");
+    };
+    assert_eq!(output, "1. Tokio\n   https://tokio.rs/\n   An asynchronous runtime for Rust\n\n2. tokio - crates.io\n   https://crates.io/crates/tokio");
+    assert_eq!(metadata.unwrap()["count"], serde_json::json!(2));
+
+    let request = request.await.unwrap();
+    assert!(request.starts_with("GET /search?q=tokio+runtime&format=json "), "{}", request);
+}
+
+#[tokio::test]
+async fn test_json_backend_with_pointers() {
+    let body = r#"{"web": {"results": [{"name": "Serde", "link": {"href": "https://serde.rs"}, "description": "Serialization"}]}}"#;
+    let (url, request) = stub_server("200 OK", body).await;
+    let config = SearchConfig {
+        backend: Some(SearchBackendConfig::Json {
+            url: format!("{}/api?query={{query}}&n=5", url),
+            headers: HashMap::from([("X-Api-Key".to_string(), "secret".to_string())]),
+            results: "/web/results".to_string(),
+            title: "/name".to_string(),
+            link: "/link/href".to_string(),
+            snippet: "/description".to_string(),
+        }),
+        ..Default::default()
+    };
+
+    let output = Tool::execute(&WebSearchTool::from_config(&config), search("serde & json", None)).await.to_string();
+    assert_eq!(output, "1. Serde\n   https://serde.rs\n   Serialization");
+
+    let request = request.await.unwrap().to_lowercase();
+    assert!(request.starts_with("get /api?query=serde%20%26%20json&n=5 "), "{}", request);
+    assert!(request.contains("x-api-key: secret"), "{}", request);
+}
+
+#[test]
+fn test_header_variables_are_expanded() {
+    let lookup = |name: &str| (name == "SEARCH_KEY").then(|| "secret".to_string());
+    assert_eq!(expand_vars("Bearer ${SEARCH_KEY}", lookup), "Bearer secret");
+    assert_eq!(expand_vars("${UNSET}-${SEARCH_KEY}", lookup), "-secret");
+    assert_eq!(expand_vars("no ${closing", lookup), "no ${closing");
+}
+
+#[test]
+fn test_web_search_only_configured_with_a_backend() {
+    assert!(!WebSearchTool::from_config(&SearchConfig::default()).is_configured());
+    let config = SearchConfig {
+        backend: Some(SearchBackendConfig::Searxng { url: "http://localhost:8888".to_string() }),
+        ..Default::default()
+    };
+    assert!(WebSearchTool::from_config(&config).is_configured());
+}
+
+#[tokio::test]
+async fn test_search_errors() {
+    let result = Tool::execute(&WebSearchTool::from_config(&SearchConfig::default()), search("anything", None)).await;
+    assert!(result.to_string().contains("not configured"));
+
+    let (url, _request) = stub_server("503 Service Unavailable", "{}").await;
+    let tool = WebSearchTool::new(Arc::new(SearxngBackend::new(url)));
+    let result = Tool::execute(&tool, search("anything", None)).await;
+    assert!(matches!(result, ToolResult::Error { .. }));
+    assert!(result.to_string().contains("503"), "{}", result);
+}
+
+#[tokio::test]
+async fn test_no_results() {
+    let (url, _request) = stub_server("200 OK", r#"{"results": []}"#).await;
+    let tool = WebSearchTool::new(Arc::new(JsonHttpBackend {
+        url: format!("{}/?q={{query}}", url),
+        headers: HashMap::new(),
+        results: "/results".to_string(),
+        title: "/title".to_string(),
+        link: "/url".to_string(),
+        snippet: "/snippet".to_string(),
+    }));
+    assert_eq!(Tool::execute(&tool, search("nothing", None)).await.to_string(), "No results for \"nothing\"");
+}
+
+#[test]
+fn test_search_config_parsing() {
+    let config: SearchConfig = serde_json::from_str(r#"{"backend": {"type": "searxng", "url": "http://localhost:8888"}}"#).unwrap();
+    assert_eq!(config.backend, Some(SearchBackendConfig::Searxng { url: "http://localhost:8888".to_string() }));
+    assert_eq!(config.max_results, 8);
+
+    let config: SearchConfig = serde_json::from_str(r#"{"backend": {"type": "json", "url": "https://api.example.com/?q={query}"}, "max_results": 3}"#).unwrap();
+    let Some(SearchBackendConfig::Json { results, link, .. }) = config.backend else {
+        panic!("Expected json backend");
+    };
+    assert_eq!((results.as_str(), link.as_str()), ("/results", "/url"));
+    assert_eq!(config.max_results, 3);
+}
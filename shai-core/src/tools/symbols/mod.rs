@@ -0,0 +1,14 @@
+pub mod structs;
+pub mod parser;
+pub mod symbols;
+pub mod pattern;
+pub mod structural;
+
+#[cfg(test)]
+mod tests;
+
+pub use structs::{SymbolsToolParams, SymbolsAction, StructuralSearchToolParams, StructuralReplaceToolParams};
+pub use parser::{Symbol, SymbolKind, SymbolLanguage, Reference, parse_symbols, find_references};
+pub use symbols::SymbolsTool;
+pub use pattern::{Pattern, PatternMatch};
+pub use structural::{StructuralSearchTool, StructuralReplaceTool};
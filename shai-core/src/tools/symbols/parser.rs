@@ -0,0 +1,318 @@
+use crate::tools::highlight::language_for_path;
+use std::path::Path;
+use tree_sitter::{Language, Node, Parser, Tree};
+
+/// Longest signature shown, whitespace collapsed
+const MAX_SIGNATURE_CHARS: usize = 160;
+
+/// Languages with a grammar, detected the same way as for highlighting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolLanguage {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Tsx,
+    Go,
+    Java,
+}
+
+impl SymbolLanguage {
+    pub const SUPPORTED: &'static str = "Rust, Python, JavaScript, TypeScript, Go and Java";
+
+    pub fn detect(path: &Path) -> Option<Self> {
+        let tsx = path.extension().is_some_and(|ext| ext == "tsx");
+        match language_for_path(&path.to_string_lossy())? {
+            "rust" => Some(Self::Rust),
+            "python" => Some(Self::Python),
+            "javascript" => Some(Self::JavaScript),
+            "typescript" if tsx => Some(Self::Tsx),
+            "typescript" => Some(Self::TypeScript),
+            "go" => Some(Self::Go),
+            "java" => Some(Self::Java),
+            _ => None,
+        }
+    }
+
+    /// Language given by name or by extension, e.g. `typescript` or `ts`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "rust" | "rs" => Some(Self::Rust),
+            "python" | "py" => Some(Self::Python),
+            "javascript" | "js" | "jsx" => Some(Self::JavaScript),
+            "typescript" | "ts" => Some(Self::TypeScript),
+            "tsx" => Some(Self::Tsx),
+            "go" | "golang" => Some(Self::Go),
+            "java" => Some(Self::Java),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Rust => "rust",
+            Self::Python => "python",
+            Self::JavaScript => "javascript",
+            Self::TypeScript | Self::Tsx => "typescript",
+            Self::Go => "go",
+            Self::Java => "java",
+        }
+    }
+
+    fn grammar(&self) -> Language {
+        match self {
+            Self::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Self::Python => tree_sitter_python::LANGUAGE.into(),
+            Self::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+            Self::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Self::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Self::Go => tree_sitter_go::LANGUAGE.into(),
+            Self::Java => tree_sitter_java::LANGUAGE.into(),
+        }
+    }
+
+    fn is_js_like(&self) -> bool {
+        matches!(self, Self::JavaScript | Self::TypeScript | Self::Tsx)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Method,
+    Class,
+    Struct,
+    Enum,
+    Interface,
+    Trait,
+    Impl,
+    Type,
+    Constant,
+    Module,
+    Macro,
+}
+
+impl SymbolKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Function => "function",
+            Self::Method => "method",
+            Self::Class => "class",
+            Self::Struct => "struct",
+            Self::Enum => "enum",
+            Self::Interface => "interface",
+            Self::Trait => "trait",
+            Self::Impl => "impl",
+            Self::Type => "type",
+            Self::Constant => "constant",
+            Self::Module => "module",
+            Self::Macro => "macro",
+        }
+    }
+
+    /// Functions declared inside these are methods of them
+    fn is_container(&self) -> bool {
+        matches!(self, Self::Class | Self::Enum | Self::Interface | Self::Trait | Self::Impl)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Class, trait or type the symbol is declared in
+    pub container: Option<String>,
+    /// Nesting level in the outline
+    pub depth: usize,
+    /// 1-based, inclusive
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Declaration up to its body, on one line
+    pub signature: String,
+}
+
+/// A use of a name in code, comments and strings are not searched
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    /// 1-based
+    pub line: usize,
+    /// 1-based, in bytes
+    pub column: usize,
+    pub line_text: String,
+    /// The name is declared here rather than used
+    pub definition: bool,
+}
+
+pub(crate) fn parse(source: &str, language: SymbolLanguage) -> Result<Tree, String> {
+    let mut parser = Parser::new();
+    parser.set_language(&language.grammar())
+        .map_err(|e| format!("Cannot load the {} grammar: {}", language.name(), e))?;
+    parser.parse(source, None)
+        .ok_or_else(|| format!("Cannot parse {} source", language.name()))
+}
+
+pub(crate) fn text<'a>(node: Node, source: &'a str) -> &'a str {
+    node.utf8_text(source.as_bytes()).unwrap_or_default()
+}
+
+/// Type an impl block is for, without path nor generics: `impl<T> fmt::Display for a::Foo<T>` is `Foo`
+fn base_type(type_text: &str) -> String {
+    let without_generics = type_text.split('<').next().unwrap_or(type_text);
+    without_generics.rsplit("::").next().unwrap_or(without_generics).trim().trim_start_matches('&').to_string()
+}
+
+/// Declaration text before the body, falls back to the first line
+fn signature(node: Node, source: &str) -> String {
+    let body = node.child_by_field_name("body")
+        .or_else(|| node.child_by_field_name("value").and_then(|value| value.child_by_field_name("body")));
+    let head = match body {
+        Some(body) if body.start_byte() > node.start_byte() => &source[node.start_byte()..body.start_byte()],
+        _ => text(node, source).lines().next().unwrap_or_default(),
+    };
+    let collapsed = head.split_whitespace().collect::<Vec<_>>().join(" ");
+    let trimmed = collapsed.trim_end_matches(['{', ':', '=', ' ']).trim_end_matches(" =>");
+    if trimmed.chars().count() > MAX_SIGNATURE_CHARS {
+        format!("{}...", trimmed.chars().take(MAX_SIGNATURE_CHARS).collect::<String>())
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// First type name in a Go method receiver: `(s *Server)` is `Server`
+fn receiver_type(node: Node, source: &str) -> Option<String> {
+    if node.kind() == "type_identifier" {
+        return Some(text(node, source).to_string());
+    }
+    let mut cursor = node.walk();
+    let children: Vec<Node> = node.named_children(&mut cursor).collect();
+    children.into_iter().find_map(|child| receiver_type(child, source))
+}
+
+/// Kind and name of the symbol a node declares, None for any other node
+fn declaration(node: Node, source: &str, language: SymbolLanguage) -> Option<(SymbolKind, String, Option<String>)> {
+    use SymbolKind::*;
+    use SymbolLanguage as L;
+
+    let name = || node.child_by_field_name("name").map(|name| text(name, source).to_string());
+    let kind = match (language, node.kind()) {
+        (L::Rust, "function_item" | "function_signature_item") => Function,
+        (L::Rust, "struct_item" | "union_item") => Struct,
+        (L::Rust, "enum_item") => Enum,
+        (L::Rust, "trait_item") => Trait,
+        (L::Rust, "type_item") => Type,
+        (L::Rust, "const_item" | "static_item") => Constant,
+        (L::Rust, "mod_item") => Module,
+        (L::Rust, "macro_definition") => Macro,
+        (L::Rust, "impl_item") => {
+            let for_type = base_type(text(node.child_by_field_name("type")?, source));
+            return Some((Impl, for_type, None));
+        }
+
+        (L::Python, "function_definition") => Function,
+        (L::Python, "class_definition") => Class,
+
+        (l, "function_declaration" | "generator_function_declaration" | "function_signature") if l.is_js_like() => Function,
+        (l, "class_declaration" | "abstract_class_declaration") if l.is_js_like() => Class,
+        (l, "method_definition" | "method_signature" | "abstract_method_signature") if l.is_js_like() => Method,
+        (l, "interface_declaration") if l.is_js_like() => Interface,
+        (l, "type_alias_declaration") if l.is_js_like() => Type,
+        (l, "enum_declaration") if l.is_js_like() => Enum,
+        (l, "internal_module") if l.is_js_like() => Module,
+        (l, "variable_declarator") if l.is_js_like() => {
+            let value = node.child_by_field_name("value")?;
+            if !matches!(value.kind(), "arrow_function" | "function_expression" | "function" | "generator_function") {
+                return None;
+            }
+            Function
+        }
+
+        (L::Go, "function_declaration") => Function,
+        (L::Go, "method_declaration") => {
+            let receiver = node.child_by_field_name("receiver").and_then(|receiver| receiver_type(receiver, source));
+            return Some((Method, name()?, receiver));
+        }
+        (L::Go, "type_spec" | "type_alias") => match node.child_by_field_name("type").map(|t| t.kind()) {
+            Some("struct_type") => Struct,
+            Some("interface_type") => Interface,
+            _ => Type,
+        },
+
+        (L::Java, "class_declaration" | "record_declaration") => Class,
+        (L::Java, "interface_declaration" | "annotation_type_declaration") => Interface,
+        (L::Java, "enum_declaration") => Enum,
+        (L::Java, "method_declaration" | "constructor_declaration") => Method,
+
+        _ => return None,
+    };
+    Some((kind, name()?, None))
+}
+
+fn collect(node: Node, source: &str, language: SymbolLanguage, container: Option<&str>, depth: usize, symbols: &mut Vec<Symbol>) {
+    let mut cursor = node.walk();
+    let children: Vec<Node> = node.named_children(&mut cursor).collect();
+    for child in children {
+        let Some((kind, name, own_container)) = declaration(child, source, language) else {
+            collect(child, source, language, container, depth, symbols);
+            continue;
+        };
+
+        let container = own_container.or_else(|| container.map(str::to_string));
+        let kind = if kind == SymbolKind::Function && container.is_some() { SymbolKind::Method } else { kind };
+        let inner = kind.is_container().then(|| name.clone());
+        symbols.push(Symbol {
+            name,
+            kind,
+            container: container.clone(),
+            depth,
+            start_line: child.start_position().row + 1,
+            end_line: child.end_position().row + 1,
+            signature: signature(child, source),
+        });
+        collect(child, source, language, inner.as_deref(), depth + 1, symbols);
+    }
+}
+
+/// Declarations of a source file in the order they appear, nested ones follow their parent
+pub fn parse_symbols(source: &str, language: SymbolLanguage) -> Result<Vec<Symbol>, String> {
+    let tree = parse(source, language)?;
+    let mut symbols = Vec::new();
+    collect(tree.root_node(), source, language, None, 0, &mut symbols);
+    Ok(symbols)
+}
+
+/// Identifiers spelled exactly like name
+pub fn find_references(source: &str, language: SymbolLanguage, name: &str) -> Result<Vec<Reference>, String> {
+    let tree = parse(source, language)?;
+    let lines: Vec<&str> = source.lines().collect();
+    let mut references = Vec::new();
+
+    let mut cursor = tree.walk();
+    loop {
+        let node = cursor.node();
+        if node.child_count() == 0 && node.kind().ends_with("identifier") && text(node, source) == name {
+            let definition = node.parent()
+                .filter(|parent| declaration(*parent, source, language).is_some())
+                .and_then(|parent| parent.child_by_field_name("name"))
+                .is_some_and(|declared| declared.id() == node.id());
+            let position = node.start_position();
+            references.push(Reference {
+                line: position.row + 1,
+                column: position.column + 1,
+                line_text: lines.get(position.row).map(|line| line.trim().to_string()).unwrap_or_default(),
+                definition,
+            });
+        }
+
+        if cursor.goto_first_child() {
+            continue;
+        }
+        loop {
+            if cursor.goto_next_sibling() {
+                break;
+            }
+            if !cursor.goto_parent() {
+                return Ok(references);
+            }
+        }
+    }
+}
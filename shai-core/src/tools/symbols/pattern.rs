@@ -0,0 +1,249 @@
+use super::parser::{parse, text, SymbolLanguage};
+use regex::{Captures, Regex};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::OnceLock;
+use tree_sitter::{Node, Tree};
+
+/// Metavariables stand for code in patterns and rewrites: `$NAME` for one node, `$$$NAME` for any number of them
+fn metavariable_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"\$\$\$([A-Z_][A-Z0-9_]*)|\$([A-Z_][A-Z0-9_]*)").unwrap())
+}
+
+/// Metavariables are turned into identifiers so that the pattern parses as code
+const SINGLE_PREFIX: &str = "__shai_one_";
+const MULTI_PREFIX: &str = "__shai_many_";
+
+/// `$_` matches anything without capturing it
+const WILDCARD: &str = "_";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Metavariable<'a> {
+    Single(&'a str),
+    Multi(&'a str),
+}
+
+/// Code matched by a pattern
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternMatch {
+    pub start_byte: usize,
+    pub end_byte: usize,
+    /// 1-based, inclusive
+    pub start_line: usize,
+    pub end_line: usize,
+    pub text: String,
+    /// Code captured by each metavariable, `$$$` ones included
+    pub captures: BTreeMap<String, String>,
+}
+
+/// Code pattern with metavariables, compiled for one language
+pub struct Pattern {
+    source: String,
+    tree: Tree,
+    language: SymbolLanguage,
+}
+
+/// Names of the metavariables used in a pattern or a rewrite
+pub fn metavariables(code: &str) -> BTreeSet<String> {
+    metavariable_regex().captures_iter(code)
+        .filter_map(|captures| captures.get(1).or_else(|| captures.get(2)))
+        .map(|name| name.as_str().to_string())
+        .filter(|name| name != WILDCARD)
+        .collect()
+}
+
+/// Anonymous tokens that can be left out next to an empty `$$$` list: `f($A, $$$REST)` matches `f(x)`
+fn is_separator(node: Node) -> bool {
+    !node.is_named() && matches!(node.kind(), "," | ";")
+}
+
+/// Children compared when matching, comments and layout tokens are ignored
+fn significant_children(node: Node) -> Vec<Node> {
+    let mut cursor = node.walk();
+    node.children(&mut cursor)
+        .filter(|child| !child.kind().contains("comment"))
+        .filter(|child| child.is_named() || (child.end_byte() > child.start_byte() && child.kind() != "\n"))
+        .collect()
+}
+
+impl Pattern {
+    pub fn compile(pattern: &str, language: SymbolLanguage) -> Result<Self, String> {
+        let source = metavariable_regex().replace_all(pattern.trim(), |captures: &Captures| match (captures.get(1), captures.get(2)) {
+            (Some(name), _) => format!("{}{}", MULTI_PREFIX, name.as_str()),
+            (_, Some(name)) => format!("{}{}", SINGLE_PREFIX, name.as_str()),
+            _ => String::new(),
+        }).to_string();
+
+        // a lone expression is only a valid statement with its semicolon in some languages
+        let mut compiled = None;
+        for source in [source.clone(), format!("{};", source)] {
+            let tree = parse(&source, language)?;
+            if !tree.root_node().has_error() {
+                compiled = Some(Self { source, tree, language });
+                break;
+            }
+        }
+        let Some(compiled) = compiled else {
+            return Err(format!("The pattern is not valid {} code, write it as code would be written in a source file", language.name()));
+        };
+        if compiled.metavariable(compiled.root()).is_some() {
+            return Err("The pattern needs code around its metavariables".to_string());
+        }
+        Ok(compiled)
+    }
+
+    /// Node the pattern stands for, without the wrappers the parser puts around a lone expression
+    fn root(&self) -> Node<'_> {
+        let trimmed = |node: Node| text(node, &self.source).trim().trim_end_matches(';').trim_end().to_string();
+        let mut node = self.tree.root_node();
+        while node.named_child_count() == 1 {
+            let Some(child) = node.named_child(0) else { break };
+            if trimmed(child) != trimmed(node) {
+                break;
+            }
+            node = child;
+        }
+        node
+    }
+
+    fn metavariable(&self, node: Node) -> Option<Metavariable<'_>> {
+        let code = text(node, &self.source).trim();
+        let is_name = |name: &str| !name.is_empty() && name.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+        match (code.strip_prefix(MULTI_PREFIX), code.strip_prefix(SINGLE_PREFIX)) {
+            (Some(name), _) if is_name(name) => Some(Metavariable::Multi(name)),
+            (_, Some(name)) if is_name(name) => Some(Metavariable::Single(name)),
+            _ => None,
+        }
+    }
+
+    /// The same metavariable must capture the same code everywhere
+    fn bind(name: &str, code: &str, captures: &mut BTreeMap<String, String>) -> bool {
+        if name == WILDCARD {
+            return true;
+        }
+        match captures.get(name) {
+            Some(captured) => captured == code,
+            None => {
+                captures.insert(name.to_string(), code.to_string());
+                true
+            }
+        }
+    }
+
+    fn match_node(&self, pattern: Node, target: Node, source: &str, captures: &mut BTreeMap<String, String>) -> bool {
+        match self.metavariable(pattern) {
+            Some(Metavariable::Single(name) | Metavariable::Multi(name)) => return Self::bind(name, text(target, source), captures),
+            None => {}
+        }
+        if pattern.kind() != target.kind() {
+            return false;
+        }
+        let pattern_children = significant_children(pattern);
+        if pattern_children.is_empty() {
+            return text(pattern, &self.source) == text(target, source);
+        }
+        self.match_sequence(&pattern_children, &significant_children(target), source, captures)
+    }
+
+    fn match_sequence(&self, patterns: &[Node], targets: &[Node], source: &str, captures: &mut BTreeMap<String, String>) -> bool {
+        let Some((first, rest)) = patterns.split_first() else {
+            return targets.is_empty();
+        };
+
+        if let Some(Metavariable::Multi(name)) = self.metavariable(*first) {
+            for taken in 0..=targets.len() {
+                let code = match taken {
+                    0 => "",
+                    _ => &source[targets[0].start_byte()..targets[taken - 1].end_byte()],
+                };
+                let mut attempt = captures.clone();
+                if Self::bind(name, code, &mut attempt) {
+                    let matched = self.match_sequence(rest, &targets[taken..], source, &mut attempt)
+                        || (taken == 0 && rest.first().is_some_and(|next| is_separator(*next))
+                            && self.match_sequence(&rest[1..], targets, source, &mut attempt));
+                    if matched {
+                        *captures = attempt;
+                        return true;
+                    }
+                }
+            }
+            return false;
+        }
+
+        if let Some((target, remaining)) = targets.split_first() {
+            let mut attempt = captures.clone();
+            if self.match_node(*first, *target, source, &mut attempt) && self.match_sequence(rest, remaining, source, &mut attempt) {
+                *captures = attempt;
+                return true;
+            }
+        }
+        is_separator(*first)
+            && rest.first().is_some_and(|next| matches!(self.metavariable(*next), Some(Metavariable::Multi(_))))
+            && self.match_sequence(rest, targets, source, captures)
+    }
+
+    /// Every place the pattern matches, a match is not searched for nested matches
+    pub fn find_all(&self, source: &str) -> Result<Vec<PatternMatch>, String> {
+        let tree = parse(source, self.language)?;
+        let root = self.root();
+        let mut matches = Vec::new();
+
+        let mut cursor = tree.walk();
+        loop {
+            let node = cursor.node();
+            let mut captures = BTreeMap::new();
+            let matched = node.kind() == root.kind() && self.match_node(root, node, source, &mut captures);
+            if matched {
+                matches.push(PatternMatch {
+                    start_byte: node.start_byte(),
+                    end_byte: node.end_byte(),
+                    start_line: node.start_position().row + 1,
+                    end_line: node.end_position().row + 1,
+                    text: text(node, source).to_string(),
+                    captures,
+                });
+            }
+
+            if !matched && cursor.goto_first_child() {
+                continue;
+            }
+            loop {
+                if cursor.goto_next_sibling() {
+                    break;
+                }
+                if !cursor.goto_parent() {
+                    return Ok(matches);
+                }
+            }
+        }
+    }
+}
+
+/// Rewrite of a match, its lines after the first are indented like the matched code
+pub fn expand(template: &str, found: &PatternMatch, source: &str) -> String {
+    let code = metavariable_regex().replace_all(template, |captures: &Captures| {
+        let name = captures.get(1).or_else(|| captures.get(2)).map(|name| name.as_str()).unwrap_or_default();
+        found.captures.get(name).cloned().unwrap_or_default()
+    });
+
+    let line_start = source[..found.start_byte].rfind('\n').map_or(0, |index| index + 1);
+    let indent: String = source[line_start..found.start_byte].chars().take_while(|c| c.is_whitespace()).collect();
+    code.lines()
+        .enumerate()
+        .map(|(index, line)| if index == 0 || line.is_empty() { line.to_string() } else { format!("{}{}", indent, line) })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Source with every match replaced by its rewrite
+pub fn rewrite_all(source: &str, matches: &[PatternMatch], template: &str) -> String {
+    let mut rewritten = String::with_capacity(source.len());
+    let mut position = 0;
+    for found in matches {
+        rewritten.push_str(&source[position..found.start_byte]);
+        rewritten.push_str(&expand(template, found, source));
+        position = found.end_byte;
+    }
+    rewritten.push_str(&source[position..]);
+    rewritten
+}
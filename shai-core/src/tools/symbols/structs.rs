@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SymbolsToolParams {
+    /// outline (symbols of a file or directory), definition (where a symbol is defined) or references (where a name is used)
+    pub action: SymbolsAction,
+    /// File or directory to look in (defaults to current directory)
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Symbol to look for, required by definition and references. `Type::method` or `Type.method` narrows a definition to one type
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Maximum number of results to return
+    #[serde(default = "default_max_results")]
+    pub max_results: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+#[schemars(inline)]
+pub enum SymbolsAction {
+    Outline,
+    Definition,
+    References,
+}
+
+fn default_max_results() -> u32 { 200 }
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct StructuralSearchToolParams {
+    /// Code to look for, with `$NAME` matching any single expression, identifier or statement and `$$$NAME` any number of them (e.g. "$CLIENT.get($URL, $$$ARGS)")
+    pub pattern: String,
+    /// Language of the pattern (rust, python, javascript, typescript, go or java), guessed from the files searched when missing
+    #[serde(default)]
+    pub language: Option<String>,
+    /// File or directory to search in (defaults to current directory)
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Maximum number of matches to return
+    #[serde(default = "default_max_results")]
+    pub max_results: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct StructuralReplaceToolParams {
+    /// Code to look for, with `$NAME` and `$$$NAME` metavariables like in structural_search
+    pub pattern: String,
+    /// Code replacing every match, the metavariables of the pattern are replaced by what they matched (e.g. "$CLIENT.fetch($URL, $$$ARGS)")
+    pub rewrite: String,
+    /// Language of the pattern (rust, python, javascript, typescript, go or java), guessed from the files searched when missing
+    #[serde(default)]
+    pub language: Option<String>,
+    /// File or directory to rewrite (defaults to current directory)
+    #[serde(default)]
+    pub path: Option<String>,
+}
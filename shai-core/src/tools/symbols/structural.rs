@@ -0,0 +1,341 @@
+use super::parser::SymbolLanguage;
+use super::pattern::{metavariables, rewrite_all, Pattern, PatternMatch};
+use super::structs::{StructuralReplaceToolParams, StructuralSearchToolParams};
+use super::symbols::source_files;
+use crate::tools::fs::{EditTool, FsOperationLog, FsOperationType, FsTransaction};
+use crate::tools::{tool, ToolResult};
+use serde_json::json;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Longest capture shown in search results
+const MAX_CAPTURE_CHARS: usize = 100;
+
+/// Files a pattern is searched in, with the pattern compiled for each of their languages
+struct Search {
+    files: Vec<(PathBuf, SymbolLanguage)>,
+    patterns: HashMap<SymbolLanguage, Pattern>,
+}
+
+impl Search {
+    /// The pattern must be valid code in the language of at least one file
+    fn prepare(root: &Path, pattern: &str, language: Option<&str>) -> Result<Self, String> {
+        let wanted = match language {
+            Some(name) => Some(SymbolLanguage::from_name(name)
+                .ok_or_else(|| format!("Unsupported language: {}, patterns can be written in {}", name, SymbolLanguage::SUPPORTED))?),
+            None => None,
+        };
+        let mut files = source_files(root)?;
+        if let Some(wanted) = wanted {
+            files.retain(|(_, language)| language.name() == wanted.name());
+        }
+        if files.is_empty() {
+            return Err(format!("No {} file found in {}", wanted.map_or("source", |language| language.name()), root.display()));
+        }
+
+        let mut patterns = HashMap::new();
+        let mut errors: Vec<String> = Vec::new();
+        for (_, language) in &files {
+            if patterns.contains_key(language) {
+                continue;
+            }
+            match Pattern::compile(pattern, *language) {
+                Ok(compiled) => {
+                    patterns.insert(*language, compiled);
+                }
+                Err(e) if !errors.contains(&e) => errors.push(e),
+                Err(_) => {}
+            }
+        }
+        if patterns.is_empty() {
+            return Err(errors.join("\n"));
+        }
+        files.retain(|(_, language)| patterns.contains_key(language));
+        Ok(Self { files, patterns })
+    }
+
+    fn find(&self, language: SymbolLanguage, source: &str) -> Vec<PatternMatch> {
+        self.patterns.get(&language)
+            .and_then(|pattern| pattern.find_all(source).ok())
+            .unwrap_or_default()
+    }
+}
+
+fn one_line(code: &str, max_chars: usize) -> String {
+    let line = code.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line.chars().count() > max_chars {
+        format!("{}...", line.chars().take(max_chars).collect::<String>())
+    } else {
+        line
+    }
+}
+
+pub struct StructuralSearchTool;
+
+impl StructuralSearchTool {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn search(root: &Path, params: &StructuralSearchToolParams) -> Result<(String, usize, bool), String> {
+        let search = Search::prepare(root, &params.pattern, params.language.as_deref())?;
+        let max_results = params.max_results as usize;
+        let mut lines = Vec::new();
+        let mut count = 0;
+        let mut files = 0;
+        for (path, language) in &search.files {
+            let Ok(source) = fs::read_to_string(path) else { continue };
+            let matches = search.find(*language, &source);
+            if !matches.is_empty() {
+                files += 1;
+            }
+            for found in matches {
+                if count >= max_results {
+                    return Ok((lines.join("\n"), count, true));
+                }
+                let first_line = found.text.lines().next().unwrap_or_default().trim();
+                let span = if found.end_line > found.start_line { format!(" (to line {})", found.end_line) } else { String::new() };
+                lines.push(format!("{}:{}: {}{}", path.display(), found.start_line, first_line, span));
+                for (name, code) in &found.captures {
+                    lines.push(format!("    ${} = {}", name, one_line(code, MAX_CAPTURE_CHARS)));
+                }
+                count += 1;
+            }
+        }
+
+        if count == 0 {
+            return Ok((format!("No match for the pattern in {}", root.display()), 0, false));
+        }
+        lines.push(format!("\n{} match(es) in {} file(s)", count, files));
+        Ok((lines.join("\n"), count, false))
+    }
+}
+
+#[tool(name = "structural_search", description = r#"Searches code by its syntax rather than its text: the pattern is code in which metavariables stand for any piece of code, and it matches whatever parses the same way, whatever the spacing, line breaks or comments.
+
+**Patterns:**
+- `$NAME` matches a single node (an expression, an identifier, a type, a statement...) and `$$$NAME` any number of them, e.g. the arguments of a call. `$_` matches anything without capturing it.
+- A metavariable used twice must match the same code both times: `$A == $A` only matches comparisons of something with itself.
+- Examples: `$CLIENT.get($URL, $$$ARGS)`, `unwrap_or(String::new())`, `if err != nil { return $$$VALUES }`, `console.log($$$ARGS)`.
+
+**Usage Notes:**
+- Works on Rust, Python, JavaScript, TypeScript, Go and Java files, skipping files ignored by `.gitignore`. Set `language` when the path holds several languages.
+- Each match is listed with what every metavariable captured. Use `structural_replace` with the same pattern to rewrite the matches."#, capabilities = [ToolCapability::Read])]
+impl StructuralSearchTool {
+    async fn execute(&self, params: StructuralSearchToolParams) -> ToolResult {
+        let mut meta = HashMap::new();
+        let search_path = params.path.clone().unwrap_or_else(|| ".".to_string());
+        meta.insert("pattern".to_string(), json!(params.pattern));
+        meta.insert("path".to_string(), json!(search_path));
+
+        let root = PathBuf::from(&search_path);
+        if !root.exists() {
+            return ToolResult::Error {
+                error: format!("Path does not exist: {}", search_path),
+                metadata: Some(meta),
+            };
+        }
+
+        // Parsing is blocking, keep it off the runtime threads
+        let result = tokio::task::spawn_blocking(move || Self::search(&root, &params)).await;
+        match result {
+            Ok(Ok((mut output, count, truncated))) => {
+                if truncated {
+                    output.push_str("\n... stopped at max_results, narrow the path or the pattern");
+                }
+                meta.insert("count".to_string(), json!(count));
+                meta.insert("truncated".to_string(), json!(truncated));
+                ToolResult::Success {
+                    output,
+                    metadata: Some(meta),
+                }
+            }
+            Ok(Err(e)) => ToolResult::Error {
+                error: e,
+                metadata: Some(meta),
+            },
+            Err(e) => ToolResult::Error {
+                error: format!("Structural search failed: {}", e),
+                metadata: Some(meta),
+            },
+        }
+    }
+}
+
+/// Hash of the contents of every file a rewrite changes, as they were when it was staged
+type Originals = HashMap<String, Option<u64>>;
+
+fn originals(transaction: &FsTransaction) -> Originals {
+    transaction.changes()
+        .map(|(path, before, _)| {
+            let hash = before.map(|before| {
+                let mut hasher = DefaultHasher::new();
+                before.hash(&mut hasher);
+                hasher.finish()
+            });
+            (path.to_string(), hash)
+        })
+        .collect()
+}
+
+/// Identifies the rewrite a preview was shown for
+fn preview_key(params: &StructuralReplaceToolParams) -> String {
+    format!("{}\0{}\0{:?}\0{:?}", params.pattern, params.rewrite, params.language, params.path)
+}
+
+#[derive(Clone)]
+pub struct StructuralReplaceTool {
+    operation_log: Arc<FsOperationLog>,
+    edit_tool: EditTool,
+    /// Files as they were when each rewrite was previewed, a rewrite is refused if they changed since
+    previews: Arc<Mutex<HashMap<String, Originals>>>,
+}
+
+impl StructuralReplaceTool {
+    pub fn new(operation_log: Arc<FsOperationLog>) -> Self {
+        let edit_tool = EditTool::new(operation_log.clone());
+        Self { operation_log, edit_tool, previews: Arc::new(Mutex::new(HashMap::new())) }
+    }
+
+    /// Files of the rewrite that changed since they were last read or since the rewrite was previewed,
+    /// a rewrite that was not previewed only changes files that were read
+    async fn check_unchanged(&self, params: &StructuralReplaceToolParams, transaction: &FsTransaction) -> Result<(), String> {
+        let previewed = self.previews.lock().unwrap().remove(&preview_key(params));
+        let Some(previewed) = previewed else {
+            for (path, _, _) in transaction.changes() {
+                self.operation_log.validate_edit_permission(path).await?;
+            }
+            return Ok(());
+        };
+        for (path, _, _) in transaction.changes() {
+            self.operation_log.validate_unchanged(path).await?;
+        }
+
+        let current = originals(transaction);
+        let mut changed: Vec<&str> = previewed.keys().chain(current.keys())
+            .filter(|path| previewed.get(*path) != current.get(*path))
+            .map(String::as_str)
+            .collect();
+        if changed.is_empty() {
+            return Ok(());
+        }
+        changed.sort();
+        changed.dedup();
+        Err(format!("{} changed since the rewrite was previewed, preview it again to see what it does now", changed.join(", ")))
+    }
+
+    /// Rewrite every match in a transaction, returns it with the number of matches
+    fn stage(root: &Path, params: &StructuralReplaceToolParams) -> Result<(FsTransaction, usize), String> {
+        let unknown: Vec<String> = metavariables(&params.rewrite)
+            .difference(&metavariables(&params.pattern))
+            .map(|name| format!("${}", name))
+            .collect();
+        if !unknown.is_empty() {
+            return Err(format!("The rewrite uses {} which the pattern does not capture", unknown.join(", ")));
+        }
+
+        let search = Search::prepare(root, &params.pattern, params.language.as_deref())?;
+        let mut transaction = FsTransaction::begin();
+        let mut count = 0;
+        for (path, language) in &search.files {
+            let path = path.to_string_lossy();
+            let Ok(source) = transaction.read(&path) else { continue };
+            let matches = search.find(*language, &source);
+            if matches.is_empty() {
+                continue;
+            }
+            count += matches.len();
+            let rewritten = rewrite_all(&source, &matches, &params.rewrite);
+            if rewritten != source {
+                transaction.write(&path, rewritten)?;
+            }
+        }
+
+        if count == 0 {
+            return Err(format!("No match for the pattern in {}, nothing to rewrite", root.display()));
+        }
+        Ok((transaction, count))
+    }
+
+    fn diff(&self, transaction: &FsTransaction) -> String {
+        transaction.changes()
+            .map(|(path, before, after)| {
+                let diff = self.edit_tool.myers_diff(before.unwrap_or_default(), after.unwrap_or_default());
+                format!("\x1b[1m{}\x1b[0m\n{}", path, diff)
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+#[tool(name = "structural_replace", description = r#"Rewrites every match of a structural pattern in one go, across a file or a whole directory: renaming a function while reordering its arguments, migrating calls to a new API, replacing an idiom everywhere.
+
+**Usage:**
+- `pattern` is written like for `structural_search`, run it there first to check what it matches.
+- `rewrite` is the code each match becomes, in which the metavariables of the pattern are replaced by the code they captured: pattern `assert_eq!($A, true)` with rewrite `assert!($A)`.
+- The whole rewrite is shown as a diff before anything is written, then every file is written together, or none if one of them cannot be.
+- When the rewrite is allowed without being previewed, every file it changes must have been read first.
+- Comments inside a match are dropped unless they are captured by a metavariable."#, capabilities = [ToolCapability::Read, ToolCapability::Write])]
+impl StructuralReplaceTool {
+    async fn execute_preview(&self, params: StructuralReplaceToolParams) -> Option<ToolResult> {
+        Some(self.execute_internal(params, true).await)
+    }
+
+    async fn execute(&self, params: StructuralReplaceToolParams) -> ToolResult {
+        self.execute_internal(params, false).await
+    }
+
+    async fn execute_internal(&self, params: StructuralReplaceToolParams, preview: bool) -> ToolResult {
+        let search_path = params.path.clone().unwrap_or_else(|| ".".to_string());
+        let root = PathBuf::from(&search_path);
+        if !root.exists() {
+            return ToolResult::error(format!("Path does not exist: {}", search_path));
+        }
+
+        // Parsing is blocking, keep it off the runtime threads
+        let staged = {
+            let params = params.clone();
+            tokio::task::spawn_blocking(move || Self::stage(&root, &params)).await
+        };
+        let (transaction, count) = match staged {
+            Ok(Ok(staged)) => staged,
+            Ok(Err(e)) => return ToolResult::error(format!("Rewrite failed, no file was changed: {}", e)),
+            Err(e) => return ToolResult::error(format!("Rewrite failed, no file was changed: {}", e)),
+        };
+
+        if preview {
+            self.previews.lock().unwrap().insert(preview_key(&params), originals(&transaction));
+        } else if let Err(e) = self.check_unchanged(&params, &transaction).await {
+            return ToolResult::error(format!("Rewrite failed, no file was changed: {}", e));
+        }
+
+        let diff = self.diff(&transaction);
+        let paths: Vec<String> = transaction.changes().map(|(path, _, _)| path.to_string()).collect();
+        if !preview {
+            if let Err(e) = transaction.commit() {
+                return ToolResult::error(format!("Rewrite failed, no file was changed: {}", e));
+            }
+            for path in &paths {
+                self.operation_log.log_operation(FsOperationType::Edit, path.clone()).await;
+            }
+        }
+
+        let mut meta = HashMap::new();
+        meta.insert("pattern".to_string(), json!(params.pattern));
+        meta.insert("rewrite".to_string(), json!(params.rewrite));
+        meta.insert("paths".to_string(), json!(paths));
+        meta.insert("file_count".to_string(), json!(paths.len()));
+        meta.insert("match_count".to_string(), json!(count));
+        meta.insert("preview_mode".to_string(), json!(preview));
+
+        let verb = if preview { "Rewrite" } else { "Rewrote" };
+        ToolResult::Success {
+            output: format!("{} {} match(es) in {} file(s)\n\n{}", verb, count, paths.len(), diff),
+            metadata: Some(meta),
+        }
+    }
+}
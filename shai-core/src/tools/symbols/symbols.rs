@@ -0,0 +1,239 @@
+use super::parser::{find_references, parse_symbols, Symbol, SymbolLanguage};
+use super::structs::{SymbolsAction, SymbolsToolParams};
+use crate::tools::{tool, ToolResult};
+use ignore::WalkBuilder;
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Bigger files are generated or vendored code, they are skipped
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Definitions shown with their source when there are at most this many
+const MAX_SHOWN_DEFINITIONS: usize = 3;
+
+/// Source lines shown for each definition
+const MAX_DEFINITION_LINES: usize = 80;
+
+/// Source files with a supported language under root, or root itself
+pub(crate) fn source_files(root: &Path) -> Result<Vec<(PathBuf, SymbolLanguage)>, String> {
+    if root.is_file() {
+        return match SymbolLanguage::detect(root) {
+            Some(language) => Ok(vec![(root.to_path_buf(), language)]),
+            None => Err(format!("{} is not in a supported language ({})", root.display(), SymbolLanguage::SUPPORTED)),
+        };
+    }
+
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .hidden(false)
+        .follow_links(false)
+        .require_git(false)
+        .filter_entry(|entry| entry.file_name() != ".git");
+
+    let mut files: Vec<(PathBuf, SymbolLanguage)> = builder.build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_some_and(|file_type| file_type.is_file()))
+        .filter(|entry| entry.metadata().is_ok_and(|metadata| metadata.len() <= MAX_FILE_BYTES))
+        .filter_map(|entry| SymbolLanguage::detect(entry.path()).map(|language| (entry.into_path(), language)))
+        .collect();
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(files)
+}
+
+pub struct SymbolsTool;
+
+impl SymbolsTool {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Splits `Type::method` or `Type.method` into the type and the name
+    fn split_qualified(name: &str) -> (Option<&str>, &str) {
+        match name.rsplit_once("::").or_else(|| name.rsplit_once('.')) {
+            Some((container, name)) => (Some(container.rsplit(['.', ':']).next().unwrap_or(container)), name),
+            None => (None, name),
+        }
+    }
+
+    fn outline(root: &Path, max_results: usize) -> Result<(String, usize, bool), String> {
+        let single_file = root.is_file();
+        let mut lines = Vec::new();
+        let mut count = 0;
+        for (path, language) in source_files(root)? {
+            let Ok(source) = fs::read_to_string(&path) else { continue };
+            let symbols = match parse_symbols(&source, language) {
+                Ok(symbols) => symbols,
+                Err(e) if single_file => return Err(e),
+                Err(_) => continue,
+            };
+            if symbols.is_empty() && !single_file {
+                continue;
+            }
+
+            lines.push(format!("{} ({}, {} symbols)", path.display(), language.name(), symbols.len()));
+            for symbol in symbols {
+                if count >= max_results {
+                    return Ok((lines.join("\n"), count, true));
+                }
+                lines.push(format!("{}{}  L{}-{}", "  ".repeat(symbol.depth + 1), symbol.signature, symbol.start_line, symbol.end_line));
+                count += 1;
+            }
+        }
+        if lines.is_empty() {
+            return Ok((format!("No symbols found in {}", root.display()), 0, false));
+        }
+        Ok((lines.join("\n"), count, false))
+    }
+
+    fn definition(root: &Path, name: &str, max_results: usize) -> Result<(String, usize, bool), String> {
+        let (container, name) = Self::split_qualified(name);
+        let mut found: Vec<(PathBuf, Symbol, String)> = Vec::new();
+        let mut truncated = false;
+        'files: for (path, language) in source_files(root)? {
+            let Ok(source) = fs::read_to_string(&path) else { continue };
+            if !source.contains(name) {
+                continue;
+            }
+            let Ok(symbols) = parse_symbols(&source, language) else { continue };
+            for symbol in symbols {
+                if symbol.name != name || container.is_some_and(|container| symbol.container.as_deref() != Some(container)) {
+                    continue;
+                }
+                if found.len() >= max_results {
+                    truncated = true;
+                    break 'files;
+                }
+                let body = source.lines()
+                    .enumerate()
+                    .skip(symbol.start_line - 1)
+                    .take((symbol.end_line - symbol.start_line + 1).min(MAX_DEFINITION_LINES))
+                    .map(|(index, line)| format!("{:4}: {}", index + 1, line))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                found.push((path.clone(), symbol, body));
+            }
+        }
+
+        if found.is_empty() {
+            return Ok((format!("No definition of {} found in {}", name, root.display()), 0, false));
+        }
+        let show_source = found.len() <= MAX_SHOWN_DEFINITIONS;
+        let count = found.len();
+        let entries: Vec<String> = found.into_iter().map(|(path, symbol, body)| {
+            let within = symbol.container.as_ref().map(|container| format!(" in {}", container)).unwrap_or_default();
+            let mut entry = format!("{}:{}-{} {}{}: {}", path.display(), symbol.start_line, symbol.end_line, symbol.kind.name(), within, symbol.signature);
+            if show_source {
+                entry.push('\n');
+                entry.push_str(&body);
+                if symbol.end_line - symbol.start_line + 1 > MAX_DEFINITION_LINES {
+                    entry.push_str(&format!("\n... {} more lines", symbol.end_line - symbol.start_line + 1 - MAX_DEFINITION_LINES));
+                }
+            }
+            entry
+        }).collect();
+        Ok((entries.join(if show_source { "\n\n" } else { "\n" }), count, truncated))
+    }
+
+    fn references(root: &Path, name: &str, max_results: usize) -> Result<(String, usize, bool), String> {
+        let (_, name) = Self::split_qualified(name);
+        let mut lines = Vec::new();
+        let mut files = 0;
+        for (path, language) in source_files(root)? {
+            let Ok(source) = fs::read_to_string(&path) else { continue };
+            if !source.contains(name) {
+                continue;
+            }
+            let Ok(references) = find_references(&source, language, name) else { continue };
+            if !references.is_empty() {
+                files += 1;
+            }
+            for reference in references {
+                if lines.len() >= max_results {
+                    return Ok((lines.join("\n"), lines.len(), true));
+                }
+                let definition = if reference.definition { "  (definition)" } else { "" };
+                lines.push(format!("{}:{}:{}: {}{}", path.display(), reference.line, reference.column, reference.line_text, definition));
+            }
+        }
+
+        if lines.is_empty() {
+            return Ok((format!("No references to {} found in {}", name, root.display()), 0, false));
+        }
+        let count = lines.len();
+        lines.push(format!("\n{} reference(s) in {} file(s)", count, files));
+        Ok((lines.join("\n"), count, false))
+    }
+}
+
+#[tool(name = "symbols", description = r#"Understands the structure of source code by parsing it, for Rust, Python, JavaScript, TypeScript, Go and Java.
+
+**Actions:**
+- `outline`: lists the functions, types, classes, traits, impls and methods of a file, or of every source file in a directory, with their line ranges. Read a large file's outline first, then only the lines you need.
+- `definition`: finds where `name` is defined and shows its source when there are few matches. `Type::method` or `Type.method` narrows the search to the methods of one type.
+- `references`: finds where `name` is used as an identifier. Unlike `find`, matches in comments, strings and longer identifiers are left out.
+
+**Usage Notes:**
+- `path` is a file or a directory, the current directory by default. Files ignored by `.gitignore` are skipped.
+- Prefer this tool over `find` to navigate code by symbol, and use `find` for text or for other languages."#, capabilities = [ToolCapability::Read])]
+impl SymbolsTool {
+    async fn execute(&self, params: SymbolsToolParams) -> ToolResult {
+        let mut meta = HashMap::new();
+        let search_path = params.path.clone().unwrap_or_else(|| ".".to_string());
+        meta.insert("action".to_string(), json!(params.action));
+        meta.insert("path".to_string(), json!(search_path));
+        if let Some(name) = &params.name {
+            meta.insert("name".to_string(), json!(name));
+        }
+
+        let root = PathBuf::from(&search_path);
+        if !root.exists() {
+            return ToolResult::Error {
+                error: format!("Path does not exist: {}", search_path),
+                metadata: Some(meta),
+            };
+        }
+        let name = match (params.action, params.name.clone()) {
+            (SymbolsAction::Outline, _) => String::new(),
+            (_, Some(name)) if !name.trim().is_empty() => name.trim().to_string(),
+            (action, _) => {
+                return ToolResult::Error {
+                    error: format!("{} needs the name of a symbol", json!(action).as_str().unwrap_or_default()),
+                    metadata: Some(meta),
+                };
+            }
+        };
+
+        // Parsing is blocking, keep it off the runtime threads
+        let max_results = params.max_results as usize;
+        let action = params.action;
+        let result = tokio::task::spawn_blocking(move || match action {
+            SymbolsAction::Outline => Self::outline(&root, max_results),
+            SymbolsAction::Definition => Self::definition(&root, &name, max_results),
+            SymbolsAction::References => Self::references(&root, &name, max_results),
+        }).await;
+
+        match result {
+            Ok(Ok((mut output, count, truncated))) => {
+                if truncated {
+                    output.push_str(&format!("\n... stopped after {} results, narrow the path or raise max_results", max_results));
+                }
+                meta.insert("count".to_string(), json!(count));
+                meta.insert("truncated".to_string(), json!(truncated));
+                ToolResult::Success {
+                    output,
+                    metadata: Some(meta),
+                }
+            }
+            Ok(Err(e)) => ToolResult::Error {
+                error: e,
+                metadata: Some(meta),
+            },
+            Err(e) => ToolResult::Error {
+                error: format!("Symbols failed: {}", e),
+                metadata: Some(meta),
+            },
+        }
+    }
+}
@@ -0,0 +1,319 @@
+use super::parser::{find_references, parse_symbols, SymbolKind, SymbolLanguage};
+use super::pattern::{rewrite_all, Pattern};
+use super::structs::{StructuralReplaceToolParams, StructuralSearchToolParams, SymbolsAction, SymbolsToolParams};
+use super::structural::{StructuralReplaceTool, StructuralSearchTool};
+use super::symbols::SymbolsTool;
+use crate::tools::{FsOperationLog, FsOperationType, Tool, ToolCapability, ToolResult};
+use shai_llm::ToolDescription;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+fn outline(source: &str, language: SymbolLanguage) -> Vec<(SymbolKind, String, Option<String>, usize)> {
+    parse_symbols(source, language).unwrap()
+        .into_iter()
+        .map(|symbol| (symbol.kind, symbol.name, symbol.container, symbol.depth))
+        .collect()
+}
+
+fn params(action: SymbolsAction, path: &Path, name: Option<&str>) -> SymbolsToolParams {
+    SymbolsToolParams {
+        action,
+        path: Some(path.to_string_lossy().to_string()),
+        name: name.map(str::to_string),
+        max_results: 200,
+    }
+}
+
+fn output(result: ToolResult) -> String {
+    match result {
+        ToolResult::Success { output, .. } => output,
+        ToolResult::Error { error, .. } => panic!("Expected success, got: {}", error),
+    }
+}
+
+const RUST_SOURCE: &str = r#"use std::fmt;
+
+/// A point
+pub struct Point {
+    x: i32,
+}
+
+impl<T> fmt::Display for Wrapper<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "wrapper")
+    }
+}
+
+impl Point {
+    pub fn new(x: i32) -> Self {
+        // new is not a reference here
+        Self { x }
+    }
+}
+
+pub fn origin() -> Point {
+    Point::new(0)
+}
+"#;
+
+#[test]
+fn test_symbols_tool_capabilities() {
+    assert_eq!(SymbolsTool::new().name(), "symbols");
+    assert_eq!(SymbolsTool::new().capabilities(), &[ToolCapability::Read]);
+}
+
+#[test]
+fn test_language_detection() {
+    assert_eq!(SymbolLanguage::detect(Path::new("src/main.rs")), Some(SymbolLanguage::Rust));
+    assert_eq!(SymbolLanguage::detect(Path::new("app/view.tsx")), Some(SymbolLanguage::Tsx));
+    assert_eq!(SymbolLanguage::detect(Path::new("app/index.mjs")), Some(SymbolLanguage::JavaScript));
+    assert_eq!(SymbolLanguage::detect(Path::new("README.md")), None);
+}
+
+#[test]
+fn test_rust_outline() {
+    assert_eq!(outline(RUST_SOURCE, SymbolLanguage::Rust), vec![
+        (SymbolKind::Struct, "Point".to_string(), None, 0),
+        (SymbolKind::Impl, "Wrapper".to_string(), None, 0),
+        (SymbolKind::Method, "fmt".to_string(), Some("Wrapper".to_string()), 1),
+        (SymbolKind::Impl, "Point".to_string(), None, 0),
+        (SymbolKind::Method, "new".to_string(), Some("Point".to_string()), 1),
+        (SymbolKind::Function, "origin".to_string(), None, 0),
+    ]);
+
+    let symbols = parse_symbols(RUST_SOURCE, SymbolLanguage::Rust).unwrap();
+    let new = symbols.iter().find(|symbol| symbol.name == "new").unwrap();
+    assert_eq!(new.signature, "pub fn new(x: i32) -> Self");
+    assert_eq!((new.start_line, new.end_line), (15, 18));
+}
+
+#[test]
+fn test_python_and_go_outline() {
+    let python = "class Store:\n    def get(self, key):\n        def inner():\n            pass\n        return key\n\ndef main():\n    pass\n";
+    assert_eq!(outline(python, SymbolLanguage::Python), vec![
+        (SymbolKind::Class, "Store".to_string(), None, 0),
+        (SymbolKind::Method, "get".to_string(), Some("Store".to_string()), 1),
+        (SymbolKind::Function, "inner".to_string(), None, 2),
+        (SymbolKind::Function, "main".to_string(), None, 0),
+    ]);
+
+    let go = "package main\n\ntype Server struct {\n\tport int\n}\n\ntype Handler interface {\n\tServe()\n}\n\nfunc (s *Server) Start() error {\n\treturn nil\n}\n\nfunc main() {}\n";
+    assert_eq!(outline(go, SymbolLanguage::Go), vec![
+        (SymbolKind::Struct, "Server".to_string(), None, 0),
+        (SymbolKind::Interface, "Handler".to_string(), None, 0),
+        (SymbolKind::Method, "Start".to_string(), Some("Server".to_string()), 0),
+        (SymbolKind::Function, "main".to_string(), None, 0),
+    ]);
+}
+
+#[test]
+fn test_typescript_and_java_outline() {
+    let typescript = "export interface User { name: string }\n\nexport const greet = (user: User): string => {\n  return user.name;\n};\n\nclass Service {\n  run(): void {}\n}\n";
+    assert_eq!(outline(typescript, SymbolLanguage::TypeScript), vec![
+        (SymbolKind::Interface, "User".to_string(), None, 0),
+        (SymbolKind::Function, "greet".to_string(), None, 0),
+        (SymbolKind::Class, "Service".to_string(), None, 0),
+        (SymbolKind::Method, "run".to_string(), Some("Service".to_string()), 1),
+    ]);
+
+    let java = "public class App {\n    public App() {}\n    public static void main(String[] args) {}\n}\n";
+    assert_eq!(outline(java, SymbolLanguage::Java), vec![
+        (SymbolKind::Class, "App".to_string(), None, 0),
+        (SymbolKind::Method, "App".to_string(), Some("App".to_string()), 1),
+        (SymbolKind::Method, "main".to_string(), Some("App".to_string()), 1),
+    ]);
+}
+
+#[test]
+fn test_references_skip_comments_and_strings() {
+    let references = find_references(RUST_SOURCE, SymbolLanguage::Rust, "new").unwrap();
+    let lines: Vec<(usize, bool)> = references.iter().map(|reference| (reference.line, reference.definition)).collect();
+    assert_eq!(lines, vec![(15, true), (22, false)]);
+    assert_eq!(references[1].line_text, "Point::new(0)");
+}
+
+#[tokio::test]
+async fn test_definition_lookup() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("src/point.rs"), RUST_SOURCE).unwrap();
+    fs::write(root.join("src/other.rs"), "struct Other;\nimpl Other {\n    fn new() -> Self { Other }\n}\n").unwrap();
+    fs::write(root.join("notes.txt"), "fn new() is everywhere").unwrap();
+
+    let tool = SymbolsTool::new();
+    let all = output(tool.execute(params(SymbolsAction::Definition, root, Some("new"))).await);
+    assert!(all.contains("other.rs:3-3 method in Other: fn new() -> Self"));
+    assert!(all.contains("point.rs:15-18 method in Point: pub fn new(x: i32) -> Self"));
+    assert!(!all.contains("notes.txt"));
+
+    let qualified = output(tool.execute(params(SymbolsAction::Definition, root, Some("Point::new"))).await);
+    assert!(qualified.contains("  17:         Self { x }"));
+    assert!(!qualified.contains("other.rs"));
+
+    let missing = tool.execute(params(SymbolsAction::References, root, None)).await;
+    assert!(missing.is_error());
+}
+
+#[tokio::test]
+async fn test_outline_of_directory_and_unsupported_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::write(root.join("lib.rs"), RUST_SOURCE).unwrap();
+    fs::write(root.join("app.py"), "def main():\n    pass\n").unwrap();
+    fs::write(root.join("data.json"), "{}").unwrap();
+
+    let tool = SymbolsTool::new();
+    let result = output(tool.execute(params(SymbolsAction::Outline, root, None)).await);
+    assert!(result.contains("app.py (python, 1 symbols)"));
+    assert!(result.contains("    pub fn new(x: i32) -> Self  L15-18"));
+    assert!(!result.contains("data.json"));
+
+    let unsupported = tool.execute(params(SymbolsAction::Outline, &root.join("data.json"), None)).await;
+    assert!(unsupported.is_error());
+}
+
+/// Matched code with its captures, in order
+fn matches(pattern: &str, source: &str, language: SymbolLanguage) -> Vec<(String, Vec<(String, String)>)> {
+    Pattern::compile(pattern, language).unwrap()
+        .find_all(source).unwrap()
+        .into_iter()
+        .map(|found| (found.text, found.captures.into_iter().collect()))
+        .collect()
+}
+
+fn capture(name: &str, code: &str) -> (String, String) {
+    (name.to_string(), code.to_string())
+}
+
+#[test]
+fn test_pattern_captures_and_ignores_layout() {
+    let source = "fn main() {\n    let a = first.unwrap_or(String::new());\n    let b = second\n        .unwrap_or( String::new() );\n    let c = third.unwrap_or(fallback);\n}\n";
+    let found = matches("$X.unwrap_or(String::new())", source, SymbolLanguage::Rust);
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0], ("first.unwrap_or(String::new())".to_string(), vec![capture("X", "first")]));
+    assert_eq!(found[1].1, vec![capture("X", "second")]);
+
+    let comments = matches("foo(1, 2)", "foo(1, /* two */ 2);\nfoo(1, 3);\n", SymbolLanguage::JavaScript);
+    assert_eq!(comments.len(), 1);
+}
+
+#[test]
+fn test_pattern_multiple_nodes_and_repeated_metavariables() {
+    let source = "f(x)\nf(x, y, z)\ng(x)\n";
+    let found = matches("f($A, $$$REST)", source, SymbolLanguage::Python);
+    assert_eq!(found, vec![
+        ("f(x)".to_string(), vec![capture("A", "x"), capture("REST", "")]),
+        ("f(x, y, z)".to_string(), vec![capture("A", "x"), capture("REST", "y, z")]),
+    ]);
+
+    let same = matches("$A == $A", "a == a\na == b\n", SymbolLanguage::Python);
+    assert_eq!(same.len(), 1);
+    assert_eq!(same[0].0, "a == a");
+
+    assert!(Pattern::compile("$A", SymbolLanguage::Python).is_err());
+    assert!(Pattern::compile("fn (", SymbolLanguage::Rust).is_err());
+}
+
+#[test]
+fn test_rewrite_all() {
+    let source = "console.log(\"start\");\nif (ok) {\n  console.log(\"a\", b);\n}\n";
+    let pattern = Pattern::compile("console.log($$$ARGS)", SymbolLanguage::JavaScript).unwrap();
+    let found = pattern.find_all(source).unwrap();
+    assert_eq!(
+        rewrite_all(source, &found, "logger.info($$$ARGS)"),
+        "logger.info(\"start\");\nif (ok) {\n  logger.info(\"a\", b);\n}\n"
+    );
+}
+
+#[tokio::test]
+async fn test_structural_search_and_replace_tools() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    let file = root.join("main.rs");
+    let source = "fn main() {\n    let a = x.unwrap_or(String::new());\n    let b = y.unwrap_or(String::new());\n}\n";
+    fs::write(&file, source).unwrap();
+    fs::write(root.join("notes.md"), "x.unwrap_or(String::new())").unwrap();
+
+    let search = StructuralSearchTool::new();
+    assert_eq!(search.capabilities(), &[ToolCapability::Read]);
+    let found = output(search.execute(StructuralSearchToolParams {
+        pattern: "$X.unwrap_or(String::new())".to_string(),
+        language: None,
+        path: Some(root.to_string_lossy().to_string()),
+        max_results: 200,
+    }).await);
+    assert!(found.contains("main.rs:2: x.unwrap_or(String::new())"));
+    assert!(found.contains("    $X = y"));
+    assert!(found.contains("2 match(es) in 1 file(s)"));
+
+    let replace = StructuralReplaceTool::new(Arc::new(FsOperationLog::with_trash_dir(temp_dir.path().join(".trash"))));
+    let params = |rewrite: &str| StructuralReplaceToolParams {
+        pattern: "$X.unwrap_or(String::new())".to_string(),
+        rewrite: rewrite.to_string(),
+        language: Some("rust".to_string()),
+        path: Some(root.to_string_lossy().to_string()),
+    };
+
+    assert!(replace.execute(params("$Y.unwrap_or_default()")).await.is_error());
+
+    let preview = output(replace.execute_preview(params("$X.unwrap_or_default()")).await.unwrap());
+    assert!(preview.contains("Rewrite 2 match(es) in 1 file(s)"));
+    assert_eq!(fs::read_to_string(&file).unwrap(), source);
+
+    output(replace.execute(params("$X.unwrap_or_default()")).await);
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "fn main() {\n    let a = x.unwrap_or_default();\n    let b = y.unwrap_or_default();\n}\n"
+    );
+}
+
+#[tokio::test]
+async fn test_structural_replace_refuses_files_changed_since_preview() {
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("lib.rs");
+    fs::write(&file, "fn f() { a.clone(); }\n").unwrap();
+
+    let replace = StructuralReplaceTool::new(Arc::new(FsOperationLog::with_trash_dir(temp_dir.path().join(".trash"))));
+    let params = StructuralReplaceToolParams {
+        pattern: "$X.clone()".to_string(),
+        rewrite: "$X.to_owned()".to_string(),
+        language: Some("rust".to_string()),
+        path: Some(temp_dir.path().to_string_lossy().to_string()),
+    };
+
+    output(replace.execute_preview(params.clone()).await.unwrap());
+    fs::write(&file, "fn f() { a.clone(); b.clone(); }\n").unwrap();
+    let result = replace.execute(params.clone()).await;
+    assert!(result.is_error());
+    assert_eq!(fs::read_to_string(&file).unwrap(), "fn f() { a.clone(); b.clone(); }\n");
+
+    output(replace.execute_preview(params.clone()).await.unwrap());
+    output(replace.execute(params).await);
+    assert_eq!(fs::read_to_string(&file).unwrap(), "fn f() { a.to_owned(); b.to_owned(); }\n");
+}
+
+#[tokio::test]
+async fn test_structural_replace_without_preview_requires_read() {
+    let temp_dir = TempDir::new().unwrap();
+    let file = temp_dir.path().join("lib.rs");
+    fs::write(&file, "fn f() { a.clone(); }\n").unwrap();
+
+    let log = Arc::new(FsOperationLog::with_trash_dir(temp_dir.path().join(".trash")));
+    let replace = StructuralReplaceTool::new(log.clone());
+    let params = StructuralReplaceToolParams {
+        pattern: "$X.clone()".to_string(),
+        rewrite: "$X.to_owned()".to_string(),
+        language: Some("rust".to_string()),
+        path: Some(temp_dir.path().to_string_lossy().to_string()),
+    };
+
+    assert!(replace.execute(params.clone()).await.is_error());
+    assert_eq!(fs::read_to_string(&file).unwrap(), "fn f() { a.clone(); }\n");
+
+    log.log_operation(FsOperationType::Read, file.to_string_lossy().to_string()).await;
+    output(replace.execute(params).await);
+    assert_eq!(fs::read_to_string(&file).unwrap(), "fn f() { a.to_owned(); }\n");
+}
@@ -3,6 +3,7 @@ mod llm_integration_tests {
     use std::sync::Arc;
     use std::sync::Once;
     use tracing::debug;
+    use tempfile::tempdir;
     use shai_llm::{ChatMessage, ChatMessageContent, LlmClient};
     use openai_dive::v1::resources::chat::{ChatCompletionParametersBuilder, ChatCompletionToolChoice};
     use crate::logging::LoggingConfig;
@@ -188,7 +189,8 @@ mod llm_integration_tests {
 
     #[tokio::test]
     async fn test_write_tool_with_llm() {
-        let tool: Arc<dyn AnyTool> = Arc::new(WriteTool::new(Arc::new(FsOperationLog::new())));
+        let dir = tempdir().unwrap();
+        let tool: Arc<dyn AnyTool> = Arc::new(WriteTool::new(Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")))));
         llm_test_with_fallback!(
             test_tool_with_llm(tool, "Write 'Hello LLM Test' to the file '/tmp/test_write.txt'"),
             "WriteTool should be called by LLM"
@@ -197,7 +199,8 @@ mod llm_integration_tests {
 
     #[tokio::test]
     async fn test_read_tool_with_llm() {
-        let tool: Arc<dyn AnyTool> = Arc::new(ReadTool::new(Arc::new(FsOperationLog::new())));
+        let dir = tempdir().unwrap();
+        let tool: Arc<dyn AnyTool> = Arc::new(ReadTool::new(Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")))));
         llm_test_with_fallback!(
             test_tool_with_llm(tool, "Read the contents of the file 'Cargo.toml'"),
             "ReadTool should be called by LLM"
@@ -206,7 +209,8 @@ mod llm_integration_tests {
 
     #[tokio::test]
     async fn test_edit_tool_with_llm() {
-        let tool: Arc<dyn AnyTool> = Arc::new(EditTool::new(Arc::new(FsOperationLog::new())));
+        let dir = tempdir().unwrap();
+        let tool: Arc<dyn AnyTool> = Arc::new(EditTool::new(Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")))));
         llm_test_with_fallback!(
             test_tool_with_llm(tool, "In the file 'Cargo.toml', replace 'name' with 'project_name'"),
             "EditTool should be called by LLM"
@@ -215,7 +219,8 @@ mod llm_integration_tests {
 
     #[tokio::test]
     async fn test_multiedit_tool_with_llm() {
-        let tool: Arc<dyn AnyTool> = Arc::new(MultiEditTool::new(Arc::new(FsOperationLog::new())));
+        let dir = tempdir().unwrap();
+        let tool: Arc<dyn AnyTool> = Arc::new(MultiEditTool::new(Arc::new(FsOperationLog::with_trash_dir(dir.path().join(".trash")))));
         llm_test_with_fallback!(
             test_tool_with_llm(tool, "In the file 'Cargo.toml', replace 'name' with 'project_name' and 'v0.0.1' with 'v0.0.2'"),
             "MultiEditTool should be called by LLM"
@@ -4,5 +4,5 @@ pub mod todo;
 #[cfg(test)]
 mod tests;
 
-pub use structs::{TodoStorage, TodoItem, TodoStatus};
-pub use todo::{TodoReadTool, TodoWriteTool, TodoWriteParams, TodoItemInput};
\ No newline at end of file
+pub use structs::{TodoStorage, TodoItem, TodoStatus, TodoPriority, todo_tree, todo_progress};
+pub use todo::{TodoReadTool, TodoWriteTool, TodoUpdateTool, TodoWriteParams, TodoUpdateParams, TodoItemInput, TodoSubtaskInput, TodoAddInput, TodoChangeInput};
\ No newline at end of file
@@ -1,24 +1,89 @@
 use serde::{Deserialize, Serialize};
 use schemars::JsonSchema;
-use tokio::sync::RwLock;
+use std::fs;
+use std::path::PathBuf;
+use tokio::sync::{broadcast, RwLock};
 
 pub struct TodoStorage {
-    store: RwLock<Vec<TodoItem>>
+    store: RwLock<Vec<TodoItem>>,
+    /// File the list is saved to after every change, None to keep it in memory only
+    path: Option<PathBuf>,
+    changes: broadcast::Sender<Vec<TodoItem>>,
 }
 
 impl TodoStorage {
     pub fn new() -> Self {
+        Self::with_todos(None, Vec::new())
+    }
+
+    fn with_todos(path: Option<PathBuf>, todos: Vec<TodoItem>) -> Self {
+        let (changes, _) = broadcast::channel(64);
         Self {
-            store: RwLock::new(Vec::new())
+            store: RwLock::new(todos),
+            path,
+            changes,
+        }
+    }
+
+    /// Todo list saved to a file, the todos already saved there are loaded
+    pub fn persisted(path: PathBuf) -> Self {
+        let todos = fs::read_to_string(&path).ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self::with_todos(Some(path), todos)
+    }
+
+    /// Where the todos of a session are saved: ~/.shai/sessions/<session_id>/todos.json
+    pub fn session_path(session_id: &str) -> Option<PathBuf> {
+        dirs::home_dir().map(|home| home.join(".shai").join("sessions").join(session_id).join("todos.json"))
+    }
+
+    /// Todo list of a session, kept in memory only if there is no home directory
+    pub fn for_session(session_id: &str) -> Self {
+        match Self::session_path(session_id) {
+            Some(path) => Self::persisted(path),
+            None => Self::new(),
         }
     }
 
+    /// Receive the whole list after every change
+    pub fn subscribe(&self) -> broadcast::Receiver<Vec<TodoItem>> {
+        self.changes.subscribe()
+    }
+
     pub async fn get_all(&self) -> Vec<TodoItem> {
         self.store.read().await.clone()
     }
 
     pub async fn replace_all(&self, items: Vec<TodoItem>) {
-        *self.store.write().await = items;
+        let mut store = self.store.write().await;
+        *store = items;
+        self.changed(&store);
+    }
+
+    /// Change the list in place, it is left untouched if the change fails
+    pub async fn modify<F>(&self, change: F) -> Result<Vec<TodoItem>, String>
+    where
+        F: FnOnce(&mut Vec<TodoItem>) -> Result<(), String>,
+    {
+        let mut store = self.store.write().await;
+        let mut todos = store.clone();
+        change(&mut todos)?;
+        *store = todos;
+        self.changed(&store);
+        Ok(store.clone())
+    }
+
+    fn changed(&self, todos: &[TodoItem]) {
+        if let Some(path) = &self.path {
+            let saved = path.parent().map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::write(path, serde_json::to_string_pretty(todos).unwrap_or_default()));
+            if let Err(e) = saved {
+                tracing::warn!(target: "tools::todo", "failed to save todos to {}: {}", path.display(), e);
+            }
+        }
+        // no subscriber is not an error
+        let _ = self.changes.send(todos.to_vec());
     }
 }
 
@@ -28,11 +93,19 @@ pub struct TodoItem {
     pub id: String,
     pub content: String,
     pub status: TodoStatus,
+    #[serde(default)]
+    pub priority: TodoPriority,
+    /// Details worth remembering about the task
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Id of the task this one is a subtask of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
     pub created_at: String,
     pub updated_at: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 #[schemars(inline)]
 pub enum TodoStatus {
@@ -41,6 +114,16 @@ pub enum TodoStatus {
     Completed,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[schemars(inline)]
+pub enum TodoPriority {
+    High,
+    #[default]
+    Medium,
+    Low,
+}
+
 impl TodoItem {
     pub fn format_for_display(&self) -> String {
         let (checkbox, color_code) = match self.status {
@@ -48,20 +131,56 @@ impl TodoItem {
             TodoStatus::InProgress => ("☐", "\x1b[1;34m"),
             TodoStatus::Completed => ("☑", "\x1b[32m"),
         };
-        
-        format!("{}{} {}\x1b[0m", color_code, checkbox, self.content)
+        let priority = match self.priority {
+            TodoPriority::High => " \x1b[31m(high)",
+            TodoPriority::Medium => "",
+            TodoPriority::Low => " \x1b[2m(low)",
+        };
+
+        format!("{}{} \x1b[2m{}.\x1b[22m {}{}\x1b[0m", color_code, checkbox, self.id, self.content, priority)
     }
 }
 
+/// Todos in display order with their depth: each task is followed by its subtasks.
+/// A task whose parent is missing is shown at the top level
+pub fn todo_tree(todos: &[TodoItem]) -> Vec<(usize, &TodoItem)> {
+    fn visit<'a>(todos: &'a [TodoItem], item: &'a TodoItem, depth: usize, tree: &mut Vec<(usize, &'a TodoItem)>) {
+        tree.push((depth, item));
+        for child in todos.iter().filter(|child| child.parent_id.as_deref() == Some(item.id.as_str())) {
+            visit(todos, child, depth + 1, tree);
+        }
+    }
+
+    let mut tree = Vec::with_capacity(todos.len());
+    for root in todos.iter().filter(|item| {
+        !item.parent_id.as_ref().is_some_and(|parent| todos.iter().any(|other| &other.id == parent))
+    }) {
+        visit(todos, root, 0, &mut tree);
+    }
+    tree
+}
+
+/// Completed tasks and total number of tasks, subtasks included
+pub fn todo_progress(todos: &[TodoItem]) -> (usize, usize) {
+    let completed = todos.iter().filter(|item| item.status == TodoStatus::Completed).count();
+    (completed, todos.len())
+}
+
 impl TodoStorage {
     pub fn format_all(&self, todos: &[TodoItem]) -> String {
         if todos.is_empty() {
             "No todos found. The todo list is empty.".to_string()
         } else {
-            todos.iter()
-                .map(|todo| todo.format_for_display())
-                .collect::<Vec<_>>()
-                .join("\n")
+            let (completed, total) = todo_progress(todos);
+            let mut lines = vec![format!("{}/{} completed", completed, total)];
+            for (depth, todo) in todo_tree(todos) {
+                let indent = "  ".repeat(depth);
+                lines.push(format!("{}{}", indent, todo.format_for_display()));
+                if let Some(notes) = &todo.notes {
+                    lines.extend(notes.lines().map(|line| format!("{}    \x1b[2m{}\x1b[0m", indent, line)));
+                }
+            }
+            lines.join("\n")
         }
     }
 }
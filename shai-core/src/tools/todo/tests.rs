@@ -2,7 +2,7 @@
 mod tests {
     use std::sync::Arc;
     use serde_json::json;
-    use crate::tools::{ToolResult, TodoStorage, TodoItem, TodoStatus, TodoReadTool, TodoWriteTool, TodoWriteParams, TodoItemInput, Tool, ToolEmptyParams};
+    use crate::tools::{ToolResult, TodoStorage, TodoItem, TodoStatus, TodoPriority, TodoReadTool, TodoWriteTool, TodoUpdateTool, TodoWriteParams, TodoUpdateParams, TodoItemInput, TodoSubtaskInput, TodoAddInput, TodoChangeInput, Tool, ToolEmptyParams};
 
     // Helper function to create test storage
     fn create_test_storage() -> Arc<TodoStorage> {
@@ -14,6 +14,9 @@ mod tests {
         TodoItemInput {
             content: content.to_string(),
             status,
+            priority: TodoPriority::Medium,
+            notes: None,
+            subtasks: vec![],
         }
     }
 
@@ -32,6 +35,9 @@ mod tests {
                 id: "1".to_string(),
                 content: "Test task".to_string(),
                 status: TodoStatus::Pending,
+                priority: TodoPriority::Medium,
+                notes: None,
+                parent_id: None,
                 created_at: "2024-01-01T00:00:00Z".to_string(),
                 updated_at: "2024-01-01T00:00:00Z".to_string(),
             }
@@ -223,4 +229,96 @@ mod tests {
             assert!(output.contains("Shared task 2"));
         }
     }
-}
\ No newline at end of file
+
+    fn update_params() -> TodoUpdateParams {
+        TodoUpdateParams { add: vec![], update: vec![], remove: vec![] }
+    }
+
+    #[tokio::test]
+    async fn test_todo_write_numbers_subtasks() {
+        let storage = create_test_storage();
+        let write_tool = TodoWriteTool::new(storage.clone());
+
+        let mut task = create_sample_todo_input("Refactor parser", TodoStatus::InProgress);
+        task.subtasks = vec![
+            TodoSubtaskInput { content: "Split lexer".to_string(), status: TodoStatus::Completed, priority: TodoPriority::High, notes: None },
+            TodoSubtaskInput { content: "Add tests".to_string(), status: TodoStatus::Pending, priority: TodoPriority::Low, notes: Some("cover errors".to_string()) },
+        ];
+        let result = write_tool.execute(TodoWriteParams { todos: vec![task, create_sample_todo_input("Release", TodoStatus::Pending)] }).await;
+        assert!(result.to_string().contains("1/4 completed"), "{}", result);
+
+        let todos = storage.get_all().await;
+        let ids: Vec<&str> = todos.iter().map(|todo| todo.id.as_str()).collect();
+        assert_eq!(ids, vec!["1", "2", "3", "4"]);
+        assert_eq!(todos[1].parent_id.as_deref(), Some("1"));
+        assert_eq!(todos[3].parent_id, None);
+    }
+
+    #[tokio::test]
+    async fn test_todo_update_by_id() {
+        let storage = create_test_storage();
+        let write_tool = TodoWriteTool::new(storage.clone());
+        let update_tool = TodoUpdateTool::new(storage.clone());
+        write_tool.execute(TodoWriteParams {
+            todos: vec![
+                create_sample_todo_input("First", TodoStatus::InProgress),
+                create_sample_todo_input("Second", TodoStatus::Pending),
+            ],
+        }).await;
+
+        let mut params = update_params();
+        params.update.push(TodoChangeInput { id: "1".to_string(), content: None, status: Some(TodoStatus::Completed), priority: None, notes: Some("done in a1b2".to_string()) });
+        params.add.push(TodoAddInput { content: "Sub of first".to_string(), status: None, priority: TodoPriority::High, notes: None, parent_id: Some("1".to_string()) });
+        assert!(update_tool.execute(params).await.is_success());
+
+        let todos = storage.get_all().await;
+        assert_eq!(todos.len(), 3);
+        assert!(matches!(todos[0].status, TodoStatus::Completed));
+        assert_eq!(todos[0].notes.as_deref(), Some("done in a1b2"));
+        // the subtask comes right after its parent, with the next free id
+        assert_eq!(todos[1].content, "Sub of first");
+        assert_eq!(todos[1].id, "3");
+        assert_eq!(todos[2].content, "Second");
+
+        let mut params = update_params();
+        params.remove.push("1".to_string());
+        assert!(update_tool.execute(params).await.is_success());
+        let todos = storage.get_all().await;
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].content, "Second");
+    }
+
+    #[tokio::test]
+    async fn test_todo_update_unknown_id_changes_nothing() {
+        let storage = create_test_storage();
+        TodoWriteTool::new(storage.clone()).execute(TodoWriteParams {
+            todos: vec![create_sample_todo_input("Only", TodoStatus::Pending)],
+        }).await;
+
+        let mut params = update_params();
+        params.update.push(TodoChangeInput { id: "1".to_string(), content: None, status: Some(TodoStatus::Completed), priority: None, notes: None });
+        params.remove.push("42".to_string());
+        let result = TodoUpdateTool::new(storage.clone()).execute(params).await;
+        assert!(result.is_error());
+        assert!(result.to_string().contains("no task with id 42"), "{}", result);
+        assert!(matches!(storage.get_all().await[0].status, TodoStatus::Pending));
+    }
+
+    #[tokio::test]
+    async fn test_todo_storage_persists_and_notifies() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session/todos.json");
+
+        let storage = Arc::new(TodoStorage::persisted(path.clone()));
+        let mut changes = storage.subscribe();
+        TodoWriteTool::new(storage.clone()).execute(TodoWriteParams {
+            todos: vec![create_sample_todo_input("Survives restarts", TodoStatus::InProgress)],
+        }).await;
+        assert_eq!(changes.recv().await.unwrap()[0].content, "Survives restarts");
+
+        let reloaded = TodoStorage::persisted(path);
+        let todos = reloaded.get_all().await;
+        assert_eq!(todos.len(), 1);
+        assert!(matches!(todos[0].status, TodoStatus::InProgress));
+    }
+}
@@ -1,4 +1,4 @@
-use super::{TodoItem, TodoStatus, TodoStorage};
+use super::{todo_progress, TodoItem, TodoPriority, TodoStatus, TodoStorage};
 use crate::tools::ToolEmptyParams;
 use crate::tools::{ToolResult, tool};
 use std::sync::Arc;
@@ -16,6 +16,26 @@ use uuid::Uuid;
 pub struct TodoItemInput {
     pub content: String,
     pub status: TodoStatus,
+    /// high, medium or low, medium if omitted
+    #[serde(default)]
+    pub priority: TodoPriority,
+    /// Details worth remembering about the task
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Steps of this task
+    #[serde(default)]
+    pub subtasks: Vec<TodoSubtaskInput>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(inline)]
+pub struct TodoSubtaskInput {
+    pub content: String,
+    pub status: TodoStatus,
+    #[serde(default)]
+    pub priority: TodoPriority,
+    #[serde(default)]
+    pub notes: Option<String>,
 }
 
 impl From<TodoItemInput> for TodoItem {
@@ -25,12 +45,35 @@ impl From<TodoItemInput> for TodoItem {
             id: Uuid::new_v4().to_string(),
             content: input.content,
             status: input.status,
+            priority: input.priority,
+            notes: input.notes,
+            parent_id: None,
             created_at: now.clone(),
             updated_at: now,
         }
     }
 }
 
+/// Short ids are easier for the llm to refer to than uuids: the next free number
+fn next_id(todos: &[TodoItem]) -> String {
+    let last = todos.iter().filter_map(|item| item.id.parse::<u64>().ok()).max().unwrap_or(0);
+    (last + 1).to_string()
+}
+
+fn new_item(todos: &[TodoItem], content: String, status: TodoStatus, priority: TodoPriority, notes: Option<String>, parent_id: Option<String>) -> TodoItem {
+    let now = Utc::now().to_rfc3339();
+    TodoItem {
+        id: next_id(todos),
+        content,
+        status,
+        priority,
+        notes,
+        parent_id,
+        created_at: now.clone(),
+        updated_at: now,
+    }
+}
+
 // Read Tool
 #[derive(Clone)]
 pub struct TodoReadTool {
@@ -62,7 +105,7 @@ impl TodoReadTool {
 // Write Tool Parameters
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TodoWriteParams {
-    /// List of todos to write (replaces entire list)
+    /// List of todos to write (replaces entire list), tasks are numbered in order to be updated by id
     pub todos: Vec<TodoItemInput>,
 }
 
@@ -72,20 +115,29 @@ pub struct TodoWriteTool {
     storage: Arc<TodoStorage>
 }
 
-#[tool(name = "todo_write", description = "Creates and manages a structured task list for the coding session. This is vital for organizing complex work, tracking progress, and showing a clear plan.")]
+#[tool(name = "todo_write", description = "Creates and manages a structured task list for the coding session. This is vital for organizing complex work, tracking progress, and showing a clear plan. It replaces the whole list, tasks can have subtasks, a priority and notes; use `todo_update` to change a few tasks of an existing list.")]
 impl TodoWriteTool {
     pub fn new(storage: Arc<TodoStorage>) -> Self {
         Self { storage }
     }
     
     async fn execute(&self, params: TodoWriteParams) -> ToolResult {
-        // Convert input todos to full TodoItems
-        let todo_items: Vec<TodoItem> = params.todos.into_iter().map(|input| input.into()).collect();
+        // Flatten the tasks and their subtasks, numbered in order
+        let mut todo_items: Vec<TodoItem> = Vec::new();
+        for input in params.todos {
+            let task = new_item(&todo_items, input.content, input.status, input.priority, input.notes, None);
+            let parent_id = task.id.clone();
+            todo_items.push(task);
+            for subtask in input.subtasks {
+                let subtask = new_item(&todo_items, subtask.content, subtask.status, subtask.priority, subtask.notes, Some(parent_id.clone()));
+                todo_items.push(subtask);
+            }
+        }
         
         // Replace entire list
         self.storage.replace_all(todo_items.clone()).await;
         
-        let output = self.storage.format_all(&todo_items);
+        let output = format!("Updated {} todo items\n{}", todo_items.len(), self.storage.format_all(&todo_items));
         
         ToolResult::Success {
             output,
@@ -98,6 +150,151 @@ impl TodoWriteTool {
     }
 }
 
+// Update Tool Parameters
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TodoUpdateParams {
+    /// New tasks, added after the existing ones (or after the other subtasks of their parent)
+    #[serde(default)]
+    pub add: Vec<TodoAddInput>,
+    /// Changes to existing tasks, the fields left out are kept
+    #[serde(default)]
+    pub update: Vec<TodoChangeInput>,
+    /// Ids of the tasks to remove, their subtasks are removed with them
+    #[serde(default)]
+    pub remove: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(inline)]
+pub struct TodoAddInput {
+    pub content: String,
+    /// pending if omitted
+    #[serde(default)]
+    pub status: Option<TodoStatus>,
+    #[serde(default)]
+    pub priority: TodoPriority,
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Id of the task this one is a subtask of
+    #[serde(default)]
+    pub parent_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(inline)]
+pub struct TodoChangeInput {
+    pub id: String,
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default)]
+    pub status: Option<TodoStatus>,
+    #[serde(default)]
+    pub priority: Option<TodoPriority>,
+    /// Replaces the notes of the task, an empty string removes them
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+/// Apply an update to the list, every problem is reported at once
+fn apply_update(todos: &mut Vec<TodoItem>, params: TodoUpdateParams) -> Result<(), String> {
+    let mut errors = Vec::new();
+    let now = Utc::now().to_rfc3339();
+
+    for change in params.update {
+        let Some(item) = todos.iter_mut().find(|item| item.id == change.id) else {
+            errors.push(format!("no task with id {}", change.id));
+            continue;
+        };
+        if let Some(content) = change.content {
+            item.content = content;
+        }
+        if let Some(status) = change.status {
+            item.status = status;
+        }
+        if let Some(priority) = change.priority {
+            item.priority = priority;
+        }
+        if let Some(notes) = change.notes {
+            item.notes = Some(notes).filter(|notes| !notes.trim().is_empty());
+        }
+        item.updated_at = now.clone();
+    }
+
+    for id in params.remove {
+        if !todos.iter().any(|item| item.id == id) {
+            errors.push(format!("no task with id {}", id));
+            continue;
+        }
+        // the task and everything below it
+        let mut removed = vec![id];
+        while let Some(child) = todos.iter()
+            .find(|item| item.parent_id.as_ref().is_some_and(|parent| removed.contains(parent)) && !removed.contains(&item.id))
+        {
+            removed.push(child.id.clone());
+        }
+        todos.retain(|item| !removed.contains(&item.id));
+    }
+
+    for input in params.add {
+        let item = new_item(todos, input.content, input.status.unwrap_or(TodoStatus::Pending), input.priority, input.notes, input.parent_id);
+        let Some(parent_id) = item.parent_id.clone() else {
+            todos.push(item);
+            continue;
+        };
+        // after the last task below the parent
+        let Some(parent) = todos.iter().position(|other| other.id == parent_id) else {
+            errors.push(format!("cannot add \"{}\": no task with id {}", item.content, parent_id));
+            continue;
+        };
+        let mut below = vec![parent_id];
+        let mut position = parent + 1;
+        for (index, other) in todos.iter().enumerate().skip(parent + 1) {
+            if other.parent_id.as_ref().is_some_and(|id| below.contains(id)) {
+                below.push(other.id.clone());
+                position = index + 1;
+            }
+        }
+        todos.insert(position, item);
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
+// Update Tool
+#[derive(Clone)]
+pub struct TodoUpdateTool {
+    storage: Arc<TodoStorage>
+}
+
+#[tool(name = "todo_update", description = "Changes the task list without rewriting it: add tasks or subtasks, update the status, priority, content or notes of tasks by id, and remove tasks. Mark each task in_progress when you start it and completed as soon as it is done.")]
+impl TodoUpdateTool {
+    pub fn new(storage: Arc<TodoStorage>) -> Self {
+        Self { storage }
+    }
+
+    async fn execute(&self, params: TodoUpdateParams) -> ToolResult {
+        let todos = match self.storage.modify(|todos| apply_update(todos, params)).await {
+            Ok(todos) => todos,
+            Err(error) => return ToolResult::error(format!("Todo list unchanged:\n{}", error)),
+        };
+
+        let (completed, total) = todo_progress(&todos);
+        ToolResult::Success {
+            output: self.storage.format_all(&todos),
+            metadata: Some({
+                let mut meta = HashMap::new();
+                meta.insert("todo_count".to_string(), json!(total));
+                meta.insert("completed_count".to_string(), json!(completed));
+                meta
+            }),
+        }
+    }
+}
+
 
 
 #[cfg(test)]
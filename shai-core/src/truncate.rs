@@ -0,0 +1,378 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+
+use crate::config::config::ShaiConfig;
+use crate::tools::ToolResult;
+
+/// Size a tool result may take in the trace
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputBudget {
+    pub max_lines: usize,
+    pub max_bytes: usize,
+}
+
+impl Default for OutputBudget {
+    fn default() -> Self {
+        Self {
+            max_lines: 1000,
+            max_bytes: 40_000,
+        }
+    }
+}
+
+/// Truncation settings, stored in the `truncation` section of ~/.shai.config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TruncationConfig {
+    /// Elide the middle of oversized tool results and spill them to a scratch file
+    pub enabled: bool,
+    /// Budget of every tool without an override
+    pub budget: OutputBudget,
+    /// Budget overrides by tool name
+    pub tools: HashMap<String, OutputBudget>,
+    /// Where full outputs are written, ~/.shai/outputs by default
+    pub scratch_dir: Option<PathBuf>,
+}
+
+impl Default for TruncationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            budget: OutputBudget::default(),
+            tools: HashMap::new(),
+            scratch_dir: None,
+        }
+    }
+}
+
+/// Head and tail kept from an oversized text
+#[derive(Debug, Clone, PartialEq)]
+struct Elision {
+    head: String,
+    tail: String,
+    /// 1-based range of the lines that are not (entirely) shown
+    omitted: (usize, usize),
+    total_lines: usize,
+}
+
+/// Keeps oversized tool results within their budget, the full output is written to a scratch file
+pub struct Truncator {
+    config: TruncationConfig,
+    scratch_dir: PathBuf,
+}
+
+impl Truncator {
+    pub fn new() -> Self {
+        Self::from_config(TruncationConfig::default())
+    }
+
+    /// Truncator that leaves every result untouched
+    pub fn disabled() -> Self {
+        Self::from_config(TruncationConfig { enabled: false, ..Default::default() })
+    }
+
+    pub fn from_config(config: TruncationConfig) -> Self {
+        let scratch_dir = config.scratch_dir.clone()
+            .unwrap_or_else(default_scratch_dir);
+        Self { config, scratch_dir }
+    }
+
+    /// Truncator configured from ~/.shai.config, builtin settings if there is none
+    pub fn load() -> Self {
+        let config = ShaiConfig::load()
+            .map(|config| config.truncation)
+            .unwrap_or_default();
+        Self::from_config(config)
+    }
+
+    pub fn budget(&self, tool_name: &str) -> OutputBudget {
+        self.config.tools.get(tool_name).copied().unwrap_or(self.config.budget)
+    }
+
+    /// Elide the middle of the output (or error) if it exceeds the budget of the tool,
+    /// the full text goes to `<scratch_dir>/<session_id>/<tool_call_id>.txt`
+    pub fn truncate_result(&self, session_id: &str, tool_name: &str, tool_call_id: &str, result: ToolResult) -> ToolResult {
+        if !self.config.enabled {
+            return result;
+        }
+
+        let (text, metadata, success) = match result {
+            ToolResult::Success { output, metadata } => (output, metadata, true),
+            ToolResult::Error { error, metadata } => (error, metadata, false),
+        };
+        let Some(elision) = elide(&text, self.budget(tool_name)) else {
+            return if success {
+                ToolResult::Success { output: text, metadata }
+            } else {
+                ToolResult::Error { error: text, metadata }
+            };
+        };
+
+        let path = self.scratch_dir.join(sanitize(session_id)).join(format!("{}.txt", sanitize(tool_call_id)));
+        let spilled = write_scratch(&path, &text);
+        let (start, end) = elision.omitted;
+        let marker = match &spilled {
+            Ok(()) => format!(
+                "[... lines {}-{} of {} elided, the full output was saved to {}: use the read tool with line_start={} and line_end={} to see them ...]",
+                start, end, elision.total_lines, path.display(), start, end),
+            Err(e) => {
+                tracing::warn!(target: "truncate", "could not save the full output to {}: {}", path.display(), e);
+                format!("[... lines {}-{} of {} elided, the full output could not be saved ...]", start, end, elision.total_lines)
+            }
+        };
+
+        let mut metadata = metadata.unwrap_or_default();
+        metadata.insert("truncated".to_string(), serde_json::json!(true));
+        metadata.insert("total_lines".to_string(), serde_json::json!(elision.total_lines));
+        if spilled.is_ok() {
+            metadata.insert("full_output_path".to_string(), serde_json::json!(path.display().to_string()));
+        }
+
+        let text = format!("{}\n{}\n{}", elision.head, marker, elision.tail);
+        if success {
+            ToolResult::Success { output: text, metadata: Some(metadata) }
+        } else {
+            ToolResult::Error { error: text, metadata: Some(metadata) }
+        }
+    }
+}
+
+impl Default for Truncator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Keep half of the budget from the head and half from the tail, None if the text fits
+fn elide(text: &str, budget: OutputBudget) -> Option<Elision> {
+    let lines: Vec<&str> = text.split('\n').collect();
+    if lines.len() <= budget.max_lines && text.len() <= budget.max_bytes {
+        return None;
+    }
+
+    let total_lines = lines.len();
+    let max_lines = budget.max_lines.max(2);
+    let (head_lines, tail_lines) = (max_lines / 2, max_lines - max_lines / 2);
+    let (head_bytes, tail_bytes) = (budget.max_bytes / 2, budget.max_bytes - budget.max_bytes / 2);
+
+    // whole lines from the head
+    let mut head = Vec::new();
+    let mut size = 0;
+    for line in lines.iter().take(head_lines) {
+        if size + line.len() + 1 > head_bytes {
+            break;
+        }
+        size += line.len() + 1;
+        head.push(*line);
+    }
+
+    // whole lines from the tail, without overlapping the head
+    let mut tail = Vec::new();
+    let mut size = 0;
+    for line in lines.iter().skip(head.len()).rev().take(tail_lines) {
+        if size + line.len() + 1 > tail_bytes {
+            break;
+        }
+        size += line.len() + 1;
+        tail.push(*line);
+    }
+    tail.reverse();
+
+    let first_omitted = head.len() + 1;
+    let last_omitted = total_lines - tail.len();
+
+    // a single line larger than the budget (e.g. minified json), show its ends
+    let head = match head.is_empty() {
+        true => prefix(lines[first_omitted - 1], head_bytes).to_string(),
+        false => head.join("\n"),
+    };
+    let tail = match tail.is_empty() {
+        true => suffix(lines[last_omitted - 1], tail_bytes).to_string(),
+        false => tail.join("\n"),
+    };
+
+    Some(Elision {
+        head,
+        tail,
+        omitted: (first_omitted, last_omitted),
+        total_lines,
+    })
+}
+
+fn prefix(line: &str, max_bytes: usize) -> &str {
+    let mut end = max_bytes.min(line.len());
+    while !line.is_char_boundary(end) {
+        end -= 1;
+    }
+    &line[..end]
+}
+
+fn suffix(line: &str, max_bytes: usize) -> &str {
+    let mut start = line.len().saturating_sub(max_bytes);
+    while !line.is_char_boundary(start) {
+        start += 1;
+    }
+    &line[start..]
+}
+
+/// Ids are used as file names, keep them from escaping the scratch folder
+fn sanitize(id: &str) -> String {
+    id.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+/// Full outputs are not redacted, they are kept in the home directory rather than in the shared temp directory
+fn default_scratch_dir() -> PathBuf {
+    dirs::home_dir()
+        .map(|home| home.join(".shai").join("outputs"))
+        .unwrap_or_else(|| std::env::temp_dir().join(format!("shai-{}", whoami())))
+}
+
+#[cfg(unix)]
+fn whoami() -> String {
+    // SAFETY: getuid cannot fail
+    unsafe { libc::getuid() }.to_string()
+}
+
+#[cfg(not(unix))]
+fn whoami() -> String {
+    std::env::var("USERNAME").unwrap_or_default()
+}
+
+/// Directories only the user can enter, and a file that did not exist before, so that nobody else can read
+/// the output nor have it written through a link they planted
+fn write_scratch(path: &Path, text: &str) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    if let Some(parent) = path.parent() {
+        let mut builder = std::fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+            builder.mode(0o700);
+            options.mode(0o600);
+        }
+        builder.create(parent)?;
+    }
+    options.open(path)?.write_all(text.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered(count: usize) -> String {
+        (1..=count).map(|i| format!("line {}", i)).collect::<Vec<_>>().join("\n")
+    }
+
+    fn truncator(dir: &Path, budget: OutputBudget) -> Truncator {
+        Truncator::from_config(TruncationConfig {
+            budget,
+            scratch_dir: Some(dir.to_path_buf()),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_small_result_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = truncator(dir.path(), OutputBudget::default())
+            .truncate_result("session", "bash", "call_1", ToolResult::success(numbered(10)));
+        assert_eq!(result.to_string(), numbered(10));
+        assert!(!dir.path().join("session").exists());
+    }
+
+    #[test]
+    fn test_keeps_head_and_tail_and_spills() {
+        let dir = tempfile::tempdir().unwrap();
+        let budget = OutputBudget { max_lines: 10, max_bytes: 10_000 };
+        let result = truncator(dir.path(), budget)
+            .truncate_result("session", "bash", "call_1", ToolResult::success(numbered(100)));
+
+        let ToolResult::Success { output, metadata } = result else {
+            panic!("Expected success result");
+        };
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[..5], ["line 1", "line 2", "line 3", "line 4", "line 5"]);
+        assert_eq!(lines[6..], ["line 96", "line 97", "line 98", "line 99", "line 100"]);
+        assert!(lines[5].contains("lines 6-95 of 100 elided"));
+        assert!(lines[5].contains("line_start=6 and line_end=95"));
+
+        let path = dir.path().join("session").join("call_1.txt");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), numbered(100));
+        let metadata = metadata.unwrap();
+        assert_eq!(metadata["truncated"], serde_json::json!(true));
+        assert_eq!(metadata["full_output_path"], serde_json::json!(path.display().to_string()));
+    }
+
+    #[test]
+    fn test_byte_budget_and_per_tool_override() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = TruncationConfig {
+            scratch_dir: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        config.tools.insert("read".to_string(), OutputBudget { max_lines: 1000, max_bytes: 100 });
+        let truncator = Truncator::from_config(config);
+
+        let text = numbered(50);
+        assert_eq!(truncator.truncate_result("s", "bash", "c1", ToolResult::success(text.clone())).to_string(), text);
+
+        let output = truncator.truncate_result("s", "read", "c2", ToolResult::success(text)).to_string();
+        assert!(output.contains("elided"));
+        assert!(output.starts_with("line 1\n"));
+        assert!(output.ends_with("line 50"));
+    }
+
+    #[test]
+    fn test_single_huge_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let budget = OutputBudget { max_lines: 10, max_bytes: 20 };
+        let text = format!("{}{}", "a".repeat(500), "b".repeat(500));
+        let result = truncator(dir.path(), budget)
+            .truncate_result("s", "fetch", "c1", ToolResult::error(text));
+
+        let ToolResult::Error { error, .. } = result else {
+            panic!("Expected error result");
+        };
+        assert!(error.starts_with(&format!("{}\n", "a".repeat(10))));
+        assert!(error.ends_with(&format!("\n{}", "b".repeat(10))));
+        assert!(error.contains("lines 1-1 of 1 elided"));
+    }
+
+    #[test]
+    fn test_scratch_files_are_private_and_new() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session").join("call.txt");
+        write_scratch(&path, "full output").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "full output");
+        // an existing file, or a link planted in its place, is never written through
+        assert!(write_scratch(&path, "other").is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "full output");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(path.parent().unwrap()), 0o700);
+            assert_eq!(mode(&path), 0o600);
+        }
+    }
+
+    #[test]
+    fn test_disabled_and_sanitized_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let text = numbered(5000);
+        assert_eq!(Truncator::disabled().truncate_result("s", "bash", "c", ToolResult::success(text.clone())).to_string(), text);
+
+        truncator(dir.path(), OutputBudget { max_lines: 10, max_bytes: 1000 })
+            .truncate_result("../s", "bash", "../../c", ToolResult::success(text));
+        assert!(dir.path().join("___s").join("______c.txt").exists());
+    }
+}
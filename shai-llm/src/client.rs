@@ -14,14 +14,17 @@ use super::providers::{
 };
 use openai_dive::v1::resources::chat::ChatCompletionParametersBuilder;
 use openai_dive::v1::resources::{
-    chat::{ChatCompletionParameters, ChatCompletionResponse, ChatMessage, ChatMessageContent},
+    chat::{ChatCompletionParameters, ChatCompletionResponse, ChatMessage, ChatMessageContent, ChatMessageContentPart},
     model::ListModelResponse,
 };
 use regex::Regex;
+use std::collections::HashMap;
 
 #[derive(Debug)]
 pub struct LlmClient {
     provider: Box<dyn LlmProvider>,
+    /// Whether a model accepts images, from the config, the provider guesses for the models not listed
+    vision: HashMap<String, bool>,
 }
 
 /// Provider Factory related method
@@ -31,6 +34,7 @@ impl LlmClient {
     pub fn from_env_openai() -> Option<Self> {
         OpenAIProvider::from_env().map(|provider| Self {
             provider: Box::new(provider),
+            vision: HashMap::new(),
         })
     }
 
@@ -39,6 +43,7 @@ impl LlmClient {
     pub fn from_env_anthropic() -> Option<Self> {
         AnthropicProvider::from_env().map(|provider| Self {
             provider: Box::new(provider),
+            vision: HashMap::new(),
         })
     }
 
@@ -47,6 +52,7 @@ impl LlmClient {
     pub fn from_env_ollama() -> Option<Self> {
         OllamaProvider::from_env().map(|provider| Self {
             provider: Box::new(provider),
+            vision: HashMap::new(),
         })
     }
 
@@ -55,6 +61,7 @@ impl LlmClient {
     pub fn from_env_openrouter() -> Option<Self> {
         OpenRouterProvider::from_env().map(|provider| Self {
             provider: Box::new(provider),
+            vision: HashMap::new(),
         })
     }
 
@@ -63,6 +70,7 @@ impl LlmClient {
     pub fn from_env_openai_compatible() -> Option<Self> {
         OpenAICompatibleProvider::from_env().map(|provider| Self {
             provider: Box::new(provider),
+            vision: HashMap::new(),
         })
     }
 
@@ -71,6 +79,7 @@ impl LlmClient {
     pub fn from_env_ovhcloud() -> Option<Self> {
         OvhCloudProvider::from_env().map(|provider| Self {
             provider: Box::new(provider),
+            vision: HashMap::new(),
         })
     }
 
@@ -79,48 +88,56 @@ impl LlmClient {
     pub fn from_env_mistral() -> Option<Self> {
         MistralProvider::from_env().map(|provider| Self {
             provider: Box::new(provider),
+            vision: HashMap::new(),
         })
     }
 
     pub fn openai(api_key: String) -> Self {
         Self {
             provider: Box::new(OpenAIProvider::new(api_key)),
+            vision: HashMap::new(),
         }
     }
 
     pub fn compatible(api_key: String, base_url: String) -> Self {
         Self {
             provider: Box::new(OpenAICompatibleProvider::new(api_key, base_url)),
+            vision: HashMap::new(),
         }
     }
 
     pub fn openrouter(api_key: String) -> Self {
         Self {
             provider: Box::new(OpenRouterProvider::new(api_key)),
+            vision: HashMap::new(),
         }
     }
 
     pub fn ovhcloud(api_key: String, base_url: Option<String>) -> Self {
         Self {
             provider: Box::new(OvhCloudProvider::new(api_key, base_url)),
+            vision: HashMap::new(),
         }
     }
 
     pub fn anthropic(api_key: String) -> Self {
         Self {
             provider: Box::new(AnthropicProvider::new(api_key)),
+            vision: HashMap::new(),
         }
     }
 
     pub fn ollama(base_url: String) -> Self {
         Self {
             provider: Box::new(OllamaProvider::new(Some(base_url))),
+            vision: HashMap::new(),
         }
     }
 
     pub fn mistral(api_key: String) -> Self {
         Self {
             provider: Box::new(MistralProvider::new(api_key)),
+            vision: HashMap::new(),
         }
     }
 
@@ -243,6 +260,18 @@ impl LlmClient {
         self.provider.name()
     }
 
+    /// Set which models accept images, overriding what the provider guesses from their name
+    pub fn with_vision(mut self, vision: HashMap<String, bool>) -> Self {
+        self.vision = vision;
+        self
+    }
+
+    /// Whether a model accepts images
+    pub fn supports_vision(&self, model: &str) -> bool {
+        self.vision.get(model).copied()
+            .unwrap_or_else(|| self.provider.supports_vision(model.to_string()))
+    }
+
     /// Get a reference to the underlying provider (for testing)
     pub fn provider(&self) -> &dyn LlmProvider {
         &*self.provider
@@ -252,8 +281,10 @@ impl LlmClient {
 /// Higher level chat client
 impl LlmClient {
     pub async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
+        let supports_vision = self.supports_vision(&request.model);
         let request = request
-            .fix_mistral_alternating();
+            .fix_mistral_alternating()
+            .images_as_text(supports_vision);
 
         let response = self.provider
             .chat(request)
@@ -264,8 +295,10 @@ impl LlmClient {
     }
 
     pub async fn chat_stream(&self, request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
+        let supports_vision = self.supports_vision(&request.model);
         let request = request
-            .fix_mistral_alternating();
+            .fix_mistral_alternating()
+            .images_as_text(supports_vision);
 
         self.provider.chat_stream(request).await
     }
@@ -296,6 +329,61 @@ impl ExtractThinkContent for ChatCompletionResponse {
     }
 }
 
+/// Replaces an image sent to a model that cannot see it
+pub const IMAGE_PLACEHOLDER: &str = "[image not shown: the current model does not support images]";
+
+/// Replaces an image of a tool result the model already answered about, so that images do not pile up in the context
+pub const EARLIER_IMAGE_PLACEHOLDER: &str = "[image not shown again: it was sent earlier in the conversation]";
+
+/// Start of the user message carrying the images of a tool result, tool messages only carry text
+pub const TOOL_ATTACHMENTS_PREFIX: &str = "Attachments of the ";
+
+pub trait ImagesAsText {
+    /// Replace images by a text placeholder, all of them if the model does not support images, else the ones of
+    /// tool results sent before its last answer: the images the user attached stay
+    fn images_as_text(self, supports_vision: bool) -> ChatCompletionParameters;
+}
+
+impl ImagesAsText for ChatCompletionParameters {
+    fn images_as_text(mut self, supports_vision: bool) -> ChatCompletionParameters {
+        let last_answer = self.messages.iter().rposition(|message| matches!(message, ChatMessage::Assistant { .. })).unwrap_or(0);
+
+        for (index, message) in self.messages.iter_mut().enumerate() {
+            let ChatMessage::User { content, .. } = message else {
+                continue;
+            };
+            let ChatMessageContent::ContentPart(parts) = content else {
+                continue;
+            };
+            if !parts.iter().any(|part| matches!(part, ChatMessageContentPart::Image(_))) {
+                continue;
+            }
+            let placeholder = if !supports_vision {
+                IMAGE_PLACEHOLDER
+            } else if index < last_answer && is_tool_attachments(parts) {
+                EARLIER_IMAGE_PLACEHOLDER
+            } else {
+                continue;
+            };
+
+            let text = parts.iter()
+                .filter_map(|part| match part {
+                    ChatMessageContentPart::Text(text_part) => Some(text_part.text.clone()),
+                    ChatMessageContentPart::Image(_) => Some(placeholder.to_string()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            *content = ChatMessageContent::Text(text);
+        }
+        self
+    }
+}
+
+fn is_tool_attachments(parts: &[ChatMessageContentPart]) -> bool {
+    matches!(parts.first(), Some(ChatMessageContentPart::Text(text)) if text.text.starts_with(TOOL_ATTACHMENTS_PREFIX))
+}
+
 pub trait FixMistralAlternating {
     /// Mistral enforces alternating of user/assistant which is problematic in multiturn 
     /// conversation where assistant or toolcall can be cancelled by the user...
@@ -338,4 +426,79 @@ impl FixMistralAlternating for ChatCompletionParameters {
         }
         res
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openai_dive::v1::resources::chat::{ChatMessageImageContentPart, ChatMessageTextContentPart, ImageUrlType};
+
+    fn request_with_image() -> ChatCompletionParameters {
+        request_with_image_after("screenshot.png")
+    }
+
+    fn request_with_image_after(text: &str) -> ChatCompletionParameters {
+        ChatCompletionParametersBuilder::default()
+            .model("qwen3-32b")
+            .messages(vec![ChatMessage::User {
+                content: ChatMessageContent::ContentPart(vec![
+                    ChatMessageContentPart::Text(ChatMessageTextContentPart { r#type: "text".to_string(), text: text.to_string() }),
+                    ChatMessageContentPart::Image(ChatMessageImageContentPart {
+                        r#type: "image_url".to_string(),
+                        image_url: ImageUrlType { url: "data:image/png;base64,iVBORw0KGgo=".to_string(), detail: None },
+                    }),
+                ]),
+                name: None,
+            }])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_images_as_text() {
+        let request = request_with_image().images_as_text(false);
+        let ChatMessage::User { content: ChatMessageContent::Text(text), .. } = &request.messages[0] else {
+            panic!("Expected a text message");
+        };
+        assert_eq!(text, &format!("screenshot.png\n{}", IMAGE_PLACEHOLDER));
+
+        let request = request_with_image().images_as_text(true);
+        assert!(matches!(&request.messages[0], ChatMessage::User { content: ChatMessageContent::ContentPart(parts), .. } if parts.len() == 2));
+    }
+
+    #[test]
+    fn test_tool_images_before_the_last_answer_are_not_sent_again() {
+        let tool_images = || request_with_image_after("Attachments of the read tool result:").messages.remove(0);
+        let mut request = request_with_image();
+        request.messages.push(tool_images());
+        request.messages.push(ChatMessage::Assistant {
+            content: Some(ChatMessageContent::Text("A login form.".to_string())),
+            reasoning_content: None, tool_calls: None, refusal: None, name: None, audio: None,
+        });
+        request.messages.push(tool_images());
+
+        let request = request.images_as_text(true);
+        // the image the user attached stays
+        assert!(matches!(&request.messages[0], ChatMessage::User { content: ChatMessageContent::ContentPart(parts), .. } if parts.len() == 2));
+        let ChatMessage::User { content: ChatMessageContent::Text(text), .. } = &request.messages[1] else {
+            panic!("Expected a text message");
+        };
+        assert_eq!(text, &format!("Attachments of the read tool result:\n{}", EARLIER_IMAGE_PLACEHOLDER));
+        assert!(matches!(&request.messages[3], ChatMessage::User { content: ChatMessageContent::ContentPart(parts), .. } if parts.len() == 2));
+    }
+
+    #[test]
+    fn test_vision_config_overrides_the_provider() {
+        let client = LlmClient::ollama("http://localhost:11434/v1".to_string());
+        assert!(!client.supports_vision("qwen3-32b"));
+        assert!(client.supports_vision("llava:13b"));
+
+        let client = client.with_vision(HashMap::from([
+            ("qwen3-32b".to_string(), true),
+            ("llava:13b".to_string(), false),
+        ]));
+        assert!(client.supports_vision("qwen3-32b"));
+        assert!(!client.supports_vision("llava:13b"));
+        assert!(!client.supports_vision("mistral-7b"));
+    }
+}
@@ -24,6 +24,10 @@ pub use openai_dive::v1::resources::chat::{
     ChatCompletionResponse, 
     ChatMessage,
     ChatMessageContent,
+    ChatMessageContentPart,
+    ChatMessageTextContentPart,
+    ChatMessageImageContentPart,
+    ImageUrlType,
     ChatCompletionTool,
     ChatCompletionToolType,
     ChatCompletionFunction,
@@ -62,6 +62,11 @@ pub trait LlmProvider: Send + Sync {
     fn supports_functions(&self, model: String) -> bool;
     
     fn supports_structured_output(&self, model: String) -> bool;
+
+    /// Whether the model accepts images, they are replaced by a text placeholder otherwise
+    fn supports_vision(&self, model: String) -> bool {
+        is_vision_model(&model)
+    }
     
     fn name(&self) -> &'static str;
     
@@ -69,6 +74,16 @@ pub trait LlmProvider: Send + Sync {
     fn info() -> ProviderInfo where Self: Sized;
 }
 
+/// Guess from its name whether a model accepts images, for providers serving many models
+pub fn is_vision_model(model: &str) -> bool {
+    const VISION_MODELS: &[&str] = &[
+        "gpt-4o", "gpt-4.1", "gpt-5", "o3", "o4", "claude", "gemini", "gemma3", "gemma-3",
+        "llava", "pixtral", "llama-4", "llama4", "vision", "-vl", "vl-", "mistral-small-3", "mistral-medium",
+    ];
+    let model = model.to_lowercase();
+    VISION_MODELS.iter().any(|name| model.contains(name))
+}
+
 impl Debug for dyn LlmProvider {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let debug = format!("LlmProvider({})", self.name());
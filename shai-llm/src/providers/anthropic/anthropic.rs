@@ -5,7 +5,7 @@ use reqwest::Client;
 use serde_json::json;
 use futures::{StreamExt, stream};
 use openai_dive::v1::resources::{
-    chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse, ChatMessage, DeltaChatMessage, ChatMessageContent, ChatCompletionChoice, ChatCompletionChunkChoice, ToolCall, Function},
+    chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse, ChatMessage, DeltaChatMessage, ChatMessageContent, ChatMessageContentPart, ChatCompletionChoice, ChatCompletionChunkChoice, ToolCall, Function},
     model::ListModelResponse,
     shared::{FinishReason, Usage},
 };
@@ -217,7 +217,7 @@ impl AnthropicProvider {
                 ChatMessage::User { content, .. } => {
                     converted_messages.push(json!({
                         "role": "user",
-                        "content": self.extract_content_text(content)
+                        "content": self.convert_user_content(content)
                     }));
                 }
                 ChatMessage::Assistant { content, tool_calls, .. } => {
@@ -300,6 +300,37 @@ impl AnthropicProvider {
         }).collect()
     }
 
+    /// Text of the message, or text and image blocks if it carries images
+    fn convert_user_content(&self, content: &ChatMessageContent) -> serde_json::Value {
+        let parts = match content {
+            ChatMessageContent::ContentPart(parts) if parts.iter().any(|part| matches!(part, ChatMessageContentPart::Image(_))) => parts,
+            _ => return json!(self.extract_content_text(content)),
+        };
+
+        let blocks: Vec<serde_json::Value> = parts.iter().filter_map(|part| {
+            match part {
+                ChatMessageContentPart::Text(text_part) => Some(json!({"type": "text", "text": text_part.text})),
+                ChatMessageContentPart::Image(image_part) => Some(Self::image_block(&image_part.image_url.url)),
+                _ => None,
+            }
+        }).collect();
+        json!(blocks)
+    }
+
+    /// Image block from an OpenAI style image url, either a `data:` url or a remote one
+    fn image_block(url: &str) -> serde_json::Value {
+        match url.strip_prefix("data:").and_then(|rest| rest.split_once(";base64,")) {
+            Some((media_type, data)) => json!({
+                "type": "image",
+                "source": {"type": "base64", "media_type": media_type, "data": data}
+            }),
+            None => json!({
+                "type": "image",
+                "source": {"type": "url", "url": url}
+            }),
+        }
+    }
+
     fn extract_content_text(&self, content: &ChatMessageContent) -> String {
         match content {
             ChatMessageContent::Text(text) => text.clone(),
@@ -489,6 +520,10 @@ impl LlmProvider for AnthropicProvider {
         false
     }
 
+    fn supports_vision(&self, model: String) -> bool {
+        true
+    }
+
     fn name(&self) -> &'static str {
         "anthropic"
     }
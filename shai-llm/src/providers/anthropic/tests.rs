@@ -174,4 +174,35 @@ mod tests {
         assert_eq!(tool_result_content[0]["tool_use_id"].as_str().unwrap(), "toolu_018qHepKa8d4rbZ9qskd2vqw");
         assert_eq!(tool_result_content[0]["content"].as_str().unwrap(), "Successfully updated file '/Users/lloiseau/Work/test/main.py' with 22 bytes");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_user_image_conversion() {
+        use openai_dive::v1::resources::chat::{ChatMessageContentPart, ChatMessageImageContentPart, ChatMessageTextContentPart, ImageUrlType};
+
+        let provider = AnthropicProvider::new("test".to_string());
+        let image = |url: &str| ChatMessageContentPart::Image(ChatMessageImageContentPart {
+            r#type: "image_url".to_string(),
+            image_url: ImageUrlType { url: url.to_string(), detail: None },
+        });
+        let request = ChatCompletionParametersBuilder::default()
+            .model("claude-sonnet-4")
+            .messages(vec![
+                ChatMessage::User {
+                    content: ChatMessageContent::ContentPart(vec![
+                        ChatMessageContentPart::Text(ChatMessageTextContentPart { r#type: "text".to_string(), text: "logo.png".to_string() }),
+                        image("data:image/png;base64,iVBORw0KGgo="),
+                        image("https://example.com/logo.png"),
+                    ]),
+                    name: None,
+                },
+            ])
+            .build()
+            .unwrap();
+
+        let anthropic_format = provider.convert_to_anthropic_format(&request);
+        let content = &anthropic_format["messages"][0]["content"];
+        assert_eq!(content[0], json!({"type": "text", "text": "logo.png"}));
+        assert_eq!(content[1], json!({"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "iVBORw0KGgo="}}));
+        assert_eq!(content[2], json!({"type": "image", "source": {"type": "url", "url": "https://example.com/logo.png"}}));
+    }
+}
@@ -76,6 +76,10 @@ impl LlmProvider for OpenAIProvider {
         true
     }
 
+    fn supports_vision(&self, model: String) -> bool {
+        !model.starts_with("gpt-3.5")
+    }
+
     fn name(&self) -> &'static str {
         "openai"
     }